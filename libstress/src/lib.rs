@@ -388,7 +388,7 @@ pub mod utils {
     ) -> Vec<Vec<u8>> {
         if unique {
             let mut keys: HashSet<Vec<u8>> = HashSet::with_capacity(count);
-            (0..count).into_iter().for_each(|_| {
+            (0..count).for_each(|_| {
                 let mut ran = ran_bytes(size, &mut rng);
                 while keys.contains(&ran) {
                     ran = ran_bytes(size, &mut rng);
@@ -397,10 +397,7 @@ pub mod utils {
             });
             keys.into_iter().collect()
         } else {
-            (0..count)
-                .into_iter()
-                .map(|_| ran_bytes(size, &mut rng))
-                .collect()
+            (0..count).map(|_| ran_bytes(size, &mut rng)).collect()
         }
     }
     /// Generate a vector of random UTF-8 valid strings
@@ -412,7 +409,7 @@ pub mod utils {
     ) -> Vec<String> {
         if unique {
             let mut keys: HashSet<String> = HashSet::with_capacity(count);
-            (0..count).into_iter().for_each(|_| {
+            (0..count).for_each(|_| {
                 let mut ran = ran_string(size, &mut rng);
                 while keys.contains(&ran) {
                     ran = ran_string(size, &mut rng);
@@ -421,10 +418,7 @@ pub mod utils {
             });
             keys.into_iter().collect()
         } else {
-            (0..count)
-                .into_iter()
-                .map(|_| ran_string(size, &mut rng))
-                .collect()
+            (0..count).map(|_| ran_string(size, &mut rng)).collect()
         }
     }
     pub fn rand_alphastring(len: usize, rng: &mut impl rand::Rng) -> String {
@@ -0,0 +1,185 @@
+/*
+ * Created on Sat Sep 18 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # `COPY` and `MOVE` queries
+//! This module provides functions to copy or move keys from the current table into another
+//! table. Combined with `ON`, keys can be copied or moved between any two tables
+
+use crate::corestore::memstore::DdlError;
+use crate::corestore::table::Table;
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::KVEngine;
+use std::sync::Arc;
+
+/// The outcome of copying or moving keys
+enum Transfer {
+    /// the number of keys that were copied or moved
+    Done(usize),
+    /// a value doesn't match the encoding of the target table (nothing was copied or moved)
+    BadEncoding,
+}
+
+/// Copy (or move, if `remove` is set) the given keys from `source` into `target`, skipping
+/// keys that don't exist in `source` or already exist in `target`. Both tables are locked
+/// for the duration of the transfer, so it happens as a unit
+fn transfer(
+    source: (&Table, &KVEngine),
+    target: (&Table, &KVEngine),
+    keys: &[&[u8]],
+    remove: bool,
+) -> Transfer {
+    let (source_table, source) = source;
+    let (target_table, target) = target;
+    let source_map = &source.__get_inner_ref().inner;
+    let target_map = &target.__get_inner_ref().inner;
+    // lock the tables in the order of their addresses; never in the order of the query
+    let (mut source_shards, mut target_shards) =
+        if (source_table as *const Table) < (target_table as *const Table) {
+            let source_shards = source_map.lock_shards_for(keys.iter().copied());
+            (
+                source_shards,
+                target_map.lock_shards_for(keys.iter().copied()),
+            )
+        } else {
+            let target_shards = target_map.lock_shards_for(keys.iter().copied());
+            (
+                source_map.lock_shards_for(keys.iter().copied()),
+                target_shards,
+            )
+        };
+    // check the encoding of every value before touching anything
+    let encoder = target.get_encoder();
+    let encoding_is_okay = keys.iter().all(|key| match source_shards.get(*key) {
+        Some(value) => encoder.is_ok(key, value),
+        None => true,
+    });
+    if !encoding_is_okay {
+        return Transfer::BadEncoding;
+    }
+    let mut done = 0;
+    for key in keys {
+        if target_shards.get(*key).is_some() {
+            continue;
+        }
+        let pair = if remove {
+            source_shards.remove(*key)
+        } else {
+            source_shards
+                .get(*key)
+                .map(|value| (Data::copy_from_slice(key), value.clone()))
+        };
+        if let Some((key, value)) = pair {
+            if remove {
+                source.account(0, value.len());
+            }
+            target.account(value.len(), 0);
+            // versions are handed out per table, so the value gets a version of the target
+            target_shards.insert(key, target.versioned(value.into_data()));
+            done += 1;
+        }
+    }
+    if done != 0 {
+        target.touch();
+        if remove {
+            source.touch();
+        }
+    }
+    Transfer::Done(done)
+}
+
+/// Run a `COPY` or a `MOVE` query: `<target entity> <key1> <key2> ...`
+async fn copy_or_move<'a, T, Strm>(
+    handle: &Corestore,
+    con: &'a mut T,
+    mut act: ActionIter<'a>,
+    remove: bool,
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    err_if_len_is!(act, con, lt 2);
+    let entity = handle_entity!(con, unsafe {
+        // SAFETY: Already checked len
+        act.next_unchecked()
+    });
+    let source = get_tbl!(handle, con);
+    // the target may be in another keyspace, whose quotas are the ones that apply
+    let target_handle = match handle.with_table(entity) {
+        Ok(target_handle) => target_handle,
+        Err(DdlError::DefaultNotFound) => return conwrite!(con, groups::DEFAULT_UNSET),
+        Err(_) => return conwrite!(con, groups::CONTAINER_NOT_FOUND),
+    };
+    let target = get_tbl!(target_handle, con);
+    let (source_kve, target_kve) = match (source.get_kvstore(), target.get_kvstore()) {
+        (Ok(source_kve), Ok(target_kve)) => (source_kve, target_kve),
+        _ => return conwrite!(con, groups::WRONG_MODEL),
+    };
+    if !registry::state_okay() {
+        return conwrite!(con, groups::SERVER_ERR);
+    }
+    if Arc::ptr_eq(&source, &target) {
+        // every key that exists is already where it's supposed to be
+        return conwrite!(con, 0usize);
+    }
+    let keys: Vec<&[u8]> = act.collect();
+    let outcome = if remove && handle.get_centity().0 == target_handle.get_centity().0 {
+        // moving keys within a keyspace doesn't change its usage
+        transfer((&source, source_kve), (&target, target_kve), &keys, remove)
+    } else {
+        quota_check!(
+            con,
+            target_handle,
+            keys.iter().fold(WriteSize::default(), |size, key| {
+                match (source_kve.value_len(key), target_kve.value_len(key)) {
+                    // keys that already exist in the target are skipped
+                    (Some(len), None) => size.with_value(None, len),
+                    _ => size,
+                }
+            })
+        );
+        transfer((&source, source_kve), (&target, target_kve), &keys, remove)
+    };
+    match outcome {
+        Transfer::Done(done) => conwrite!(con, done),
+        Transfer::BadEncoding => conwrite!(con, groups::ENCODING_ERROR),
+    }
+}
+
+action!(
+    /// Run a `COPY` query, which copies keys from the current table into another table
+    fn copy(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        copy_or_move(handle, con, act, false).await
+    }
+);
+
+action!(
+    /// Run a `MOVE` query, which moves keys from the current table into another table
+    fn move_keys(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        copy_or_move(handle, con, act, true).await
+    }
+);
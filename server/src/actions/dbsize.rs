@@ -33,7 +33,7 @@ action!(
         if act.len() == 0 {
             let len;
             {
-                len = kve!(con, handle).len();
+                len = get_tbl!(handle, con).count();
             }
             con.write_response(len).await?;
         } else {
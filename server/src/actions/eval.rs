@@ -0,0 +1,164 @@
+/*
+ * Created on Tue Sep 14 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # `EVAL` queries
+//!
+//! This module provides the actions that run and manage scripts (see [`crate::scripting`])
+
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::resp::BytesWrapper;
+use crate::scripting::{self, ScriptError};
+use bytes::Bytes;
+
+const LOAD: &[u8] = "LOAD".as_bytes();
+const EXISTS: &[u8] = "EXISTS".as_bytes();
+const FLUSH: &[u8] = "FLUSH".as_bytes();
+
+/// Returns the response for a failed script. Compile and runtime errors carry the error
+/// message since there's no way to debug a script without it
+fn error_response(e: ScriptError) -> Vec<u8> {
+    let message = match e {
+        ScriptError::TooManySteps => return groups::SCRIPT_STEP_LIMIT.to_owned(),
+        ScriptError::Timeout => return groups::SCRIPT_TIMEOUT.to_owned(),
+        ScriptError::OutOfMemory => return groups::SCRIPT_MEMORY_LIMIT.to_owned(),
+        ScriptError::Compile(..) => format!("script-compile-error: {}", e),
+        ScriptError::Runtime(_) => format!("script-error: {}", e),
+    };
+    format!("!{}\n{}\n", message.len(), message).into_bytes()
+}
+
+action! {
+    /// Run an `EVAL` query: `EVAL <script> <numkeys> <key1> ... <arg1> ...`
+    fn eval(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 2);
+        let src = unsafe {
+            // SAFETY: Already checked len
+            act.next_unchecked()
+        };
+        match scripting::load(src) {
+            Ok((_, script)) => run_script(handle, con, &script, act).await,
+            Err(e) => conwrite!(con, error_response(e)),
+        }
+    }
+}
+
+action! {
+    /// Run an `EVALSHA` query: `EVALSHA <sha1> <numkeys> <key1> ... <arg1> ...`, which runs
+    /// a cached script
+    fn evalsha(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 2);
+        let sha = unsafe {
+            // SAFETY: Already checked len
+            act.next_unchecked()
+        };
+        match scripting::get(&String::from_utf8_lossy(sha)) {
+            Some(script) => run_script(handle, con, &script, act).await,
+            None => conwrite!(con, groups::SCRIPT_NOT_FOUND),
+        }
+    }
+}
+
+action! {
+    /// Run a `SCRIPT` query:
+    /// - `SCRIPT LOAD <script>` compiles and caches a script, returning its SHA1 digest
+    /// - `SCRIPT EXISTS <sha1> ...` returns how many of the scripts are cached
+    /// - `SCRIPT FLUSH` removes all the cached scripts
+    fn script(_handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, eq 0);
+        let mut subcommand = unsafe {
+            // SAFETY: Already checked len
+            act.next_unchecked()
+        }.to_vec();
+        subcommand.make_ascii_uppercase();
+        match subcommand.as_ref() {
+            LOAD => {
+                err_if_len_is!(act, con, not 1);
+                let src = unsafe {
+                    // SAFETY: Already checked len
+                    act.next_unchecked()
+                };
+                match scripting::load(src) {
+                    Ok((sha, _)) => con.write_response(BytesWrapper(Bytes::from(sha))).await,
+                    Err(e) => conwrite!(con, error_response(e)),
+                }
+            }
+            EXISTS => {
+                err_if_len_is!(act, con, eq 0);
+                let how_many_of_them_exist = act
+                    .filter(|sha| scripting::exists(&String::from_utf8_lossy(sha)))
+                    .count();
+                conwrite!(con, how_many_of_them_exist)
+            }
+            FLUSH => {
+                err_if_len_is!(act, con, not 0);
+                scripting::flush();
+                conwrite!(con, groups::OKAY)
+            }
+            _ => conwrite!(con, groups::UNKNOWN_SCRIPT_QUERY),
+        }
+    }
+}
+
+/// Run a script with `<numkeys> <key1> ... <arg1> ...` from `act`
+async fn run_script<'a, T, Strm>(
+    handle: &Corestore,
+    con: &'a mut T,
+    script: &scripting::Script,
+    mut act: ActionIter<'a>,
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    let numkeys = unsafe {
+        // SAFETY: The callers have checked that there's atleast one more arg
+        act.next_unchecked()
+    };
+    let numkeys = match String::from_utf8_lossy(numkeys).parse::<usize>() {
+        Ok(numkeys) if numkeys <= act.len() => numkeys,
+        Ok(_) => return conwrite!(con, groups::ACTION_ERR),
+        Err(_) => return conwrite!(con, groups::WRONGTYPE_ERR),
+    };
+    let kve = kve!(con, handle);
+    let keys: Vec<Data> = act
+        .by_ref()
+        .take(numkeys)
+        .map(Data::copy_from_slice)
+        .collect();
+    let argv: Vec<Data> = act.map(Data::copy_from_slice).collect();
+    let mut out = Vec::new();
+    let keyspace = handle.get_ctable_keyspace();
+    // the script runs synchronously, so move the other tasks off this worker while it runs
+    let result = tokio::task::block_in_place(|| {
+        scripting::run(kve, keyspace.as_deref(), script, keys, argv)
+    });
+    match result {
+        Ok(value) => scripting::to_response(&value, &mut out),
+        Err(e) => out = error_response(e),
+    }
+    conwrite!(con, out)
+}
@@ -0,0 +1,166 @@
+/*
+ * Created on Tue Sep 21 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Hash actions
+//!
+//! Actions for hash tables, where every key maps to a collection of fields and their values
+
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::resp::writer::{self, TypedArrayWriter};
+use crate::util::compiler;
+
+action!(
+    /// Run an `HSET` query: `HSET <key> <field> <value> [<field> <value> ...]`. This returns
+    /// the number of fields that were newly added
+    fn hset(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        let howmany = act.len();
+        if howmany < 3 || !is_lowbit_set!(howmany) {
+            // we need a key and then pairs of fields and values
+            return conwrite!(con, groups::ACTION_ERR);
+        }
+        let hash = hashstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let key = unsafe { Data::copy_from_slice(act.next_unchecked()) };
+        let mut fields = Vec::with_capacity(howmany / 2);
+        let added = {
+            quota_check!(con, handle, hash.write_size(&key, act.clone()));
+            while let (Some(field), Some(value)) = (act.next(), act.next()) {
+                fields.push((Data::copy_from_slice(field), Data::copy_from_slice(value)));
+            }
+            hash.set_fields(key, fields)
+        };
+        match added {
+            Ok(added) => conwrite!(con, added),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `HGET` query: `HGET <key> <field>`
+    fn hget(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let hash = hashstore!(con, handle);
+        let (key, field) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+        match hash.get_field(key, field) {
+            Ok(Some(value)) => unsafe { writer::write_raw_mono(con, hash.get_vt(), &value).await },
+            Ok(None) => conwrite!(con, groups::NIL),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `HMGET` query: `HMGET <key> <field> [<field> ...]`. This returns a typed array
+    /// with a null for every field that doesn't exist
+    fn hmget(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 2);
+        let hash = hashstore!(con, handle);
+        let key = unsafe { act.next_unchecked() };
+        let fields: Vec<&[u8]> = act.collect();
+        let values = match hash.get_fields(key, &fields) {
+            Ok(values) => values,
+            Err(()) => return compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        };
+        let mut writer = unsafe {
+            // SAFETY: We are getting the value type ourselves
+            TypedArrayWriter::new(con, hash.get_vt(), values.len())
+        }
+        .await?;
+        for value in values {
+            match value {
+                Some(value) => writer.write_element(&value).await?,
+                None => writer.write_null().await?,
+            }
+        }
+        Ok(())
+    }
+);
+
+action!(
+    /// Run an `HDEL` query: `HDEL <key> <field> [<field> ...]`. This returns the number of
+    /// fields that were removed
+    fn hdel(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 2);
+        let hash = hashstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let key = unsafe { act.next_unchecked() };
+        let fields: Vec<&[u8]> = act.collect();
+        match hash.remove_fields(key, &fields) {
+            Ok(removed) => conwrite!(con, removed),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `HGETALL` query: `HGETALL <key>`. This returns an array of two typed arrays:
+    /// the fields and their values (in the same order). Both are empty if the key doesn't
+    /// exist
+    fn hgetall(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let hash = hashstore!(con, handle);
+        let pairs = match hash.get_all(unsafe { act.next_unchecked() }) {
+            Ok(pairs) => pairs,
+            Err(()) => return compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        };
+        con.write_array_length(2).await?;
+        let mut writer = unsafe {
+            // SAFETY: We are getting the field type ourselves
+            TypedArrayWriter::new(con, hash.get_kt(), pairs.len())
+        }
+        .await?;
+        for (field, _) in pairs.iter() {
+            writer.write_element(field).await?;
+        }
+        let mut writer = unsafe {
+            // SAFETY: We are getting the value type ourselves
+            TypedArrayWriter::new(con, hash.get_vt(), pairs.len())
+        }
+        .await?;
+        for (_, value) in pairs.iter() {
+            writer.write_element(value).await?;
+        }
+        Ok(())
+    }
+);
+
+action!(
+    /// Run an `HLEN` query: `HLEN <key>`. This returns the number of fields of the key
+    fn hlen(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let hash = hashstore!(con, handle);
+        match hash.count_fields(unsafe { act.next_unchecked() }) {
+            Ok(count) => conwrite!(con, count),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
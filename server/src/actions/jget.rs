@@ -81,8 +81,8 @@ mod json {
     #[test]
     fn test_buildjson() {
         let mut jblob = JSONBlob::new(128);
-        jblob.insert(&"key".to_owned(), Some(&Bytes::from("value".as_bytes())));
-        jblob.insert(&"key2".to_owned(), None);
+        jblob.insert("key", Some(&Bytes::from("value".as_bytes())));
+        jblob.insert("key2", None);
         assert_eq!(
             "{\"key\":\"value\",\"key2\":null}",
             String::from_utf8_lossy(&jblob.finish().0)
@@ -0,0 +1,81 @@
+/*
+ * Created on Sun Sep 26 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Pattern-based bulk actions
+//!
+//! `DELMATCH` and `COUNTMATCH` delete or count all the keys of the current table that match
+//! a glob pattern (see [`Pattern`] for the syntax; a prefix is just `<prefix>*`). Instead of
+//! locking the whole table, they go over the table one shard at a time, so queries on the
+//! other shards aren't held up even if the table is large
+
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::glob::Pattern;
+use crate::kvengine::KVEngine;
+
+/// Delete or count the keys that match the pattern, one shard at a time
+fn for_each_shard(kve: &KVEngine, pattern: &Pattern, remove: bool) -> usize {
+    let mut affected = 0;
+    for shard in 0..kve.shard_count() {
+        affected += if remove {
+            kve.remove_matching(shard, pattern)
+        } else {
+            kve.count_matching(shard, pattern)
+        };
+    }
+    affected
+}
+
+action!(
+    /// Run a `DELMATCH` query: `DELMATCH <pattern>`. This returns the number of deleted keys
+    fn delmatch(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let kve = kve!(con, handle);
+        let pattern = match Pattern::new(unsafe { act.next_unchecked() }) {
+            Some(pattern) => pattern,
+            None => return conwrite!(con, groups::BAD_EXPRESSION),
+        };
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let deleted = for_each_shard(kve, &pattern, true);
+        conwrite!(con, deleted)
+    }
+);
+
+action!(
+    /// Run a `COUNTMATCH` query: `COUNTMATCH <pattern>`. This returns the number of keys
+    /// that match the pattern
+    fn countmatch(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let kve = kve!(con, handle);
+        let pattern = match Pattern::new(unsafe { act.next_unchecked() }) {
+            Some(pattern) => pattern,
+            None => return conwrite!(con, groups::BAD_EXPRESSION),
+        };
+        let count = for_each_shard(kve, &pattern, false);
+        conwrite!(con, count)
+    }
+);
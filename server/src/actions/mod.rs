@@ -30,13 +30,17 @@
 //! of the actions supported by Skytable
 //!
 
+pub mod copy;
 pub mod dbsize;
 pub mod del;
+pub mod eval;
 pub mod exists;
 pub mod flushdb;
 pub mod get;
+pub mod hash;
 pub mod jget;
 pub mod keylen;
+pub mod keymatch;
 pub mod lskeys;
 pub mod mget;
 pub mod mpop;
@@ -44,9 +48,15 @@ pub mod mset;
 pub mod mupdate;
 pub mod pop;
 pub mod set;
+pub mod sets;
+pub mod sketch;
+pub mod sortedsets;
+pub mod strings;
 pub mod strong;
+pub mod timeseries;
 pub mod update;
 pub mod uset;
+pub mod versioned;
 pub mod heya {
     //! Respond to `HEYA` queries
     use crate::dbnet::connection::prelude::*;
@@ -92,49 +102,49 @@ macro_rules! err_if_len_is {
     ($buf:ident, $con:ident, eq $len:literal) => {
         if $buf.len() == $len {
             return $con
-                .write_response(crate::protocol::responses::groups::ACTION_ERR)
+                .write_response($crate::protocol::responses::groups::ACTION_ERR)
                 .await;
         }
     };
     ($buf:ident, $con:ident, not $len:literal) => {
         if $buf.len() != $len {
             return $con
-                .write_response(crate::protocol::responses::groups::ACTION_ERR)
+                .write_response($crate::protocol::responses::groups::ACTION_ERR)
                 .await;
         }
     };
     ($buf:ident, $con:ident, gt $len:literal) => {
         if $buf.len() > $len {
             return $con
-                .write_response(crate::protocol::responses::groups::ACTION_ERR)
+                .write_response($crate::protocol::responses::groups::ACTION_ERR)
                 .await;
         }
     };
     ($buf:ident, $con:ident, lt $len:literal) => {
         if $buf.len() < $len {
             return $con
-                .write_response(crate::protocol::responses::groups::ACTION_ERR)
+                .write_response($crate::protocol::responses::groups::ACTION_ERR)
                 .await;
         }
     };
     ($buf:ident, $con:ident, gt_or_eq $len:literal) => {
         if $buf.len() >= $len {
             return $con
-                .write_response(crate::protocol::responses::groups::ACTION_ERR)
+                .write_response($crate::protocol::responses::groups::ACTION_ERR)
                 .await;
         }
     };
     ($buf:ident, $con:ident, lt_or_eq $len:literal) => {
         if $buf.len() <= $len {
             return $con
-                .write_response(crate::protocol::responses::groups::ACTION_ERR)
+                .write_response($crate::protocol::responses::groups::ACTION_ERR)
                 .await;
         }
     };
     ($con:ident, $expr:expr) => {
         if $expr {
             return $con
-                .write_response(crate::protocol::responses::groups::ACTION_ERR)
+                .write_response($crate::protocol::responses::groups::ACTION_ERR)
                 .await;
         }
     };
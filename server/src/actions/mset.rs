@@ -49,20 +49,20 @@ action!(
             })
         };
         if compiler::likely(encoding_is_okay) {
-            let done_howmany: Option<usize>;
-            if registry::state_okay() {
-                let mut didmany = 0;
+            let done_howmany = if registry::state_okay() {
+                quota_check!(con, handle, kve.write_size(act.clone()));
+                let mut didmany = 0usize;
                 while let (Some(key), Some(val)) = (act.next(), act.next()) {
                     if kve.set_unchecked(Data::copy_from_slice(key), Data::copy_from_slice(val)) {
                         didmany += 1;
                     }
                 }
-                done_howmany = Some(didmany);
+                Some(didmany)
             } else {
-                done_howmany = None;
-            }
+                None
+            };
             if let Some(done_howmany) = done_howmany {
-                return con.write_response(done_howmany as usize).await;
+                return con.write_response(done_howmany).await;
             } else {
                 return con.write_response(responses::groups::SERVER_ERR).await;
             }
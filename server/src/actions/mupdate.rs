@@ -51,6 +51,7 @@ action!(
         let done_howmany: Option<usize>;
         if compiler::likely(encoding_is_okay) {
             if registry::state_okay() {
+                quota_check!(con, handle, kve.write_size(act.clone()));
                 let mut didmany = 0;
                 while let (Some(key), Some(val)) = (act.next(), act.next()) {
                     if kve.update_unchecked(Data::copy_from_slice(key), Data::copy_from_slice(val))
@@ -63,7 +64,7 @@ action!(
                 done_howmany = None;
             }
             if let Some(done_howmany) = done_howmany {
-                return con.write_response(done_howmany as usize).await;
+                return con.write_response(done_howmany).await;
             } else {
                 return con.write_response(responses::groups::SERVER_ERR).await;
             }
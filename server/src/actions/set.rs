@@ -41,6 +41,7 @@ action!(
         if registry::state_okay() {
             let did_we = {
                 let writer = kve!(con, handle);
+                quota_check!(con, handle, writer.write_size(act.clone()));
                 match unsafe {
                     // UNSAFE(@ohsayan): This is completely safe as we've already checked
                     // that there are exactly 2 arguments
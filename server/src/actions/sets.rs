@@ -0,0 +1,242 @@
+/*
+ * Created on Wed Sep 22 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Set actions
+//!
+//! Actions for set tables, where every key maps to a set of unique members. Unions,
+//! intersections and differences are computed on the server while the involved keys are
+//! locked, so they always see a consistent view of the keys
+
+use crate::corestore::memstore::Keyspace;
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::hashset::SetOp;
+use crate::kvengine::SetEngine;
+use crate::resp::writer::TypedArrayWriter;
+use crate::util::compiler;
+
+/// Write the given members as a typed array
+async fn write_members<'a, T, Strm>(
+    con: &'a mut T,
+    set: &SetEngine,
+    members: Vec<Data>,
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    let mut writer = unsafe {
+        // SAFETY: We are getting the member type ourselves
+        TypedArrayWriter::new(con, set.get_mt(), members.len())
+    }
+    .await?;
+    for member in members {
+        writer.write_element(&member).await?;
+    }
+    Ok(())
+}
+
+/// Run a `SUNION`, `SINTER` or `SDIFF` query: `<key1> <key2> ...`
+async fn combine<'a, T, Strm>(
+    handle: &Corestore,
+    con: &'a mut T,
+    act: ActionIter<'a>,
+    op: SetOp,
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    err_if_len_is!(act, con, eq 0);
+    let set = setstore!(con, handle);
+    let keys: Vec<&[u8]> = act.collect();
+    match set.combine(op, &keys) {
+        Ok(members) => write_members(con, set, members).await,
+        Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+    }
+}
+
+/// Run a `SUNIONSTORE`, `SINTERSTORE` or `SDIFFSTORE` query: `<dest> <key1> <key2> ...`.
+/// This returns the number of members stored in `dest`
+async fn combine_into<'a, T, Strm>(
+    handle: &Corestore,
+    con: &'a mut T,
+    mut act: ActionIter<'a>,
+    op: SetOp,
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    err_if_len_is!(act, con, lt 2);
+    let set = setstore!(con, handle);
+    if !registry::state_okay() {
+        return conwrite!(con, groups::SERVER_ERR);
+    }
+    let dest = unsafe {
+        // SAFETY: Already checked len
+        Data::copy_from_slice(act.next_unchecked())
+    };
+    let keys: Vec<&[u8]> = act.collect();
+    // the size of the result is only known once the keys are locked, and the usage can't
+    // be looked up then
+    let keyspace = handle.get_ctable_keyspace();
+    let combined = {
+        let mut quota = keyspace.as_deref().and_then(Keyspace::quota_tracker);
+        let allow = |size| match quota.as_mut() {
+            Some(quota) => quota.allow(size),
+            None => true,
+        };
+        set.combine_into(op, dest, &keys, allow)
+    };
+    match combined {
+        Ok(Some(count)) => conwrite!(con, count),
+        Ok(None) => conwrite!(con, groups::QUOTA_EXCEEDED),
+        Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+    }
+}
+
+action!(
+    /// Run an `SADD` query: `SADD <key> <member1> <member2> ...`. This returns the number
+    /// of members that weren't already in the set
+    fn sadd(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 2);
+        let set = setstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let key = unsafe { Data::copy_from_slice(act.next_unchecked()) };
+        let added = {
+            quota_check!(con, handle, set.write_size(&key, act.clone()));
+            let members = act.map(Data::copy_from_slice).collect();
+            set.add_members(key, members)
+        };
+        match added {
+            Ok(added) => conwrite!(con, added),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `SREM` query: `SREM <key> <member1> <member2> ...`. This returns the number
+    /// of members that were removed
+    fn srem(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 2);
+        let set = setstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let key = unsafe { act.next_unchecked() };
+        let members: Vec<&[u8]> = act.collect();
+        match set.remove_members(key, &members) {
+            Ok(removed) => conwrite!(con, removed),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `SISMEMBER` query: `SISMEMBER <key> <member>`. This returns `1` if the member
+    /// is in the set and `0` otherwise
+    fn sismember(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let set = setstore!(con, handle);
+        let (key, member) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+        match set.is_member(key, member) {
+            Ok(is_member) => conwrite!(con, is_member as usize),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `SCARD` query: `SCARD <key>`. This returns the number of members of the key
+    fn scard(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let set = setstore!(con, handle);
+        match set.count_members(unsafe { act.next_unchecked() }) {
+            Ok(count) => conwrite!(con, count),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `SMEMBERS` query: `SMEMBERS <key>`. This returns the members of the key as a
+    /// typed array
+    fn smembers(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let set = setstore!(con, handle);
+        match set.get_members(unsafe { act.next_unchecked() }) {
+            Ok(members) => write_members(con, set, members).await,
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `SUNION` query, which returns the members that are in any of the given keys
+    fn sunion(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        combine(handle, con, act, SetOp::Union).await
+    }
+);
+
+action!(
+    /// Run an `SINTER` query, which returns the members that are in all of the given keys
+    fn sinter(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        combine(handle, con, act, SetOp::Intersection).await
+    }
+);
+
+action!(
+    /// Run an `SDIFF` query, which returns the members of the first key that aren't in any
+    /// of the other keys
+    fn sdiff(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        combine(handle, con, act, SetOp::Difference).await
+    }
+);
+
+action!(
+    /// Run an `SUNIONSTORE` query, which stores the union of the given keys in a key
+    fn sunionstore(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        combine_into(handle, con, act, SetOp::Union).await
+    }
+);
+
+action!(
+    /// Run an `SINTERSTORE` query, which stores the intersection of the given keys in a key
+    fn sinterstore(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        combine_into(handle, con, act, SetOp::Intersection).await
+    }
+);
+
+action!(
+    /// Run an `SDIFFSTORE` query, which stores the difference of the given keys in a key
+    fn sdiffstore(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        combine_into(handle, con, act, SetOp::Difference).await
+    }
+);
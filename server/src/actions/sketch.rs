@@ -0,0 +1,221 @@
+/*
+ * Created on Fri Sep 24 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Sketch actions
+//!
+//! Actions for HyperLogLog counters (`PF*`) and Bloom filters (`BF*`), which are stored as
+//! the values of keys in the current key/value table (which needs `binstr` values)
+
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::sketch::bloom::FilterParams;
+use crate::kvengine::sketch::hyperloglog::HLL_LEN;
+use crate::kvengine::sketch::SketchError;
+use crate::util::compiler;
+
+/// Returns the response for a sketch error
+const fn sketch_error(e: SketchError) -> &'static [u8] {
+    match e {
+        SketchError::Encoding => groups::ENCODING_ERROR,
+        SketchError::WrongType => groups::WRONGTYPE_ERR,
+        SketchError::WrongModel => groups::WRONG_MODEL,
+    }
+}
+
+action!(
+    /// Run a `PFADD` query: `PFADD <key> <element1> <element2> ...`. This returns `1` if
+    /// the counter was created or changed and `0` otherwise
+    fn pfadd(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, eq 0);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let key = unsafe { Data::copy_from_slice(act.next_unchecked()) };
+        let elements: Vec<&[u8]> = act.collect();
+        let modified = {
+            quota_check!(
+                con,
+                handle,
+                WriteSize::default().with_value(kve.value_len(&key), HLL_LEN)
+            );
+            kve.hll_add(key, &elements)
+        };
+        match modified {
+            Ok(modified) => conwrite!(con, modified as usize),
+            Err(e) => compiler::cold_err(conwrite!(con, sketch_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `PFCOUNT` query: `PFCOUNT <key1> <key2> ...`. This returns the estimated number
+    /// of unique elements added to the counters of the given keys
+    fn pfcount(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        err_if_len_is!(act, con, eq 0);
+        let kve = kve!(con, handle);
+        let keys: Vec<&[u8]> = act.collect();
+        match kve.hll_count(&keys) {
+            Ok(count) => conwrite!(con, count),
+            Err(e) => compiler::cold_err(conwrite!(con, sketch_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `PFMERGE` query: `PFMERGE <dest> <key1> <key2> ...`, which merges the counters
+    /// of the given keys into the counter of `dest`
+    fn pfmerge(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, eq 0);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let dest = unsafe { Data::copy_from_slice(act.next_unchecked()) };
+        let keys: Vec<&[u8]> = act.collect();
+        let merged = {
+            quota_check!(
+                con,
+                handle,
+                WriteSize::default().with_value(kve.value_len(&dest), HLL_LEN)
+            );
+            kve.hll_merge(dest, &keys)
+        };
+        match merged {
+            Ok(()) => conwrite!(con, groups::OKAY),
+            Err(e) => compiler::cold_err(conwrite!(con, sketch_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `BFRESERVE` query: `BFRESERVE <key> <error rate> <capacity>`, which creates an
+    /// empty Bloom filter that holds `capacity` items with the given error rate
+    fn bfreserve(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let (key, error_rate, capacity) = unsafe {
+            (
+                Data::copy_from_slice(act.next_unchecked()),
+                act.next_unchecked(),
+                act.next_unchecked(),
+            )
+        };
+        let (error_rate, capacity) = match (
+            String::from_utf8_lossy(error_rate).parse::<f64>(),
+            String::from_utf8_lossy(capacity).parse::<u64>(),
+        ) {
+            (Ok(error_rate), Ok(capacity)) => (error_rate, capacity),
+            _ => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        let params = match FilterParams::new(error_rate, capacity) {
+            Some(params) => params,
+            None => return conwrite!(con, groups::ACTION_ERR),
+        };
+        let reserved = {
+            quota_check!(
+                con,
+                handle,
+                WriteSize::default().with_value(kve.value_len(&key), params.filter_len())
+            );
+            kve.bloom_reserve(key, params)
+        };
+        match reserved {
+            Ok(true) => conwrite!(con, groups::OKAY),
+            Ok(false) => conwrite!(con, groups::OVERWRITE_ERR),
+            Err(e) => compiler::cold_err(conwrite!(con, sketch_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `BFADD` query: `BFADD <key> <item>`. This returns `1` if the item definitely
+    /// wasn't added before and `0` otherwise
+    fn bfadd(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let (key, item) = unsafe {
+            (
+                Data::copy_from_slice(act.next_unchecked()),
+                act.next_unchecked(),
+            )
+        };
+        let added = {
+            quota_check!(con, handle, {
+                let older = kve.value_len(&key);
+                let len = older.unwrap_or_else(|| FilterParams::default().filter_len());
+                WriteSize::default().with_value(older, len)
+            });
+            kve.bloom_add(key, item)
+        };
+        match added {
+            Ok(added) => conwrite!(con, added as usize),
+            Err(e) => compiler::cold_err(conwrite!(con, sketch_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `BFEXISTS` query: `BFEXISTS <key> <item>`. This returns `1` if the item was
+    /// probably added and `0` if it definitely wasn't
+    fn bfexists(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let kve = kve!(con, handle);
+        let (key, item) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+        match kve.bloom_check(key, &[item]) {
+            Ok(found) => conwrite!(con, found[0] as usize),
+            Err(e) => compiler::cold_err(conwrite!(con, sketch_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `BFMEXISTS` query: `BFMEXISTS <key> <item1> <item2> ...`. This returns an
+    /// array with a `1` for every item that was probably added and a `0` for every item
+    /// that definitely wasn't
+    fn bfmexists(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 2);
+        let kve = kve!(con, handle);
+        let key = unsafe { act.next_unchecked() };
+        let items: Vec<&[u8]> = act.collect();
+        match kve.bloom_check(key, &items) {
+            Ok(found) => {
+                con.write_array_length(found.len()).await?;
+                for found in found {
+                    conwrite!(con, found as usize)?;
+                }
+                Ok(())
+            }
+            Err(e) => compiler::cold_err(conwrite!(con, sketch_error(e))),
+        }
+    }
+);
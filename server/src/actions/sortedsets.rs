@@ -0,0 +1,316 @@
+/*
+ * Created on Thu Sep 23 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Sorted set actions
+//!
+//! Actions for sorted set tables, where every key maps to a set of unique members ranked by
+//! their scores. Scores are finite floats and are returned as strings, while ranks are
+//! zero-based (with negative ranks in range queries counted from the end)
+
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::types;
+use crate::kvengine::SortedSetEngine;
+use crate::resp::writer;
+use crate::resp::writer::TypedArrayWriter;
+use crate::resp::TSYMBOL_UNICODE;
+use crate::util::compiler;
+use core::iter;
+
+/// Parse a score, which has to be a finite float
+fn parse_score(bytes: &[u8]) -> Option<f64> {
+    if types::is_float(bytes) {
+        // validated floats are ASCII, so this is valid UTF-8
+        unsafe { core::str::from_utf8_unchecked(bytes) }
+            .parse()
+            .ok()
+    } else {
+        None
+    }
+}
+
+/// Parse a rank, which can be negative to count from the end
+fn parse_rank(bytes: &[u8]) -> Option<i64> {
+    String::from_utf8_lossy(bytes).parse().ok()
+}
+
+/// Write a score as a string (Skyhash has no float type)
+async fn write_score<'a, T, Strm>(con: &'a mut T, score: f64) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    unsafe {
+        // SAFETY: The formatted score is valid unicode
+        writer::write_raw_mono(con, TSYMBOL_UNICODE, &Data::from(score.to_string())).await
+    }
+}
+
+/// Write the given members as a typed array
+async fn write_members<'a, T, Strm>(
+    con: &'a mut T,
+    zset: &SortedSetEngine,
+    members: Vec<Data>,
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    let mut writer = unsafe {
+        // SAFETY: We are getting the member type ourselves
+        TypedArrayWriter::new(con, zset.get_mt(), members.len())
+    }
+    .await?;
+    for member in members {
+        writer.write_element(&member).await?;
+    }
+    Ok(())
+}
+
+/// Run a `ZRANK` or `ZREVRANK` query: `<key> <member>`
+async fn rank<'a, T, Strm>(
+    handle: &Corestore,
+    con: &'a mut T,
+    mut act: ActionIter<'a>,
+    rev: bool,
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    err_if_len_is!(act, con, not 2);
+    let zset = sortedsetstore!(con, handle);
+    let (key, member) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+    match zset.get_rank(key, member, rev) {
+        Ok(Some(rank)) => conwrite!(con, rank),
+        Ok(None) => conwrite!(con, groups::NIL),
+        Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+    }
+}
+
+/// Run a `ZRANGE` or `ZREVRANGE` query: `<key> <start> <stop>`
+async fn range<'a, T, Strm>(
+    handle: &Corestore,
+    con: &'a mut T,
+    mut act: ActionIter<'a>,
+    rev: bool,
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    err_if_len_is!(act, con, not 3);
+    let zset = sortedsetstore!(con, handle);
+    let (key, start, stop) = unsafe {
+        (
+            act.next_unchecked(),
+            act.next_unchecked(),
+            act.next_unchecked(),
+        )
+    };
+    let (start, stop) = match (parse_rank(start), parse_rank(stop)) {
+        (Some(start), Some(stop)) => (start, stop),
+        _ => return conwrite!(con, groups::WRONGTYPE_ERR),
+    };
+    match zset.range_by_rank(key, start, stop, rev) {
+        Ok(members) => write_members(con, zset, members).await,
+        Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+    }
+}
+
+action!(
+    /// Run a `ZADD` query: `ZADD <key> <score> <member> [<score> <member> ...]`. The scores
+    /// of existing members are updated and this returns the number of members that were
+    /// newly added
+    fn zadd(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        let howmany = act.len();
+        if howmany < 3 || !is_lowbit_set!(howmany) {
+            // we need a key and then pairs of scores and members
+            return conwrite!(con, groups::ACTION_ERR);
+        }
+        let zset = sortedsetstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let key = unsafe { Data::copy_from_slice(act.next_unchecked()) };
+        let mut members = Vec::with_capacity(howmany / 2);
+        while let (Some(score), Some(member)) = (act.next(), act.next()) {
+            match parse_score(score) {
+                Some(score) => members.push((score, Data::copy_from_slice(member))),
+                None => return conwrite!(con, groups::WRONGTYPE_ERR),
+            }
+        }
+        let added = {
+            quota_check!(
+                con,
+                handle,
+                zset.write_size(&key, members.iter().map(|(_, member)| member.as_ref()))
+            );
+            zset.add_members(key, members)
+        };
+        match added {
+            Ok(added) => conwrite!(con, added),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `ZINCRBY` query: `ZINCRBY <key> <increment> <member>`. This returns the new
+    /// score of the member
+    fn zincrby(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let zset = sortedsetstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let (key, by, member) = unsafe {
+            (
+                Data::copy_from_slice(act.next_unchecked()),
+                act.next_unchecked(),
+                Data::copy_from_slice(act.next_unchecked()),
+            )
+        };
+        let by = match parse_score(by) {
+            Some(by) => by,
+            None => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        let score = {
+            quota_check!(
+                con,
+                handle,
+                zset.write_size(&key, iter::once(member.as_ref()))
+            );
+            zset.incr_score(key, member, by)
+        };
+        match score {
+            Ok(Some(score)) => write_score(con, score).await,
+            // the new score would overflow
+            Ok(None) => conwrite!(con, groups::ACTION_ERR),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `ZREM` query: `ZREM <key> <member1> <member2> ...`. This returns the number
+    /// of members that were removed
+    fn zrem(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 2);
+        let zset = sortedsetstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let key = unsafe { act.next_unchecked() };
+        let members: Vec<&[u8]> = act.collect();
+        match zset.remove_members(key, &members) {
+            Ok(removed) => conwrite!(con, removed),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `ZSCORE` query: `ZSCORE <key> <member>`
+    fn zscore(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let zset = sortedsetstore!(con, handle);
+        let (key, member) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+        match zset.get_score(key, member) {
+            Ok(Some(score)) => write_score(con, score).await,
+            Ok(None) => conwrite!(con, groups::NIL),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `ZRANK` query: `ZRANK <key> <member>`, which returns the rank of the member
+    /// counting from the lowest score
+    fn zrank(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        rank(handle, con, act, false).await
+    }
+);
+
+action!(
+    /// Run a `ZREVRANK` query: `ZREVRANK <key> <member>`, which returns the rank of the
+    /// member counting from the highest score
+    fn zrevrank(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        rank(handle, con, act, true).await
+    }
+);
+
+action!(
+    /// Run a `ZCARD` query: `ZCARD <key>`. This returns the number of members of the key
+    fn zcard(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let zset = sortedsetstore!(con, handle);
+        match zset.count_members(unsafe { act.next_unchecked() }) {
+            Ok(count) => conwrite!(con, count),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `ZRANGE` query: `ZRANGE <key> <start> <stop>`, which returns the members
+    /// ranked from `start` to `stop`, from the lowest score to the highest
+    fn zrange(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        range(handle, con, act, false).await
+    }
+);
+
+action!(
+    /// Run a `ZREVRANGE` query: `ZREVRANGE <key> <start> <stop>`, which returns the members
+    /// ranked from `start` to `stop`, from the highest score to the lowest
+    fn zrevrange(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        range(handle, con, act, true).await
+    }
+);
+
+action!(
+    /// Run a `ZRANGEBYSCORE` query: `ZRANGEBYSCORE <key> <min> <max>`, which returns the
+    /// members with a score between `min` and `max` (both inclusive)
+    fn zrangebyscore(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let zset = sortedsetstore!(con, handle);
+        let (key, min, max) = unsafe {
+            (
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+            )
+        };
+        let (min, max) = match (parse_score(min), parse_score(max)) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        match zset.range_by_score(key, min, max) {
+            Ok(members) => write_members(con, zset, members).await,
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
@@ -0,0 +1,180 @@
+/*
+ * Created on Mon Sep 27 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # String actions
+//!
+//! Atomic actions on (parts of) the values of the current key/value table. `APPEND`,
+//! `STRLEN`, `GETRANGE` and `SETRANGE` work on tables with `str` or `binstr` values and use
+//! byte offsets, while `GETSET` works on any table
+
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::strings::StringOpError;
+use crate::resp::writer;
+use crate::util::compiler;
+
+/// Returns the response for a string operation error
+const fn string_op_error(e: StringOpError) -> &'static [u8] {
+    match e {
+        StringOpError::Encoding => groups::ENCODING_ERROR,
+        StringOpError::WrongType => groups::WRONGTYPE_ERR,
+        StringOpError::TooLarge => groups::ACTION_ERR,
+    }
+}
+
+action!(
+    /// Run an `APPEND` query: `APPEND <key> <value>`. This appends to the value of the key
+    /// (creating it if it doesn't exist) and returns the new length of the value
+    fn append(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let (key, suffix) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+        let appended = {
+            quota_check!(con, handle, {
+                let older = kve.value_len(key);
+                WriteSize::default().with_value(older, older.unwrap_or(0) + suffix.len())
+            });
+            kve.append(Data::copy_from_slice(key), suffix)
+        };
+        match appended {
+            Ok(len) => conwrite!(con, len),
+            Err(e) => compiler::cold_err(conwrite!(con, string_op_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `STRLEN` query: `STRLEN <key>`. This returns the length (in bytes) of the value
+    /// of the key or nil if the key doesn't exist
+    fn strlen(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let kve = kve!(con, handle);
+        match kve.strlen(unsafe { act.next_unchecked() }) {
+            Ok(Some(len)) => conwrite!(con, len),
+            Ok(None) => conwrite!(con, groups::NIL),
+            Err(e) => compiler::cold_err(conwrite!(con, string_op_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `GETRANGE` query: `GETRANGE <key> <start> <end>`. This returns the bytes of
+    /// the value between `start` and `end` (both inclusive, with negative offsets counted
+    /// from the end) or nil if the key doesn't exist
+    fn getrange(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let kve = kve!(con, handle);
+        let (key, start, end) = unsafe {
+            (
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+            )
+        };
+        let parse = |bytes: &[u8]| String::from_utf8_lossy(bytes).parse::<i64>().ok();
+        let (start, end) = match (parse(start), parse(end)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        match kve.get_range(key, start, end) {
+            Ok(Some(range)) => unsafe {
+                // SAFETY: The range is valid for the value type
+                writer::write_raw_mono(con, kve.get_vt(), &range).await
+            },
+            Ok(None) => conwrite!(con, groups::NIL),
+            Err(e) => compiler::cold_err(conwrite!(con, string_op_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `SETRANGE` query: `SETRANGE <key> <offset> <value>`. This overwrites the value
+    /// of the key starting at `offset` (padding it with zero bytes if needed and creating
+    /// the key if it doesn't exist) and returns the new length of the value
+    fn setrange(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let (key, offset, bytes) = unsafe {
+            (
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+            )
+        };
+        let offset = match String::from_utf8_lossy(offset).parse::<usize>() {
+            Ok(offset) => offset,
+            Err(_) => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        let written = {
+            quota_check!(con, handle, {
+                let older = kve.value_len(key);
+                let len = older.unwrap_or(0).max(offset.saturating_add(bytes.len()));
+                WriteSize::default().with_value(older, len)
+            });
+            kve.set_range(Data::copy_from_slice(key), offset, bytes)
+        };
+        match written {
+            Ok(len) => conwrite!(con, len),
+            Err(e) => compiler::cold_err(conwrite!(con, string_op_error(e))),
+        }
+    }
+);
+
+action!(
+    /// Run a `GETSET` query: `GETSET <key> <value>`. This sets the value of the key (whether
+    /// it exists or not) and returns the older value or nil if the key didn't exist
+    fn getset(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let older = {
+            quota_check!(con, handle, kve.write_size(act.clone()));
+            let (key, value) = unsafe {
+                (
+                    Data::copy_from_slice(act.next_unchecked()),
+                    Data::copy_from_slice(act.next_unchecked()),
+                )
+            };
+            kve.getset(key, value)
+        };
+        match older {
+            Ok(Some(older)) => unsafe {
+                // SAFETY: The older value was validated when it was set
+                writer::write_raw_mono(con, kve.get_vt(), &older).await
+            },
+            Ok(None) => conwrite!(con, groups::NIL),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
@@ -103,15 +103,20 @@ pub(super) fn snapshot_and_del(
         // guarantee upholded: consistency
         if iter_stat_ok {
             // nice, all keys exist; let's plonk 'em
-            let kve = kve;
             let lowtable = kve.__get_inner_ref();
             act.zip(snapshots).for_each(|(key, snapshot)| {
                 // the check is very important: some thread may have updated the
                 // value after we snapshotted it. In that case, let this key
                 // be whatever the "newer" value is. Since our snapshot is a "happens-before"
                 // thing, this is absolutely fine
-                let _ = lowtable.remove_if(key, |_, val| val.eq(&snapshot));
+                if lowtable
+                    .remove_if(key, |_, val| val.data().eq(&snapshot))
+                    .is_some()
+                {
+                    kve.account(0, snapshot.len());
+                }
             });
+            kve.touch();
             StrongActionResult::Okay
         } else {
             StrongActionResult::Nil
@@ -158,15 +163,20 @@ pub(super) fn snapshot_and_del_test(
         // guarantee upholded: consistency
         if iter_stat_ok {
             // nice, all keys exist; let's plonk 'em
-            let kve = kve;
             let lowtable = kve.__get_inner_ref();
             act.zip(snapshots).for_each(|(key, snapshot)| {
                 // the check is very important: some thread may have updated the
                 // value after we snapshotted it. In that case, let this key
                 // be whatever the "newer" value is. Since our snapshot is a "happens-before"
                 // thing, this is absolutely fine
-                let _ = lowtable.remove_if(&key, |_, val| val.eq(&snapshot));
+                if lowtable
+                    .remove_if(&key, |_, val| val.data().eq(&snapshot))
+                    .is_some()
+                {
+                    kve.account(0, snapshot.len());
+                }
             });
+            kve.touch();
             StrongActionResult::Okay
         } else {
             StrongActionResult::Nil
@@ -45,6 +45,7 @@ action! {
         if registry::state_okay() {
             let encoder = kve.get_encoder();
             let outcome = {
+                quota_check!(con, handle, kve.write_size(act.clone()));
                 self::snapshot_and_insert(kve, encoder, act)
             };
             match outcome {
@@ -99,15 +100,16 @@ pub(super) fn snapshot_and_insert(
     if registry::state_okay() {
         if key_iter_stat_ok {
             let _kve = kve;
-            let lowtable = lowtable;
             // fine, the keys were non-existent when we looked at them
             while let (Some(key), Some(value)) = (act.next(), act.next()) {
                 if let Some(fresh) = lowtable.fresh_entry(Data::copy_from_slice(key)) {
-                    fresh.insert(Data::copy_from_slice(value));
+                    kve.account(value.len(), 0);
+                    fresh.insert(kve.versioned(Data::copy_from_slice(value)));
                 }
                 // we don't care if some other thread initialized the value we checked
                 // it. We expected a fresh entry, so that's what we'll check and use
             }
+            kve.touch();
             StrongActionResult::Okay
         } else {
             StrongActionResult::OverwriteError
@@ -150,15 +152,16 @@ pub(super) fn snapshot_and_insert_test(
     if registry::state_okay() {
         if key_iter_stat_ok {
             let _kve = kve;
-            let lowtable = lowtable;
             // fine, the keys were non-existent when we looked at them
             while let (Some(key), Some(value)) = (act.next(), act.next()) {
                 if let Some(fresh) = lowtable.fresh_entry(Data::from(key)) {
-                    fresh.insert(Data::from(value));
+                    kve.account(value.len(), 0);
+                    fresh.insert(kve.versioned(Data::from(value)));
                 }
                 // we don't care if some other thread initialized the value we checked
                 // it. We expected a fresh entry, so that's what we'll check and use
             }
+            kve.touch();
             StrongActionResult::Okay
         } else {
             StrongActionResult::OverwriteError
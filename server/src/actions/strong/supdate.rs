@@ -45,6 +45,7 @@ action! {
         if registry::state_okay() {
             let encoder = kve.get_encoder();
             let outcome = {
+                quota_check!(con, handle, kve.write_size(act.clone()));
                 self::snapshot_and_update(kve, encoder, act)
             };
             match outcome {
@@ -109,7 +110,6 @@ pub(super) fn snapshot_and_update(
     if registry::state_okay() {
         // uphold consistency
         if iter_stat_ok {
-            let kve = kve;
             // good, so all the values existed when we snapshotted them; let's update 'em
             let mut snap_cc = snapshots.into_iter();
             let lowtable = kve.__get_inner_ref();
@@ -119,13 +119,15 @@ pub(super) fn snapshot_and_update(
                 // When we snapshotted, we looked at `snapshot`. If the value is still the
                 // same, then we'll update it. Otherwise, let it be
                 if let Some(mut mutable) = lowtable.mut_entry(Data::copy_from_slice(key)) {
-                    if mutable.value().eq(&snapshot) {
-                        mutable.insert(Data::copy_from_slice(value));
+                    if mutable.value().data().eq(&snapshot) {
+                        kve.account(value.len(), snapshot.len());
+                        mutable.insert(kve.versioned(Data::copy_from_slice(value)));
                     } else {
                         drop(mutable);
                     }
                 }
             }
+            kve.touch();
             StrongActionResult::Okay
         } else {
             StrongActionResult::Nil
@@ -175,7 +177,6 @@ pub(super) fn snapshot_and_update_test(
     if registry::state_okay() {
         // uphold consistency
         if iter_stat_ok {
-            let kve = kve;
             // good, so all the values existed when we snapshotted them; let's update 'em
             let mut snap_cc = snapshots.into_iter();
             let lowtable = kve.__get_inner_ref();
@@ -185,13 +186,15 @@ pub(super) fn snapshot_and_update_test(
                 // When we snapshotted, we looked at `snapshot`. If the value is still the
                 // same, then we'll update it. Otherwise, let it be
                 if let Some(mut mutable) = lowtable.mut_entry(Data::from(key)) {
-                    if mutable.value().eq(&snapshot) {
-                        mutable.insert(Data::from(value));
+                    if mutable.value().data().eq(&snapshot) {
+                        kve.account(value.len(), snapshot.len());
+                        mutable.insert(kve.versioned(Data::from(value)));
                     } else {
                         drop(mutable);
                     }
                 }
             }
+            kve.touch();
             StrongActionResult::Okay
         } else {
             StrongActionResult::Nil
@@ -0,0 +1,261 @@
+/*
+ * Created on Sat Sep 25 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Time series actions
+//!
+//! Actions for time series tables, where every key maps to an append-only series of samples.
+//! Timestamps are UNIX timestamps in milliseconds while values are finite floats (returned
+//! as strings). Ranges are inclusive, with `-` and `+` standing for the oldest and the
+//! newest possible timestamp
+
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::timeseries::Aggregation;
+use crate::kvengine::timeseries::Sample;
+use crate::kvengine::types;
+use crate::registry::stats;
+use crate::resp::writer;
+use crate::resp::TSYMBOL_UNICODE;
+use crate::util::compiler;
+
+/// Parse an unsigned integer (like a timestamp or a duration)
+fn parse_uint(bytes: &[u8]) -> Option<u64> {
+    if types::is_uint(bytes) {
+        // validated uints are ASCII, so this is valid UTF-8
+        unsafe { core::str::from_utf8_unchecked(bytes) }
+            .parse()
+            .ok()
+    } else {
+        None
+    }
+}
+
+/// Parse a sample value, which has to be a finite float
+fn parse_value(bytes: &[u8]) -> Option<f64> {
+    if types::is_float(bytes) {
+        // validated floats are ASCII, so this is valid UTF-8
+        unsafe { core::str::from_utf8_unchecked(bytes) }
+            .parse()
+            .ok()
+    } else {
+        None
+    }
+}
+
+/// Parse the bounds of a range: `<from> <to>`, where `-` and `+` are open bounds
+fn parse_range(from: &[u8], to: &[u8]) -> Option<(u64, u64)> {
+    let from = match from {
+        b"-" => 0,
+        _ => parse_uint(from)?,
+    };
+    let to = match to {
+        b"+" => u64::MAX,
+        _ => parse_uint(to)?,
+    };
+    Some((from, to))
+}
+
+/// Write a value as a string (Skyhash has no float type)
+async fn write_value<'a, T, Strm>(con: &'a mut T, value: f64) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    unsafe {
+        // SAFETY: The formatted value is valid unicode
+        writer::write_raw_mono(con, TSYMBOL_UNICODE, &Data::from(value.to_string())).await
+    }
+}
+
+/// Write the given samples as an array of two arrays: the timestamps and their values (in
+/// the same order)
+async fn write_samples<'a, T, Strm>(con: &'a mut T, samples: Vec<Sample>) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    con.write_array_length(2).await?;
+    con.write_array_length(samples.len()).await?;
+    for (timestamp, _) in samples.iter() {
+        conwrite!(con, *timestamp)?;
+    }
+    con.write_array_length(samples.len()).await?;
+    for (_, value) in samples {
+        write_value(con, value).await?;
+    }
+    Ok(())
+}
+
+action!(
+    /// Run a `TSCREATE` query: `TSCREATE <key> <retention>`. This creates an empty series
+    /// that only keeps the samples of the last `<retention>` milliseconds (or all of them
+    /// if `<retention>` is `0`) and returns an overwrite error if the key already exists
+    fn tscreate(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let ts = tsstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let (key, retention) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+        let retention = match parse_uint(retention) {
+            Some(retention) => retention,
+            None => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        let created = {
+            quota_check!(con, handle, ts.write_size(key, 0));
+            ts.create(Data::copy_from_slice(key), retention)
+        };
+        match created {
+            Ok(true) => conwrite!(con, groups::OKAY),
+            Ok(false) => conwrite!(con, groups::OVERWRITE_ERR),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `TSADD` query: `TSADD <key> <timestamp> <value>`, where a `*` timestamp is the
+    /// current server time. The key is created (keeping all samples) if it doesn't exist and
+    /// samples that aren't newer than the newest sample of the series are rejected
+    fn tsadd(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let ts = tsstore!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let (key, timestamp, value) = unsafe {
+            (
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+            )
+        };
+        let timestamp = match timestamp {
+            b"*" => Some(stats::unix_now_millis()),
+            _ => parse_uint(timestamp),
+        };
+        let (timestamp, value) = match (timestamp, parse_value(value)) {
+            (Some(timestamp), Some(value)) => (timestamp, value),
+            _ => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        let added = {
+            quota_check!(con, handle, ts.write_size(key, 1));
+            ts.add_sample(Data::copy_from_slice(key), timestamp, value)
+        };
+        match added {
+            Ok(true) => conwrite!(con, groups::OKAY),
+            Ok(false) => conwrite!(con, groups::OUT_OF_ORDER_SAMPLE),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `TSGET` query: `TSGET <key>`. This returns the timestamp and the value of the
+    /// newest sample as an array, or nil if the series is empty or doesn't exist
+    fn tsget(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let ts = tsstore!(con, handle);
+        match ts.last_sample(unsafe { act.next_unchecked() }) {
+            Ok(Some((timestamp, value))) => {
+                con.write_array_length(2).await?;
+                conwrite!(con, timestamp)?;
+                write_value(con, value).await
+            }
+            Ok(None) => conwrite!(con, groups::NIL),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `TSLEN` query: `TSLEN <key>`. This returns the number of samples in the
+    /// series, which is `0` if the key doesn't exist
+    fn tslen(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let ts = tsstore!(con, handle);
+        match ts.count_samples(unsafe { act.next_unchecked() }) {
+            Ok(count) => conwrite!(con, count),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `TSRANGE` query: `TSRANGE <key> <from> <to>`. This returns an array of two
+    /// arrays: the timestamps of the samples in the range (oldest first) and their values
+    fn tsrange(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let ts = tsstore!(con, handle);
+        let (key, from, to) = unsafe {
+            (
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+            )
+        };
+        let (from, to) = match parse_range(from, to) {
+            Some(range) => range,
+            None => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        match ts.range(key, from, to) {
+            Ok(samples) => write_samples(con, samples).await,
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `TSAGG` query: `TSAGG <key> <from> <to> <min|max|avg|sum> <bucket>`. This
+    /// downsamples the samples in the range into buckets of `<bucket>` milliseconds and
+    /// returns an array of two arrays: the start of every bucket that has samples (oldest
+    /// first) and the aggregated value of its samples
+    fn tsagg(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 5);
+        let ts = tsstore!(con, handle);
+        let (key, from, to, aggregation, bucket) = unsafe {
+            (
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+            )
+        };
+        let aggregation = match Aggregation::from_name(&aggregation.to_ascii_lowercase()) {
+            Some(aggregation) => aggregation,
+            None => return conwrite!(con, groups::ACTION_ERR),
+        };
+        let (from, to, bucket) = match (parse_range(from, to), parse_uint(bucket)) {
+            (Some((from, to)), Some(bucket)) if bucket != 0 => (from, to, bucket),
+            _ => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        match ts.aggregate(key, from, to, aggregation, bucket) {
+            Ok(buckets) => write_samples(con, buckets).await,
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
@@ -54,8 +54,14 @@ action!(
         };
         if compiler::likely(encoding_is_okay) {
             if registry::state_okay() {
-                while let (Some(key), Some(val)) = (act.next(), act.next()) {
-                    kve.upsert_unchecked(Data::copy_from_slice(key), Data::copy_from_slice(val));
+                {
+                    quota_check!(con, handle, kve.write_size(act.clone()));
+                    while let (Some(key), Some(val)) = (act.next(), act.next()) {
+                        kve.upsert_unchecked(
+                            Data::copy_from_slice(key),
+                            Data::copy_from_slice(val),
+                        );
+                    }
                 }
                 conwrite!(con, howmany / 2)
             } else {
@@ -0,0 +1,130 @@
+/*
+ * Created on Tue Sep 28 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Versioned actions
+//!
+//! Every value of a key/value table has a version which changes with every write to the
+//! key. `GETV` returns the version along with the value, and `UPDATEV` and `DELV` only
+//! write if the key is still at the version that the client expects (so that clients can
+//! do a read-modify-write without losing the writes of others in between)
+
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::VersionCheck;
+use crate::resp::writer;
+use crate::util::compiler;
+
+/// Returns the response for the outcome of a conditional write
+const fn version_check(check: VersionCheck) -> &'static [u8] {
+    match check {
+        VersionCheck::Okay => groups::OKAY,
+        VersionCheck::Mismatch => groups::VERSION_MISMATCH,
+        VersionCheck::Nil => groups::NIL,
+    }
+}
+
+/// Parse an expected version
+fn parse_version(version: &[u8]) -> Option<u64> {
+    String::from_utf8_lossy(version).parse::<u64>().ok()
+}
+
+action!(
+    /// Run a `GETV` query: `GETV <key>`. This returns an array with the value of the key
+    /// and its version, or nil if the key doesn't exist
+    fn getv(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let kve = kve!(con, handle);
+        match kve.get_versioned(unsafe { act.next_unchecked() }) {
+            Ok(Some(versioned)) => {
+                con.write_array_length(2).await?;
+                unsafe {
+                    // SAFETY: The value was validated when it was set
+                    writer::write_raw_mono(con, kve.get_vt(), versioned.data()).await?;
+                }
+                conwrite!(con, versioned.version())
+            }
+            Ok(None) => conwrite!(con, groups::NIL),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run an `UPDATEV` query: `UPDATEV <key> <value> <version>`. This updates the value
+    /// of the key only if it is still at the given version
+    fn updatev(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let args = act.clone();
+        let (key, value, version) = unsafe {
+            (
+                act.next_unchecked(),
+                act.next_unchecked(),
+                act.next_unchecked(),
+            )
+        };
+        let version = match parse_version(version) {
+            Some(version) => version,
+            None => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        let check = {
+            quota_check!(con, handle, kve.write_size(args));
+            kve.update_if_version(
+                Data::copy_from_slice(key),
+                Data::copy_from_slice(value),
+                version,
+            )
+        };
+        match check {
+            Ok(check) => conwrite!(con, version_check(check)),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
+
+action!(
+    /// Run a `DELV` query: `DELV <key> <version>`. This removes the key only if it is
+    /// still at the given version
+    fn delv(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 2);
+        let kve = kve!(con, handle);
+        if !registry::state_okay() {
+            return conwrite!(con, groups::SERVER_ERR);
+        }
+        let (key, version) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+        let version = match parse_version(version) {
+            Some(version) => version,
+            None => return conwrite!(con, groups::WRONGTYPE_ERR),
+        };
+        match kve.remove_if_version(Data::copy_from_slice(key), version) {
+            Ok(check) => conwrite!(con, version_check(check)),
+            Err(()) => compiler::cold_err(conwrite!(con, groups::ENCODING_ERROR)),
+        }
+    }
+);
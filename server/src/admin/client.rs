@@ -0,0 +1,98 @@
+/*
+ * Created on Thu Sep 09 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The `CLIENT` action
+//!
+//! `CLIENT` lets one inspect the live connections, name the current connection or
+//! disconnect a client (see [`crate::registry::clients`])
+
+use crate::dbnet::connection::prelude::*;
+use crate::registry::clients;
+use crate::resp::writer::TypedArrayWriter;
+
+const LIST: &[u8] = "LIST".as_bytes();
+const SETNAME: &[u8] = "SETNAME".as_bytes();
+const KILL: &[u8] = "KILL".as_bytes();
+
+action! {
+    /// Runs a `CLIENT` query:
+    /// - `CLIENT LIST` returns a line for every connected client
+    /// - `CLIENT SETNAME <name>` names the current connection
+    /// - `CLIENT KILL <id>` disconnects the client with the given ID
+    fn client(_handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, eq 0);
+        let mut subcommand = unsafe {
+            // SAFETY: Already checked len
+            act.next_unchecked()
+        }.to_vec();
+        subcommand.make_ascii_uppercase();
+        match subcommand.as_ref() {
+            LIST => {
+                err_if_len_is!(act, con, not 0);
+                let clients = clients::list();
+                let mut writer = unsafe {
+                    TypedArrayWriter::new(con, b'+', clients.len())
+                }.await?;
+                for client in clients {
+                    writer.write_element(client.to_string()).await?;
+                }
+            }
+            SETNAME => {
+                err_if_len_is!(act, con, not 1);
+                let name = unsafe {
+                    // SAFETY: Already checked len
+                    act.next_unchecked()
+                };
+                match String::from_utf8(name.to_vec()) {
+                    Ok(name) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                        con.get_client().set_name(name);
+                        conwrite!(con, groups::OKAY)?;
+                    }
+                    _ => conwrite!(con, groups::BAD_CLIENT_NAME)?,
+                }
+            }
+            KILL => {
+                err_if_len_is!(act, con, not 1);
+                let id = unsafe {
+                    // SAFETY: Already checked len
+                    act.next_unchecked()
+                };
+                match String::from_utf8_lossy(id).parse::<u64>() {
+                    Ok(id) => {
+                        if clients::kill(id) {
+                            conwrite!(con, groups::OKAY)?;
+                        } else {
+                            conwrite!(con, groups::NIL)?;
+                        }
+                    }
+                    Err(_) => conwrite!(con, groups::WRONGTYPE_ERR)?,
+                }
+            }
+            _ => conwrite!(con, groups::UNKNOWN_CLIENT_QUERY)?,
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * Created on Sun Sep 12 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The `CONFIG` action
+//!
+//! `CONFIG` reads and changes runtime settings, and writes them back to the configuration
+//! file (see [`crate::config::runtime`])
+
+use crate::config::runtime::{self, RuntimeConfigError};
+use crate::dbnet::connection::prelude::*;
+use crate::resp::BytesWrapper;
+use bytes::Bytes;
+
+const GET: &[u8] = "GET".as_bytes();
+const SET: &[u8] = "SET".as_bytes();
+const REWRITE: &[u8] = "REWRITE".as_bytes();
+
+action! {
+    /// Runs a `CONFIG` query:
+    /// - `CONFIG GET <key>` returns the current value of a setting
+    /// - `CONFIG SET <key> <value>` changes a setting
+    /// - `CONFIG REWRITE` writes the current settings to the configuration file
+    fn config(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, eq 0);
+        let mut subcommand = unsafe {
+            // SAFETY: Already checked len
+            act.next_unchecked()
+        }.to_vec();
+        subcommand.make_ascii_uppercase();
+        match subcommand.as_ref() {
+            GET => {
+                err_if_len_is!(act, con, not 1);
+                let key = unsafe {
+                    // SAFETY: Already checked len
+                    act.next_unchecked()
+                };
+                let key = String::from_utf8_lossy(key).to_ascii_lowercase();
+                match runtime::get(handle.get_engine(), &key) {
+                    Ok(value) => con.write_response(BytesWrapper(Bytes::from(value))).await?,
+                    Err(e) => conwrite!(con, error_response(e))?,
+                }
+            }
+            SET => {
+                err_if_len_is!(act, con, not 2);
+                let (key, value) = unsafe {
+                    // SAFETY: Already checked len
+                    (act.next_unchecked(), act.next_unchecked())
+                };
+                let key = String::from_utf8_lossy(key).to_ascii_lowercase();
+                match runtime::set(handle.get_engine(), &key, &String::from_utf8_lossy(value)) {
+                    Ok(()) => conwrite!(con, groups::OKAY)?,
+                    Err(e) => conwrite!(con, error_response(e))?,
+                }
+            }
+            REWRITE => {
+                err_if_len_is!(act, con, not 0);
+                match runtime::rewrite(handle.get_engine()) {
+                    Ok(()) => conwrite!(con, groups::OKAY)?,
+                    Err(e) => {
+                        log::error!("Failed to rewrite configuration: {}", e);
+                        conwrite!(con, groups::REWRITE_FAILED)?;
+                    }
+                }
+            }
+            _ => conwrite!(con, groups::UNKNOWN_CONFIG_QUERY)?,
+        }
+        Ok(())
+    }
+}
+
+/// Returns the response for a [`RuntimeConfigError`]
+const fn error_response(e: RuntimeConfigError) -> &'static [u8] {
+    match e {
+        RuntimeConfigError::UnknownKey => groups::UNKNOWN_CONFIG_KEY,
+        RuntimeConfigError::BadValue => groups::BAD_CONFIG_VALUE,
+        RuntimeConfigError::SnapshotDisabled => groups::SNAPSHOT_DISABLED,
+    }
+}
@@ -26,4 +26,8 @@
 
 //! Modules for administration of Skytable
 
+pub mod client;
+pub mod config;
 pub mod mksnap;
+pub mod slowlog;
+pub mod sys;
@@ -0,0 +1,83 @@
+/*
+ * Created on Wed Sep 08 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The `SLOWLOG` action
+//!
+//! `SLOWLOG` reads or clears the slow query log (see [`crate::registry::slowlog`])
+
+use crate::dbnet::connection::prelude::*;
+use crate::registry::slowlog;
+use crate::resp::writer::TypedArrayWriter;
+
+const GET: &[u8] = "GET".as_bytes();
+const LEN: &[u8] = "LEN".as_bytes();
+const RESET: &[u8] = "RESET".as_bytes();
+const DEFAULT_COUNT: usize = 10;
+
+action! {
+    /// Runs a `SLOWLOG` query:
+    /// - `SLOWLOG GET [count]` returns the `count` (default 10) most recent entries
+    /// - `SLOWLOG LEN` returns the number of entries
+    /// - `SLOWLOG RESET` clears the slowlog
+    fn slowlog(_handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, eq 0);
+        let mut subcommand = unsafe {
+            // SAFETY: Already checked len
+            act.next_unchecked()
+        }.to_vec();
+        subcommand.make_ascii_uppercase();
+        match subcommand.as_ref() {
+            GET => {
+                err_if_len_is!(act, con, gt 1);
+                let count = match act.next() {
+                    Some(count) => match String::from_utf8_lossy(count).parse::<usize>() {
+                        Ok(count) => count,
+                        Err(_) => return conwrite!(con, groups::WRONGTYPE_ERR),
+                    },
+                    None => DEFAULT_COUNT,
+                };
+                let entries = slowlog::get(count);
+                let mut writer = unsafe {
+                    TypedArrayWriter::new(con, b'+', entries.len())
+                }.await?;
+                for entry in entries {
+                    writer.write_element(entry.to_string()).await?;
+                }
+            }
+            LEN => {
+                err_if_len_is!(act, con, not 0);
+                conwrite!(con, slowlog::len())?;
+            }
+            RESET => {
+                err_if_len_is!(act, con, not 0);
+                slowlog::reset();
+                conwrite!(con, groups::OKAY)?;
+            }
+            _ => conwrite!(con, groups::UNKNOWN_SLOWLOG_QUERY)?,
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+/*
+ * Created on Mon Sep 06 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The `SYS` action
+//!
+//! `SYS INFO` reports server statistics grouped into sections. Each line is a
+//! `key:value` pair and every section is preceded by a `# <section>` line. `SYS RELOAD`
+//! re-reads the configuration file (see [`crate::services::reload`])
+
+use crate::dbnet::connection::prelude::*;
+use crate::registry::stats;
+use crate::resp::writer::TypedArrayWriter;
+use crate::services::reload;
+use core::fmt::Display;
+
+const INFO: &[u8] = "INFO".as_bytes();
+const RELOAD: &[u8] = "RELOAD".as_bytes();
+const SERVER: &[u8] = "SERVER".as_bytes();
+const CLIENTS: &[u8] = "CLIENTS".as_bytes();
+const PERSISTENCE: &[u8] = "PERSISTENCE".as_bytes();
+const MEMORY: &[u8] = "MEMORY".as_bytes();
+const KEYSPACES: &[u8] = "KEYSPACES".as_bytes();
+
+action! {
+    /// Runs a `SYS` query:
+    /// - `SYS INFO` returns all the sections
+    /// - `SYS INFO <section>` returns a single section
+    /// - `SYS RELOAD` reloads the configuration file
+    fn sys(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        match act.next() {
+            Some(sys_what) => {
+                let mut sys_what = sys_what.to_vec();
+                sys_what.make_ascii_uppercase();
+                match sys_what.as_ref() {
+                    INFO => sys_info(handle, con, act).await?,
+                    RELOAD => {
+                        err_if_len_is!(act, con, not 0);
+                        match reload::reload(handle) {
+                            Ok(()) => conwrite!(con, groups::OKAY)?,
+                            Err(e) => {
+                                log::error!("Failed to reload configuration: {}", e);
+                                conwrite!(con, groups::RELOAD_FAILED)?;
+                            }
+                        }
+                    }
+                    _ => conwrite!(con, groups::UNKNOWN_SYS_QUERY)?,
+                }
+            }
+            None => aerr!(con, aerr),
+        }
+        Ok(())
+    }
+}
+
+action! {
+    /// Run `SYS INFO [section]`
+    fn sys_info(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, gt 1);
+        let mut lines = Vec::new();
+        match act.next() {
+            Some(section) => {
+                let mut section = section.to_vec();
+                section.make_ascii_uppercase();
+                match section.as_ref() {
+                    SERVER => info_server(&mut lines),
+                    CLIENTS => info_clients(&mut lines),
+                    PERSISTENCE => info_persistence(handle, &mut lines),
+                    MEMORY => info_memory(handle, &mut lines),
+                    KEYSPACES => info_keyspaces(handle, &mut lines),
+                    _ => return conwrite!(con, groups::UNKNOWN_SYS_QUERY),
+                }
+            }
+            None => {
+                info_server(&mut lines);
+                info_clients(&mut lines);
+                info_persistence(handle, &mut lines);
+                info_memory(handle, &mut lines);
+                info_keyspaces(handle, &mut lines);
+            }
+        }
+        let mut writer = unsafe {
+            TypedArrayWriter::new(con, b'+', lines.len())
+        }.await?;
+        for line in lines {
+            writer.write_element(line).await?;
+        }
+        Ok(())
+    }
+}
+
+fn push_line(lines: &mut Vec<String>, key: &str, value: impl Display) {
+    lines.push(format!("{}:{}", key, value));
+}
+
+fn info_server(lines: &mut Vec<String>) {
+    lines.push("# server".to_owned());
+    push_line(lines, "version", libsky::VERSION);
+    push_line(lines, "started_at", stats::start_time());
+    push_line(lines, "uptime", stats::uptime());
+    push_line(lines, "commands_processed", stats::commands_processed());
+    push_line(lines, "bytes_in", stats::bytes_in());
+    push_line(lines, "bytes_out", stats::bytes_out());
+}
+
+fn info_clients(lines: &mut Vec<String>) {
+    lines.push("# clients".to_owned());
+    push_line(lines, "connected_clients", stats::active_connections());
+    push_line(lines, "max_clients", stats::max_connections());
+    push_line(lines, "total_connections", stats::total_connections());
+}
+
+fn info_persistence(handle: &Corestore, lines: &mut Vec<String>) {
+    lines.push("# persistence".to_owned());
+    push_line(lines, "poisoned", !registry::state_okay());
+    let bgsave_status = if stats::bgsave_runs() == 0 {
+        "none"
+    } else if stats::bgsave_last_okay() {
+        "ok"
+    } else {
+        "err"
+    };
+    push_line(lines, "bgsave_last_status", bgsave_status);
+    push_line(lines, "bgsave_last_time", stats::bgsave_last_time());
+    push_line(lines, "bgsave_runs", stats::bgsave_runs());
+    push_line(lines, "bgsave_failures", stats::bgsave_failures());
+    let queue = handle.get_engine().queue_state();
+    push_line(lines, "snapshot_enabled", queue.enabled);
+    push_line(lines, "snapshot_busy", queue.busy);
+    push_line(lines, "snapshot_queue_len", queue.len);
+    push_line(lines, "snapshot_queue_max", queue.maxlen);
+    push_line(lines, "snapshot_last_time", stats::snapshot_last_time());
+    push_line(lines, "snapshots_created", stats::snapshots_created());
+    push_line(lines, "snapshots_failed", stats::snapshots_failed());
+}
+
+fn info_memory(handle: &Corestore, lines: &mut Vec<String>) {
+    lines.push("# memory".to_owned());
+    let used: usize = handle
+        .get_store()
+        .keyspaces
+        .iter()
+        .map(|ks| {
+            ks.value()
+                .tables
+                .iter()
+                .map(|tbl| tbl.value().approx_mem_usage())
+                .sum::<usize>()
+        })
+        .sum();
+    push_line(lines, "used_memory_approx", used);
+}
+
+fn info_keyspaces(handle: &Corestore, lines: &mut Vec<String>) {
+    lines.push("# keyspaces".to_owned());
+    let store = handle.get_store();
+    push_line(lines, "keyspaces", store.keyspaces.len());
+    for ks in store.keyspaces.iter() {
+        let tables = &ks.value().tables;
+        let keys: usize = tables.iter().map(|tbl| tbl.value().count()).sum();
+        push_line(
+            lines,
+            &String::from_utf8_lossy(ks.key()),
+            format_args!("tables={},keys={}", tables.len(), keys),
+        );
+    }
+}
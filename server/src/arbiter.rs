@@ -24,14 +24,15 @@
  *
 */
 
-use crate::config::BGSave;
+use crate::config::ParsedConfig;
 use crate::config::SnapshotConfig;
 use crate::config::SnapshotPref;
 use crate::corestore::Corestore;
 use crate::dbnet::{self, Terminator};
+use crate::registry::slowlog;
+use crate::registry::stats;
 use crate::services;
 use crate::storage::sengine::SnapshotEngine;
-use crate::PortConfig;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 
@@ -65,12 +66,23 @@ impl Future for UnixTerminationSignal {
 
 /// Start the server waiting for incoming connections or a termsig
 pub async fn run(
-    ports: PortConfig,
-    bgsave_cfg: BGSave,
-    snapshot_cfg: SnapshotConfig,
+    cfg: ParsedConfig,
     _restore_filepath: Option<String>,
-    maxcon: usize,
 ) -> Result<Corestore, String> {
+    let ParsedConfig {
+        ports,
+        bgsave: bgsave_cfg,
+        snapshot: snapshot_cfg,
+        maxcon,
+        metrics: metrics_cfg,
+        slowlog: slowlog_cfg,
+        limits: limits_cfg,
+        ..
+    } = cfg;
+    stats::init(maxcon);
+    services::bgsave::configure(&bgsave_cfg);
+    slowlog::init(&slowlog_cfg);
+    dbnet::limits::init(&limits_cfg);
     // Intialize the broadcast channel
     let (signal, _) = broadcast::channel(1);
     let engine;
@@ -92,7 +104,6 @@ pub async fn run(
     // initialize the background services
     let bgsave_handle = tokio::spawn(services::bgsave::bgsave_scheduler(
         db.clone(),
-        bgsave_cfg,
         Terminator::new(signal.subscribe()),
     ));
     let snapshot_handle = tokio::spawn(services::snapshot::snapshot_service(
@@ -101,6 +112,19 @@ pub async fn run(
         snapshot_cfg,
         Terminator::new(signal.subscribe()),
     ));
+    let metrics_listener = services::metrics::bind(&metrics_cfg)
+        .await
+        .map_err(|e| format!("Failed to bind to metrics port: {}", e))?;
+    let metrics_handle = tokio::spawn(services::metrics::metrics_service(
+        db.clone(),
+        metrics_listener,
+        Terminator::new(signal.subscribe()),
+    ));
+    #[cfg(unix)]
+    let reload_handle = tokio::spawn(services::reload::sighup_service(
+        db.clone(),
+        Terminator::new(signal.subscribe()),
+    ));
 
     // bind the ctrlc handler
     let sig = tokio::signal::ctrl_c();
@@ -121,8 +145,8 @@ pub async fn run(
     #[cfg(unix)]
     {
         let sigterm = UnixTerminationSignal::init()?;
-        // apart from CTRLC, the only other thing we care about is SIGTERM
-        // FIXME(@ohsayan): Maybe we should respond to SIGHUP too?
+        // apart from CTRLC, the only other thing we care about is SIGTERM (SIGHUP is
+        // handled by the reload service)
         tokio::select! {
             _ = server.run_server() => {},
             _ = sig => {},
@@ -138,5 +162,8 @@ pub async fn run(
     // wait for the background services to terminate
     let _ = snapshot_handle.await;
     let _ = bgsave_handle.await;
+    let _ = metrics_handle.await;
+    #[cfg(unix)]
+    let _ = reload_handle.await;
     Ok(db)
 }
@@ -29,6 +29,7 @@
 use crate::dbnet::MAXIMUM_CONNECTION_LIMIT;
 #[cfg(test)]
 use libsky::TResult;
+use parking_lot::{const_mutex, Mutex};
 use serde::Deserialize;
 use std::error::Error;
 use std::fmt;
@@ -36,11 +37,16 @@ use std::fs;
 #[cfg(test)]
 use std::net::Ipv6Addr;
 use std::net::{IpAddr, Ipv4Addr};
+pub mod runtime;
 const DEFAULT_IPV4: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
 #[cfg(test)]
 const DEFAULT_PORT: u16 = 2003;
 const DEFAULT_SSL_PORT: u16 = 2004;
 
+/// The configuration file that the server was started with (if any). This is the file that is
+/// re-read when the configuration is reloaded
+static CONFIG_FILE: Mutex<Option<String>> = const_mutex(None);
+
 /// This struct is an _object representation_ used for parsing the TOML file
 #[derive(Deserialize, Debug, PartialEq)]
 pub struct Config {
@@ -52,6 +58,12 @@ pub struct Config {
     snapshot: Option<ConfigKeySnapshot>,
     /// SSL configuration
     ssl: Option<KeySslOpts>,
+    /// The metrics key
+    metrics: Option<ConfigKeyMetrics>,
+    /// The slowlog key
+    slowlog: Option<ConfigKeySlowlog>,
+    /// The limits key
+    limits: Option<ConfigKeyLimits>,
 }
 
 /// The BGSAVE section in the config file
@@ -128,15 +140,123 @@ pub struct ConfigKeySnapshot {
     failsafe: Option<bool>,
 }
 
+/// The metrics section in the TOML file
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct ConfigKeyMetrics {
+    /// The local port on which the metrics endpoint will listen
+    port: u16,
+}
+
+/// The metrics exporter configuration
+///
+/// If the exporter is enabled, the `Enabled` variant carries the local port to
+/// which the HTTP endpoint binds
+#[derive(Debug, PartialEq)]
+pub enum MetricsConfig {
+    Enabled(u16),
+    Disabled,
+}
+
+impl MetricsConfig {
+    /// The metrics exporter is disabled by default
+    pub const fn default() -> Self {
+        MetricsConfig::Disabled
+    }
+}
+
+/// The slowlog section in the TOML file
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct ConfigKeySlowlog {
+    /// Queries that take at least these many microseconds are logged
+    threshold: Option<u64>,
+    /// The maximum number of entries to keep. If this is set to `0`, the slowlog is disabled
+    maxlen: Option<usize>,
+    /// Whether the entries should also be written to the server log
+    log: Option<bool>,
+}
+
+/// The slowlog configuration
+#[derive(Debug, PartialEq)]
+pub struct SlowlogConfig {
+    /// The threshold in microseconds
+    pub threshold: u64,
+    /// The maximum number of entries in the slowlog
+    pub maxlen: usize,
+    /// Write entries to the server log
+    pub log: bool,
+}
+
+impl SlowlogConfig {
+    /// Create a new slowlog configuration with all the fields
+    pub const fn new(threshold: u64, maxlen: usize, log: bool) -> Self {
+        SlowlogConfig {
+            threshold,
+            maxlen,
+            log,
+        }
+    }
+    /// The default slowlog configuration
+    ///
+    /// Defaults:
+    /// - `threshold`: 10000 (10ms)
+    /// - `maxlen`: 128
+    /// - `log`: false
+    pub const fn default() -> Self {
+        SlowlogConfig::new(10_000, 128, false)
+    }
+}
+
+/// The limits section in the TOML file
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct ConfigKeyLimits {
+    /// The maximum size of a query packet in bytes
+    maxquerysize: Option<usize>,
+    /// The maximum number of elements in an array (or queries in a pipeline)
+    maxelements: Option<usize>,
+    /// The number of seconds after which an idle connection is closed. `0` disables the timeout
+    idletimeout: Option<u64>,
+}
+
+/// Limits that are applied to every connection
+#[derive(Debug, PartialEq)]
+pub struct LimitsConfig {
+    /// The maximum size of a query packet in bytes
+    pub max_query_size: usize,
+    /// The maximum number of elements in an array
+    pub max_elements: usize,
+    /// The idle timeout in seconds (`0` if disabled)
+    pub idle_timeout: u64,
+}
+
+impl LimitsConfig {
+    /// Create a new limits configuration with all the fields
+    pub const fn new(max_query_size: usize, max_elements: usize, idle_timeout: u64) -> Self {
+        LimitsConfig {
+            max_query_size,
+            max_elements,
+            idle_timeout,
+        }
+    }
+    /// The default limits
+    ///
+    /// Defaults:
+    /// - `max_query_size`: 64 MiB
+    /// - `max_elements`: 1048576
+    /// - `idle_timeout`: 0 (disabled)
+    pub const fn default() -> Self {
+        LimitsConfig::new(64 * 1024 * 1024, 1024 * 1024, 0)
+    }
+}
+
 /// Port configuration
 ///
 /// This enumeration determines whether the ports are:
 /// - `Multi`: This means that the database server will be listening to both
-/// SSL **and** non-SSL requests
+///   SSL **and** non-SSL requests
 /// - `SecureOnly` : This means that the database server will only accept SSL requests
-/// and will not even activate the non-SSL socket
+///   and will not even activate the non-SSL socket
 /// - `InsecureOnly` : This indicates that the server would only accept non-SSL connections
-/// and will not even activate the SSL socket
+///   and will not even activate the SSL socket
 #[derive(Debug, PartialEq)]
 pub enum PortConfig {
     SecureOnly {
@@ -183,6 +303,44 @@ pub struct KeySslOpts {
     port: u16,
     only: Option<bool>,
     passin: Option<String>,
+    /// The CA bundle used to verify client certificates
+    clientca: Option<String>,
+    /// Whether client certificates should be requested and verified
+    verify: Option<TlsVerifyMode>,
+}
+
+/// Client certificate verification mode
+///
+/// - `None`: Client certificates are not requested
+/// - `Optional`: Client certificates are requested and verified if the client sends one
+/// - `Required`: Clients without a valid certificate are rejected
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum TlsVerifyMode {
+    None,
+    Optional,
+    Required,
+}
+
+impl TlsVerifyMode {
+    /// Returns the verification mode for the given client CA bundle: if a CA bundle was
+    /// provided, then client certificates are required by default
+    pub const fn default_for(client_ca: &Option<String>) -> Self {
+        if client_ca.is_some() {
+            TlsVerifyMode::Required
+        } else {
+            TlsVerifyMode::None
+        }
+    }
+    /// Parse a verification mode from a string
+    pub fn from_arg(mode: &str) -> Option<Self> {
+        match mode {
+            "none" => Some(TlsVerifyMode::None),
+            "optional" => Some(TlsVerifyMode::Optional),
+            "required" => Some(TlsVerifyMode::Required),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, PartialEq)]
@@ -191,15 +349,38 @@ pub struct SslOpts {
     pub chain: String,
     pub port: u16,
     pub passfile: Option<String>,
+    /// The CA bundle used to verify client certificates
+    pub client_ca: Option<String>,
+    /// The client certificate verification mode
+    pub verify: TlsVerifyMode,
 }
 
 impl SslOpts {
-    pub const fn new(key: String, chain: String, port: u16, passfile: Option<String>) -> Self {
+    pub const fn new(
+        key: String,
+        chain: String,
+        port: u16,
+        passfile: Option<String>,
+        client_ca: Option<String>,
+        verify: TlsVerifyMode,
+    ) -> Self {
         SslOpts {
             key,
             chain,
             port,
             passfile,
+            client_ca,
+            verify,
+        }
+    }
+    /// Returns an error if client certificates have to be verified but no CA bundle was given
+    pub const fn check_client_ca(&self) -> Result<(), ConfigError> {
+        if self.client_ca.is_none() && !matches!(self.verify, TlsVerifyMode::None) {
+            Err(ConfigError::CfgError(
+                "A client CA bundle is required to verify client certificates",
+            ))
+        } else {
+            Ok(())
         }
     }
 }
@@ -268,6 +449,12 @@ pub struct ParsedConfig {
     pub ports: PortConfig,
     /// The maximum number of connections
     pub maxcon: usize,
+    /// The metrics exporter configuration
+    pub metrics: MetricsConfig,
+    /// The slowlog configuration
+    pub slowlog: SlowlogConfig,
+    /// The connection limits
+    pub limits: LimitsConfig,
 }
 
 impl ParsedConfig {
@@ -309,6 +496,10 @@ impl ParsedConfig {
                 })
                 .unwrap_or_else(SnapshotConfig::default),
             ports: if let Some(sslopts) = cfg_info.ssl {
+                let verify = option_unwrap_or!(
+                    sslopts.verify,
+                    TlsVerifyMode::default_for(&sslopts.clientca)
+                );
                 if option_unwrap_or!(sslopts.only, false) {
                     PortConfig::SecureOnly {
                         ssl: SslOpts {
@@ -316,6 +507,8 @@ impl ParsedConfig {
                             chain: sslopts.chain,
                             port: sslopts.port,
                             passfile: sslopts.passin,
+                            client_ca: sslopts.clientca,
+                            verify,
                         },
                         host: cfg_info.server.host,
                     }
@@ -326,6 +519,8 @@ impl ParsedConfig {
                             chain: sslopts.chain,
                             port: sslopts.port,
                             passfile: sslopts.passin,
+                            client_ca: sslopts.clientca,
+                            verify,
                         },
                         host: cfg_info.server.host,
                         port: cfg_info.server.port,
@@ -338,6 +533,30 @@ impl ParsedConfig {
                 }
             },
             maxcon: option_unwrap_or!(cfg_info.server.maxclient, MAXIMUM_CONNECTION_LIMIT),
+            metrics: cfg_info
+                .metrics
+                .map(|metrics| MetricsConfig::Enabled(metrics.port))
+                .unwrap_or_else(MetricsConfig::default),
+            slowlog: if let Some(slowlog) = cfg_info.slowlog {
+                let default = SlowlogConfig::default();
+                SlowlogConfig::new(
+                    option_unwrap_or!(slowlog.threshold, default.threshold),
+                    option_unwrap_or!(slowlog.maxlen, default.maxlen),
+                    option_unwrap_or!(slowlog.log, default.log),
+                )
+            } else {
+                SlowlogConfig::default()
+            },
+            limits: if let Some(limits) = cfg_info.limits {
+                let default = LimitsConfig::default();
+                LimitsConfig::new(
+                    option_unwrap_or!(limits.maxquerysize, default.max_query_size),
+                    option_unwrap_or!(limits.maxelements, default.max_elements),
+                    option_unwrap_or!(limits.idletimeout, default.idle_timeout),
+                )
+            } else {
+                LimitsConfig::default()
+            },
         }
     }
     #[cfg(test)]
@@ -346,12 +565,16 @@ impl ParsedConfig {
         Ok(ParsedConfig::from_config(toml::from_str(&tomlstr)?))
     }
     /// Create a new `ParsedConfig` with all the fields
+    #[allow(clippy::too_many_arguments)]
     pub const fn new(
         noart: bool,
         bgsave: BGSave,
         snapshot: SnapshotConfig,
         ports: PortConfig,
         maxcon: usize,
+        metrics: MetricsConfig,
+        slowlog: SlowlogConfig,
+        limits: LimitsConfig,
     ) -> Self {
         ParsedConfig {
             noart,
@@ -359,6 +582,9 @@ impl ParsedConfig {
             snapshot,
             ports,
             maxcon,
+            metrics,
+            slowlog,
+            limits,
         }
     }
     /// Create a default `ParsedConfig` with the following setup defaults:
@@ -368,6 +594,9 @@ impl ParsedConfig {
     /// - `bgsave_enabled` : true
     /// - `bgsave_duration` : 120
     /// - `ssl` : disabled
+    /// - `metrics` : disabled
+    /// - `slowlog` : 10ms threshold, 128 entries
+    /// - `limits` : 64 MiB queries, 1048576 elements, no idle timeout
     pub const fn default() -> Self {
         ParsedConfig {
             noart: false,
@@ -375,6 +604,9 @@ impl ParsedConfig {
             snapshot: SnapshotConfig::default(),
             ports: PortConfig::new_insecure_only(DEFAULT_IPV4, 2003),
             maxcon: MAXIMUM_CONNECTION_LIMIT,
+            metrics: MetricsConfig::default(),
+            slowlog: SlowlogConfig::default(),
+            limits: LimitsConfig::default(),
         }
     }
     /// Returns `false` if `noart` is enabled. Otherwise it returns `true`
@@ -398,7 +630,7 @@ pub enum ConfigType<T, U> {
 /// - The config file was not found (`OSError`)
 /// - The config file was invalid (`SyntaxError`)
 /// - The config file has an invalid value, which is syntatically correct
-/// but logically incorrect (`CfgError`)
+///   but logically incorrect (`CfgError`)
 /// - The command line arguments have an invalid value/invalid values (`CliArgError`)
 pub enum ConfigError {
     OSError(Box<dyn Error>),
@@ -444,6 +676,9 @@ pub fn get_config_file_or_return_cfg() -> Result<ConfigType<ParsedConfig, String
     let sslchain = matches.value_of("sslchain");
     let maxcon = matches.value_of("maxcon");
     let passfile = matches.value_of("tlspassin");
+    let clientca = matches.value_of("tlsclientca");
+    let tlsverify = matches.value_of("tlsverify");
+    let metricsport = matches.value_of("metricsport");
     let cli_has_overrideable_args = host.is_some()
         || port.is_some()
         || noart
@@ -456,6 +691,9 @@ pub fn get_config_file_or_return_cfg() -> Result<ConfigType<ParsedConfig, String
         || maxcon.is_some()
         || custom_ssl_port
         || passfile.is_some()
+        || clientca.is_some()
+        || tlsverify.is_some()
+        || metricsport.is_some()
         || sslonly;
     if filename.is_some() && cli_has_overrideable_args {
         return Err(ConfigError::CfgError(
@@ -508,6 +746,14 @@ pub fn get_config_file_or_return_cfg() -> Result<ConfigType<ParsedConfig, String
             },
             None => MAXIMUM_CONNECTION_LIMIT,
         };
+        let metrics =
+            match metricsport.map(|port| port.parse()) {
+                Some(Ok(port)) => MetricsConfig::Enabled(port),
+                Some(Err(_)) => return Err(ConfigError::CliArgErr(
+                    "Invalid value for `--metricsport`. Expected a valid unsigned 16-bit integer",
+                )),
+                None => MetricsConfig::default(),
+            };
         let bgsave = if nosave {
             if saveduration.is_some() {
                 // If there is both `nosave` and `saveduration` - the arguments aren't logically correct!
@@ -581,6 +827,14 @@ pub fn get_config_file_or_return_cfg() -> Result<ConfigType<ParsedConfig, String
             }
             (None, None) => SnapshotConfig::Disabled,
         };
+        let clientca = clientca.map(|v| v.to_string());
+        let tlsverify = match tlsverify.map(TlsVerifyMode::from_arg) {
+            Some(Some(mode)) => mode,
+            Some(None) => return Err(ConfigError::CliArgErr(
+                "Invalid value for `--tlsverify`. Expected one of `none`, `optional` or `required`",
+            )),
+            None => TlsVerifyMode::default_for(&clientca),
+        };
         let portcfg = match (
             sslkey.map(|val| val.to_owned()),
             sslchain.map(|val| val.to_owned()),
@@ -594,21 +848,26 @@ pub fn get_config_file_or_return_cfg() -> Result<ConfigType<ParsedConfig, String
                     if custom_ssl_port {
                         log::warn!("Ignoring value for `--sslport` as TLS was not enabled");
                     }
+                    if clientca.is_some() {
+                        log::warn!("Ignoring value for `--tlsclientca` as TLS was not enabled");
+                    }
                     PortConfig::new_insecure_only(host, port)
                 }
             }
             (Some(key), Some(chain)) => {
+                let sslopts = SslOpts::new(
+                    key,
+                    chain,
+                    sslport,
+                    passfile.map(|v| v.to_string()),
+                    clientca,
+                    tlsverify,
+                );
+                sslopts.check_client_ca()?;
                 if sslonly {
-                    PortConfig::new_secure_only(
-                        host,
-                        SslOpts::new(key, chain, sslport, passfile.map(|v| v.to_string())),
-                    )
+                    PortConfig::new_secure_only(host, sslopts)
                 } else {
-                    PortConfig::new_multi(
-                        host,
-                        port,
-                        SslOpts::new(key, chain, sslport, passfile.map(|v| v.to_string())),
-                    )
+                    PortConfig::new_multi(host, port, sslopts)
                 }
             }
             _ => {
@@ -617,38 +876,67 @@ pub fn get_config_file_or_return_cfg() -> Result<ConfigType<ParsedConfig, String
                 ));
             }
         };
-        let cfg = ParsedConfig::new(noart, bgsave, snapcfg, portcfg, maxcon);
+        let cfg = ParsedConfig::new(
+            noart,
+            bgsave,
+            snapcfg,
+            portcfg,
+            maxcon,
+            metrics,
+            SlowlogConfig::default(),
+            LimitsConfig::default(),
+        );
         return Ok(ConfigType::Custom(cfg, restorefile));
     }
     if let Some(filename) = filename {
-        match ParsedConfig::new_from_file(filename.to_owned()) {
-            Ok(cfg) => {
-                if cfg.bgsave.is_disabled() {
-                    log::warn!("BGSAVE is disabled: If this system crashes unexpectedly, it may lead to the loss of data");
-                }
-                if let SnapshotConfig::Enabled(e) = &cfg.snapshot {
-                    if e.every == 0 {
-                        return Err(ConfigError::CfgError(
-                            "The snapshot duration has to be greater than 0!",
-                        ));
-                    }
-                }
-                if let BGSave::Enabled(dur) = &cfg.bgsave {
-                    if *dur == 0 {
-                        return Err(ConfigError::CfgError(
-                            "The BGSAVE duration has to be greater than 0!",
-                        ));
-                    }
-                }
-                Ok(ConfigType::Custom(cfg, restorefile))
-            }
-            Err(e) => Err(e),
+        let cfg = parse_config_file(filename)?;
+        if cfg.bgsave.is_disabled() {
+            log::warn!("BGSAVE is disabled: If this system crashes unexpectedly, it may lead to the loss of data");
         }
+        *CONFIG_FILE.lock() = Some(filename.to_owned());
+        Ok(ConfigType::Custom(cfg, restorefile))
     } else {
         Ok(ConfigType::Def(ParsedConfig::default(), restorefile))
     }
 }
 
+/// Parse the configuration file at `filename` and check it for logically invalid values
+pub fn parse_config_file(filename: &str) -> Result<ParsedConfig, ConfigError> {
+    let cfg = ParsedConfig::new_from_file(filename.to_owned())?;
+    if let SnapshotConfig::Enabled(e) = &cfg.snapshot {
+        if e.every == 0 {
+            return Err(ConfigError::CfgError(
+                "The snapshot duration has to be greater than 0!",
+            ));
+        }
+    }
+    if let BGSave::Enabled(dur) = &cfg.bgsave {
+        if *dur == 0 {
+            return Err(ConfigError::CfgError(
+                "The BGSAVE duration has to be greater than 0!",
+            ));
+        }
+    }
+    match &cfg.ports {
+        PortConfig::SecureOnly { ssl, .. } | PortConfig::Multi { ssl, .. } => {
+            ssl.check_client_ca()?
+        }
+        PortConfig::InsecureOnly { .. } => {}
+    }
+    if cfg.limits.max_query_size == 0 || cfg.limits.max_elements == 0 {
+        return Err(ConfigError::CfgError(
+            "The maximum query size and the maximum number of elements have to be greater than 0!",
+        ));
+    }
+    Ok(cfg)
+}
+
+/// Returns the path of the configuration file the server was started with. This is `None`
+/// if the server was started with the default configuration or with command line arguments
+pub fn config_file() -> Option<String> {
+    CONFIG_FILE.lock().clone()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -722,7 +1010,10 @@ mod tests {
                 bgsave: BGSave::default(),
                 snapshot: SnapshotConfig::default(),
                 ports: PortConfig::default(),
-                maxcon: MAXIMUM_CONNECTION_LIMIT
+                maxcon: MAXIMUM_CONNECTION_LIMIT,
+                metrics: MetricsConfig::default(),
+                slowlog: SlowlogConfig::default(),
+                limits: LimitsConfig::default()
             }
         );
     }
@@ -741,7 +1032,10 @@ mod tests {
                     IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0x1)),
                     DEFAULT_PORT
                 ),
-                maxcon: MAXIMUM_CONNECTION_LIMIT
+                maxcon: MAXIMUM_CONNECTION_LIMIT,
+                metrics: MetricsConfig::default(),
+                slowlog: SlowlogConfig::default(),
+                limits: LimitsConfig::default()
             }
         );
     }
@@ -762,10 +1056,15 @@ mod tests {
                         "/path/to/keyfile.pem".into(),
                         "/path/to/chain.pem".into(),
                         2004,
-                        Some("/path/to/cert/passphrase.txt".to_owned())
+                        Some("/path/to/cert/passphrase.txt".to_owned()),
+                        Some("/path/to/clientca.pem".to_owned()),
+                        TlsVerifyMode::Required
                     )
                 ),
-                MAXIMUM_CONNECTION_LIMIT
+                MAXIMUM_CONNECTION_LIMIT,
+                MetricsConfig::Enabled(2005),
+                SlowlogConfig::new(20_000, 128, false),
+                LimitsConfig::new(16 * 1024 * 1024, 1024 * 1024, 300)
             )
         );
     }
@@ -788,7 +1087,10 @@ mod tests {
                 bgsave: BGSave::new(true, 600),
                 snapshot: SnapshotConfig::default(),
                 ports: PortConfig::default(),
-                maxcon: MAXIMUM_CONNECTION_LIMIT
+                maxcon: MAXIMUM_CONNECTION_LIMIT,
+                metrics: MetricsConfig::default(),
+                slowlog: SlowlogConfig::default(),
+                limits: LimitsConfig::default()
             }
         );
     }
@@ -808,7 +1110,10 @@ mod tests {
                 bgsave: BGSave::default(),
                 snapshot: SnapshotConfig::default(),
                 ports: PortConfig::default(),
-                maxcon: MAXIMUM_CONNECTION_LIMIT
+                maxcon: MAXIMUM_CONNECTION_LIMIT,
+                metrics: MetricsConfig::default(),
+                slowlog: SlowlogConfig::default(),
+                limits: LimitsConfig::default()
             }
         )
     }
@@ -828,7 +1133,10 @@ mod tests {
                 bgsave: BGSave::new(true, 600),
                 snapshot: SnapshotConfig::default(),
                 ports: PortConfig::default(),
-                maxcon: MAXIMUM_CONNECTION_LIMIT
+                maxcon: MAXIMUM_CONNECTION_LIMIT,
+                metrics: MetricsConfig::default(),
+                slowlog: SlowlogConfig::default(),
+                limits: LimitsConfig::default()
             }
         )
     }
@@ -844,7 +1152,10 @@ mod tests {
                 bgsave: BGSave::default(),
                 noart: false,
                 ports: PortConfig::default(),
-                maxcon: MAXIMUM_CONNECTION_LIMIT
+                maxcon: MAXIMUM_CONNECTION_LIMIT,
+                metrics: MetricsConfig::default(),
+                slowlog: SlowlogConfig::default(),
+                limits: LimitsConfig::default()
             }
         );
     }
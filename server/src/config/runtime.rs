@@ -0,0 +1,241 @@
+/*
+ * Created on Sun Sep 12 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Runtime configuration
+//!
+//! A few settings can be read and changed while the server is running with `CONFIG GET` and
+//! `CONFIG SET`. Changes are picked up by the running services immediately and can be
+//! persisted to the configuration file with `CONFIG REWRITE`. The keys are:
+//! - `bgsave.enabled` and `bgsave.every`
+//! - `snapshot.every`, `snapshot.atmost` and `snapshot.failsafe` (only if snapshots were
+//!   enabled on startup)
+//! - `maxcon`
+//! - `loglevel` (this isn't written to the configuration file, since the startup level
+//!   comes from `SKY_LOG`)
+
+use super::BGSave;
+use super::SnapshotConfig;
+use crate::dbnet::limits;
+use crate::services::{bgsave, snapshot};
+use crate::storage::sengine::SnapshotEngine;
+use core::str::FromStr;
+use log::LevelFilter;
+use std::fs;
+
+const BGSAVE_ENABLED: &str = "bgsave.enabled";
+const BGSAVE_EVERY: &str = "bgsave.every";
+const SNAPSHOT_EVERY: &str = "snapshot.every";
+const SNAPSHOT_ATMOST: &str = "snapshot.atmost";
+const SNAPSHOT_FAILSAFE: &str = "snapshot.failsafe";
+const MAXCON: &str = "maxcon";
+const LOGLEVEL: &str = "loglevel";
+
+/// Errors that can arise while reading or changing a runtime setting
+#[derive(Debug, PartialEq)]
+pub enum RuntimeConfigError {
+    /// The key is unknown
+    UnknownKey,
+    /// The value is invalid for the key
+    BadValue,
+    /// The key is a snapshot setting, but snapshots are disabled
+    SnapshotDisabled,
+}
+
+type RuntimeResult<T> = Result<T, RuntimeConfigError>;
+
+/// Parse `value`. Zero is rejected if `nonzero` is set
+fn parse_value<T: FromStr + Default + PartialEq>(value: &str, nonzero: bool) -> RuntimeResult<T> {
+    match value.parse() {
+        Ok(parsed) if !(nonzero && parsed == T::default()) => Ok(parsed),
+        _ => Err(RuntimeConfigError::BadValue),
+    }
+}
+
+/// Returns the current value of the setting `key`
+pub fn get(engine: &SnapshotEngine, key: &str) -> RuntimeResult<String> {
+    let value = match key {
+        BGSAVE_ENABLED => (!bgsave::config().is_disabled()).to_string(),
+        BGSAVE_EVERY => bgsave::every().to_string(),
+        SNAPSHOT_EVERY | SNAPSHOT_ATMOST | SNAPSHOT_FAILSAFE => match snapshot::config(engine) {
+            SnapshotConfig::Enabled(pref) => match key {
+                SNAPSHOT_EVERY => pref.every.to_string(),
+                SNAPSHOT_ATMOST => pref.atmost.to_string(),
+                _ => pref.poison.to_string(),
+            },
+            SnapshotConfig::Disabled => return Err(RuntimeConfigError::SnapshotDisabled),
+        },
+        MAXCON => limits::maxcon().to_string(),
+        LOGLEVEL => log::max_level().to_string().to_lowercase(),
+        _ => return Err(RuntimeConfigError::UnknownKey),
+    };
+    Ok(value)
+}
+
+/// Change the setting `key` to `value`
+pub fn set(engine: &SnapshotEngine, key: &str, value: &str) -> RuntimeResult<()> {
+    match key {
+        BGSAVE_ENABLED => {
+            let enabled = parse_value(value, false)?;
+            bgsave::configure(&BGSave::new(enabled, bgsave::every()));
+        }
+        BGSAVE_EVERY => bgsave::set_every(parse_value(value, true)?),
+        SNAPSHOT_EVERY | SNAPSHOT_ATMOST | SNAPSHOT_FAILSAFE => match snapshot::config(engine) {
+            SnapshotConfig::Enabled(mut pref) => {
+                match key {
+                    SNAPSHOT_EVERY => pref.every = parse_value(value, true)?,
+                    SNAPSHOT_ATMOST => pref.atmost = parse_value(value, false)?,
+                    _ => pref.poison = parse_value(value, false)?,
+                }
+                snapshot::configure(engine, &pref);
+            }
+            SnapshotConfig::Disabled => return Err(RuntimeConfigError::SnapshotDisabled),
+        },
+        MAXCON => limits::set_maxcon(parse_value(value, true)?),
+        LOGLEVEL => match LevelFilter::from_str(value) {
+            Ok(level) => log::set_max_level(level),
+            Err(_) => return Err(RuntimeConfigError::BadValue),
+        },
+        _ => return Err(RuntimeConfigError::UnknownKey),
+    }
+    log::info!("Configuration changed: {} = {}", key, value);
+    Ok(())
+}
+
+/// Write the current values of the runtime settings to the configuration file that the
+/// server was started with. Everything else in the file (including comments) is left as is
+pub fn rewrite(engine: &SnapshotEngine) -> Result<(), String> {
+    let filename = match super::config_file() {
+        Some(filename) => filename,
+        None => return Err("the server wasn't started with a configuration file".to_owned()),
+    };
+    let mut file = fs::read_to_string(&filename)
+        .map_err(|e| format!("failed to read configuration file: {}", e))?;
+    let mut updates = vec![
+        (
+            "bgsave",
+            "enabled",
+            (!bgsave::config().is_disabled()).to_string(),
+        ),
+        ("bgsave", "every", bgsave::every().to_string()),
+        ("server", "maxclient", limits::maxcon().to_string()),
+    ];
+    if let SnapshotConfig::Enabled(pref) = snapshot::config(engine) {
+        updates.push(("snapshot", "every", pref.every.to_string()));
+        updates.push(("snapshot", "atmost", pref.atmost.to_string()));
+        updates.push(("snapshot", "failsafe", pref.poison.to_string()));
+    }
+    for (table, key, value) in updates {
+        file = set_toml_key(&file, table, key, &value);
+    }
+    // write to a temporary file first so that we never leave behind a half written file
+    let tmpfile = format!("{}.tmp", filename);
+    fs::write(&tmpfile, file)
+        .and_then(|_| fs::rename(&tmpfile, &filename))
+        .map_err(|e| format!("failed to write configuration file: {}", e))?;
+    log::info!("Rewrote configuration file {}", filename);
+    Ok(())
+}
+
+/// Set `key` to `value` under `[table]` in the TOML document `src`, keeping the rest of the
+/// document (and any comment on the same line) intact. The key is added to the table (and the
+/// table to the document) if it doesn't exist
+fn set_toml_key(src: &str, table: &str, key: &str, value: &str) -> String {
+    let mut lines: Vec<String> = src.lines().map(|line| line.to_owned()).collect();
+    let mut current_table = String::new();
+    let mut header = None;
+    let mut found = false;
+    for (idx, line) in lines.iter_mut().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            current_table = trimmed
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_owned();
+            if current_table == table {
+                header = Some(idx);
+            }
+            continue;
+        }
+        if current_table != table || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some((lhs, rhs)) = line.split_once('=') {
+            if lhs.trim() == key {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                let newline = match rhs.find('#') {
+                    Some(pos) => {
+                        // keep the comment and the padding before it
+                        let before = &rhs[..pos];
+                        let padding = &before[before.trim_end().len()..];
+                        format!("{}{} = {}{}{}", indent, key, value, padding, &rhs[pos..])
+                    }
+                    None => format!("{}{} = {}", indent, key, value),
+                };
+                *line = newline;
+                found = true;
+                break;
+            }
+        }
+    }
+    if !found {
+        match header {
+            Some(idx) => lines.insert(idx + 1, format!("{} = {}", key, value)),
+            None => {
+                if lines.last().map(|line| !line.trim().is_empty()) == Some(true) {
+                    lines.push(String::new());
+                }
+                lines.push(format!("[{}]", table));
+                lines.push(format!("{} = {}", key, value));
+            }
+        }
+    }
+    let mut ret = lines.join("\n");
+    ret.push('\n');
+    ret
+}
+
+#[test]
+fn test_set_toml_key() {
+    let src = "[server]\nhost = \"127.0.0.1\"\nport = 2003\n\n[bgsave]\nevery = 120 # in seconds\n";
+    // replace an existing key and keep the comment
+    let updated = set_toml_key(src, "bgsave", "every", "60");
+    assert_eq!(
+        updated,
+        "[server]\nhost = \"127.0.0.1\"\nport = 2003\n\n[bgsave]\nevery = 60 # in seconds\n"
+    );
+    // add a key to an existing table
+    let updated = set_toml_key(&updated, "server", "maxclient", "100");
+    assert_eq!(
+        updated,
+        "[server]\nmaxclient = 100\nhost = \"127.0.0.1\"\nport = 2003\n\n[bgsave]\nevery = 60 # in seconds\n"
+    );
+    // add a table
+    let updated = set_toml_key(&updated, "snapshot", "atmost", "4");
+    assert!(updated.ends_with("every = 60 # in seconds\n\n[snapshot]\natmost = 4\n"));
+}
@@ -214,7 +214,7 @@ impl<T, const N: usize> Array<T, N> {
     }
     /// Pop an item off the array
     pub fn pop(&mut self) -> Option<T> {
-        if self.len() == 0 {
+        if self.is_empty() {
             // nothing here
             None
         } else {
@@ -234,7 +234,7 @@ impl<T, const N: usize> Array<T, N> {
             // we need to drop off a part of the array
             unsafe {
                 // drop_in_place will handle the ZST invariant for us
-                ptr::drop_in_place(slice::from_raw_parts_mut(
+                ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
                     self.as_mut_ptr().add(new_len),
                     len - new_len,
                 ))
@@ -25,7 +25,7 @@
 */
 
 use crate::corestore::map::{
-    bref::{Entry, OccupiedEntry, Ref, VacantEntry},
+    bref::{Entry, OccupiedEntry, Ref, RefMut, VacantEntry},
     iter::{BorrowedIter, OwnedIter},
     Skymap,
 };
@@ -108,6 +108,20 @@ where
     pub fn iter(&self) -> BorrowedIter<'_, K, V, RandomState> {
         self.inner.get_iter()
     }
+    /// Returns the number of shards that the entries are spread over
+    pub fn shard_count(&self) -> usize {
+        self.inner.shard_count()
+    }
+    /// Count the entries of a shard that satisfy a certain condition (see
+    /// [`Skymap::count_in_shard`])
+    pub fn count_in_shard(&self, shard: usize, f: impl FnMut(&K, &V) -> bool) -> usize {
+        self.inner.count_in_shard(shard, f)
+    }
+    /// Remove the entries of a shard that satisfy a certain condition (see
+    /// [`Skymap::remove_in_shard`])
+    pub fn remove_in_shard(&self, shard: usize, f: impl FnMut(&K, &V) -> bool) -> usize {
+        self.inner.remove_in_shard(shard, f)
+    }
     /// Get a reference to the value of a key, if it exists
     pub fn get<Q>(&self, key: &Q) -> Option<Ref<'_, K, V>>
     where
@@ -116,6 +130,18 @@ where
     {
         self.inner.get(key)
     }
+    /// Get a mutable reference to the value of a key, if it exists
+    pub fn get_mut<Q>(&self, key: &Q) -> Option<RefMut<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get_mut(key)
+    }
+    /// Get an entry for in-place mutation
+    pub fn entry(&self, key: K) -> Entry<'_, K, V, RandomState> {
+        self.inner.entry(key)
+    }
     /// Returns true if the non-existent key was assigned to a value
     pub fn true_if_insert(&self, k: K, v: V) -> bool {
         if let Entry::Vacant(ve) = self.inner.entry(k) {
@@ -152,14 +178,14 @@ where
             false
         }
     }
-    pub fn mut_entry(&self, key: K) -> Option<OccupiedEntry<K, V, RandomState>> {
+    pub fn mut_entry(&self, key: K) -> Option<OccupiedEntry<'_, K, V, RandomState>> {
         if let Entry::Occupied(oe) = self.inner.entry(key) {
             Some(oe)
         } else {
             None
         }
     }
-    pub fn fresh_entry(&self, key: K) -> Option<VacantEntry<K, V, RandomState>> {
+    pub fn fresh_entry(&self, key: K) -> Option<VacantEntry<'_, K, V, RandomState>> {
         if let Entry::Vacant(ve) = self.inner.entry(key) {
             Some(ve)
         } else {
@@ -178,7 +204,7 @@ impl<K: Eq + Hash, V: Clone> Coremap<K, V> {
     }
 }
 
-impl Coremap<Data, Data> {
+impl<V> Coremap<Data, V> {
     /// Returns atleast `count` number of keys from the hashtable
     pub fn get_keys(&self, count: usize) -> Vec<Bytes> {
         let mut v = Vec::with_capacity(count);
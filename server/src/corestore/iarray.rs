@@ -222,7 +222,7 @@ impl<A: MemoryBlock> IArray<A> {
         }
     }
     /// Mutable version of `meta_triple`
-    fn meta_triple_mut(&mut self) -> DataptrLenptrCapacityMut<A::LayoutItem> {
+    fn meta_triple_mut(&mut self) -> DataptrLenptrCapacityMut<'_, A::LayoutItem> {
         unsafe {
             if self.went_off_stack() {
                 // get heap
@@ -177,7 +177,7 @@ cfg_test!(
         }
     }
 
-    struct WeirdTestStruct(u8);
+    struct WeirdTestStruct;
     impl Drop for WeirdTestStruct {
         fn drop(&mut self) {
             panic!("PANIC ON DROP! THIS IS OKAY!");
@@ -188,7 +188,7 @@ cfg_test!(
     fn test_drop() {
         // this is only when the lazy is initialized in local scope and not global scope
         let x: Lazy<WeirdTestStruct, fn() -> WeirdTestStruct> = Lazy::new(|| {
-            WeirdTestStruct(0)
+            WeirdTestStruct
         });
         // just do an useless deref to make the pointer non null
         let _deref = &*x;
@@ -197,7 +197,7 @@ cfg_test!(
     #[test]
     fn test_no_drop_null() {
         let x: Lazy<WeirdTestStruct, fn() -> WeirdTestStruct> = Lazy::new(|| {
-            WeirdTestStruct(0)
+            WeirdTestStruct
         });
         drop(x); // no panic because it is null
     }
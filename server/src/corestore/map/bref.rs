@@ -152,13 +152,17 @@ impl<'a, K: Hash + Eq, V, S: BuildHasher> OccupiedEntry<'a, K, V, S> {
     pub fn value(&self) -> &V {
         self.elem.1
     }
+    /// Get a mutable ref to the value
+    pub fn value_mut(&mut self) -> &mut V {
+        self.elem.1
+    }
     /// Insert a value into this bucket
     pub fn insert(&mut self, other: V) -> V {
         mem::replace(self.elem.1, other)
     }
     /// Remove this element from the map
     pub fn remove(mut self) -> V {
-        let hash = super::make_hash::<K, _, S>(&self.hasher, &self.key);
+        let hash = super::make_hash::<_, S>(&self.hasher, &self.key);
         unsafe {
             self.guard
                 .remove_entry(hash, super::ceq(self.elem.0))
@@ -190,7 +194,7 @@ impl<'a, K: Hash + Eq, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
             let &mut (ref mut k, ref mut v) = self.guard.insert_entry(
                 hash,
                 (self.key, value),
-                super::make_hasher::<K, _, V, S>(&self.hasher),
+                super::make_hasher::<_, V, S>(&self.hasher),
             );
             let kptr = compiler::extend_lifetime(k);
             let vptr = compiler::extend_lifetime_mut(v);
@@ -31,7 +31,6 @@ use core::borrow::Borrow;
 use core::fmt;
 use core::hash::BuildHasher;
 use core::hash::Hash;
-use core::hash::Hasher;
 use core::iter::FromIterator;
 use core::mem;
 use parking_lot::RwLock;
@@ -50,15 +49,12 @@ type SWlock<'a, K, V> = RwLockWriteGuard<'a, hashbrown::raw::RawTable<(K, V)>>;
 const BITS_IN_USIZE: usize = mem::size_of::<usize>() * 8;
 const DEFAULT_CAP: usize = 128;
 
-fn make_hash<K, Q, S>(hash_builder: &S, val: &Q) -> u64
+fn make_hash<Q, S>(hash_builder: &S, val: &Q) -> u64
 where
-    K: Borrow<Q>,
     Q: Hash + ?Sized,
     S: BuildHasher,
 {
-    let mut state = hash_builder.build_hasher();
-    val.hash(&mut state);
-    state.finish()
+    hash_builder.hash_one(val)
 }
 
 fn make_insert_hash<K, S>(hash_builder: &S, val: &K) -> u64
@@ -66,18 +62,15 @@ where
     K: Hash,
     S: BuildHasher,
 {
-    let mut state = hash_builder.build_hasher();
-    val.hash(&mut state);
-    state.finish()
+    hash_builder.hash_one(val)
 }
 
-fn make_hasher<K, Q, V, S>(hash_builder: &S) -> impl Fn(&(Q, V)) -> u64 + '_
+fn make_hasher<Q, V, S>(hash_builder: &S) -> impl Fn(&(Q, V)) -> u64 + '_
 where
-    K: Borrow<Q>,
     Q: Hash,
     S: BuildHasher,
 {
-    move |val| make_hash::<K, Q, S>(hash_builder, &val.0)
+    move |val| make_hash::<Q, S>(hash_builder, &val.0)
 }
 
 fn ceq<Q, K, V>(k: &Q) -> impl Fn(&(K, V)) -> bool + '_
@@ -190,11 +183,11 @@ where
         self.len() == 0
     }
     /// Get a borrowed iterator for the Skymap. Bound to the lifetime
-    pub fn get_iter(&self) -> BorrowedIter<K, V, S> {
+    pub fn get_iter(&self) -> BorrowedIter<'_, K, V, S> {
         BorrowedIter::new(self)
     }
     /// Get a borrowed mutable iterator for the Skymap, Bound to the lifetime
-    pub fn get_iter_mut(&self) -> BorrowedIterMut<K, V, S> {
+    pub fn get_iter_mut(&self) -> BorrowedIterMut<'_, K, V, S> {
         BorrowedIterMut::new(self)
     }
     /// Get an owned iterator to the Skymap
@@ -237,7 +230,7 @@ where
             if let Some((_, item)) = lowtable.get_mut(hash, ceq(&k)) {
                 Some(mem::replace(item, v))
             } else {
-                lowtable.insert(hash, (k, v), make_hasher::<K, _, V, S>(self.h()));
+                lowtable.insert(hash, (k, v), make_hasher::<_, V, S>(self.h()));
                 None
             }
             // end critical section
@@ -249,7 +242,7 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let hash = make_hash::<K, Q, S>(self.h(), k);
+        let hash = make_hash::<Q, S>(self.h(), k);
         let idx = self.determine_shard(hash as usize);
         unsafe {
             // begin critical section
@@ -267,7 +260,7 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let hash = make_hash::<K, Q, S>(self.h(), k);
+        let hash = make_hash::<Q, S>(self.h(), k);
         let idx = self.determine_shard(hash as usize);
         unsafe {
             // begin critical section
@@ -296,7 +289,7 @@ impl<'a, K: 'a + Hash + Eq, V: 'a, S: BuildHasher + Clone> Skymap<K, V, S> {
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let hash = make_hash::<K, Q, S>(self.h(), k);
+        let hash = make_hash::<Q, S>(self.h(), k);
         let idx = self.determine_shard(hash as usize);
         unsafe {
             // begin critical section
@@ -319,7 +312,7 @@ impl<'a, K: 'a + Hash + Eq, V: 'a, S: BuildHasher + Clone> Skymap<K, V, S> {
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let hash = make_hash::<K, Q, S>(self.h(), k);
+        let hash = make_hash::<Q, S>(self.h(), k);
         let idx = self.determine_shard(hash as usize);
         unsafe {
             // begin critical section
@@ -379,7 +372,7 @@ impl<'a, K, V: Clone, S: BuildHasher> Skymap<K, V, S> {
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let hash = make_hash::<K, Q, S>(self.h(), k);
+        let hash = make_hash::<Q, S>(self.h(), k);
         let idx = self.determine_shard(hash as usize);
         unsafe {
             // begin critical section
@@ -393,6 +386,140 @@ impl<'a, K, V: Clone, S: BuildHasher> Skymap<K, V, S> {
     }
 }
 
+// shard-wise impls
+impl<K, V, S> Skymap<K, V, S> {
+    /// Returns the number of shards
+    pub fn shard_count(&self) -> usize {
+        self.shards().len()
+    }
+    /// Count the entries of the given shard that satisfy a certain condition. Only this
+    /// shard is (read) locked while counting
+    ///
+    /// ## Panics
+    /// If the shard index is out of bounds
+    pub fn count_in_shard(&self, shard: usize, mut f: impl FnMut(&K, &V) -> bool) -> usize {
+        let lowtable = self.shards()[shard].read();
+        unsafe {
+            // UNSAFE(@ohsayan): The iterator doesn't outlive the table since we hold the lock
+            lowtable
+                .iter()
+                .filter(|bucket| {
+                    let (k, v) = bucket.as_ref();
+                    f(k, v)
+                })
+                .count()
+        }
+    }
+    /// Remove the entries of the given shard that satisfy a certain condition, returning
+    /// the number of removed entries. Only this shard is (write) locked while removing
+    ///
+    /// ## Panics
+    /// If the shard index is out of bounds
+    pub fn remove_in_shard(&self, shard: usize, mut f: impl FnMut(&K, &V) -> bool) -> usize {
+        let mut lowtable = self.shards()[shard].write();
+        let mut removed = 0;
+        unsafe {
+            // UNSAFE(@ohsayan): Erasing the bucket that we're at is fine; see the `retain`
+            // impl of hashbrown's HashMap
+            for bucket in lowtable.iter() {
+                let (k, v) = bucket.as_ref();
+                if f(k, v) {
+                    lowtable.erase(bucket);
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+}
+
+// multi-shard lock impls
+impl<'a, K: 'a + Hash + Eq, V: 'a, S: BuildHasher + Clone> Skymap<K, V, S> {
+    /// Write-lock all the shards that the given keys belong to. The shards are always locked
+    /// in ascending order, so two callers locking overlapping sets of shards can never
+    /// deadlock. Other operations on the locked shards will block until the returned
+    /// [`LockedShards`] is dropped
+    pub fn lock_shards_for<'k, Q, I>(&'a self, keys: I) -> LockedShards<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'k,
+        I: IntoIterator<Item = &'k Q>,
+    {
+        let mut idx: Vec<usize> = keys
+            .into_iter()
+            .map(|key| self.determine_shard(make_hash::<Q, S>(self.h(), key) as usize))
+            .collect();
+        idx.sort_unstable();
+        idx.dedup();
+        let shards = idx
+            .into_iter()
+            .map(|shard| unsafe {
+                // SAFETY: determine_shard always returns a valid shard index
+                (shard, self.get_wshard_unchecked(shard))
+            })
+            .collect();
+        LockedShards { map: self, shards }
+    }
+}
+
+/// A set of write-locked shards of a [`Skymap`], created by [`Skymap::lock_shards_for`]
+///
+/// Only keys that belong to the locked shards can be accessed; accessing any other key
+/// will panic
+pub struct LockedShards<'a, K, V, S> {
+    map: &'a Skymap<K, V, S>,
+    shards: Vec<(usize, SWlock<'a, K, V>)>,
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher + Clone> LockedShards<'a, K, V, S> {
+    /// Returns the locked shard for the given hash
+    fn shard(&self, hash: u64) -> &LowMap<K, V> {
+        let idx = self.map.determine_shard(hash as usize);
+        match self.shards.binary_search_by_key(&idx, |(shard, _)| *shard) {
+            Ok(pos) => &self.shards[pos].1,
+            Err(_) => panic!("accessed a key in a shard that wasn't locked"),
+        }
+    }
+    /// Returns the locked shard for the given hash, mutably
+    fn shard_mut(&mut self, hash: u64) -> &mut LowMap<K, V> {
+        let idx = self.map.determine_shard(hash as usize);
+        match self.shards.binary_search_by_key(&idx, |(shard, _)| *shard) {
+            Ok(pos) => &mut self.shards[pos].1,
+            Err(_) => panic!("accessed a key in a shard that wasn't locked"),
+        }
+    }
+    /// Get a ref to the value of a key
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = make_hash::<Q, S>(self.map.h(), k);
+        self.shard(hash).get(hash, ceq(k)).map(|(_, v)| v)
+    }
+    /// Insert a key/value, returning the older value (if any)
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let map = self.map;
+        let hash = make_insert_hash::<K, S>(map.h(), &k);
+        let lowtable = self.shard_mut(hash);
+        if let Some((_, item)) = lowtable.get_mut(hash, ceq(&k)) {
+            Some(mem::replace(item, v))
+        } else {
+            lowtable.insert(hash, (k, v), make_hasher::<_, V, S>(map.h()));
+            None
+        }
+    }
+    /// Remove a key/value
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = make_hash::<Q, S>(self.map.h(), k);
+        self.shard_mut(hash).remove_entry(hash, ceq(k))
+    }
+}
+
 // inner impls
 impl<'a, K: 'a, V: 'a, S> Skymap<K, V, S> {
     /// Get a rlock to a certain stripe
@@ -405,6 +532,24 @@ impl<'a, K: 'a, V: 'a, S> Skymap<K, V, S> {
     }
 }
 
+#[test]
+fn test_shardwise_count_remove() {
+    let map: Skymap<usize, usize> = (0..1000).map(|i| (i, i * 2)).collect();
+    let count_even = |map: &Skymap<usize, usize>| {
+        (0..map.shard_count())
+            .map(|shard| map.count_in_shard(shard, |k, _| k % 2 == 0))
+            .sum::<usize>()
+    };
+    assert_eq!(count_even(&map), 500);
+    let removed: usize = (0..map.shard_count())
+        .map(|shard| map.remove_in_shard(shard, |k, v| k % 2 == 0 && *v != 20))
+        .sum();
+    assert_eq!(removed, 499);
+    assert_eq!(count_even(&map), 1);
+    assert_eq!(map.len(), 501);
+    assert_eq!(map.get(&10).map(|v| *v.value()), Some(20));
+}
+
 #[test]
 fn test_insert_remove() {
     let map = Skymap::default();
@@ -429,6 +574,19 @@ fn test_insert_get() {
     assert_eq!(*_ref, "likes computational dark arts")
 }
 
+#[test]
+fn test_lock_shards_for() {
+    let map = Skymap::default();
+    map.insert("hello", "world");
+    let mut locked = map.lock_shards_for(["hello", "sayan"].iter().copied());
+    assert_eq!(locked.get("hello"), Some(&"world"));
+    assert_eq!(locked.insert("sayan", "likes locks"), None);
+    assert_eq!(locked.remove("hello"), Some(("hello", "world")));
+    drop(locked);
+    assert!(map.get("hello").is_none());
+    assert_eq!(*map.get("sayan").unwrap(), "likes locks");
+}
+
 #[test]
 fn test_entry() {
     let map = Skymap::default();
@@ -58,11 +58,13 @@ use super::KeyspaceResult;
 use crate::corestore::array::Array;
 use crate::corestore::htable::Coremap;
 use crate::corestore::lock::{QLGuard, QuickLock};
+use crate::corestore::quota::{Quota, QuotaTracker, Quotas, WriteSize};
 use crate::corestore::table::Table;
 use crate::registry;
 use core::borrow::Borrow;
 use core::hash::Hash;
 use core::mem::MaybeUninit;
+use parking_lot::Mutex;
 use std::sync::Arc;
 
 #[sky_macros::array]
@@ -98,31 +100,23 @@ fn test_def_macro_sanity() {
     }
 }
 
+// these are placeholders for clustering and replication, which aren't implemented yet
+#[allow(dead_code)]
 mod cluster {
     /// This is for the future where every node will be allocated a shard
-    #[derive(Debug)]
+    #[derive(Debug, Default)]
     pub enum ClusterShardRange {
+        #[default]
         SingleNode,
     }
 
-    impl Default for ClusterShardRange {
-        fn default() -> Self {
-            Self::SingleNode
-        }
-    }
-
     /// This is for the future for determining the replication strategy
-    #[derive(Debug)]
+    #[derive(Debug, Default)]
     pub enum ReplicationStrategy {
         /// Single node, no replica sets
+        #[default]
         Default,
     }
-
-    impl Default for ReplicationStrategy {
-        fn default() -> Self {
-            Self::Default
-        }
-    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -146,6 +140,8 @@ pub enum DdlError {
     NotEmpty,
     /// The DDL transaction failed
     DdlTransactionFailure,
+    /// The quotas of the keyspace don't allow this
+    QuotaExceeded,
 }
 
 #[derive(Debug)]
@@ -159,6 +155,7 @@ pub struct Memstore {
     pub keyspaces: Coremap<ObjectID, Arc<Keyspace>>,
     /// the snapshot configuration
     /// A **virtual lock** on the preload file
+    #[allow(dead_code)]
     preload_lock: QuickLock<()>,
 }
 
@@ -300,6 +297,60 @@ impl Memstore {
             }
         }
     }
+    /// Rename a keyspace only if it is not protected and if neither it nor any of its tables
+    /// are in use, since a connection using the keyspace would otherwise be left with an
+    /// older name
+    ///
+    /// The invariants maintained here are:
+    /// 1. The keyspace is not referenced to
+    /// 2. The tables in the keyspace are not referenced to
+    ///
+    /// **Trip switch handled:** Yes
+    pub fn rename_keyspace(&self, ksid: &ObjectID, new_ksid: ObjectID) -> KeyspaceResult<()> {
+        if ksid.eq(&SYSTEM) || ksid.eq(&DEFAULT) {
+            Err(DdlError::ProtectedObject)
+        } else if !self.keyspaces.contains_key(ksid) {
+            Err(DdlError::ObjectNotFound)
+        } else if self.keyspaces.contains_key(&new_ksid) {
+            Err(DdlError::AlreadyExists)
+        } else {
+            let removed_keyspace = self.keyspaces.remove_if(ksid, |_ksid, keyspace| {
+                // (see the comment in force_drop_keyspace to know why)
+                Arc::strong_count(keyspace) == 1
+                    && keyspace
+                        .tables
+                        .iter()
+                        .all(|table| Arc::strong_count(table.value()) == 1)
+            });
+            match removed_keyspace {
+                Some((ksid, keyspace)) => match self.keyspaces.fresh_entry(new_ksid) {
+                    Some(entry) => {
+                        entry.insert(keyspace);
+                        // trip the preload switch
+                        registry::get_preload_tripswitch().trip();
+                        Ok(())
+                    }
+                    None => {
+                        // someone created a keyspace with the new name in the meantime
+                        self.keyspaces.upsert(ksid, keyspace);
+                        Err(DdlError::AlreadyExists)
+                    }
+                },
+                None => Err(DdlError::StillInUse),
+            }
+        }
+    }
+    /// Undo a rename of a keyspace (see [`Memstore::rename_keyspace`]) that couldn't be
+    /// persisted. Unlike a rename, this doesn't check if the keyspace is in use, since a
+    /// connection could have started using it under its new name in the meantime
+    ///
+    /// **Trip switch handled:** Yes
+    pub fn undo_rename_keyspace(&self, new_ksid: &ObjectID, ksid: ObjectID) {
+        if let Some((_, keyspace)) = self.keyspaces.remove(new_ksid) {
+            self.keyspaces.upsert(ksid, keyspace);
+            registry::get_preload_tripswitch().trip();
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -308,9 +359,14 @@ pub struct Keyspace {
     /// the tables
     pub tables: Coremap<ObjectID, Arc<Table>>,
     /// the replication strategy for this keyspace
+    #[allow(dead_code)]
     replication_strategy: cluster::ReplicationStrategy,
     /// A **virtual lock** on the partmap for this keyspace
     partmap_lock: QuickLock<()>,
+    /// the quotas for this keyspace
+    quotas: Quotas,
+    /// held by writes to this keyspace while they check the quotas and write
+    quota_lock: Mutex<()>,
 }
 
 #[cfg(test)]
@@ -332,13 +388,21 @@ impl Keyspace {
             },
             replication_strategy: cluster::ReplicationStrategy::default(),
             partmap_lock: QuickLock::new(()),
+            quotas: Quotas::new(),
+            quota_lock: Mutex::new(()),
         }
     }
     pub fn init_with_all_def_strategy(tables: Coremap<ObjectID, Arc<Table>>) -> Self {
+        Self::init_with_quotas(tables, Quotas::new())
+    }
+    /// Create a keyspace with the given tables and quotas (and the default strategy)
+    pub fn init_with_quotas(tables: Coremap<ObjectID, Arc<Table>>, quotas: Quotas) -> Self {
         Self {
             tables,
             replication_strategy: cluster::ReplicationStrategy::default(),
             partmap_lock: QuickLock::new(()),
+            quotas,
+            quota_lock: Mutex::new(()),
         }
     }
     /// Create a new empty keyspace with zero tables
@@ -347,11 +411,72 @@ impl Keyspace {
             tables: Coremap::new(),
             replication_strategy: cluster::ReplicationStrategy::default(),
             partmap_lock: QuickLock::new(()),
+            quotas: Quotas::new(),
+            quota_lock: Mutex::new(()),
         }
     }
     pub fn table_count(&self) -> usize {
         self.tables.len()
     }
+    /// Returns the quotas of this keyspace
+    pub fn quotas(&self) -> &Quotas {
+        &self.quotas
+    }
+    /// Returns the number of keys and the total footprint of the values across all the
+    /// tables in this keyspace
+    pub fn usage(&self) -> (usize, usize) {
+        self.tables.iter().fold((0, 0), |(keys, bytes), table| {
+            (keys + table.count(), bytes + table.value_bytes())
+        })
+    }
+    /// Returns true if the quotas of this keyspace allow a write of the given size to
+    /// one of its tables
+    pub fn allows_write(&self, size: WriteSize) -> bool {
+        if self.quotas.is_unlimited() {
+            return true;
+        }
+        let (keys, bytes) = self.usage();
+        self.quotas.allows_write(keys, bytes, size)
+    }
+    /// Returns a tracker for the quotas of this keyspace, or `None` if there are no limits.
+    /// This blocks until no other write to this keyspace holds a tracker
+    pub fn quota_tracker(&self) -> Option<QuotaTracker<'_>> {
+        if self.quotas.is_unlimited() {
+            return None;
+        }
+        let lock = self.quota_lock.lock();
+        let (keys, bytes) = self.usage();
+        Some(QuotaTracker::new(&self.quotas, lock, keys, bytes))
+    }
+    /// Check a write of the size returned by `size` against the quotas of this keyspace,
+    /// returning an error if it would go over a limit. Otherwise, this returns the tracker
+    /// that has recorded the write (or `None` if there are no limits), which has to be held
+    /// until the write is done. The size is only computed if there are limits
+    pub fn check_write(
+        &self,
+        size: impl FnOnce() -> WriteSize,
+    ) -> Result<Option<QuotaTracker<'_>>, ()> {
+        match self.quota_tracker() {
+            Some(mut quota) => {
+                if quota.allow(size()) {
+                    Ok(Some(quota))
+                } else {
+                    Err(())
+                }
+            }
+            None => Ok(None),
+        }
+    }
+    /// Returns true if the quotas of this keyspace allow adding the given table (along
+    /// with any data that it already has)
+    pub fn allows_table(&self, table: &Table) -> bool {
+        if self.quotas.is_unlimited() {
+            return true;
+        }
+        // the values were already written, so there's no point in checking their sizes
+        let size = WriteSize::new(table.count(), table.value_bytes(), 0);
+        self.quotas.allows(Quota::Tables, self.table_count() + 1) && self.allows_write(size)
+    }
     /// Get an atomic reference to a table in this keyspace if it exists
     pub fn get_table_atomic_ref<Q>(&self, table_identifier: &Q) -> Option<Arc<Table>>
     where
@@ -398,6 +523,54 @@ impl Keyspace {
         }
     }
 
+    /// Rename a table if it exists, if it is not forbidden and if no one references back
+    /// to it, for the same reasons as [`Keyspace::drop_table`]: a connection that is still
+    /// using the table would otherwise be left with an older name
+    ///
+    /// **Trip switch handled:** Yes
+    pub fn rename_table(&self, tblid: &ObjectID, new_tblid: ObjectID) -> KeyspaceResult<()> {
+        if tblid.eq(&DEFAULT) {
+            Err(DdlError::ProtectedObject)
+        } else if !self.tables.contains_key(tblid) {
+            Err(DdlError::ObjectNotFound)
+        } else if self.tables.contains_key(&new_tblid) {
+            Err(DdlError::AlreadyExists)
+        } else {
+            let removed_table = self.tables.remove_if(tblid, |_table_id, table_atomic_ref| {
+                // 1 because this should just be us, the one instance
+                Arc::strong_count(table_atomic_ref) == 1
+            });
+            match removed_table {
+                Some((tblid, table)) => match self.tables.fresh_entry(new_tblid) {
+                    Some(entry) => {
+                        entry.insert(table);
+                        // we need to re-init tree; so trip
+                        registry::get_preload_tripswitch().trip();
+                        Ok(())
+                    }
+                    None => {
+                        // someone created a table with the new name in the meantime
+                        self.tables.upsert(tblid, table);
+                        Err(DdlError::AlreadyExists)
+                    }
+                },
+                None => Err(DdlError::StillInUse),
+            }
+        }
+    }
+
+    /// Undo a rename of a table (see [`Keyspace::rename_table`]) that couldn't be persisted.
+    /// Unlike a rename, this doesn't check if the table is in use, since a connection could
+    /// have started using it under its new name in the meantime
+    ///
+    /// **Trip switch handled:** Yes
+    pub fn undo_rename_table(&self, new_tblid: &ObjectID, tblid: ObjectID) {
+        if let Some((_, table)) = self.tables.remove(new_tblid) {
+            self.tables.upsert(tblid, table);
+            registry::get_preload_tripswitch().trip();
+        }
+    }
+
     /// Remove a table without doing any reference checks. This will just pull it off
     pub unsafe fn force_remove_table(&self, tblid: &ObjectID) {
         // atomic remember? nobody cares about the result
@@ -449,3 +622,127 @@ fn test_keyspace_try_delete_protected_table() {
         DdlError::ProtectedObject
     );
 }
+
+#[test]
+fn test_keyspace_rename_table() {
+    let our_keyspace = Keyspace::empty_default();
+    assert!(our_keyspace.create_table(
+        unsafe_objectid_from_slice!("apps"),
+        Table::new_default_kve()
+    ));
+    assert!(our_keyspace
+        .rename_table(
+            &unsafe_objectid_from_slice!("apps"),
+            unsafe_objectid_from_slice!("myapps")
+        )
+        .is_ok());
+    assert!(our_keyspace
+        .get_table_atomic_ref(&unsafe_objectid_from_slice!("apps"))
+        .is_none());
+    assert!(our_keyspace
+        .get_table_atomic_ref(&unsafe_objectid_from_slice!("myapps"))
+        .is_some());
+}
+
+#[test]
+fn test_keyspace_rename_table_fail_with_atomic_ref() {
+    let our_keyspace = Keyspace::empty_default();
+    assert!(our_keyspace.create_table(
+        unsafe_objectid_from_slice!("apps"),
+        Table::new_default_kve()
+    ));
+    let _atomic_tbl_ref = our_keyspace
+        .get_table_atomic_ref(&unsafe_objectid_from_slice!("apps"))
+        .unwrap();
+    assert_eq!(
+        our_keyspace
+            .rename_table(
+                &unsafe_objectid_from_slice!("apps"),
+                unsafe_objectid_from_slice!("myapps")
+            )
+            .unwrap_err(),
+        DdlError::StillInUse
+    );
+    // the table is still there under its older name
+    assert!(our_keyspace
+        .get_table_atomic_ref(&unsafe_objectid_from_slice!("apps"))
+        .is_some());
+}
+
+#[test]
+fn test_keyspace_rename_table_already_exists() {
+    let our_keyspace = Keyspace::empty_default();
+    assert!(our_keyspace.create_table(
+        unsafe_objectid_from_slice!("apps"),
+        Table::new_default_kve()
+    ));
+    assert_eq!(
+        our_keyspace
+            .rename_table(&unsafe_objectid_from_slice!("apps"), DEFAULT)
+            .unwrap_err(),
+        DdlError::AlreadyExists
+    );
+}
+
+#[test]
+fn test_keyspace_quotas() {
+    use crate::corestore::Data;
+    let our_keyspace = Keyspace::empty_default();
+    let default = our_keyspace.get_table_atomic_ref(&DEFAULT).unwrap();
+    let kve = default.get_kvstore().unwrap();
+    kve.set(Data::from("hello"), Data::from("world")).unwrap();
+    assert_eq!(our_keyspace.usage(), (1, 5));
+    // no limits
+    assert!(our_keyspace.quota_tracker().is_none());
+    assert!(our_keyspace.allows_write(WriteSize::new(1000, 1000, 1000)));
+    our_keyspace.quotas().set(Quota::Tables, 2);
+    our_keyspace.quotas().set(Quota::Bytes, 9);
+    assert!(our_keyspace.allows_write(WriteSize::new(1, 4, 4)));
+    assert!(!our_keyspace.allows_write(WriteSize::new(1, 5, 5)));
+    // a copy of the default table would go over the byte limit
+    assert!(our_keyspace.allows_table(&Table::new_default_kve()));
+    assert!(!our_keyspace.allows_table(&default.duplicate()));
+    assert!(our_keyspace.create_table(
+        unsafe_objectid_from_slice!("apps"),
+        Table::new_default_kve()
+    ));
+    // and now there are too many tables
+    assert!(!our_keyspace.allows_table(&Table::new_default_kve()));
+}
+
+#[test]
+fn test_keyspace_quotas_with_concurrent_writers() {
+    use crate::corestore::Data;
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+    let our_keyspace = Arc::new(Keyspace::empty_default());
+    our_keyspace.quotas().set(Quota::Keys, 10);
+    let barrier = Arc::new(Barrier::new(8));
+    let writers: Vec<_> = (0..8)
+        .map(|writer| {
+            let keyspace = our_keyspace.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let default = keyspace.get_table_atomic_ref(&DEFAULT).unwrap();
+                let kve = default.get_kvstore().unwrap();
+                let mut added = 0;
+                barrier.wait();
+                for key in 0..10 {
+                    let key = Data::from(format!("{}-{}", writer, key));
+                    // the tracker is held until the key is in, so the other writers can't
+                    // check the quotas in the meantime
+                    if let Ok(_quota) = keyspace.check_write(|| WriteSize::new(1, 5, 5)) {
+                        thread::sleep(Duration::from_millis(1));
+                        kve.set(key, Data::from("hello")).unwrap();
+                        added += 1;
+                    }
+                }
+                added
+            })
+        })
+        .collect();
+    let added: usize = writers.into_iter().map(|w| w.join().unwrap()).sum();
+    assert_eq!(added, 10);
+    assert_eq!(our_keyspace.usage(), (10, 50));
+}
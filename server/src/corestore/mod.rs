@@ -29,9 +29,14 @@ use crate::corestore::memstore::Keyspace;
 use crate::corestore::memstore::Memstore;
 use crate::corestore::memstore::ObjectID;
 use crate::corestore::memstore::DEFAULT;
+use crate::corestore::quota::{Quota, WriteSize};
 use crate::corestore::table::Table;
 use crate::dbnet::connection::ProtocolConnectionExt;
+use crate::kvengine::HashEngine;
 use crate::kvengine::KVEngine;
+use crate::kvengine::SetEngine;
+use crate::kvengine::SortedSetEngine;
+use crate::kvengine::TimeSeriesEngine;
 use crate::protocol::responses;
 use crate::protocol::Query;
 use crate::queryengine;
@@ -54,6 +59,7 @@ pub mod lazy;
 pub mod lock;
 pub mod map;
 pub mod memstore;
+pub mod quota;
 pub mod table;
 #[cfg(test)]
 mod tests;
@@ -95,6 +101,20 @@ impl<'a> From<BorrowedEntityGroupRaw<'a>> for BorrowedEntityGroup<'a> {
     }
 }
 
+/// Returns the name of an entity in the `keyspace:table` form (or just `keyspace` if
+/// there is no table)
+pub fn entity_name(entity: &OwnedEntityGroup) -> String {
+    match entity {
+        (Some(ks), Some(tbl)) => format!(
+            "{}:{}",
+            String::from_utf8_lossy(ks),
+            String::from_utf8_lossy(tbl)
+        ),
+        (Some(ks), None) => String::from_utf8_lossy(ks).into_owned(),
+        _ => String::new(),
+    }
+}
+
 /// The top level abstraction for the in-memory store. This is free to be shared across
 /// threads, cloned and well, whatever. Most importantly, clones have an independent container
 /// state that is the state of one connection and its container state preferences are never
@@ -105,6 +125,8 @@ pub struct Corestore {
     cks: Option<Arc<Keyspace>>,
     /// the current table for this instance of the object
     ctable: Option<Arc<Table>>,
+    /// the name of the current entity (keyspace and table) for this instance of the object
+    centity: Arc<OwnedEntityGroup>,
     /// an atomic reference to the actual backing storage
     store: Arc<Memstore>,
     /// the snapshot engine
@@ -127,6 +149,7 @@ impl Corestore {
         Self {
             cks: Some(cks),
             ctable: Some(ctable),
+            centity: Arc::new((Some(DEFAULT), Some(DEFAULT))),
             store: Arc::new(store),
             sengine,
         }
@@ -151,6 +174,10 @@ impl Corestore {
                 Some(ksref) => {
                     self.cks = Some(ksref);
                     self.ctable = None;
+                    self.centity = Arc::new(unsafe {
+                        // SAFETY: The keyspace exists, so the length is valid
+                        (Some(ObjectID::from_slice(ks)), None)
+                    });
                 }
                 None => return Err(DdlError::ObjectNotFound),
             },
@@ -160,7 +187,16 @@ impl Corestore {
                 vb: Some(tbl),
             } => match self.store.get_keyspace_atomic_ref(ks) {
                 Some(kspace) => match kspace.get_table_atomic_ref(tbl) {
-                    Some(tblref) => self.ctable = Some(tblref),
+                    Some(tblref) => {
+                        self.ctable = Some(tblref);
+                        self.centity = Arc::new(unsafe {
+                            // SAFETY: The table exists, so the lengths are valid
+                            (
+                                Some(ObjectID::from_slice(ks)),
+                                Some(ObjectID::from_slice(tbl)),
+                            )
+                        });
+                    }
                     None => return Err(DdlError::ObjectNotFound),
                 },
                 None => return Err(DdlError::ObjectNotFound),
@@ -169,6 +205,15 @@ impl Corestore {
         }
         Ok(())
     }
+    /// Returns the name of the current entity in the `keyspace:table` form (or just
+    /// `keyspace` if no table is selected)
+    pub fn get_centity_name(&self) -> String {
+        entity_name(&self.centity)
+    }
+    /// Returns an atomic reference to the current entity
+    pub fn get_centity(&self) -> Arc<OwnedEntityGroup> {
+        self.centity.clone()
+    }
     pub fn get_keyspace<Q>(&self, ksid: &Q) -> Option<Arc<Keyspace>>
     where
         ObjectID: Borrow<Q>,
@@ -202,9 +247,54 @@ impl Corestore {
             _ => unsafe { impossible!() },
         }
     }
+    /// Get an atomic reference to a table, along with the ID of its keyspace and its own ID
+    pub fn get_table_with_ids(
+        &self,
+        entity: BorrowedEntityGroup,
+    ) -> KeyspaceResult<(ObjectID, ObjectID, Arc<Table>)> {
+        let (ksid, ks, tblid) = self.get_table_keyspace(entity)?;
+        match ks.get_table_atomic_ref(&tblid) {
+            Some(tbl) => Ok((ksid, tblid, tbl)),
+            None => Err(DdlError::ObjectNotFound),
+        }
+    }
     pub fn get_ctable(&self) -> Option<Arc<Table>> {
         self.ctable.clone()
     }
+    /// Returns a copy of this instance with its current table set to the given table,
+    /// which is resolved just like [`Corestore::get_table`] does. The current table of
+    /// this instance is left untouched
+    pub fn with_table(&self, entity: BorrowedEntityGroup) -> KeyspaceResult<Self> {
+        let centity = match entity {
+            BorrowedEntityGroup {
+                va: Some(ks),
+                vb: Some(tbl),
+            } => (Some(ks), tbl),
+            BorrowedEntityGroup {
+                va: Some(tbl),
+                vb: None,
+            } => (None, tbl),
+            _ => unsafe { impossible!() },
+        };
+        let ctable = self.get_table(entity)?;
+        let centity = unsafe {
+            // SAFETY: The table exists, so the lengths are valid
+            match centity {
+                (Some(ks), tbl) => (
+                    Some(ObjectID::from_slice(ks)),
+                    Some(ObjectID::from_slice(tbl)),
+                ),
+                (None, tbl) => (self.centity.0.clone(), Some(ObjectID::from_slice(tbl))),
+            }
+        };
+        Ok(Self {
+            cks: self.cks.clone(),
+            ctable: Some(ctable),
+            centity: Arc::new(centity),
+            store: self.store.clone(),
+            sengine: self.sengine.clone(),
+        })
+    }
 
     /// Get the key/value store
     ///
@@ -220,6 +310,50 @@ impl Corestore {
         }
     }
 
+    /// Get the hash store
+    ///
+    /// `Err`s are propagated if the target table has an incorrect table or if
+    /// the default table is unset
+    pub fn get_hashstore(&self) -> KeyspaceResult<&HashEngine> {
+        match &self.ctable {
+            Some(tbl) => tbl.get_hashstore(),
+            None => Err(DdlError::DefaultNotFound),
+        }
+    }
+
+    /// Get the set store
+    ///
+    /// `Err`s are propagated if the target table has an incorrect table or if
+    /// the default table is unset
+    pub fn get_setstore(&self) -> KeyspaceResult<&SetEngine> {
+        match &self.ctable {
+            Some(tbl) => tbl.get_setstore(),
+            None => Err(DdlError::DefaultNotFound),
+        }
+    }
+
+    /// Get the sorted set store
+    ///
+    /// `Err`s are propagated if the target table has an incorrect table or if
+    /// the default table is unset
+    pub fn get_sortedsetstore(&self) -> KeyspaceResult<&SortedSetEngine> {
+        match &self.ctable {
+            Some(tbl) => tbl.get_sortedsetstore(),
+            None => Err(DdlError::DefaultNotFound),
+        }
+    }
+
+    /// Get the time series store
+    ///
+    /// `Err`s are propagated if the target table has an incorrect table or if
+    /// the default table is unset
+    pub fn get_timeseriesstore(&self) -> KeyspaceResult<&TimeSeriesEngine> {
+        match &self.ctable {
+            Some(tbl) => tbl.get_timeseriesstore(),
+            None => Err(DdlError::DefaultNotFound),
+        }
+    }
+
     /// Create a table: in-memory; **no transactional guarantees**. Two tables can be created
     /// simultaneously, but are never flushed unless we are very lucky. If the global flush
     /// system is close to a flush cycle -- then we are in luck: we pause the flush cycle
@@ -234,6 +368,36 @@ impl Corestore {
         modelcode: u8,
         volatile: bool,
     ) -> KeyspaceResult<()> {
+        match Table::from_model_code(modelcode, volatile) {
+            Some(tbl) => self.create_table_with(entity, tbl),
+            None => Err(DdlError::WrongModel),
+        }
+    }
+
+    /// Create a table just like the table `like`, along with a copy of its data if `with_data`
+    /// is set. The copy is made before the table is created, so the new table is never seen
+    /// half-filled (see [`Corestore::create_table`] for the guarantees)
+    ///
+    /// **Trip switch handled:** Yes
+    pub fn create_table_like(
+        &self,
+        entity: OwnedEntityGroup,
+        like: BorrowedEntityGroup,
+        with_data: bool,
+    ) -> KeyspaceResult<()> {
+        let like = self.get_table(like)?;
+        let tbl = if with_data {
+            like.duplicate()
+        } else {
+            like.new_like()
+        };
+        self.create_table_with(entity, tbl)
+    }
+
+    /// Add an already initialized table (see [`Corestore::create_table`] for the guarantees)
+    ///
+    /// **Trip switch handled:** Yes
+    fn create_table_with(&self, entity: OwnedEntityGroup, tbl: Table) -> KeyspaceResult<()> {
         // first lock the global flush state
         let flush_lock = registry::lock_flush_state();
         let ret;
@@ -241,18 +405,14 @@ impl Corestore {
             // Important: create table <tblname> is only ks
             (Some(tblid), None) => {
                 ret = match &self.cks {
+                    Some(ks) if !ks.allows_table(&tbl) => Err(DdlError::QuotaExceeded),
                     Some(ks) => {
-                        let tbl = Table::from_model_code(modelcode, volatile);
-                        if let Some(tbl) = tbl {
-                            if ks.create_table(tblid, tbl) {
-                                // we need to re-init tree; so trip
-                                registry::get_preload_tripswitch().trip();
-                                Ok(())
-                            } else {
-                                Err(DdlError::AlreadyExists)
-                            }
+                        if ks.create_table(tblid, tbl) {
+                            // we need to re-init tree; so trip
+                            registry::get_preload_tripswitch().trip();
+                            Ok(())
                         } else {
-                            Err(DdlError::WrongModel)
+                            Err(DdlError::AlreadyExists)
                         }
                     }
                     None => Err(DdlError::DefaultNotFound),
@@ -260,18 +420,14 @@ impl Corestore {
             }
             (Some(ksid), Some(tblid)) => {
                 ret = match self.store.get_keyspace_atomic_ref(&ksid) {
+                    Some(kspace) if !kspace.allows_table(&tbl) => Err(DdlError::QuotaExceeded),
                     Some(kspace) => {
-                        let tbl = Table::from_model_code(modelcode, volatile);
-                        if let Some(tbl) = tbl {
-                            if kspace.create_table(tblid, tbl) {
-                                // trip the preload switch
-                                registry::get_preload_tripswitch().trip();
-                                Ok(())
-                            } else {
-                                Err(DdlError::AlreadyExists)
-                            }
+                        if kspace.create_table(tblid, tbl) {
+                            // trip the preload switch
+                            registry::get_preload_tripswitch().trip();
+                            Ok(())
                         } else {
-                            Err(DdlError::WrongModel)
+                            Err(DdlError::AlreadyExists)
                         }
                     }
                     None => Err(DdlError::ObjectNotFound),
@@ -305,6 +461,160 @@ impl Corestore {
         }
     }
 
+    /// Resolve the keyspace of a table, returning the ID of the keyspace, the keyspace and
+    /// the ID of the table
+    fn get_table_keyspace(
+        &self,
+        entity: BorrowedEntityGroup,
+    ) -> KeyspaceResult<(ObjectID, Arc<Keyspace>, ObjectID)> {
+        match entity {
+            BorrowedEntityGroup {
+                va: Some(tblid),
+                vb: None,
+            } => match (&self.centity.0, &self.cks) {
+                (Some(ksid), Some(ks)) => Ok((ksid.clone(), ks.clone(), unsafe {
+                    // SAFETY: The length of the entity was validated by the parser
+                    ObjectID::from_slice(tblid)
+                })),
+                _ => Err(DdlError::DefaultNotFound),
+            },
+            BorrowedEntityGroup {
+                va: Some(ksid),
+                vb: Some(tblid),
+            } => match self.store.get_keyspace_atomic_ref(ksid) {
+                Some(ks) => Ok(unsafe {
+                    // SAFETY: The keyspace exists and the length of the table was validated
+                    // by the parser
+                    (ObjectID::from_slice(ksid), ks, ObjectID::from_slice(tblid))
+                }),
+                None => Err(DdlError::ObjectNotFound),
+            },
+            _ => unsafe { impossible!() },
+        }
+    }
+
+    /// Check the outcome of persisting the effects of a DDL query. If that failed, then the
+    /// on-disk layout might only be partly updated, so we poison the system state (just like
+    /// a failed BGSAVE would). The caller has to undo the changes in memory, so that the
+    /// query either takes effect or fails as a whole
+    fn persist_ddl(result: IoResult<()>) -> KeyspaceResult<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                log::error!("Failed to persist DDL query: {}", e);
+                registry::poison();
+                Err(DdlError::DdlTransactionFailure)
+            }
+        }
+    }
+
+    /// Rename a table (it stays in the same keyspace) if no one is using it. The on-disk
+    /// layout is updated before this returns, and the rename is undone if that fails
+    ///
+    /// **Trip switch handled:** Yes
+    pub fn rename_table(
+        &self,
+        entity: BorrowedEntityGroup,
+        new_tblid: ObjectID,
+    ) -> KeyspaceResult<()> {
+        let (ksid, ks, tblid) = self.get_table_keyspace(entity)?;
+        // lock the global flush state so that BGSAVE doesn't see a half-renamed table
+        let flush_lock = registry::lock_flush_state();
+        let ret = ks.rename_table(&tblid, new_tblid.clone()).and_then(|_| {
+            Self::persist_ddl(storage::flush::flush_renamed_table(
+                &ksid, &ks, &tblid, &new_tblid,
+            ))
+            .inspect_err(|_| {
+                ks.undo_rename_table(&new_tblid, tblid.clone());
+            })
+        });
+        drop(flush_lock);
+        ret
+    }
+
+    /// Rename a keyspace if no one is using it or any of its tables. The on-disk layout
+    /// is updated before this returns, and the rename is undone if that fails
+    ///
+    /// **Trip switch handled:** Yes
+    pub fn rename_keyspace(&self, ksid: ObjectID, new_ksid: ObjectID) -> KeyspaceResult<()> {
+        // lock the global flush state (see rename_table)
+        let flush_lock = registry::lock_flush_state();
+        let ret = self
+            .store
+            .rename_keyspace(&ksid, new_ksid.clone())
+            .and_then(|_| {
+                Self::persist_ddl(storage::flush::flush_renamed_keyspace(
+                    &self.store,
+                    &ksid,
+                    &new_ksid,
+                ))
+                .inspect_err(|_| {
+                    self.store.undo_rename_keyspace(&new_ksid, ksid.clone());
+                })
+            });
+        drop(flush_lock);
+        ret
+    }
+
+    /// Switch a table between being volatile and persistent. Unlike renames, this is
+    /// fine even if the table is in use. The on-disk layout is updated before this returns,
+    /// and the table is switched back if that fails
+    pub fn alter_table(&self, entity: BorrowedEntityGroup, volatile: bool) -> KeyspaceResult<()> {
+        let (ksid, ks, tblid) = self.get_table_keyspace(entity)?;
+        let table = match ks.get_table_atomic_ref(&tblid) {
+            Some(table) => table,
+            None => return Err(DdlError::ObjectNotFound),
+        };
+        // lock the global flush state (see rename_table)
+        let flush_lock = registry::lock_flush_state();
+        let ret = if table.set_volatile(volatile) == volatile {
+            // nothing to do here
+            Ok(())
+        } else {
+            Self::persist_ddl(storage::flush::flush_altered_table(
+                &ksid, &ks, &tblid, &table,
+            ))
+            .inspect_err(|_| {
+                table.set_volatile(!volatile);
+            })
+        };
+        drop(flush_lock);
+        ret
+    }
+
+    /// Set the limit of a quota of a keyspace (`0` removes the limit). Limits that are
+    /// lower than the current usage only stop new writes; nothing is removed. The on-disk
+    /// layout is updated before this returns
+    pub fn set_quota(&self, ksid: &ObjectID, quota: Quota, limit: u64) -> KeyspaceResult<()> {
+        let ks = match self.store.get_keyspace_atomic_ref(ksid) {
+            Some(ks) => ks,
+            None => return Err(DdlError::ObjectNotFound),
+        };
+        // lock the global flush state (see rename_table)
+        let flush_lock = registry::lock_flush_state();
+        ks.quotas().set(quota, limit);
+        let ret = Self::persist_ddl(storage::flush::flush_keyspace_quotas(ksid, &ks));
+        drop(flush_lock);
+        ret
+    }
+
+    /// Returns true if the quotas of the keyspace of the current table allow a write of the
+    /// size returned by `size` to it. The size is only computed if the keyspace has quotas
+    pub fn quota_allows(&self, size: impl FnOnce() -> WriteSize) -> bool {
+        match self.get_ctable_keyspace() {
+            Some(ks) => ks.quotas().is_unlimited() || ks.allows_write(size()),
+            None => true,
+        }
+    }
+
+    /// Returns an atomic reference to the keyspace of the current table
+    pub fn get_ctable_keyspace(&self) -> Option<Arc<Keyspace>> {
+        self.centity
+            .0
+            .as_ref()
+            .and_then(|ksid| self.store.get_keyspace_atomic_ref(ksid))
+    }
+
     /// Create a keyspace **without any transactional guarantees**
     ///
     /// **Trip switch handled:** Yes
@@ -0,0 +1,255 @@
+/*
+ * Created on Wed Sep 29 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Quotas
+//!
+//! Every keyspace can be given limits on the number of tables it holds, on the number of keys
+//! and on the total size of the values across all its tables and on the size of any single
+//! value. A limit of `0` means that there is no limit, which is what every keyspace starts with.
+//! For tables that map keys to collections, the size of a value is the footprint of the
+//! collection: the fields and values of a hash, the members of a set (along with their scores
+//! in a sorted set) and the samples of a series. For these tables, the value size limit
+//! applies to the values of a hash and to the members of a set.
+//!
+//! Writes are checked against the quotas of the keyspace of the table they target before they
+//! run, by looking at the values that they would replace. For a keyspace with limits, the check
+//! and the write happen under the quota lock of the keyspace (held by a [`QuotaTracker`]), so
+//! concurrent writes can't all pass the check and go over a limit together. Removals don't
+//! take the lock and are always allowed, so lowering a limit below the current usage only stops
+//! new writes
+
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use parking_lot::MutexGuard;
+
+/// The number of quotas that a keyspace has
+pub const QUOTA_COUNT: usize = 4;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// A limit that can be set on a keyspace
+pub enum Quota {
+    /// The maximum number of tables
+    Tables = 0,
+    /// The maximum number of keys across all the tables
+    Keys = 1,
+    /// The maximum size (in bytes) of all the values across all the tables
+    Bytes = 2,
+    /// The maximum size (in bytes) of a single value
+    ValueSize = 3,
+}
+
+impl Quota {
+    /// Returns the quota with the given name (as used in `ALTER KEYSPACE`)
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        let quota = match name {
+            b"max_tables" => Self::Tables,
+            b"max_keys" => Self::Keys,
+            b"max_bytes" => Self::Bytes,
+            b"max_value_size" => Self::ValueSize,
+            _ => return None,
+        };
+        Some(quota)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+/// The size of a write to a table
+pub struct WriteSize {
+    /// the number of keys that the write adds
+    keys: usize,
+    /// the number of bytes that the write adds
+    bytes: usize,
+    /// the size of the largest value that is written
+    largest: usize,
+}
+
+impl WriteSize {
+    pub const fn new(keys: usize, bytes: usize, largest: usize) -> Self {
+        Self {
+            keys,
+            bytes,
+            largest,
+        }
+    }
+    /// Add a value of the given size to this write, which replaces the value of the
+    /// given size (or adds a key if there's no older value)
+    pub fn with_value(self, older: Option<usize>, value: usize) -> Self {
+        let (keys, bytes) = match older {
+            Some(older) => (0, value.saturating_sub(older)),
+            None => (1, value),
+        };
+        Self::new(
+            self.keys + keys,
+            self.bytes + bytes,
+            self.largest.max(value),
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+/// The quotas of a keyspace
+pub struct Quotas {
+    limits: [AtomicU64; QUOTA_COUNT],
+}
+
+impl Quotas {
+    /// Create a new set of quotas without any limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Create a new set of quotas from the given limits (in the order of [`Quota`])
+    pub fn from_array(limits: [u64; QUOTA_COUNT]) -> Self {
+        let [max_tables, max_keys, max_bytes, max_value_size] = limits;
+        Self {
+            limits: [
+                AtomicU64::new(max_tables),
+                AtomicU64::new(max_keys),
+                AtomicU64::new(max_bytes),
+                AtomicU64::new(max_value_size),
+            ],
+        }
+    }
+    /// Returns the limits (in the order of [`Quota`])
+    pub fn to_array(&self) -> [u64; QUOTA_COUNT] {
+        [
+            self.get(Quota::Tables),
+            self.get(Quota::Keys),
+            self.get(Quota::Bytes),
+            self.get(Quota::ValueSize),
+        ]
+    }
+    /// Returns the limit for the given quota (`0` if there's no limit)
+    pub fn get(&self, quota: Quota) -> u64 {
+        self.limits[quota as usize].load(Ordering::Acquire)
+    }
+    /// Set the limit for the given quota (`0` removes the limit)
+    pub fn set(&self, quota: Quota, limit: u64) {
+        self.limits[quota as usize].store(limit, Ordering::Release)
+    }
+    /// Returns true if none of the quotas has a limit
+    pub fn is_unlimited(&self) -> bool {
+        self.to_array().iter().all(|limit| *limit == 0)
+    }
+    /// Returns true if `value` is within the limit of the given quota
+    pub fn allows(&self, quota: Quota, value: usize) -> bool {
+        let limit = self.get(quota);
+        limit == 0 || value as u64 <= limit
+    }
+    /// Returns true if a write of the given size is fine for a keyspace that currently has
+    /// `keys` keys and `bytes` bytes of values
+    pub fn allows_write(&self, keys: usize, bytes: usize, size: WriteSize) -> bool {
+        self.allows(Quota::ValueSize, size.largest)
+            && self.allows(Quota::Keys, keys + size.keys)
+            && self.allows(Quota::Bytes, bytes + size.bytes)
+    }
+}
+
+/// The quotas of a keyspace along with its usage, for checking a series of writes while the
+/// usage can't be looked up (like when the shards of a table are locked). This holds the
+/// quota lock of the keyspace, so it has to be kept until the writes are done
+pub struct QuotaTracker<'a> {
+    quotas: &'a Quotas,
+    _lock: MutexGuard<'a, ()>,
+    keys: usize,
+    bytes: usize,
+}
+
+impl<'a> QuotaTracker<'a> {
+    pub const fn new(
+        quotas: &'a Quotas,
+        lock: MutexGuard<'a, ()>,
+        keys: usize,
+        bytes: usize,
+    ) -> Self {
+        Self {
+            quotas,
+            _lock: lock,
+            keys,
+            bytes,
+        }
+    }
+    /// Returns true (and records the write) if a write of the given size is fine
+    pub fn allow(&mut self, size: WriteSize) -> bool {
+        let allowed = self.quotas.allows_write(self.keys, self.bytes, size);
+        if allowed {
+            self.keys += size.keys;
+            self.bytes += size.bytes;
+        }
+        allowed
+    }
+}
+
+#[test]
+fn test_quota_from_name() {
+    assert_eq!(Quota::from_name(b"max_tables"), Some(Quota::Tables));
+    assert_eq!(Quota::from_name(b"max_value_size"), Some(Quota::ValueSize));
+    assert_eq!(Quota::from_name(b"MAX_KEYS"), None);
+    assert_eq!(Quota::from_name(b"max_values"), None);
+}
+
+#[test]
+fn test_write_size() {
+    let size = WriteSize::default()
+        .with_value(None, 5)
+        .with_value(Some(2), 6)
+        .with_value(Some(10), 3);
+    assert_eq!(size, WriteSize::new(1, 9, 6));
+}
+
+#[test]
+fn test_quotas() {
+    let quotas = Quotas::new();
+    assert!(quotas.is_unlimited());
+    assert!(quotas.allows_write(usize::MAX / 2, usize::MAX / 2, WriteSize::new(1, 0, 1024)));
+    quotas.set(Quota::Keys, 10);
+    quotas.set(Quota::ValueSize, 4);
+    assert!(!quotas.is_unlimited());
+    assert_eq!(quotas.to_array(), [0, 10, 0, 4]);
+    assert_eq!(quotas.get(Quota::Tables), 0);
+    assert!(quotas.allows_write(9, 100, WriteSize::new(1, 0, 4)));
+    // too many keys
+    assert!(!quotas.allows_write(10, 100, WriteSize::new(1, 0, 4)));
+    // an update doesn't add keys
+    assert!(quotas.allows_write(10, 100, WriteSize::new(0, 0, 4)));
+    // value too large
+    assert!(!quotas.allows_write(0, 0, WriteSize::new(1, 0, 5)));
+    quotas.set(Quota::Bytes, 100);
+    assert!(quotas.allows_write(0, 96, WriteSize::new(1, 4, 4)));
+    assert!(!quotas.allows_write(0, 97, WriteSize::new(1, 4, 4)));
+    quotas.set(Quota::Bytes, 0);
+    quotas.set(Quota::Keys, 0);
+    assert!(quotas.allows_write(100, 100, WriteSize::new(1, 0, 4)));
+    let lock = parking_lot::Mutex::new(());
+    let mut tracker = QuotaTracker::new(&quotas, lock.lock(), 0, 0);
+    assert!(tracker.allow(WriteSize::new(1, 4, 4)));
+    assert!(tracker.allow(WriteSize::new(1, 4, 4)));
+    quotas.set(Quota::Keys, 2);
+    assert!(!tracker.allow(WriteSize::new(1, 4, 4)));
+    assert!(tracker.allow(WriteSize::new(0, 4, 4)));
+    quotas.set(Quota::Keys, 0);
+    let restored = Quotas::from_array(quotas.to_array());
+    assert_eq!(restored.to_array(), [0, 0, 0, 4]);
+}
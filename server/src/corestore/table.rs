@@ -28,12 +28,185 @@ use crate::corestore::htable::Coremap;
 use crate::corestore::memstore::DdlError;
 use crate::corestore::Data;
 use crate::corestore::KeyspaceResult;
+use crate::kvengine::hashmap::FieldMap;
+use crate::kvengine::hashset::MemberSet;
+use crate::kvengine::sortedset::ScoreSet;
+use crate::kvengine::timeseries::Series;
+use crate::kvengine::HashEngine;
 use crate::kvengine::KVEngine;
+use crate::kvengine::SetEngine;
+use crate::kvengine::SortedSetEngine;
+use crate::kvengine::TimeSeriesEngine;
+use crate::kvengine::ValueType;
+use crate::kvengine::Versioned;
 use crate::storage::bytemarks;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+
+const ORD_ACQ: Ordering = Ordering::Acquire;
+const ORD_ACR: Ordering = Ordering::AcqRel;
+
+/// The model codes of key/value tables along with their key encoding and value type. See
+/// [`bytemarks`] for more info
+const KV_MODEL_CODES: [(u8, bool, ValueType); 12] = [
+    (
+        bytemarks::BYTEMARK_MODEL_KV_BIN_BIN,
+        false,
+        ValueType::Binstr,
+    ),
+    (bytemarks::BYTEMARK_MODEL_KV_BIN_STR, false, ValueType::Str),
+    (bytemarks::BYTEMARK_MODEL_KV_STR_STR, true, ValueType::Str),
+    (
+        bytemarks::BYTEMARK_MODEL_KV_STR_BIN,
+        true,
+        ValueType::Binstr,
+    ),
+    (
+        bytemarks::BYTEMARK_MODEL_KV_BIN_UINT,
+        false,
+        ValueType::UInt,
+    ),
+    (
+        bytemarks::BYTEMARK_MODEL_KV_BIN_SINT,
+        false,
+        ValueType::SInt,
+    ),
+    (
+        bytemarks::BYTEMARK_MODEL_KV_BIN_FLOAT,
+        false,
+        ValueType::Float,
+    ),
+    (
+        bytemarks::BYTEMARK_MODEL_KV_BIN_BOOL,
+        false,
+        ValueType::Bool,
+    ),
+    (bytemarks::BYTEMARK_MODEL_KV_STR_UINT, true, ValueType::UInt),
+    (bytemarks::BYTEMARK_MODEL_KV_STR_SINT, true, ValueType::SInt),
+    (
+        bytemarks::BYTEMARK_MODEL_KV_STR_FLOAT,
+        true,
+        ValueType::Float,
+    ),
+    (bytemarks::BYTEMARK_MODEL_KV_STR_BOOL, true, ValueType::Bool),
+];
+
+/// Returns the model code of a key/value table with the given key encoding and value type
+pub fn kv_model_code(k_enc: bool, v_type: ValueType) -> u8 {
+    match KV_MODEL_CODES
+        .iter()
+        .find(|(_, code_k_enc, code_v_type)| *code_k_enc == k_enc && *code_v_type == v_type)
+    {
+        Some((code, _, _)) => *code,
+        None => unsafe { impossible!() },
+    }
+}
+
+/// Returns the key encoding and the value type of a key/value model code
+pub fn kv_types(code: u8) -> Option<(bool, ValueType)> {
+    KV_MODEL_CODES
+        .iter()
+        .find(|(model_code, _, _)| *model_code == code)
+        .map(|(_, k_enc, v_type)| (*k_enc, *v_type))
+}
+
+/// Returns the model code of a hash table with the given key (and field) and value encoding
+pub const fn hash_model_code(k_enc: bool, v_enc: bool) -> u8 {
+    match (k_enc, v_enc) {
+        (false, false) => bytemarks::BYTEMARK_MODEL_HASH_BIN_BIN,
+        (false, true) => bytemarks::BYTEMARK_MODEL_HASH_BIN_STR,
+        (true, true) => bytemarks::BYTEMARK_MODEL_HASH_STR_STR,
+        (true, false) => bytemarks::BYTEMARK_MODEL_HASH_STR_BIN,
+    }
+}
+
+/// Returns the key (and field) and value encoding of a hash model code
+pub const fn hash_encoding(code: u8) -> Option<(bool, bool)> {
+    match code {
+        bytemarks::BYTEMARK_MODEL_HASH_BIN_BIN => Some((false, false)),
+        bytemarks::BYTEMARK_MODEL_HASH_BIN_STR => Some((false, true)),
+        bytemarks::BYTEMARK_MODEL_HASH_STR_STR => Some((true, true)),
+        bytemarks::BYTEMARK_MODEL_HASH_STR_BIN => Some((true, false)),
+        _ => None,
+    }
+}
+
+/// Returns the model code of a set table with the given key and member encoding
+pub const fn set_model_code(k_enc: bool, m_enc: bool) -> u8 {
+    match (k_enc, m_enc) {
+        (false, false) => bytemarks::BYTEMARK_MODEL_SET_BIN_BIN,
+        (false, true) => bytemarks::BYTEMARK_MODEL_SET_BIN_STR,
+        (true, true) => bytemarks::BYTEMARK_MODEL_SET_STR_STR,
+        (true, false) => bytemarks::BYTEMARK_MODEL_SET_STR_BIN,
+    }
+}
+
+/// Returns the key and member encoding of a set model code
+pub const fn set_encoding(code: u8) -> Option<(bool, bool)> {
+    match code {
+        bytemarks::BYTEMARK_MODEL_SET_BIN_BIN => Some((false, false)),
+        bytemarks::BYTEMARK_MODEL_SET_BIN_STR => Some((false, true)),
+        bytemarks::BYTEMARK_MODEL_SET_STR_STR => Some((true, true)),
+        bytemarks::BYTEMARK_MODEL_SET_STR_BIN => Some((true, false)),
+        _ => None,
+    }
+}
+
+/// Returns the model code of a sorted set table with the given key and member encoding
+pub const fn sortedset_model_code(k_enc: bool, m_enc: bool) -> u8 {
+    match (k_enc, m_enc) {
+        (false, false) => bytemarks::BYTEMARK_MODEL_SORTEDSET_BIN_BIN,
+        (false, true) => bytemarks::BYTEMARK_MODEL_SORTEDSET_BIN_STR,
+        (true, true) => bytemarks::BYTEMARK_MODEL_SORTEDSET_STR_STR,
+        (true, false) => bytemarks::BYTEMARK_MODEL_SORTEDSET_STR_BIN,
+    }
+}
+
+/// Returns the key and member encoding of a sorted set model code
+pub const fn sortedset_encoding(code: u8) -> Option<(bool, bool)> {
+    match code {
+        bytemarks::BYTEMARK_MODEL_SORTEDSET_BIN_BIN => Some((false, false)),
+        bytemarks::BYTEMARK_MODEL_SORTEDSET_BIN_STR => Some((false, true)),
+        bytemarks::BYTEMARK_MODEL_SORTEDSET_STR_STR => Some((true, true)),
+        bytemarks::BYTEMARK_MODEL_SORTEDSET_STR_BIN => Some((true, false)),
+        _ => None,
+    }
+}
+
+/// Returns the model code of a time series table with the given key encoding
+pub const fn timeseries_model_code(k_enc: bool) -> u8 {
+    if k_enc {
+        bytemarks::BYTEMARK_MODEL_TIMESERIES_STR
+    } else {
+        bytemarks::BYTEMARK_MODEL_TIMESERIES_BIN
+    }
+}
+
+/// Returns the key encoding of a time series model code
+pub const fn timeseries_encoding(code: u8) -> Option<bool> {
+    match code {
+        bytemarks::BYTEMARK_MODEL_TIMESERIES_BIN => Some(false),
+        bytemarks::BYTEMARK_MODEL_TIMESERIES_STR => Some(true),
+        _ => None,
+    }
+}
+
+/// Returns the name of the type of a key/field or value with the given encoding
+const fn encoding_name(enc: bool) -> &'static str {
+    if enc {
+        "str"
+    } else {
+        "binstr"
+    }
+}
 
 #[derive(Debug)]
 pub enum DataModel {
     KV(KVEngine),
+    Hash(HashEngine),
+    Set(SetEngine),
+    SortedSet(SortedSetEngine),
+    TimeSeries(TimeSeriesEngine),
 }
 
 // same 8 byte ptrs; any chance of optimizations?
@@ -43,79 +216,351 @@ pub enum DataModel {
 pub struct Table {
     /// a key/value store
     model_store: DataModel,
-    /// is the table volatile
-    volatile: bool,
+    /// is the table volatile (this can be changed with `ALTER TABLE`)
+    volatile: AtomicBool,
 }
 
 impl Table {
     /// Get the key/value store if the table is a key/value store
     pub const fn get_kvstore(&self) -> KeyspaceResult<&KVEngine> {
-        #[allow(irrefutable_let_patterns)]
         if let DataModel::KV(kvs) = &self.model_store {
             Ok(kvs)
         } else {
             Err(DdlError::WrongModel)
         }
     }
+    /// Get the hash store if the table is a hash store
+    pub const fn get_hashstore(&self) -> KeyspaceResult<&HashEngine> {
+        if let DataModel::Hash(hash) = &self.model_store {
+            Ok(hash)
+        } else {
+            Err(DdlError::WrongModel)
+        }
+    }
+    /// Get the set store if the table is a set store
+    pub const fn get_setstore(&self) -> KeyspaceResult<&SetEngine> {
+        if let DataModel::Set(set) = &self.model_store {
+            Ok(set)
+        } else {
+            Err(DdlError::WrongModel)
+        }
+    }
+    /// Get the sorted set store if the table is a sorted set store
+    pub const fn get_sortedsetstore(&self) -> KeyspaceResult<&SortedSetEngine> {
+        if let DataModel::SortedSet(zset) = &self.model_store {
+            Ok(zset)
+        } else {
+            Err(DdlError::WrongModel)
+        }
+    }
+    /// Get the time series store if the table is a time series store
+    pub const fn get_timeseriesstore(&self) -> KeyspaceResult<&TimeSeriesEngine> {
+        if let DataModel::TimeSeries(ts) = &self.model_store {
+            Ok(ts)
+        } else {
+            Err(DdlError::WrongModel)
+        }
+    }
     pub fn count(&self) -> usize {
         match &self.model_store {
             DataModel::KV(kv) => kv.len(),
+            DataModel::Hash(hash) => hash.len(),
+            DataModel::Set(set) => set.len(),
+            DataModel::SortedSet(zset) => zset.len(),
+            DataModel::TimeSeries(ts) => ts.len(),
         }
     }
-    /// Returns this table's _description_
-    pub fn describe_self(&self) -> &'static str {
-        match self.get_model_code() {
-            0 if self.is_volatile() => "Keymap { data:(binstr,binstr), volatile:true }",
-            0 if !self.is_volatile() => "Keymap { data:(binstr,binstr), volatile:false }",
-            1 if self.is_volatile() => "Keymap { data:(binstr,str), volatile:true }",
-            1 if !self.is_volatile() => "Keymap { data:(binstr,str), volatile:false }",
-            2 if self.is_volatile() => "Keymap { data:(str,str), volatile:true }",
-            2 if !self.is_volatile() => "Keymap { data:(str,str), volatile:false }",
-            3 if self.is_volatile() => "Keymap { data:(str,binstr), volatile:true }",
-            3 if !self.is_volatile() => "Keymap { data:(str,binstr), volatile:false }",
-            _ => unsafe { impossible!() },
+    /// Returns the total footprint of the values in this table
+    pub fn value_bytes(&self) -> usize {
+        match &self.model_store {
+            DataModel::KV(kv) => kv.value_bytes(),
+            DataModel::Hash(hash) => hash.__get_inner_ref().value_bytes(),
+            DataModel::Set(set) => set.__get_inner_ref().value_bytes(),
+            DataModel::SortedSet(zset) => zset.__get_inner_ref().value_bytes(),
+            DataModel::TimeSeries(ts) => ts.__get_inner_ref().value_bytes(),
+        }
+    }
+    /// Returns the approximate number of bytes used by the data in this table
+    pub fn approx_mem_usage(&self) -> usize {
+        match &self.model_store {
+            DataModel::KV(kv) => kv.__get_inner_ref().approx_mem_usage(),
+            DataModel::Hash(hash) => hash.__get_inner_ref().approx_mem_usage(),
+            DataModel::Set(set) => set.__get_inner_ref().approx_mem_usage(),
+            DataModel::SortedSet(zset) => zset.__get_inner_ref().approx_mem_usage(),
+            DataModel::TimeSeries(ts) => ts.__get_inner_ref().approx_mem_usage(),
+        }
+    }
+    /// Returns the name of the model of this table
+    pub fn model_name(&self) -> &'static str {
+        match &self.model_store {
+            DataModel::KV(_) => "keymap",
+            DataModel::Hash(_) => "hashmap",
+            DataModel::Set(_) => "set",
+            DataModel::SortedSet(_) => "sortedset",
+            DataModel::TimeSeries(_) => "timeseries",
+        }
+    }
+    /// Returns the names of the types of the data in this table (for a key/value table, the
+    /// key type and the value type while for a hash table, the key (and field) type and the
+    /// value type, for a (sorted) set table, the key type and the member type and for a time
+    /// series table, the key type and the sample type)
+    pub fn data_types(&self) -> (&'static str, &'static str) {
+        match &self.model_store {
+            DataModel::KV(kv) => {
+                let (k_enc, v_type) = kv.get_types();
+                (encoding_name(k_enc), v_type.name())
+            }
+            DataModel::Hash(hash) => {
+                let (k_enc, v_enc) = hash.get_encoding();
+                (encoding_name(k_enc), encoding_name(v_enc))
+            }
+            DataModel::Set(set) => {
+                let (k_enc, m_enc) = set.get_encoding();
+                (encoding_name(k_enc), encoding_name(m_enc))
+            }
+            DataModel::SortedSet(zset) => {
+                let (k_enc, m_enc) = zset.get_encoding();
+                (encoding_name(k_enc), encoding_name(m_enc))
+            }
+            DataModel::TimeSeries(ts) => (encoding_name(ts.get_encoding()), "float"),
+        }
+    }
+    /// Returns the time (UNIX timestamp in seconds) of the last write to this table, or `0`
+    /// if it wasn't written to since startup
+    pub fn last_modified(&self) -> u64 {
+        match &self.model_store {
+            DataModel::KV(kv) => kv.__get_inner_ref().last_modified(),
+            DataModel::Hash(hash) => hash.__get_inner_ref().last_modified(),
+            DataModel::Set(set) => set.__get_inner_ref().last_modified(),
+            DataModel::SortedSet(zset) => zset.__get_inner_ref().last_modified(),
+            DataModel::TimeSeries(ts) => ts.__get_inner_ref().last_modified(),
         }
     }
     pub fn truncate_table(&self) {
         match self.model_store {
             DataModel::KV(ref kv) => kv.truncate_table(),
+            DataModel::Hash(ref hash) => {
+                hash.__get_inner_ref().truncate();
+            }
+            DataModel::Set(ref set) => {
+                set.__get_inner_ref().truncate();
+            }
+            DataModel::SortedSet(ref zset) => {
+                zset.__get_inner_ref().truncate();
+            }
+            DataModel::TimeSeries(ref ts) => {
+                ts.__get_inner_ref().truncate();
+            }
         }
     }
     /// Returns the storage type as an 8-bit uint
-    pub const fn storage_type(&self) -> u8 {
-        self.volatile as u8
+    pub fn storage_type(&self) -> u8 {
+        self.is_volatile() as u8
     }
     /// Returns the volatility of the table
-    pub const fn is_volatile(&self) -> bool {
-        self.volatile
+    pub fn is_volatile(&self) -> bool {
+        self.volatile.load(ORD_ACQ)
+    }
+    /// Set the volatility of the table, returning the older volatility
+    pub fn set_volatile(&self, volatile: bool) -> bool {
+        self.volatile.swap(volatile, ORD_ACR)
     }
     /// Create a new KVE Table with the provided settings
     pub fn new_kve_with_data(
-        data: Coremap<Data, Data>,
+        data: Coremap<Data, Versioned>,
         volatile: bool,
         k_enc: bool,
         v_enc: bool,
     ) -> Self {
         Self {
-            volatile,
+            volatile: AtomicBool::new(volatile),
             model_store: DataModel::KV(KVEngine::init_with_data(k_enc, v_enc, data)),
         }
     }
+    /// Create a new KVE Table with the provided settings and value type
+    pub fn new_kve_typed_with_data(
+        data: Coremap<Data, Versioned>,
+        volatile: bool,
+        k_enc: bool,
+        v_type: ValueType,
+    ) -> Self {
+        Self {
+            volatile: AtomicBool::new(volatile),
+            model_store: DataModel::KV(KVEngine::init_typed_with_data(k_enc, v_type, data)),
+        }
+    }
+    /// Create a new hash table with the provided settings
+    pub fn new_hash_with_data(
+        data: Coremap<Data, FieldMap>,
+        volatile: bool,
+        k_enc: bool,
+        v_enc: bool,
+    ) -> Self {
+        Self {
+            volatile: AtomicBool::new(volatile),
+            model_store: DataModel::Hash(HashEngine::init_with_data(k_enc, v_enc, data)),
+        }
+    }
+    /// Create a new set table with the provided settings
+    pub fn new_set_with_data(
+        data: Coremap<Data, MemberSet>,
+        volatile: bool,
+        k_enc: bool,
+        m_enc: bool,
+    ) -> Self {
+        Self {
+            volatile: AtomicBool::new(volatile),
+            model_store: DataModel::Set(SetEngine::init_with_data(k_enc, m_enc, data)),
+        }
+    }
+    /// Create a new sorted set table with the provided settings
+    pub fn new_sortedset_with_data(
+        data: Coremap<Data, ScoreSet>,
+        volatile: bool,
+        k_enc: bool,
+        m_enc: bool,
+    ) -> Self {
+        Self {
+            volatile: AtomicBool::new(volatile),
+            model_store: DataModel::SortedSet(SortedSetEngine::init_with_data(k_enc, m_enc, data)),
+        }
+    }
+    /// Create a new time series table with the provided settings
+    pub fn new_timeseries_with_data(
+        data: Coremap<Data, Series>,
+        volatile: bool,
+        k_enc: bool,
+    ) -> Self {
+        Self {
+            volatile: AtomicBool::new(volatile),
+            model_store: DataModel::TimeSeries(TimeSeriesEngine::init_with_data(k_enc, data)),
+        }
+    }
     pub fn new_kve_with_encoding(volatile: bool, k_enc: bool, v_enc: bool) -> Self {
         Self {
-            volatile,
+            volatile: AtomicBool::new(volatile),
             model_store: DataModel::KV(KVEngine::init(k_enc, v_enc)),
         }
     }
     pub fn from_model_code(code: u8, volatile: bool) -> Option<Self> {
-        let ret = match code {
-            0 => Self::new_kve_with_encoding(volatile, false, false),
-            1 => Self::new_kve_with_encoding(volatile, false, true),
-            2 => Self::new_kve_with_encoding(volatile, true, true),
-            3 => Self::new_kve_with_encoding(volatile, true, false),
-            _ => return None,
-        };
-        Some(ret)
+        if let Some((k_enc, v_enc)) = hash_encoding(code) {
+            return Some(Self::new_hash_with_data(
+                Coremap::new(),
+                volatile,
+                k_enc,
+                v_enc,
+            ));
+        }
+        if let Some((k_enc, m_enc)) = set_encoding(code) {
+            return Some(Self::new_set_with_data(
+                Coremap::new(),
+                volatile,
+                k_enc,
+                m_enc,
+            ));
+        }
+        if let Some((k_enc, m_enc)) = sortedset_encoding(code) {
+            return Some(Self::new_sortedset_with_data(
+                Coremap::new(),
+                volatile,
+                k_enc,
+                m_enc,
+            ));
+        }
+        if let Some(k_enc) = timeseries_encoding(code) {
+            return Some(Self::new_timeseries_with_data(
+                Coremap::new(),
+                volatile,
+                k_enc,
+            ));
+        }
+        Self::from_model_code_with_data(code, volatile, Coremap::new())
+    }
+    /// Create a key/value table with the provided model code and data, returning `None` if
+    /// the model code isn't a known key/value model code
+    pub fn from_model_code_with_data(
+        code: u8,
+        volatile: bool,
+        data: Coremap<Data, Versioned>,
+    ) -> Option<Self> {
+        kv_types(code)
+            .map(|(k_enc, v_type)| Self::new_kve_typed_with_data(data, volatile, k_enc, v_type))
+    }
+    /// Returns a new empty table with the same model and settings as this table
+    pub fn new_like(&self) -> Self {
+        match &self.model_store {
+            DataModel::KV(kve) => {
+                let (k_enc, v_type) = kve.get_types();
+                Self::new_kve_typed_with_data(Coremap::new(), self.is_volatile(), k_enc, v_type)
+            }
+            DataModel::Hash(hash) => {
+                let (k_enc, v_enc) = hash.get_encoding();
+                Self::new_hash_with_data(Coremap::new(), self.is_volatile(), k_enc, v_enc)
+            }
+            DataModel::Set(set) => {
+                let (k_enc, m_enc) = set.get_encoding();
+                Self::new_set_with_data(Coremap::new(), self.is_volatile(), k_enc, m_enc)
+            }
+            DataModel::SortedSet(zset) => {
+                let (k_enc, m_enc) = zset.get_encoding();
+                Self::new_sortedset_with_data(Coremap::new(), self.is_volatile(), k_enc, m_enc)
+            }
+            DataModel::TimeSeries(ts) => Self::new_timeseries_with_data(
+                Coremap::new(),
+                self.is_volatile(),
+                ts.get_encoding(),
+            ),
+        }
+    }
+    /// Returns a copy of this table, along with its model, settings and data
+    pub fn duplicate(&self) -> Self {
+        match &self.model_store {
+            DataModel::KV(kve) => {
+                let (k_enc, v_type) = kve.get_types();
+                let data = Coremap::with_capacity(kve.len());
+                for kv in kve.__get_inner_ref().iter() {
+                    data.upsert(kv.key().clone(), kv.value().clone());
+                }
+                let duplicate =
+                    Self::new_kve_typed_with_data(data, self.is_volatile(), k_enc, v_type);
+                if let DataModel::KV(dup) = &duplicate.model_store {
+                    // the copied values keep their versions, so keep the counter too
+                    dup.restore_version(kve.version());
+                }
+                duplicate
+            }
+            DataModel::Hash(hash) => {
+                let (k_enc, v_enc) = hash.get_encoding();
+                let data = Coremap::with_capacity(hash.len());
+                for kv in hash.__get_inner_ref().iter() {
+                    data.upsert(kv.key().clone(), kv.value().clone());
+                }
+                Self::new_hash_with_data(data, self.is_volatile(), k_enc, v_enc)
+            }
+            DataModel::Set(set) => {
+                let (k_enc, m_enc) = set.get_encoding();
+                let data = Coremap::with_capacity(set.len());
+                for kv in set.__get_inner_ref().iter() {
+                    data.upsert(kv.key().clone(), kv.value().clone());
+                }
+                Self::new_set_with_data(data, self.is_volatile(), k_enc, m_enc)
+            }
+            DataModel::SortedSet(zset) => {
+                let (k_enc, m_enc) = zset.get_encoding();
+                let data = Coremap::with_capacity(zset.len());
+                for kv in zset.__get_inner_ref().iter() {
+                    data.upsert(kv.key().clone(), kv.value().clone());
+                }
+                Self::new_sortedset_with_data(data, self.is_volatile(), k_enc, m_enc)
+            }
+            DataModel::TimeSeries(ts) => {
+                let data = Coremap::with_capacity(ts.len());
+                for kv in ts.__get_inner_ref().iter() {
+                    data.upsert(kv.key().clone(), kv.value().clone());
+                }
+                Self::new_timeseries_with_data(data, self.is_volatile(), ts.get_encoding())
+            }
+        }
     }
     /// Create a new kve with default settings but with provided volatile configuration
     pub fn new_kve_with_volatile(volatile: bool) -> Self {
@@ -132,29 +577,22 @@ impl Table {
     pub fn get_model_code(&self) -> u8 {
         match &self.model_store {
             DataModel::KV(kvs) => {
-                /*
-                bin,bin => 0
-                bin,str => 1
-                str,str => 2
-                str,bin => 3
-                */
-                let (kbin, vbin) = kvs.get_encoding();
-                if kbin {
-                    if vbin {
-                        // both k + v are str
-                        bytemarks::BYTEMARK_MODEL_KV_STR_STR
-                    } else {
-                        // only k is str
-                        bytemarks::BYTEMARK_MODEL_KV_STR_BIN
-                    }
-                } else if vbin {
-                    // k is bin, v is str
-                    bytemarks::BYTEMARK_MODEL_KV_BIN_STR
-                } else {
-                    // both are bin
-                    bytemarks::BYTEMARK_MODEL_KV_BIN_BIN
-                }
+                let (k_enc, v_type) = kvs.get_types();
+                kv_model_code(k_enc, v_type)
+            }
+            DataModel::Hash(hash) => {
+                let (k_enc, v_enc) = hash.get_encoding();
+                hash_model_code(k_enc, v_enc)
+            }
+            DataModel::Set(set) => {
+                let (k_enc, m_enc) = set.get_encoding();
+                set_model_code(k_enc, m_enc)
+            }
+            DataModel::SortedSet(zset) => {
+                let (k_enc, m_enc) = zset.get_encoding();
+                sortedset_model_code(k_enc, m_enc)
             }
+            DataModel::TimeSeries(ts) => timeseries_model_code(ts.get_encoding()),
         }
     }
     /// Returns the inner data model
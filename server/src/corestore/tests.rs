@@ -118,4 +118,289 @@ mod memstore_keyspace_tests {
         // should succeed because the keyspace is non-empty, but no table is referenced to
         assert!(ms.force_drop_keyspace(obj).is_ok());
     }
+
+    #[test]
+    fn test_rename_keyspace() {
+        let ms = Memstore::new_empty();
+        let obj = unsafe { ObjectID::from_slice("myks") };
+        let new_obj = unsafe { ObjectID::from_slice("mynewks") };
+        let tblid = unsafe { ObjectID::from_slice("mytbl") };
+        ms.create_keyspace(obj.clone());
+        let ks_ref = ms.get_keyspace_atomic_ref(&obj).unwrap();
+        ks_ref.create_table(tblid.clone(), Table::new_default_kve());
+        drop(ks_ref);
+        assert!(ms.rename_keyspace(&obj, new_obj.clone()).is_ok());
+        assert!(ms.get_keyspace_atomic_ref(&obj).is_none());
+        // the tables move along with the keyspace
+        let ks_ref = ms.get_keyspace_atomic_ref(&new_obj).unwrap();
+        assert!(ks_ref.get_table_atomic_ref(&tblid).is_some());
+    }
+
+    #[test]
+    fn test_rename_keyspace_still_accessed() {
+        let ms = Memstore::new_empty();
+        let obj = unsafe { ObjectID::from_slice("myks") };
+        let new_obj = unsafe { ObjectID::from_slice("mynewks") };
+        ms.create_keyspace(obj.clone());
+        let _ks_ref = ms.get_keyspace_atomic_ref(&obj);
+        assert_eq!(
+            ms.rename_keyspace(&obj, new_obj.clone()).unwrap_err(),
+            DdlError::StillInUse
+        );
+        // nothing was changed
+        assert!(ms.get_keyspace_atomic_ref(&obj).is_some());
+        assert!(ms.get_keyspace_atomic_ref(&new_obj).is_none());
+    }
+
+    #[test]
+    fn test_rename_keyspace_table_referenced() {
+        let ms = Memstore::new_empty();
+        let obj = unsafe { ObjectID::from_slice("myks") };
+        let tblid = unsafe { ObjectID::from_slice("mytbl") };
+        ms.create_keyspace(obj.clone());
+        let ks_ref = ms.get_keyspace_atomic_ref(&obj).unwrap();
+        ks_ref.create_table(tblid.clone(), Table::new_default_kve());
+        let _tbl_ref = ks_ref.get_table_atomic_ref(&tblid).unwrap();
+        drop(ks_ref);
+        assert_eq!(
+            ms.rename_keyspace(&obj, unsafe { ObjectID::from_slice("mynewks") })
+                .unwrap_err(),
+            DdlError::StillInUse
+        );
+    }
+
+    #[test]
+    fn test_rename_keyspace_already_exists() {
+        let ms = Memstore::new_empty();
+        let obj = unsafe { ObjectID::from_slice("myks") };
+        let other = unsafe { ObjectID::from_slice("myotherks") };
+        ms.create_keyspace(obj.clone());
+        ms.create_keyspace(other.clone());
+        assert_eq!(
+            ms.rename_keyspace(&obj, other).unwrap_err(),
+            DdlError::AlreadyExists
+        );
+    }
+
+    #[test]
+    fn test_undo_rename_keyspace() {
+        let ms = Memstore::new_empty();
+        let obj = unsafe { ObjectID::from_slice("myks") };
+        let new_obj = unsafe { ObjectID::from_slice("mynewks") };
+        ms.create_keyspace(obj.clone());
+        assert!(ms.rename_keyspace(&obj, new_obj.clone()).is_ok());
+        // undoing works even if someone picked up the keyspace under its new name
+        let _ks_ref = ms.get_keyspace_atomic_ref(&new_obj).unwrap();
+        ms.undo_rename_keyspace(&new_obj, obj.clone());
+        assert!(ms.get_keyspace_atomic_ref(&obj).is_some());
+        assert!(ms.get_keyspace_atomic_ref(&new_obj).is_none());
+    }
+
+    #[test]
+    fn test_undo_rename_table() {
+        let ms = Memstore::new_empty();
+        let obj = unsafe { ObjectID::from_slice("myks") };
+        let tblid = unsafe { ObjectID::from_slice("mytbl") };
+        let new_tblid = unsafe { ObjectID::from_slice("mynewtbl") };
+        ms.create_keyspace(obj.clone());
+        let ks_ref = ms.get_keyspace_atomic_ref(&obj).unwrap();
+        ks_ref.create_table(tblid.clone(), Table::new_default_kve());
+        assert!(ks_ref.rename_table(&tblid, new_tblid.clone()).is_ok());
+        let _tbl_ref = ks_ref.get_table_atomic_ref(&new_tblid).unwrap();
+        ks_ref.undo_rename_table(&new_tblid, tblid.clone());
+        assert!(ks_ref.get_table_atomic_ref(&tblid).is_some());
+        assert!(ks_ref.get_table_atomic_ref(&new_tblid).is_none());
+    }
+
+    #[test]
+    fn test_rename_keyspace_protected() {
+        let ms = Memstore::new_default();
+        assert_eq!(
+            ms.rename_keyspace(&DEFAULT, unsafe { ObjectID::from_slice("myks") })
+                .unwrap_err(),
+            DdlError::ProtectedObject
+        );
+    }
+}
+
+mod table_tests {
+    use super::super::table::Table;
+    use super::super::Data;
+
+    #[test]
+    fn test_table_data_types() {
+        let tbl = Table::new_kve_with_encoding(false, true, false);
+        assert_eq!(tbl.model_name(), "keymap");
+        assert_eq!(tbl.data_types(), ("str", "binstr"));
+        assert_eq!(tbl.last_modified(), 0);
+        tbl.get_kvstore()
+            .unwrap()
+            .upsert(Data::from("hello"), Data::from("world"))
+            .unwrap();
+        assert_ne!(tbl.last_modified(), 0);
+    }
+
+    #[test]
+    fn test_table_new_like() {
+        let tbl = Table::new_kve_with_encoding(true, true, false);
+        tbl.get_kvstore()
+            .unwrap()
+            .set(Data::from("hello"), Data::from("world"))
+            .unwrap();
+        let new = tbl.new_like();
+        assert!(new.is_volatile());
+        assert_eq!(new.get_model_code(), tbl.get_model_code());
+        assert_eq!(new.count(), 0);
+    }
+
+    #[test]
+    fn test_table_duplicate() {
+        let tbl = Table::new_kve_with_encoding(false, true, true);
+        let kve = tbl.get_kvstore().unwrap();
+        kve.set(Data::from("hello"), Data::from("world")).unwrap();
+        let dup = tbl.duplicate();
+        assert!(!dup.is_volatile());
+        assert_eq!(dup.get_model_code(), tbl.get_model_code());
+        // the data is copied, not shared
+        kve.set(Data::from("foo"), Data::from("bar")).unwrap();
+        let dup_kve = dup.get_kvstore().unwrap();
+        assert_eq!(dup.count(), 1);
+        assert_eq!(
+            dup_kve.get_cloned(b"hello".as_ref()).unwrap().unwrap(),
+            Data::from("world")
+        );
+        assert!(!dup_kve.exists(b"foo".as_ref()).unwrap());
+    }
+
+    #[test]
+    fn test_table_typed_model_codes() {
+        use super::super::table;
+        use crate::kvengine::ValueType;
+        for k_enc in [false, true] {
+            for v_type in [
+                ValueType::Binstr,
+                ValueType::Str,
+                ValueType::UInt,
+                ValueType::SInt,
+                ValueType::Float,
+                ValueType::Bool,
+            ] {
+                let code = table::kv_model_code(k_enc, v_type);
+                assert_eq!(table::kv_types(code), Some((k_enc, v_type)));
+                let tbl = Table::from_model_code(code, false).unwrap();
+                assert_eq!(tbl.get_model_code(), code);
+                assert_eq!(tbl.data_types().1, v_type.name());
+            }
+        }
+        assert_eq!(table::kv_types(12), None);
+    }
+
+    #[test]
+    fn test_table_hash_model_codes() {
+        use super::super::table;
+        for (k_enc, v_enc) in [(false, false), (false, true), (true, true), (true, false)] {
+            let code = table::hash_model_code(k_enc, v_enc);
+            assert_eq!(table::hash_encoding(code), Some((k_enc, v_enc)));
+            assert_eq!(table::kv_types(code), None);
+            let tbl = Table::from_model_code(code, true).unwrap();
+            assert_eq!(tbl.model_name(), "hashmap");
+            assert_eq!(tbl.get_model_code(), code);
+            assert!(tbl.get_kvstore().is_err());
+            assert!(tbl.get_hashstore().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_table_set_model_codes() {
+        use super::super::table;
+        for (k_enc, m_enc) in [(false, false), (false, true), (true, true), (true, false)] {
+            let code = table::set_model_code(k_enc, m_enc);
+            assert_eq!(table::set_encoding(code), Some((k_enc, m_enc)));
+            assert_eq!(table::hash_encoding(code), None);
+            assert_eq!(table::kv_types(code), None);
+            let tbl = Table::from_model_code(code, false).unwrap();
+            assert_eq!(tbl.model_name(), "set");
+            assert_eq!(tbl.get_model_code(), code);
+            assert!(tbl.get_setstore().is_ok());
+            assert!(tbl.get_hashstore().is_err());
+        }
+    }
+
+    #[test]
+    fn test_table_sortedset_model_codes() {
+        use super::super::table;
+        for (k_enc, m_enc) in [(false, false), (false, true), (true, true), (true, false)] {
+            let code = table::sortedset_model_code(k_enc, m_enc);
+            assert_eq!(table::sortedset_encoding(code), Some((k_enc, m_enc)));
+            assert_eq!(table::set_encoding(code), None);
+            assert_eq!(table::hash_encoding(code), None);
+            assert_eq!(table::kv_types(code), None);
+            let tbl = Table::from_model_code(code, false).unwrap();
+            assert_eq!(tbl.model_name(), "sortedset");
+            assert_eq!(tbl.get_model_code(), code);
+            assert!(tbl.get_sortedsetstore().is_ok());
+            assert!(tbl.get_setstore().is_err());
+        }
+    }
+
+    #[test]
+    fn test_table_timeseries_model_codes() {
+        use super::super::table;
+        for k_enc in [false, true] {
+            let code = table::timeseries_model_code(k_enc);
+            assert_eq!(table::timeseries_encoding(code), Some(k_enc));
+            assert_eq!(table::sortedset_encoding(code), None);
+            assert_eq!(table::kv_types(code), None);
+            let tbl = Table::from_model_code(code, false).unwrap();
+            assert_eq!(tbl.model_name(), "timeseries");
+            assert_eq!(tbl.data_types().1, "float");
+            assert_eq!(tbl.get_model_code(), code);
+            assert!(tbl.get_timeseriesstore().is_ok());
+            assert!(tbl.get_kvstore().is_err());
+        }
+    }
+
+    #[test]
+    fn test_hash_table_duplicate() {
+        use super::super::table;
+        let tbl = Table::from_model_code(table::hash_model_code(true, true), false).unwrap();
+        let hash = tbl.get_hashstore().unwrap();
+        hash.set_fields(
+            Data::from("sayan"),
+            vec![(Data::from("lang"), Data::from("rust"))],
+        )
+        .unwrap();
+        let dup = tbl.duplicate();
+        hash.set_fields(
+            Data::from("sayan"),
+            vec![(Data::from("lang"), Data::from("c"))],
+        )
+        .unwrap();
+        assert_eq!(dup.data_types(), ("str", "str"));
+        assert_eq!(
+            dup.get_hashstore()
+                .unwrap()
+                .get_field(b"sayan", b"lang")
+                .unwrap()
+                .unwrap(),
+            Data::from("rust")
+        );
+        assert_eq!(tbl.new_like().count(), 0);
+    }
+
+    #[test]
+    fn test_table_typed_validation() {
+        use super::super::table;
+        use crate::kvengine::ValueType;
+        let code = table::kv_model_code(true, ValueType::UInt);
+        let tbl = Table::from_model_code(code, false).unwrap();
+        let kve = tbl.get_kvstore().unwrap();
+        assert!(kve.set(Data::from("x"), Data::from("100")).unwrap());
+        assert!(kve.set(Data::from("y"), Data::from("-100")).is_err());
+        assert!(kve.update(Data::from("x"), Data::from("1.5")).is_err());
+        assert_eq!(
+            kve.get_cloned(b"x".as_ref()).unwrap().unwrap(),
+            Data::from("100")
+        );
+    }
 }
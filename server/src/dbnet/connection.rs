@@ -37,12 +37,17 @@
 
 use super::tcp::Connection;
 use crate::corestore::Corestore;
+use crate::dbnet::limits;
 use crate::dbnet::tcp::BufferedSocketStream;
 use crate::dbnet::Terminator;
 use crate::protocol;
 use crate::protocol::responses;
 use crate::protocol::ParseError;
 use crate::protocol::Query;
+use crate::queryengine::txn::Transaction;
+use crate::registry::clients;
+use crate::registry::clients::ClientInfo;
+use crate::registry::stats;
 use crate::resp::Writable;
 use crate::IoResult;
 use bytes::Buf;
@@ -59,6 +64,7 @@ use tokio::io::AsyncWriteExt;
 use tokio::io::BufWriter;
 use tokio::sync::mpsc;
 use tokio::sync::Semaphore;
+use tokio::time;
 
 pub const SIMPLE_QUERY_HEADER: [u8; 3] = [b'*', b'1', b'\n'];
 type QueryWithAdvance = (Query, usize);
@@ -66,6 +72,8 @@ type QueryWithAdvance = (Query, usize);
 pub enum QueryResult {
     Q(QueryWithAdvance),
     E(&'static [u8]),
+    /// A limit was violated; the connection has to be closed after sending this response
+    Fatal(&'static [u8]),
     Empty,
     Wrongtype,
 }
@@ -77,17 +85,22 @@ pub mod prelude {
     pub use super::ProtocolConnectionExt;
     pub use crate::aerr;
     pub use crate::conwrite;
+    pub use crate::corestore::quota::WriteSize;
     pub use crate::corestore::Corestore;
-    pub use crate::default_keyspace;
     pub use crate::err_if_len_is;
     pub use crate::get_tbl;
     pub use crate::handle_entity;
+    pub use crate::hashstore;
     pub use crate::is_lowbit_set;
     pub use crate::kve;
     pub use crate::protocol::responses;
     pub use crate::protocol::responses::groups;
     pub use crate::queryengine::ActionIter;
+    pub use crate::quota_check;
     pub use crate::registry;
+    pub use crate::setstore;
+    pub use crate::sortedsetstore;
+    pub use crate::tsstore;
     pub use crate::util::Unwrappable;
     pub use tokio::io::{AsyncReadExt, AsyncWriteExt};
     #[macro_export]
@@ -98,7 +111,83 @@ pub mod prelude {
                 _ => {
                     // wrong model
                     return $con
-                        .write_response(crate::protocol::responses::groups::WRONG_MODEL)
+                        .write_response($crate::protocol::responses::groups::WRONG_MODEL)
+                        .await;
+                }
+            }
+        };
+    }
+    #[macro_export]
+    macro_rules! hashstore {
+        ($con:expr, $store:expr) => {
+            match $store.get_hashstore() {
+                Ok(store) => store,
+                Err($crate::corestore::memstore::DdlError::DefaultNotFound) => {
+                    return $con
+                        .write_response($crate::protocol::responses::groups::DEFAULT_UNSET)
+                        .await;
+                }
+                _ => {
+                    // wrong model
+                    return $con
+                        .write_response($crate::protocol::responses::groups::WRONG_MODEL)
+                        .await;
+                }
+            }
+        };
+    }
+    #[macro_export]
+    macro_rules! setstore {
+        ($con:expr, $store:expr) => {
+            match $store.get_setstore() {
+                Ok(store) => store,
+                Err($crate::corestore::memstore::DdlError::DefaultNotFound) => {
+                    return $con
+                        .write_response($crate::protocol::responses::groups::DEFAULT_UNSET)
+                        .await;
+                }
+                _ => {
+                    // wrong model
+                    return $con
+                        .write_response($crate::protocol::responses::groups::WRONG_MODEL)
+                        .await;
+                }
+            }
+        };
+    }
+    #[macro_export]
+    macro_rules! sortedsetstore {
+        ($con:expr, $store:expr) => {
+            match $store.get_sortedsetstore() {
+                Ok(store) => store,
+                Err($crate::corestore::memstore::DdlError::DefaultNotFound) => {
+                    return $con
+                        .write_response($crate::protocol::responses::groups::DEFAULT_UNSET)
+                        .await;
+                }
+                _ => {
+                    // wrong model
+                    return $con
+                        .write_response($crate::protocol::responses::groups::WRONG_MODEL)
+                        .await;
+                }
+            }
+        };
+    }
+    #[macro_export]
+    macro_rules! tsstore {
+        ($con:expr, $store:expr) => {
+            match $store.get_timeseriesstore() {
+                Ok(store) => store,
+                Err($crate::corestore::memstore::DdlError::DefaultNotFound) => {
+                    return $con
+                        .write_response($crate::protocol::responses::groups::DEFAULT_UNSET)
+                        .await;
+                }
+                _ => {
+                    // wrong model
+                    return $con
+                        .write_response($crate::protocol::responses::groups::WRONG_MODEL)
                         .await;
                 }
             }
@@ -111,12 +200,30 @@ pub mod prelude {
                 Ok(ks) => ks,
                 Err(_) => {
                     return $con
-                        .write_response(crate::protocol::responses::groups::DEFAULT_UNSET)
+                        .write_response($crate::protocol::responses::groups::DEFAULT_UNSET)
                         .await;
                 }
             }
         };
     }
+    /// Check a write against the quotas of the keyspace of the current table, returning early
+    /// if it would go over a limit. This holds the quota lock of the keyspace till the end of
+    /// the enclosing block, so the write has to be made in the same block (and that block
+    /// can't await anything)
+    #[macro_export]
+    macro_rules! quota_check {
+        ($con:expr, $store:expr, $size:expr) => {
+            let keyspace = $store.get_ctable_keyspace();
+            let Ok(_quota) = keyspace
+                .as_deref()
+                .map_or(Ok(None), |ks| ks.check_write(|| $size))
+            else {
+                return $con
+                    .write_response($crate::protocol::responses::groups::QUOTA_EXCEEDED)
+                    .await;
+            };
+        };
+    }
     #[macro_export]
     macro_rules! conwrite {
         ($con:expr, $what:expr) => {
@@ -126,22 +233,22 @@ pub mod prelude {
     #[macro_export]
     macro_rules! aerr {
         ($con:expr, aerr) => {
-            return conwrite!($con, crate::protocol::responses::groups::ACTION_ERR)
+            return conwrite!($con, $crate::protocol::responses::groups::ACTION_ERR)
         };
     }
     #[macro_export]
     macro_rules! get_tbl {
         ($entity:expr, $store:expr, $con:expr) => {{
-            use crate::corestore::memstore::DdlError;
+            use $crate::corestore::memstore::DdlError;
             match $store.get_table($entity) {
                 Ok(tbl) => tbl,
                 Err(DdlError::DefaultNotFound) => {
-                    return conwrite!($con, crate::protocol::responses::groups::DEFAULT_UNSET);
+                    return conwrite!($con, $crate::protocol::responses::groups::DEFAULT_UNSET);
                 }
                 Err(DdlError::ObjectNotFound) => {
                     return conwrite!(
                         $con,
-                        crate::protocol::responses::groups::CONTAINER_NOT_FOUND
+                        $crate::protocol::responses::groups::CONTAINER_NOT_FOUND
                     );
                 }
                 Err(_) => unsafe { impossible!() },
@@ -150,14 +257,14 @@ pub mod prelude {
         ($store:expr, $con:expr) => {{
             match $store.get_ctable() {
                 Some(tbl) => tbl,
-                None => return conwrite!($con, crate::protocol::responses::groups::DEFAULT_UNSET),
+                None => return conwrite!($con, $crate::protocol::responses::groups::DEFAULT_UNSET),
             }
         }};
     }
     #[macro_export]
     macro_rules! handle_entity {
         ($con:expr, $ident:expr) => {{
-            match crate::queryengine::parser::get_query_entity(&$ident) {
+            match $crate::queryengine::parser::get_query_entity(&$ident) {
                 Ok(e) => e,
                 Err(e) => return conwrite!($con, e),
             }
@@ -209,7 +316,9 @@ where
         if self.get_buffer().is_empty() {
             return Err(ParseError::Empty);
         }
-        protocol::Parser::new(self.get_buffer()).parse()
+        protocol::Parser::new(self.get_buffer())
+            .with_max_elements(limits::max_elements())
+            .parse()
     }
     /// Read a query from the remote end
     ///
@@ -232,7 +341,18 @@ where
                             return Ok(QueryResult::Q(query_with_advance));
                         }
                         Err(ParseError::Empty) => return Ok(QueryResult::Empty),
-                        Err(ParseError::NotEnough) => (),
+                        Err(ParseError::NotEnough) => {
+                            if mv_self.get_buffer().len() > limits::max_query_size() {
+                                return Ok(QueryResult::Fatal(
+                                    responses::full_responses::R_QUERY_TOO_LARGE,
+                                ));
+                            }
+                        }
+                        Err(ParseError::TooManyElements) => {
+                            return Ok(QueryResult::Fatal(
+                                responses::full_responses::R_TOO_MANY_ELEMENTS,
+                            ));
+                        }
                         Err(ParseError::DatatypeParseFailure) => return Ok(QueryResult::Wrongtype),
                         Err(ParseError::UnexpectedByte) | Err(ParseError::BadPacket) => {
                             return Ok(QueryResult::E(responses::full_responses::R_PACKET_ERR));
@@ -283,26 +403,6 @@ where
             ret
         })
     }
-    /// Write the flat array length (`_<size>\n`)
-    fn write_flat_array_length<'r, 's>(
-        &'r mut self,
-        len: usize,
-    ) -> Pin<Box<dyn Future<Output = IoResult<()>> + Send + 's>>
-    where
-        'r: 's,
-        Self: Send + 's,
-    {
-        Box::pin(async move {
-            let mv_self = self;
-            let ret: IoResult<()> = {
-                mv_self.write_response([b'_']).await?;
-                mv_self.write_response(len.to_string().into_bytes()).await?;
-                mv_self.write_response([b'\n']).await?;
-                Ok(())
-            };
-            ret
-        })
-    }
     /// Write the array length (`&<size>\n`)
     fn write_array_length<'r, 's>(
         &'r mut self,
@@ -382,8 +482,6 @@ where
 pub trait ProtocolConnection<Strm> {
     /// Returns an **immutable** reference to the underlying read buffer
     fn get_buffer(&self) -> &BytesMut;
-    /// Returns an **immutable** reference to the underlying stream
-    fn get_stream(&self) -> &BufWriter<Strm>;
     /// Returns a **mutable** reference to the underlying read buffer
     fn get_mut_buffer(&mut self) -> &mut BytesMut;
     /// Returns a **mutable** reference to the underlying stream
@@ -392,14 +490,16 @@ pub trait ProtocolConnection<Strm> {
     ///
     /// This is to avoid double mutable reference errors
     fn get_mut_both(&mut self) -> (&mut BytesMut, &mut BufWriter<Strm>);
+    /// Returns the client that this connection belongs to
+    fn get_client(&self) -> &Arc<ClientInfo>;
+    /// Returns the transaction state of this connection
+    fn get_txn(&self) -> &Transaction;
+    /// Returns a **mutable** reference to the transaction state of this connection
+    fn get_txn_mut(&mut self) -> &mut Transaction;
     /// Advance the read buffer by `forward_by` positions
     fn advance_buffer(&mut self, forward_by: usize) {
         self.get_mut_buffer().advance(forward_by)
     }
-    /// Clear the internal buffer completely
-    fn clear_buffer(&mut self) {
-        self.get_mut_buffer().clear()
-    }
 }
 
 // Give ProtocolConnection implementors a free ProtocolConnectionExt impl
@@ -418,9 +518,6 @@ where
     fn get_buffer(&self) -> &BytesMut {
         &self.buffer
     }
-    fn get_stream(&self) -> &BufWriter<T> {
-        &self.stream
-    }
     fn get_mut_buffer(&mut self) -> &mut BytesMut {
         &mut self.buffer
     }
@@ -430,6 +527,15 @@ where
     fn get_mut_both(&mut self) -> (&mut BytesMut, &mut BufWriter<T>) {
         (&mut self.buffer, &mut self.stream)
     }
+    fn get_client(&self) -> &Arc<ClientInfo> {
+        &self.client
+    }
+    fn get_txn(&self) -> &Transaction {
+        &self.txn
+    }
+    fn get_txn_mut(&mut self) -> &mut Transaction {
+        &mut self.txn
+    }
 }
 
 /// # A generic connection handler
@@ -447,6 +553,8 @@ where
     con: T,
     climit: Arc<Semaphore>,
     terminator: Terminator,
+    /// the client's own kill switch
+    kill_switch: Terminator,
     _term_sig_tx: mpsc::Sender<()>,
     _marker: PhantomData<Strm>,
 }
@@ -463,29 +571,51 @@ where
         terminator: Terminator,
         _term_sig_tx: mpsc::Sender<()>,
     ) -> Self {
+        stats::connection_opened();
+        let client = con.get_client();
+        let kill_switch = Terminator::new(client.subscribe_kill());
+        clients::register(client.clone());
         Self {
             db,
             con,
             climit,
             terminator,
+            kill_switch,
             _term_sig_tx,
             _marker: PhantomData,
         }
     }
     pub async fn run(&mut self) -> TResult<()> {
         while !self.terminator.is_termination_signal() {
+            let idle_timeout = limits::idle_timeout();
             let try_df = tokio::select! {
                 tdf = self.con.read_query() => tdf,
+                _ = time::sleep(idle_timeout.unwrap_or_default()), if idle_timeout.is_some() => {
+                    self.con
+                        .close_conn_with_error(responses::full_responses::R_IDLE_TIMEOUT)
+                        .await?;
+                    return Ok(());
+                }
                 _ = self.terminator.receive_signal() => {
                     return Ok(());
                 }
+                _ = self.kill_switch.receive_signal() => {
+                    // this client was killed
+                    return Ok(());
+                }
             };
             match try_df {
                 Ok(QueryResult::Q((query, advance_by))) => {
                     self.db.execute_query(query, &mut self.con).await?;
                     self.con.advance_buffer(advance_by);
+                    self.con.get_client().set_entity(self.db.get_centity());
+                    stats::command_processed();
                 }
                 Ok(QueryResult::E(r)) => self.con.close_conn_with_error(r).await?,
+                Ok(QueryResult::Fatal(r)) => {
+                    self.con.close_conn_with_error(r).await?;
+                    return Ok(());
+                }
                 Ok(QueryResult::Wrongtype) => {
                     self.con
                         .close_conn_with_error(responses::groups::WRONGTYPE_ERR.to_owned())
@@ -514,5 +644,7 @@ where
         // Make sure that the permit is returned to the semaphore
         // in the case that there is a panic inside
         self.climit.add_permits(1);
+        clients::deregister(self.con.get_client().id());
+        stats::connection_closed();
     }
 }
@@ -0,0 +1,182 @@
+/*
+ * Created on Fri Sep 10 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Connection limits
+//!
+//! These limits are applied to every connection so that a single client cannot exhaust the
+//! server's memory with an enormous (or never ending) packet or hold on to a connection
+//! permit forever. A connection that violates a limit is sent an error and is then closed
+//!
+//! The maximum number of connections is enforced by the listeners' semaphore, which is
+//! registered here so that the limit can be changed at runtime
+
+use crate::config::LimitsConfig;
+use crate::registry::stats;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
+use parking_lot::{const_mutex, Mutex};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const ORD_RLX: Ordering = Ordering::Relaxed;
+
+/// The maximum size of a query packet in bytes
+static MAX_QUERY_SIZE: AtomicUsize = AtomicUsize::new(LimitsConfig::default().max_query_size);
+/// The maximum number of elements in an array
+static MAX_ELEMENTS: AtomicUsize = AtomicUsize::new(LimitsConfig::default().max_elements);
+/// The idle timeout in seconds
+static IDLE_TIMEOUT: AtomicU64 = AtomicU64::new(LimitsConfig::default().idle_timeout);
+
+/// The semaphore shared by the listeners along with the connection limit it was sized for
+struct ConnectionLimit {
+    semaphore: Arc<Semaphore>,
+    maxcon: usize,
+    /// the number of permits that still have to be taken away from the semaphore because
+    /// the limit was lowered while they were in use
+    debt: usize,
+}
+
+static CLIMIT: Mutex<Option<ConnectionLimit>> = const_mutex(None);
+
+/// Apply the limits configuration
+pub fn init(cfg: &LimitsConfig) {
+    MAX_QUERY_SIZE.store(cfg.max_query_size, ORD_RLX);
+    MAX_ELEMENTS.store(cfg.max_elements, ORD_RLX);
+    IDLE_TIMEOUT.store(cfg.idle_timeout, ORD_RLX);
+}
+
+pub fn max_query_size() -> usize {
+    MAX_QUERY_SIZE.load(ORD_RLX)
+}
+
+pub fn max_elements() -> usize {
+    MAX_ELEMENTS.load(ORD_RLX)
+}
+
+/// Returns the idle timeout or `None` if it is disabled
+pub fn idle_timeout() -> Option<Duration> {
+    match IDLE_TIMEOUT.load(ORD_RLX) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Register the semaphore that limits the number of connections to `maxcon`
+pub fn init_climit(semaphore: Arc<Semaphore>, maxcon: usize) {
+    *CLIMIT.lock() = Some(ConnectionLimit {
+        semaphore,
+        maxcon,
+        debt: 0,
+    });
+}
+
+/// Returns the maximum number of connections
+pub fn maxcon() -> usize {
+    match CLIMIT.lock().as_ref() {
+        Some(climit) => climit.maxcon,
+        None => stats::max_connections(),
+    }
+}
+
+/// Change the maximum number of connections. If the limit is lowered, existing connections
+/// are kept but new connections have to wait till the server is below the new limit
+pub fn set_maxcon(maxcon: usize) {
+    if resize(&CLIMIT, maxcon) {
+        stats::set_max_connections(maxcon);
+    }
+}
+
+/// Change the connection limit registered in `slot`, returning false if there is none
+fn resize(slot: &'static Mutex<Option<ConnectionLimit>>, maxcon: usize) -> bool {
+    let mut climit = slot.lock();
+    let climit = match climit.as_mut() {
+        Some(climit) => climit,
+        None => return false,
+    };
+    if maxcon > climit.maxcon {
+        // permits that weren't taken away yet pay for the raise first
+        let raise = maxcon - climit.maxcon;
+        let paid = raise.min(climit.debt);
+        climit.debt -= paid;
+        climit.semaphore.add_permits(raise - paid);
+    } else if maxcon < climit.maxcon {
+        let had_debt = climit.debt != 0;
+        climit.debt += climit.maxcon - maxcon;
+        if !had_debt {
+            // take away the extra permits as and when they are released
+            tokio::spawn(collect_debt(slot, climit.semaphore.clone()));
+        }
+    }
+    climit.maxcon = maxcon;
+    true
+}
+
+/// Take permits away from the semaphore till the connection limit in `slot` has no debt
+/// left. A permit that is acquired after the debt was paid off by a raise is given back
+async fn collect_debt(slot: &'static Mutex<Option<ConnectionLimit>>, semaphore: Arc<Semaphore>) {
+    loop {
+        let permit = match semaphore.acquire().await {
+            Ok(permit) => permit,
+            Err(_) => return,
+        };
+        let mut climit = slot.lock();
+        match climit.as_mut() {
+            Some(climit) if climit.debt != 0 => {
+                permit.forget();
+                climit.debt -= 1;
+                if climit.debt == 0 {
+                    return;
+                }
+            }
+            _ => return,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_resize_raise_pays_debt() {
+    // use a slot of our own since the other tests can change the limit of the server
+    static SLOT: Mutex<Option<ConnectionLimit>> = const_mutex(None);
+    let semaphore = Arc::new(Semaphore::new(4));
+    *SLOT.lock() = Some(ConnectionLimit {
+        semaphore: semaphore.clone(),
+        maxcon: 4,
+        debt: 0,
+    });
+    let held = semaphore.clone().acquire_many_owned(4).await.unwrap();
+    // all the permits are in use, so the two extra ones are taken away once they're released
+    assert!(resize(&SLOT, 2));
+    // the raise pays for the debt first, leaving one new permit for new connections
+    assert!(resize(&SLOT, 5));
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(semaphore.available_permits(), 1);
+    drop(held);
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(semaphore.available_permits(), 5);
+    assert_eq!(SLOT.lock().as_ref().unwrap().debt, 0);
+}
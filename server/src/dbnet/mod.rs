@@ -35,7 +35,7 @@
 //! 2. An asynchronous is spawned on the Tokio runtime
 //! 3. Data from the socket is asynchronously read into an 8KB read buffer
 //! 4. Once the data is read completely (i.e the source sends an EOF byte), the `protocol` module
-//! is used to parse the stream
+//!    is used to parse the stream
 //! 5. Now errors are handled if they occur. Otherwise, the query is executed by `Corestore::execute_query()`
 //!
 
@@ -52,10 +52,11 @@ use tokio::net::TcpListener;
 use tokio::sync::Semaphore;
 use tokio::sync::{broadcast, mpsc};
 pub mod connection;
+pub mod limits;
 #[macro_use]
 mod macros;
 mod tcp;
-mod tls;
+pub mod tls;
 
 pub const MAXIMUM_CONNECTION_LIMIT: usize = 50000;
 
@@ -158,8 +159,8 @@ macro_rules! bindaddr {
 /// - The `SecureOnly` variant holds an `SslListener`
 /// - The `InsecureOnly` variant holds a `Listener`
 /// - The `Multi` variant holds both an `SslListener` and a `Listener`
-///     This variant enables listening to both secure and insecure sockets at the same time
-///     asynchronously
+///   This variant enables listening to both secure and insecure sockets at the same time
+///   asynchronously
 pub enum MultiListener {
     SecureOnly(SslListener),
     InsecureOnly(Listener),
@@ -176,8 +177,15 @@ impl MultiListener {
     pub fn new_secure_only(base: BaseListener, ssl: SslOpts) -> Result<Self, String> {
         let bindaddr = bindaddr!(base);
         let slf = MultiListener::SecureOnly(
-            SslListener::new_pem_based_ssl_connection(ssl.key, ssl.chain, base, ssl.passfile)
-                .map_err(|e| format!("Couldn't bind to secure port: {}", e))?,
+            SslListener::new_pem_based_ssl_connection(
+                ssl.key,
+                ssl.chain,
+                base,
+                ssl.passfile,
+                ssl.client_ca,
+                ssl.verify,
+            )
+            .map_err(|e| format!("Couldn't bind to secure port: {}", e))?,
         );
         log::info!("Server started on: skyhash-secure://{}", bindaddr);
         Ok(slf)
@@ -195,6 +203,8 @@ impl MultiListener {
             ssl.chain,
             ssl_base_listener,
             ssl.passfile,
+            ssl.client_ca,
+            ssl.verify,
         )
         .map_err(|e| format!("Couldn't bind to secure port: {}", e))?;
         let insecure_listener = Listener {
@@ -253,6 +263,7 @@ pub async fn connect(
     signal: broadcast::Sender<()>,
 ) -> Result<MultiListener, String> {
     let climit = Arc::new(Semaphore::const_new(maxcon));
+    limits::init_climit(climit.clone(), maxcon);
     let server = match ports {
         PortConfig::InsecureOnly { host, port } => MultiListener::new_insecure_only(
             BaseListener::init(&db, host, port, climit.clone(), signal.clone())
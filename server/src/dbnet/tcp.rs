@@ -27,15 +27,22 @@
 use crate::dbnet::connection::ConnectionHandler;
 use crate::dbnet::BaseListener;
 use crate::dbnet::Terminator;
-use crate::protocol;
+use crate::queryengine::txn::Transaction;
+use crate::registry::clients::ClientInfo;
+use crate::registry::stats;
 use bytes::BytesMut;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 use libsky::TResult;
 use libsky::BUF_CAP;
-pub use protocol::ParseResult;
-pub use protocol::Query;
+use std::io::Result as IoResult;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::AsyncRead;
 use tokio::io::AsyncWrite;
 use tokio::io::BufWriter;
+use tokio::io::ReadBuf;
 use tokio::net::TcpStream;
 use tokio::time;
 
@@ -43,6 +50,59 @@ pub trait BufferedSocketStream: AsyncWrite {}
 
 impl BufferedSocketStream for TcpStream {}
 
+/// A socket stream wrapper that records the number of bytes read from and written
+/// to the underlying socket in the global statistics and in the client's statistics
+pub struct CountingStream<T> {
+    inner: T,
+    client: Arc<ClientInfo>,
+}
+
+impl<T> CountingStream<T> {
+    pub const fn new(inner: T, client: Arc<ClientInfo>) -> Self {
+        Self { inner, client }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for CountingStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IoResult<()>> {
+        let before = buf.filled().len();
+        let ret = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = ret {
+            let read = buf.filled().len() - before;
+            stats::add_bytes_in(read);
+            self.client.add_bytes_in(read);
+        }
+        ret
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for CountingStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<IoResult<usize>> {
+        let ret = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = ret {
+            stats::add_bytes_out(written);
+            self.client.add_bytes_out(written);
+        }
+        ret
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl<T: BufferedSocketStream + Unpin> BufferedSocketStream for CountingStream<T> {}
+
 /// A TCP/SSL connection wrapper
 pub struct Connection<T>
 where
@@ -53,6 +113,10 @@ where
     pub stream: BufWriter<T>,
     /// The in-memory read buffer. The size is given by `BUF_CAP`
     pub buffer: BytesMut,
+    /// The client that this connection belongs to
+    pub client: Arc<ClientInfo>,
+    /// The transaction state of this connection
+    pub txn: Transaction,
 }
 
 impl<T> Connection<T>
@@ -60,10 +124,12 @@ where
     T: BufferedSocketStream,
 {
     /// Initiailize a new `Connection` instance
-    pub fn new(stream: T) -> Self {
+    pub fn new(stream: T, client: Arc<ClientInfo>) -> Self {
         Connection {
             stream: BufWriter::new(stream),
             buffer: BytesMut::with_capacity(BUF_CAP),
+            client,
+            txn: Transaction::default(),
         }
     }
 }
@@ -77,13 +143,12 @@ pub struct Listener {
 
 impl Listener {
     /// Accept an incoming connection
-    async fn accept(&mut self) -> TResult<TcpStream> {
+    async fn accept(&mut self) -> TResult<(TcpStream, SocketAddr)> {
         // We will steal the idea of Ethernet's backoff for connection errors
         let mut backoff = 1;
         loop {
             match self.base.listener.accept().await {
-                // We don't need the bindaddr
-                Ok((stream, _)) => return Ok(stream),
+                Ok((stream, peer)) => return Ok((stream, peer)),
                 Err(e) => {
                     if backoff > 64 {
                         // Too many retries, goodbye user
@@ -111,10 +176,11 @@ impl Listener {
              can arise and it will flood the log and might also result
              in a crash
             */
-            let stream = skip_loop_err!(self.accept().await);
+            let (stream, peer) = skip_loop_err!(self.accept().await);
+            let client = ClientInfo::new(peer, false, None);
             let mut chandle = ConnectionHandler::new(
                 self.base.db.clone(),
-                Connection::new(stream),
+                Connection::new(CountingStream::new(stream, client.clone()), client),
                 self.base.climit.clone(),
                 Terminator::new(self.base.signal.subscribe()),
                 self.base.terminate_tx.clone(),
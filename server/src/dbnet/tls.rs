@@ -25,26 +25,102 @@
 */
 
 use super::connection::ConnectionHandler;
+use crate::config::SslOpts;
+use crate::config::TlsVerifyMode;
 use crate::dbnet::tcp::BufferedSocketStream;
 use crate::dbnet::tcp::Connection;
+use crate::dbnet::tcp::CountingStream;
 use crate::dbnet::BaseListener;
 use crate::dbnet::Terminator;
+use crate::registry::clients::ClientInfo;
 use libsky::TResult;
 use openssl::pkey::PKey;
 use openssl::rsa::Rsa;
-use openssl::ssl::{Ssl, SslAcceptor, SslFiletype, SslMethod};
+use openssl::ssl::{Ssl, SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+use openssl::x509::{X509Name, X509NameRef};
+use parking_lot::{const_rwlock, RwLock};
 use std::fs;
 use std::io::Error as IoError;
+use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio::net::TcpStream;
 use tokio::time::{self, Duration};
 use tokio_openssl::SslStream;
 
 impl BufferedSocketStream for SslStream<TcpStream> {}
 
+/// The acceptor used by the TLS listener for new connections. It is swapped out when the
+/// certificates are reloaded, and connections that were already accepted are unaffected
+static ACCEPTOR: RwLock<Option<SslAcceptor>> = const_rwlock(None);
+
+/// Re-read the TLS key, chain and client CA files and use them for all new connections.
+/// Returns false if the TLS listener isn't running. On error, the current certificates
+/// are kept
+pub fn reload_certificates(ssl: &SslOpts) -> TResult<bool> {
+    if ACCEPTOR.read().is_none() {
+        return Ok(false);
+    }
+    let acceptor = build_acceptor(
+        ssl.key.clone(),
+        ssl.chain.clone(),
+        ssl.passfile.clone(),
+        ssl.client_ca.clone(),
+        ssl.verify,
+    )?;
+    *ACCEPTOR.write() = Some(acceptor);
+    Ok(true)
+}
+
+/// Build an acceptor from the given key, chain and (optionally) client CA files
+fn build_acceptor(
+    key_file: String,
+    chain_file: String,
+    tls_passfile: Option<String>,
+    client_ca: Option<String>,
+    verify: TlsVerifyMode,
+) -> TResult<SslAcceptor> {
+    let mut acceptor_builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+    // cert is the same for both
+    acceptor_builder.set_certificate_chain_file(chain_file)?;
+    if let Some(tls_passfile) = tls_passfile {
+        // first read in the private key
+        let tls_private_key = fs::read(key_file).map_err(|e: IoError| {
+            format!("Failed to read TLS private key file with error: {}", e)
+        })?;
+        // read the passphrase because the passphrase file stream was provided
+        let tls_keyfile_stream = fs::read(tls_passfile).map_err(|e: IoError| {
+            format!(
+                "Failed to read TLS private key passphrase file with error: {}",
+                e
+            )
+        })?;
+        // decrypt the private key
+        let pkey = Rsa::private_key_from_pem_passphrase(&tls_private_key, &tls_keyfile_stream)?;
+        let pkey = PKey::from_rsa(pkey)?;
+        // set the private key for the acceptor
+        acceptor_builder.set_private_key(&pkey)?;
+    } else {
+        // no passphrase, needs interactive
+        acceptor_builder.set_private_key_file(key_file, SslFiletype::PEM)?;
+    }
+    if let Some(client_ca) = client_ca {
+        // trust the client CA bundle and advertise its subjects to clients
+        acceptor_builder.set_ca_file(&client_ca)?;
+        acceptor_builder.set_client_ca_list(X509Name::load_client_ca_file(&client_ca)?);
+    }
+    match verify {
+        TlsVerifyMode::None => {}
+        TlsVerifyMode::Optional => acceptor_builder.set_verify(SslVerifyMode::PEER),
+        TlsVerifyMode::Required => {
+            acceptor_builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT)
+        }
+    }
+    Ok(acceptor_builder.build())
+}
+
 pub struct SslListener {
     pub base: BaseListener,
-    acceptor: SslAcceptor,
 }
 
 impl SslListener {
@@ -53,48 +129,26 @@ impl SslListener {
         chain_file: String,
         base: BaseListener,
         tls_passfile: Option<String>,
+        client_ca: Option<String>,
+        verify: TlsVerifyMode,
     ) -> TResult<Self> {
-        let mut acceptor_builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
-        // cert is the same for both
-        acceptor_builder.set_certificate_chain_file(chain_file)?;
-        if let Some(tls_passfile) = tls_passfile {
-            // first read in the private key
-            let tls_private_key = fs::read(key_file).map_err(|e: IoError| {
-                format!("Failed to read TLS private key file with error: {}", e)
-            })?;
-            // read the passphrase because the passphrase file stream was provided
-            let tls_keyfile_stream = fs::read(tls_passfile).map_err(|e: IoError| {
-                format!(
-                    "Failed to read TLS private key passphrase file with error: {}",
-                    e
-                )
-            })?;
-            // decrypt the private key
-            let pkey = Rsa::private_key_from_pem_passphrase(&tls_private_key, &tls_keyfile_stream)?;
-            let pkey = PKey::from_rsa(pkey)?;
-            // set the private key for the acceptor
-            acceptor_builder.set_private_key(&pkey)?;
-        } else {
-            // no passphrase, needs interactive
-            acceptor_builder.set_private_key_file(key_file, SslFiletype::PEM)?;
-        }
-        Ok(SslListener {
-            base,
-            acceptor: acceptor_builder.build(),
-        })
+        let acceptor = build_acceptor(key_file, chain_file, tls_passfile, client_ca, verify)?;
+        *ACCEPTOR.write() = Some(acceptor);
+        Ok(SslListener { base })
     }
-    async fn accept(&mut self) -> TResult<SslStream<TcpStream>> {
+    /// Accept a connection, returning the stream and the info of the client
+    async fn accept(&mut self) -> TResult<(SslStream<TcpStream>, Arc<ClientInfo>)> {
         let mut backoff = 1;
         loop {
             match self.base.listener.accept().await {
-                // We don't need the bindaddr
                 // We get the encrypted stream which we need to decrypt
                 // by using the acceptor
-                Ok((stream, _)) => {
-                    let ssl = Ssl::new(self.acceptor.context())?;
-                    let mut stream = SslStream::new(ssl, stream)?;
-                    Pin::new(&mut stream).accept().await?;
-                    return Ok(stream);
+                Ok((stream, peer)) => {
+                    let ssl = match ACCEPTOR.read().as_ref() {
+                        Some(acceptor) => Ssl::new(acceptor.context())?,
+                        None => return Err("TLS acceptor not initialized".into()),
+                    };
+                    return handshake(ssl, stream, peer).await;
                 }
                 Err(e) => {
                     if backoff > 64 {
@@ -122,10 +176,18 @@ impl SslListener {
              can arise and it will flood the log and might also result
              in a crash
             */
-            let stream = skip_loop_err!(self.accept().await);
+            let (stream, client) = match self.accept().await {
+                Ok(ret) => ret,
+                Err(_) => {
+                    // failed handshakes (for example, a rejected client certificate) never
+                    // reach a handler, so we need to give back the permit ourselves
+                    self.base.climit.add_permits(1);
+                    continue;
+                }
+            };
             let mut sslhandle = ConnectionHandler::new(
                 self.base.db.clone(),
-                Connection::new(stream),
+                Connection::new(CountingStream::new(stream, client.clone()), client),
                 self.base.climit.clone(),
                 Terminator::new(self.base.signal.subscribe()),
                 self.base.terminate_tx.clone(),
@@ -139,3 +201,137 @@ impl SslListener {
         }
     }
 }
+
+/// Run the TLS handshake on an accepted stream, returning the decrypted stream and the info
+/// of the client (along with the subject of its certificate, if it sent one)
+async fn handshake(
+    ssl: Ssl,
+    stream: TcpStream,
+    peer: SocketAddr,
+) -> TResult<(SslStream<TcpStream>, Arc<ClientInfo>)> {
+    let mut stream = SslStream::new(ssl, stream)?;
+    Pin::new(&mut stream).accept().await?;
+    // if we got here and the client sent a certificate, it was verified
+    let subject = stream
+        .ssl()
+        .peer_certificate()
+        .map(|cert| format_subject(cert.subject_name()));
+    Ok((stream, ClientInfo::new(peer, true, subject)))
+}
+
+/// Format the subject of a certificate as a comma separated list of `SN=value` entries
+/// (for example, `C=IN,O=Skytable,CN=client`)
+fn format_subject(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("UNDEF");
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[test]
+fn test_format_subject() {
+    use openssl::x509::X509NameBuilder;
+    let mut builder = X509NameBuilder::new().unwrap();
+    builder.append_entry_by_text("C", "IN").unwrap();
+    builder.append_entry_by_text("O", "Skytable").unwrap();
+    builder.append_entry_by_text("CN", "client").unwrap();
+    let name = builder.build();
+    assert_eq!(format_subject(&name), "C=IN,O=Skytable,CN=client");
+}
+
+#[cfg(test)]
+/// Returns the path of a file generated by `ci/ssl.sh` in `ROOT_DIR`
+fn test_file(name: &str) -> String {
+    let mut path = std::env::var("ROOT_DIR").expect("ROOT_DIR unset");
+    path.push('/');
+    path.push_str(name);
+    path
+}
+
+#[test]
+fn test_reload_certificates() {
+    let (key, cert) = (test_file("key.pem"), test_file("cert.pem"));
+    let acceptor = build_acceptor(key.clone(), cert.clone(), None, None, TlsVerifyMode::None);
+    *ACCEPTOR.write() = Some(acceptor.unwrap());
+    let ssl = SslOpts::new(
+        key,
+        cert.clone(),
+        2004,
+        None,
+        Some(cert),
+        TlsVerifyMode::Required,
+    );
+    assert!(reload_certificates(&ssl).unwrap());
+    let acceptor = ACCEPTOR.read();
+    let context = acceptor.as_ref().unwrap().context();
+    // the new acceptor trusts the client CA and requires client certificates
+    assert_eq!(
+        context.verify_mode(),
+        SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT
+    );
+    assert_eq!(context.cert_store().objects().len(), 1);
+}
+
+#[cfg(test)]
+/// Run a handshake against an acceptor that trusts the test certificate as the client CA,
+/// returning the info of the client if the server accepted it
+async fn test_handshake(verify: TlsVerifyMode, with_cert: bool) -> Option<Arc<ClientInfo>> {
+    use openssl::ssl::SslConnector;
+    use tokio::net::TcpListener;
+    let (key, cert) = (test_file("key.pem"), test_file("cert.pem"));
+    let acceptor = build_acceptor(key.clone(), cert.clone(), None, Some(cert.clone()), verify);
+    let ssl = Ssl::new(acceptor.unwrap().context()).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = tokio::spawn(async move {
+        let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+        connector.set_verify(SslVerifyMode::NONE);
+        if with_cert {
+            connector
+                .set_certificate_file(&cert, SslFiletype::PEM)
+                .unwrap();
+            connector
+                .set_private_key_file(&key, SslFiletype::PEM)
+                .unwrap();
+        }
+        let ssl = connector.build().configure().unwrap().into_ssl("localhost");
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = SslStream::new(ssl.unwrap(), stream).unwrap();
+        // the server might only reject the certificate after the client is done
+        let _ = Pin::new(&mut stream).connect().await;
+    });
+    let (stream, peer) = listener.accept().await.unwrap();
+    let accepted = handshake(ssl, stream, peer).await;
+    client.await.unwrap();
+    accepted.ok().map(|(_, client)| client)
+}
+
+#[tokio::test]
+async fn test_handshake_required_rejects_anonymous() {
+    assert!(test_handshake(TlsVerifyMode::Required, false)
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_handshake_optional_accepts_anonymous() {
+    let client = test_handshake(TlsVerifyMode::Optional, false)
+        .await
+        .unwrap();
+    assert_eq!(client.cert_subject(), None);
+}
+
+#[tokio::test]
+async fn test_handshake_subject() {
+    // the test certificate is generated by `ci/ssl.sh`
+    let client = test_handshake(TlsVerifyMode::Required, true).await.unwrap();
+    assert_eq!(client.cert_subject(), Some("C=US,CN=example.com"));
+}
@@ -0,0 +1,246 @@
+/*
+ * Created on Sun Sep 26 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Glob patterns
+//!
+//! Byte-wise glob patterns for matching keys, supporting:
+//! - `*`: any sequence of bytes (including an empty one)
+//! - `?`: any single byte
+//! - `[abc]`, `[a-z]`: any byte in the class; classes are negated with `[!abc]` or `[^abc]`
+//! - `\x`: the byte `x` itself (to match `*`, `?`, `[` or `\`)
+//!
+//! Patterns that are just a literal prefix followed by a `*` (like `user:*`) are matched
+//! with a plain prefix comparison
+
+/// A single token of a compiled pattern
+#[derive(Debug, PartialEq)]
+pub enum Token {
+    /// a byte that has to match exactly
+    Literal(u8),
+    /// `?`
+    AnyByte,
+    /// `*`
+    AnySequence,
+    /// `[...]`: inclusive byte ranges and whether the class is negated
+    Class(Vec<(u8, u8)>, bool),
+}
+
+impl Token {
+    /// Check if this (non-`*`) token matches the given byte
+    fn matches(&self, byte: u8) -> bool {
+        match self {
+            Self::Literal(literal) => *literal == byte,
+            Self::AnyByte => true,
+            Self::Class(ranges, negated) => {
+                ranges
+                    .iter()
+                    .any(|(start, end)| (*start..=*end).contains(&byte))
+                    != *negated
+            }
+            Self::AnySequence => unreachable!("`*` doesn't match a single byte"),
+        }
+    }
+}
+
+/// A compiled glob pattern
+#[derive(Debug, PartialEq)]
+pub enum Pattern {
+    /// a literal prefix followed by a `*`
+    Prefix(Vec<u8>),
+    /// any other pattern
+    Glob(Vec<Token>),
+}
+
+impl Pattern {
+    /// Compile a pattern, returning `None` if it has an unterminated class or ends with an
+    /// unescaped `\`
+    pub fn new(pattern: &[u8]) -> Option<Self> {
+        let mut tokens = Vec::with_capacity(pattern.len());
+        let mut bytes = pattern.iter().copied();
+        while let Some(byte) = bytes.next() {
+            let token = match byte {
+                b'*' => {
+                    if tokens.last() == Some(&Token::AnySequence) {
+                        // `**` is the same as `*`
+                        continue;
+                    }
+                    Token::AnySequence
+                }
+                b'?' => Token::AnyByte,
+                b'\\' => Token::Literal(bytes.next()?),
+                b'[' => Self::compile_class(&mut bytes)?,
+                _ => Token::Literal(byte),
+            };
+            tokens.push(token);
+        }
+        if let Some((Token::AnySequence, prefix)) = tokens.split_last() {
+            if prefix
+                .iter()
+                .all(|token| matches!(token, Token::Literal(_)))
+            {
+                let prefix = prefix
+                    .iter()
+                    .map(|token| match token {
+                        Token::Literal(byte) => *byte,
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                return Some(Self::Prefix(prefix));
+            }
+        }
+        Some(Self::Glob(tokens))
+    }
+    /// Compile a class, right after its `[`
+    fn compile_class(bytes: &mut impl Iterator<Item = u8>) -> Option<Token> {
+        let mut byte = bytes.next()?;
+        let negated = matches!(byte, b'!' | b'^');
+        if negated {
+            byte = bytes.next()?;
+        }
+        // the (unescaped) bytes of the class along with whether they were escaped
+        let mut items = Vec::new();
+        loop {
+            match byte {
+                // a `]` right at the start is a literal `]`
+                b']' if !items.is_empty() => break,
+                b'\\' => items.push((bytes.next()?, true)),
+                _ => items.push((byte, false)),
+            }
+            byte = bytes.next()?;
+        }
+        let mut ranges = Vec::with_capacity(items.len());
+        let mut items = items.as_slice();
+        while let Some(((start, _), rest)) = items.split_first() {
+            match rest {
+                // an unescaped `-` between two bytes is a range
+                [(b'-', false), (end, _), rest @ ..] => {
+                    ranges.push((*start.min(end), *start.max(end)));
+                    items = rest;
+                }
+                _ => {
+                    ranges.push((*start, *start));
+                    items = rest;
+                }
+            }
+        }
+        Some(Token::Class(ranges, negated))
+    }
+    /// Check if the given key matches this pattern
+    pub fn matches(&self, key: &[u8]) -> bool {
+        match self {
+            Self::Prefix(prefix) => key.starts_with(prefix),
+            Self::Glob(tokens) => Self::matches_glob(tokens, key),
+        }
+    }
+    /// Match the given tokens against a key by backtracking to the last `*` on a mismatch
+    /// (which takes `O(tokens * key)` time at worst)
+    fn matches_glob(tokens: &[Token], key: &[u8]) -> bool {
+        let (mut t, mut k) = (0, 0);
+        // the position of the token after the last `*` and the position in the key that it
+        // was matched at
+        let mut backtrack: Option<(usize, usize)> = None;
+        while k < key.len() {
+            match tokens.get(t) {
+                Some(Token::AnySequence) => {
+                    // first try matching an empty sequence
+                    t += 1;
+                    backtrack = Some((t, k));
+                }
+                Some(token) if token.matches(key[k]) => {
+                    t += 1;
+                    k += 1;
+                }
+                _ => match backtrack.as_mut() {
+                    Some((star_t, star_k)) => {
+                        // let the last `*` consume one more byte
+                        *star_k += 1;
+                        t = *star_t;
+                        k = *star_k;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        // only `*`s can match what's left of the pattern
+        tokens[t..].iter().all(|token| *token == Token::AnySequence)
+    }
+}
+
+#[test]
+fn test_glob_prefix() {
+    assert_eq!(
+        Pattern::new(b"user:*").unwrap(),
+        Pattern::Prefix(b"user:".to_vec())
+    );
+    let pattern = Pattern::new(b"user:**").unwrap();
+    assert!(pattern.matches(b"user:"));
+    assert!(pattern.matches(b"user:sayan"));
+    assert!(!pattern.matches(b"users"));
+    assert!(Pattern::new(b"*").unwrap().matches(b""));
+}
+
+#[test]
+fn test_glob_wildcards() {
+    let pattern = Pattern::new(b"*:session:?").unwrap();
+    assert!(pattern.matches(b"user:session:1"));
+    assert!(pattern.matches(b":session:1"));
+    assert!(!pattern.matches(b"user:session:10"));
+    assert!(!pattern.matches(b"user:session:"));
+    let pattern = Pattern::new(b"a*b*c").unwrap();
+    assert!(pattern.matches(b"abc"));
+    assert!(pattern.matches(b"aXbYbZc"));
+    assert!(!pattern.matches(b"aXbYcZ"));
+    // escapes
+    let pattern = Pattern::new(b"what\\?").unwrap();
+    assert!(pattern.matches(b"what?"));
+    assert!(!pattern.matches(b"whats"));
+    assert!(Pattern::new(b"\\*").unwrap().matches(b"*"));
+}
+
+#[test]
+fn test_glob_classes() {
+    let pattern = Pattern::new(b"log:[0-9a]").unwrap();
+    assert!(pattern.matches(b"log:5"));
+    assert!(pattern.matches(b"log:a"));
+    assert!(!pattern.matches(b"log:b"));
+    let pattern = Pattern::new(b"log:[!0-9]").unwrap();
+    assert!(pattern.matches(b"log:b"));
+    assert!(!pattern.matches(b"log:5"));
+    // leading `]` and trailing `-` are literals
+    let pattern = Pattern::new(b"[]-]").unwrap();
+    assert!(pattern.matches(b"]"));
+    assert!(pattern.matches(b"-"));
+    assert!(!pattern.matches(b"a"));
+    // reversed ranges work too
+    assert!(Pattern::new(b"[z-x]").unwrap().matches(b"y"));
+}
+
+#[test]
+fn test_glob_malformed() {
+    assert_eq!(Pattern::new(b"user:[abc"), None);
+    assert_eq!(Pattern::new(b"user:\\"), None);
+    assert_eq!(Pattern::new(b"[]"), None);
+}
@@ -0,0 +1,385 @@
+/*
+ * Created on Tue Sep 21 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The hash engine
+//!
+//! The hash engine is the in-memory backing store for `hashmap` tables, where every key
+//! maps to a collection of field/value pairs. Keys and fields share the key encoding while
+//! the values have their own encoding. All writes to the fields of a key are done while
+//! holding the lock of the key's shard, so no reader ever sees a half-updated key. A key
+//! that loses its last field is removed altogether
+
+use super::encoding;
+use super::EngineTable;
+use super::Footprint;
+use crate::corestore::htable::Coremap;
+use crate::corestore::map::bref::Entry;
+use crate::corestore::quota::WriteSize;
+use crate::corestore::Data;
+use crate::resp::TSYMBOL_BINARY;
+use crate::resp::TSYMBOL_UNICODE;
+use std::collections::HashMap;
+
+/// The fields of a key
+pub type FieldMap = HashMap<Data, Data>;
+
+impl Footprint for FieldMap {
+    fn footprint(&self) -> usize {
+        self.iter()
+            .map(|(field, value)| field.len() + value.len())
+            .sum()
+    }
+}
+
+/// The hash engine that acts as the in-memory backing store for hash tables
+#[derive(Debug)]
+pub struct HashEngine {
+    /// the atomic table
+    table: EngineTable<FieldMap>,
+    /// the encoding switch for the keys and the fields
+    encoded_k: bool,
+    /// the encoding switch for the values
+    encoded_v: bool,
+}
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine::init(false, false)
+    }
+}
+
+impl HashEngine {
+    /// Create a new in-memory hash engine with the specified encoding schemes
+    pub fn init(encoded_k: bool, encoded_v: bool) -> Self {
+        Self::init_with_data(encoded_k, encoded_v, Coremap::new())
+    }
+    pub fn init_with_data(
+        encoded_k: bool,
+        encoded_v: bool,
+        table: Coremap<Data, FieldMap>,
+    ) -> Self {
+        Self {
+            table: EngineTable::new(table),
+            encoded_k,
+            encoded_v,
+        }
+    }
+    /// Returns the encoding of the keys (and fields) and the values
+    pub const fn get_encoding(&self) -> (bool, bool) {
+        (self.encoded_k, self.encoded_v)
+    }
+    /// Returns the tsymbol of the keys and the fields
+    pub const fn get_kt(&self) -> u8 {
+        if self.encoded_k {
+            TSYMBOL_UNICODE
+        } else {
+            TSYMBOL_BINARY
+        }
+    }
+    /// Returns the tsymbol of the values
+    pub const fn get_vt(&self) -> u8 {
+        if self.encoded_v {
+            TSYMBOL_UNICODE
+        } else {
+            TSYMBOL_BINARY
+        }
+    }
+    /// Returns the number of keys
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+    pub fn __get_inner_ref(&self) -> &EngineTable<FieldMap> {
+        &self.table
+    }
+    /// Check the encoding of a key or a field
+    fn _encode_key(&self, key: &[u8]) -> Result<(), ()> {
+        if !self.encoded_k || encoding::is_utf8(key) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+    /// Check the encoding of a value
+    fn _encode_value(&self, value: &[u8]) -> Result<(), ()> {
+        if !self.encoded_v || encoding::is_utf8(value) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+    /// Check the encoding of a key along with the given fields
+    fn _encode_key_fields(&self, key: &[u8], fields: &[&[u8]]) -> Result<(), ()> {
+        self._encode_key(key)?;
+        fields.iter().try_for_each(|field| self._encode_key(field))
+    }
+    /// Returns the size of a write of alternating fields and values to a key, taking the
+    /// values that they'd replace into account
+    pub fn write_size<'a>(
+        &self,
+        key: &[u8],
+        mut fields: impl Iterator<Item = &'a [u8]>,
+    ) -> WriteSize {
+        let fieldmap = self.table.get(key);
+        let (mut bytes, mut largest) = (0, 0);
+        while let (Some(field), Some(value)) = (fields.next(), fields.next()) {
+            bytes += match fieldmap.as_ref().and_then(|fieldmap| fieldmap.get(field)) {
+                Some(older) => value.len().saturating_sub(older.len()),
+                None => field.len() + value.len(),
+            };
+            largest = largest.max(value.len());
+        }
+        WriteSize::new(fieldmap.is_none() as usize, bytes, largest)
+    }
+    /// Set the given fields of a key, creating the key if it doesn't exist. Returns the
+    /// number of fields that didn't exist before
+    pub fn set_fields(&self, key: Data, fields: Vec<(Data, Data)>) -> Result<usize, ()> {
+        self._encode_key(&key)?;
+        fields.iter().try_for_each(|(field, value)| {
+            self._encode_key(field)?;
+            self._encode_value(value)
+        })?;
+        let added = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                let fieldmap = oe.value_mut();
+                let (mut added, mut added_bytes, mut removed_bytes) = (0, 0, 0);
+                for (field, value) in fields {
+                    let (field_len, value_len) = (field.len(), value.len());
+                    match fieldmap.insert(field, value) {
+                        Some(older) => {
+                            added_bytes += value_len;
+                            removed_bytes += older.len();
+                        }
+                        None => {
+                            added += 1;
+                            added_bytes += field_len + value_len;
+                        }
+                    }
+                }
+                self.table.account(added_bytes, removed_bytes);
+                added
+            }
+            Entry::Vacant(ve) => {
+                let fieldmap: FieldMap = fields.into_iter().collect();
+                let added = fieldmap.len();
+                self.table.account(fieldmap.footprint(), 0);
+                ve.insert(fieldmap);
+                added
+            }
+        };
+        self.table.touch();
+        Ok(added)
+    }
+    /// Get the value of a field of a key, if both exist
+    pub fn get_field(&self, key: &[u8], field: &[u8]) -> Result<Option<Data>, ()> {
+        self._encode_key_fields(key, &[field])?;
+        Ok(self
+            .table
+            .get(key)
+            .and_then(|fieldmap| fieldmap.get(field).cloned()))
+    }
+    /// Get the values of the given fields of a key, with a `None` for every field that
+    /// doesn't exist (which is every field if the key doesn't exist)
+    pub fn get_fields(&self, key: &[u8], fields: &[&[u8]]) -> Result<Vec<Option<Data>>, ()> {
+        self._encode_key_fields(key, fields)?;
+        let ret = match self.table.get(key) {
+            Some(fieldmap) => fields
+                .iter()
+                .map(|field| fieldmap.get(*field).cloned())
+                .collect(),
+            None => vec![None; fields.len()],
+        };
+        Ok(ret)
+    }
+    /// Get all the fields of a key along with their values. This is empty if the key
+    /// doesn't exist
+    pub fn get_all(&self, key: &[u8]) -> Result<Vec<(Data, Data)>, ()> {
+        self._encode_key(key)?;
+        let ret = match self.table.get(key) {
+            Some(fieldmap) => fieldmap
+                .iter()
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(ret)
+    }
+    /// Returns the number of fields of a key, which is `0` if the key doesn't exist
+    pub fn count_fields(&self, key: &[u8]) -> Result<usize, ()> {
+        self._encode_key(key)?;
+        Ok(self
+            .table
+            .get(key)
+            .map(|fieldmap| fieldmap.len())
+            .unwrap_or(0))
+    }
+    /// Remove the given fields of a key, returning the number of fields that were
+    /// removed. The key is removed if it has no fields left
+    pub fn remove_fields(&self, key: &[u8], fields: &[&[u8]]) -> Result<usize, ()> {
+        self._encode_key_fields(key, fields)?;
+        let removed = match self.table.get_mut(key) {
+            Some(mut fieldmap) => {
+                let fieldmap = fieldmap.value_mut();
+                let (mut removed, mut removed_bytes) = (0, 0);
+                for field in fields {
+                    if let Some(value) = fieldmap.remove(*field) {
+                        removed += 1;
+                        removed_bytes += field.len() + value.len();
+                    }
+                }
+                self.table.account(0, removed_bytes);
+                removed
+            }
+            None => 0,
+        };
+        // a concurrent write may have added fields after we let go of the key, so only
+        // remove it if it's still empty
+        self.table
+            .true_remove_if(key, |_, fieldmap| fieldmap.is_empty());
+        self.table.touch_if(removed != 0);
+        Ok(removed)
+    }
+}
+
+#[test]
+fn test_set_get_fields() {
+    let hash = HashEngine::default();
+    assert_eq!(
+        hash.set_fields(
+            Data::from("user"),
+            vec![
+                (Data::from("name"), Data::from("sayan")),
+                (Data::from("lang"), Data::from("rust"))
+            ]
+        )
+        .unwrap(),
+        2
+    );
+    // only new fields are counted
+    assert_eq!(
+        hash.set_fields(
+            Data::from("user"),
+            vec![
+                (Data::from("lang"), Data::from("c")),
+                (Data::from("city"), Data::from("kolkata"))
+            ]
+        )
+        .unwrap(),
+        1
+    );
+    assert_eq!(hash.len(), 1);
+    assert_eq!(hash.count_fields(b"user").unwrap(), 3);
+    assert_eq!(
+        hash.get_field(b"user", b"lang").unwrap().unwrap(),
+        Data::from("c")
+    );
+    assert_eq!(
+        hash.get_fields(b"user", &[b"name", b"age"]).unwrap(),
+        vec![Some(Data::from("sayan")), None]
+    );
+    assert_eq!(
+        hash.get_fields(b"nouser", &[b"name", b"age"]).unwrap(),
+        vec![None, None]
+    );
+    assert_eq!(hash.get_all(b"user").unwrap().len(), 3);
+    assert!(hash.get_all(b"nouser").unwrap().is_empty());
+}
+
+#[test]
+fn test_remove_fields() {
+    let hash = HashEngine::default();
+    hash.set_fields(
+        Data::from("user"),
+        vec![
+            (Data::from("name"), Data::from("sayan")),
+            (Data::from("lang"), Data::from("rust")),
+        ],
+    )
+    .unwrap();
+    assert_eq!(hash.remove_fields(b"user", &[b"name", b"age"]).unwrap(), 1);
+    assert_eq!(hash.len(), 1);
+    // the key goes away along with its last field
+    assert_eq!(hash.remove_fields(b"user", &[b"lang"]).unwrap(), 1);
+    assert_eq!(hash.len(), 0);
+    assert_eq!(hash.remove_fields(b"user", &[b"lang"]).unwrap(), 0);
+}
+
+#[test]
+fn test_hash_value_bytes() {
+    let hash = HashEngine::default();
+    let size = hash.write_size(b"user", [&b"name"[..], b"sayan"].iter().copied());
+    assert_eq!(size, WriteSize::new(1, 9, 5));
+    hash.set_fields(
+        Data::from("user"),
+        vec![(Data::from("name"), Data::from("sayan"))],
+    )
+    .unwrap();
+    assert_eq!(hash.__get_inner_ref().value_bytes(), 9);
+    // replacing a value only adds the difference
+    let size = hash.write_size(b"user", [&b"name"[..], b"nandan"].iter().copied());
+    assert_eq!(size, WriteSize::new(0, 1, 6));
+    hash.set_fields(
+        Data::from("user"),
+        vec![
+            (Data::from("name"), Data::from("sn")),
+            (Data::from("lang"), Data::from("rust")),
+        ],
+    )
+    .unwrap();
+    assert_eq!(hash.__get_inner_ref().value_bytes(), 14);
+    hash.remove_fields(b"user", &[b"lang"]).unwrap();
+    assert_eq!(hash.__get_inner_ref().value_bytes(), 6);
+    hash.__get_inner_ref().truncate();
+    assert_eq!(hash.__get_inner_ref().value_bytes(), 0);
+}
+
+#[test]
+fn test_hash_encoding() {
+    let bad_unicode = b"Hello \xF0\x90\x80World".to_vec();
+    let hash = HashEngine::init(true, false);
+    // bad field
+    assert!(hash
+        .set_fields(
+            Data::from("user"),
+            vec![(Data::from(bad_unicode.clone()), Data::from("sayan"))]
+        )
+        .is_err());
+    // a binary value is fine
+    assert!(hash
+        .set_fields(
+            Data::from("user"),
+            vec![(Data::from("name"), Data::from(bad_unicode.clone()))]
+        )
+        .is_ok());
+    let hash = HashEngine::init(false, true);
+    assert!(hash
+        .set_fields(
+            Data::from(bad_unicode.clone()),
+            vec![(Data::from("name"), Data::from(bad_unicode))]
+        )
+        .is_err());
+    assert_eq!(hash.len(), 0);
+    assert_eq!(hash.__get_inner_ref().last_modified(), 0);
+}
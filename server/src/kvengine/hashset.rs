@@ -0,0 +1,433 @@
+/*
+ * Created on Wed Sep 22 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The set engine
+//!
+//! The set engine is the in-memory backing store for `set` tables, where every key maps to
+//! a set of unique members. Like with the hash engine, a key that loses its last member is
+//! removed altogether. Operations that span multiple keys (unions, intersections and
+//! differences) lock the shards of all the involved keys, so that they either see (or
+//! change) all the keys at a single point in time
+
+use super::encoding;
+use super::EngineTable;
+use super::Footprint;
+use crate::corestore::htable::Coremap;
+use crate::corestore::map::bref::Entry;
+use crate::corestore::quota::WriteSize;
+use crate::corestore::Data;
+use crate::resp::TSYMBOL_BINARY;
+use crate::resp::TSYMBOL_UNICODE;
+use core::iter;
+use std::collections::HashSet;
+
+/// The members of a key
+pub type MemberSet = HashSet<Data>;
+
+impl Footprint for MemberSet {
+    fn footprint(&self) -> usize {
+        self.iter().map(|member| member.len()).sum()
+    }
+}
+
+/// An operation that combines multiple sets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOp {
+    /// members that are in any of the sets
+    Union,
+    /// members that are in all the sets
+    Intersection,
+    /// members of the first set that aren't in any of the other sets
+    Difference,
+}
+
+/// Combine the given sets (a `None` being a set that doesn't exist) with the given operation
+fn combine_sets(op: SetOp, sets: &[Option<&MemberSet>]) -> MemberSet {
+    let (first, rest) = match sets.split_first() {
+        Some((first, rest)) => (first, rest),
+        None => return MemberSet::new(),
+    };
+    match op {
+        SetOp::Union => sets
+            .iter()
+            .flatten()
+            .flat_map(|set| set.iter())
+            .cloned()
+            .collect(),
+        SetOp::Intersection => match first {
+            Some(first) => first
+                .iter()
+                .filter(|member| {
+                    rest.iter()
+                        .all(|set| matches!(set, Some(set) if set.contains(*member)))
+                })
+                .cloned()
+                .collect(),
+            None => MemberSet::new(),
+        },
+        SetOp::Difference => match first {
+            Some(first) => first
+                .iter()
+                .filter(|member| {
+                    !rest
+                        .iter()
+                        .any(|set| matches!(set, Some(set) if set.contains(*member)))
+                })
+                .cloned()
+                .collect(),
+            None => MemberSet::new(),
+        },
+    }
+}
+
+/// The set engine that acts as the in-memory backing store for set tables
+#[derive(Debug)]
+pub struct SetEngine {
+    /// the atomic table
+    table: EngineTable<MemberSet>,
+    /// the encoding switch for the keys
+    encoded_k: bool,
+    /// the encoding switch for the members
+    encoded_m: bool,
+}
+
+impl Default for SetEngine {
+    fn default() -> Self {
+        SetEngine::init(false, false)
+    }
+}
+
+impl SetEngine {
+    /// Create a new in-memory set engine with the specified encoding schemes
+    pub fn init(encoded_k: bool, encoded_m: bool) -> Self {
+        Self::init_with_data(encoded_k, encoded_m, Coremap::new())
+    }
+    pub fn init_with_data(
+        encoded_k: bool,
+        encoded_m: bool,
+        table: Coremap<Data, MemberSet>,
+    ) -> Self {
+        Self {
+            table: EngineTable::new(table),
+            encoded_k,
+            encoded_m,
+        }
+    }
+    /// Returns the encoding of the keys and the members
+    pub const fn get_encoding(&self) -> (bool, bool) {
+        (self.encoded_k, self.encoded_m)
+    }
+    /// Returns the tsymbol of the members
+    pub const fn get_mt(&self) -> u8 {
+        if self.encoded_m {
+            TSYMBOL_UNICODE
+        } else {
+            TSYMBOL_BINARY
+        }
+    }
+    /// Returns the number of keys
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+    pub fn __get_inner_ref(&self) -> &EngineTable<MemberSet> {
+        &self.table
+    }
+    /// Check the encoding of the given keys
+    fn _encode_keys(&self, keys: &[&[u8]]) -> Result<(), ()> {
+        if !self.encoded_k || keys.iter().all(encoding::is_utf8) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+    /// Check the encoding of the given members
+    fn _encode_members<T: AsRef<[u8]>>(&self, members: &[T]) -> Result<(), ()> {
+        if !self.encoded_m || members.iter().all(|m| encoding::is_utf8(m.as_ref())) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+    /// Returns the size of a write of the given members to a key
+    pub fn write_size<'a>(&self, key: &[u8], members: impl Iterator<Item = &'a [u8]>) -> WriteSize {
+        let set = self.table.get(key);
+        let (mut bytes, mut largest) = (0, 0);
+        for member in members {
+            if !matches!(set.as_ref(), Some(set) if set.contains(member)) {
+                bytes += member.len();
+            }
+            largest = largest.max(member.len());
+        }
+        WriteSize::new(set.is_none() as usize, bytes, largest)
+    }
+    /// Add the given members to a key, creating the key if it doesn't exist. Returns the
+    /// number of members that weren't already in the set
+    pub fn add_members(&self, key: Data, members: Vec<Data>) -> Result<usize, ()> {
+        self._encode_keys(&[&key])?;
+        self._encode_members(&members)?;
+        let added = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                let set = oe.value_mut();
+                let (mut added, mut added_bytes) = (0, 0);
+                for member in members {
+                    let len = member.len();
+                    if set.insert(member) {
+                        added += 1;
+                        added_bytes += len;
+                    }
+                }
+                self.table.account(added_bytes, 0);
+                added
+            }
+            Entry::Vacant(ve) => {
+                let set: MemberSet = members.into_iter().collect();
+                let added = set.len();
+                self.table.account(set.footprint(), 0);
+                ve.insert(set);
+                added
+            }
+        };
+        self.table.touch_if(added != 0);
+        Ok(added)
+    }
+    /// Remove the given members from a key, returning the number of members that were
+    /// removed. The key is removed if it has no members left
+    pub fn remove_members(&self, key: &[u8], members: &[&[u8]]) -> Result<usize, ()> {
+        self._encode_keys(&[key])?;
+        self._encode_members(members)?;
+        let removed = match self.table.get_mut(key) {
+            Some(mut set) => {
+                let set = set.value_mut();
+                let (mut removed, mut removed_bytes) = (0, 0);
+                for member in members {
+                    if set.remove(*member) {
+                        removed += 1;
+                        removed_bytes += member.len();
+                    }
+                }
+                self.table.account(0, removed_bytes);
+                removed
+            }
+            None => 0,
+        };
+        // a concurrent write may have added members after we let go of the key, so only
+        // remove it if it's still empty
+        self.table.true_remove_if(key, |_, set| set.is_empty());
+        self.table.touch_if(removed != 0);
+        Ok(removed)
+    }
+    /// Check if the given member is in the set of a key
+    pub fn is_member(&self, key: &[u8], member: &[u8]) -> Result<bool, ()> {
+        self._encode_keys(&[key])?;
+        self._encode_members(&[member])?;
+        Ok(matches!(self.table.get(key), Some(set) if set.contains(member)))
+    }
+    /// Returns the number of members of a key, which is `0` if the key doesn't exist
+    pub fn count_members(&self, key: &[u8]) -> Result<usize, ()> {
+        self._encode_keys(&[key])?;
+        Ok(self.table.get(key).map(|set| set.len()).unwrap_or(0))
+    }
+    /// Returns the members of a key. This is empty if the key doesn't exist
+    pub fn get_members(&self, key: &[u8]) -> Result<Vec<Data>, ()> {
+        self._encode_keys(&[key])?;
+        let ret = match self.table.get(key) {
+            Some(set) => set.iter().cloned().collect(),
+            None => Vec::new(),
+        };
+        Ok(ret)
+    }
+    /// Combine the sets of the given keys with the given operation, returning the members
+    /// of the result
+    pub fn combine(&self, op: SetOp, keys: &[&[u8]]) -> Result<Vec<Data>, ()> {
+        self._encode_keys(keys)?;
+        let locked = self.table.inner.lock_shards_for(keys.iter().copied());
+        let sets: Vec<Option<&MemberSet>> = keys.iter().map(|key| locked.get(*key)).collect();
+        Ok(combine_sets(op, &sets).into_iter().collect())
+    }
+    /// Combine the sets of the given keys with the given operation and store the result in
+    /// `dest` (replacing its older set, if any), returning the number of members of the
+    /// result. `dest` is removed if the result is empty. Since the size of the result isn't
+    /// known till the keys are locked, `allow` is asked (under the locks) whether storing it
+    /// is fine; if it isn't, nothing is changed and `None` is returned
+    pub fn combine_into(
+        &self,
+        op: SetOp,
+        dest: Data,
+        keys: &[&[u8]],
+        allow: impl FnOnce(WriteSize) -> bool,
+    ) -> Result<Option<usize>, ()> {
+        self._encode_keys(&[&dest])?;
+        self._encode_keys(keys)?;
+        let mut locked = self
+            .table
+            .inner
+            .lock_shards_for(keys.iter().copied().chain(iter::once(dest.as_ref())));
+        let result = {
+            let sets: Vec<Option<&MemberSet>> = keys.iter().map(|key| locked.get(*key)).collect();
+            combine_sets(op, &sets)
+        };
+        let count = result.len();
+        let older = locked.get(dest.as_ref()).map(MemberSet::footprint);
+        if count != 0 {
+            let footprint = result.footprint();
+            let largest = result.iter().map(|member| member.len()).max().unwrap_or(0);
+            let size = WriteSize::new(
+                older.is_none() as usize,
+                footprint.saturating_sub(older.unwrap_or(0)),
+                largest,
+            );
+            if !allow(size) {
+                return Ok(None);
+            }
+            self.table.account(footprint, 0);
+        }
+        let modified = if count == 0 {
+            locked.remove(dest.as_ref()).is_some()
+        } else {
+            locked.insert(dest, result);
+            true
+        };
+        self.table.account(0, older.unwrap_or(0));
+        self.table.touch_if(modified);
+        Ok(Some(count))
+    }
+}
+
+#[test]
+fn test_add_remove_members() {
+    let set = SetEngine::default();
+    assert_eq!(
+        set.add_members(
+            Data::from("tags"),
+            vec![Data::from("rust"), Data::from("db")]
+        )
+        .unwrap(),
+        2
+    );
+    assert_eq!(
+        set.add_members(
+            Data::from("tags"),
+            vec![Data::from("rust"), Data::from("c")]
+        )
+        .unwrap(),
+        1
+    );
+    assert_eq!(set.count_members(b"tags").unwrap(), 3);
+    assert!(set.is_member(b"tags", b"c").unwrap());
+    assert!(!set.is_member(b"tags", b"go").unwrap());
+    assert!(!set.is_member(b"notags", b"c").unwrap());
+    assert_eq!(
+        set.write_size(b"tags", [&b"rust"[..], b"go"].iter().copied()),
+        WriteSize::new(0, 2, 4)
+    );
+    assert_eq!(set.__get_inner_ref().value_bytes(), 7);
+    assert_eq!(set.remove_members(b"tags", &[b"c", b"go"]).unwrap(), 1);
+    assert_eq!(set.remove_members(b"tags", &[b"rust", b"db"]).unwrap(), 2);
+    assert_eq!(set.__get_inner_ref().value_bytes(), 0);
+    // the key goes away along with its last member
+    assert_eq!(set.len(), 0);
+    assert!(set.get_members(b"tags").unwrap().is_empty());
+}
+
+#[test]
+fn test_set_algebra() {
+    fn sorted(mut members: Vec<Data>) -> Vec<Data> {
+        members.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        members
+    }
+    let set = SetEngine::default();
+    set.add_members(
+        Data::from("a"),
+        vec![Data::from("1"), Data::from("2"), Data::from("3")],
+    )
+    .unwrap();
+    set.add_members(
+        Data::from("b"),
+        vec![Data::from("2"), Data::from("3"), Data::from("4")],
+    )
+    .unwrap();
+    assert_eq!(
+        sorted(set.combine(SetOp::Union, &[b"a", b"b", b"c"]).unwrap()),
+        vec![
+            Data::from("1"),
+            Data::from("2"),
+            Data::from("3"),
+            Data::from("4")
+        ]
+    );
+    assert_eq!(
+        sorted(set.combine(SetOp::Intersection, &[b"a", b"b"]).unwrap()),
+        vec![Data::from("2"), Data::from("3")]
+    );
+    // a missing set is an empty set
+    assert!(set
+        .combine(SetOp::Intersection, &[b"a", b"b", b"c"])
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        sorted(set.combine(SetOp::Difference, &[b"a", b"b"]).unwrap()),
+        vec![Data::from("1")]
+    );
+    assert_eq!(
+        set.combine_into(SetOp::Union, Data::from("a"), &[b"a", b"b"], |_| true)
+            .unwrap(),
+        Some(4)
+    );
+    assert_eq!(set.count_members(b"a").unwrap(), 4);
+    // a result that isn't allowed isn't stored
+    assert_eq!(
+        set.combine_into(SetOp::Union, Data::from("c"), &[b"a"], |size| {
+            assert_eq!(size, WriteSize::new(1, 4, 1));
+            false
+        })
+        .unwrap(),
+        None
+    );
+    assert_eq!(set.len(), 2);
+    // an empty result removes the destination
+    assert_eq!(
+        set.combine_into(SetOp::Difference, Data::from("a"), &[b"b", b"a"], |_| true)
+            .unwrap(),
+        Some(0)
+    );
+    assert_eq!(set.len(), 1);
+    assert_eq!(set.__get_inner_ref().value_bytes(), 3);
+}
+
+#[test]
+fn test_set_encoding() {
+    let bad_unicode = b"Hello \xF0\x90\x80World".to_vec();
+    let set = SetEngine::init(false, true);
+    assert!(set
+        .add_members(Data::from("tags"), vec![Data::from(bad_unicode.clone())])
+        .is_err());
+    let set = SetEngine::init(true, false);
+    assert!(set
+        .add_members(Data::from(bad_unicode.clone()), vec![Data::from("rust")])
+        .is_err());
+    assert!(set.combine(SetOp::Union, &[&bad_unicode]).is_err());
+    assert_eq!(set.len(), 0);
+}
@@ -26,12 +26,37 @@
 
 use crate::corestore::htable::Coremap;
 use crate::corestore::htable::Data;
+use crate::corestore::map::bref::Entry;
 use crate::corestore::map::bref::Ref;
+use crate::corestore::quota::WriteSize;
+use crate::kvengine::glob::Pattern;
+use crate::registry::stats;
 use crate::resp::TSYMBOL_BINARY;
+use crate::resp::TSYMBOL_UINT;
 use crate::resp::TSYMBOL_UNICODE;
 use core::borrow::Borrow;
 use core::hash::Hash;
+use core::ops::Deref;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
+pub use hashmap::HashEngine;
+pub use hashset::SetEngine;
+pub use sortedset::SortedSetEngine;
+pub use timeseries::TimeSeriesEngine;
+pub use types::ValueType;
+pub use versioned::VersionCheck;
+pub use versioned::Versioned;
 pub mod encoding;
+pub mod glob;
+pub mod hashmap;
+pub mod hashset;
+pub mod sketch;
+pub mod sortedset;
+pub mod strings;
+pub mod timeseries;
+pub mod types;
+pub mod versioned;
 
 /// An arbitrary unicode/binary _double encoder_ for two byte slice inputs
 pub struct DoubleEncoder {
@@ -74,6 +99,23 @@ macro_rules! d_encoder {
     };
 }
 
+/// Returns a double encoder for values of a typed scalar type
+macro_rules! typed_encoder {
+    ($validator:path, $t:expr, $encoded_k:expr) => {
+        if $encoded_k {
+            fn is_okay(key: &[u8], value: &[u8]) -> bool {
+                encoding::is_utf8(key) && $validator(value)
+            }
+            d_encoder!(is_okay, $t)
+        } else {
+            fn is_okay(_key: &[u8], value: &[u8]) -> bool {
+                $validator(value)
+            }
+            d_encoder!(is_okay, $t)
+        }
+    };
+}
+
 macro_rules! s_encoder {
     ($fn:expr, $t:expr) => {
         SingleEncoder {
@@ -83,6 +125,99 @@ macro_rules! s_encoder {
     };
 }
 
+/// The number of bytes held by a value of an engine, which is what the value adds to the
+/// memory usage of its table
+pub trait Footprint {
+    fn footprint(&self) -> usize;
+}
+
+/// The table of an engine along with the bookkeeping that every engine does on it. This
+/// derefs to the underlying [`Coremap`], through which the engines access their data
+#[derive(Debug)]
+pub struct EngineTable<V> {
+    /// the atomic table
+    map: Coremap<Data, V>,
+    /// the time (UNIX timestamp in seconds) of the last write. `0` if there wasn't one
+    /// since startup
+    last_modified: AtomicU64,
+    /// the total footprint of the values
+    value_bytes: AtomicUsize,
+}
+
+impl<V> Deref for EngineTable<V> {
+    type Target = Coremap<Data, V>;
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<V: Footprint> EngineTable<V> {
+    pub fn new(map: Coremap<Data, V>) -> Self {
+        let value_bytes = map.iter().map(|kv| kv.value().footprint()).sum();
+        Self {
+            map,
+            last_modified: AtomicU64::new(0),
+            value_bytes: AtomicUsize::new(value_bytes),
+        }
+    }
+    /// Returns the time (UNIX timestamp in seconds) of the last write, or `0` if there
+    /// wasn't one since startup
+    pub fn last_modified(&self) -> u64 {
+        self.last_modified.load(Ordering::Relaxed)
+    }
+    /// Record a write. This has to be called by anyone who writes to the table directly
+    /// instead of going through the methods of the engine
+    pub fn touch(&self) {
+        self.last_modified
+            .store(stats::unix_now(), Ordering::Relaxed);
+    }
+    /// Record a write if `modified` is set, returning `modified`
+    pub fn touch_if(&self, modified: bool) -> bool {
+        if modified {
+            self.touch();
+        }
+        modified
+    }
+    /// Returns the total footprint of the values in this table
+    pub fn value_bytes(&self) -> usize {
+        self.value_bytes.load(Ordering::Acquire)
+    }
+    /// Record that values with a footprint of `added` bytes were written to the table and
+    /// that values with a footprint of `removed` bytes left it. Just like [`Self::touch`],
+    /// this has to be called by anyone who writes to the table directly. Additions have to
+    /// be recorded while holding the lock on the shard of the key, so that they're always
+    /// recorded before the removal of the same value
+    pub fn account(&self, added: usize, removed: usize) {
+        if added > removed {
+            self.value_bytes
+                .fetch_add(added - removed, Ordering::AcqRel);
+        } else if removed > added {
+            self.value_bytes
+                .fetch_sub(removed - added, Ordering::AcqRel);
+        }
+    }
+    /// Returns the approximate number of bytes held by the keys and the values
+    pub fn approx_mem_usage(&self) -> usize {
+        self.map
+            .iter()
+            .map(|kv| kv.key().len() + kv.value().footprint())
+            .sum()
+    }
+    /// Truncate the table
+    pub fn truncate(&self) {
+        // we go shard by shard (instead of clearing the table) to know what we removed
+        let mut removed = 0;
+        for shard in 0..self.map.shard_count() {
+            self.map.remove_in_shard(shard, |_, value| {
+                removed += value.footprint();
+                true
+            });
+        }
+        self.account(0, removed);
+        self.touch();
+    }
+}
+
 // DROP impl isn't required as ShardLock's field types need-drop (std::mem)
 
 /// The key/value engine that acts as the in-memory backing store for the database
@@ -90,11 +225,13 @@ macro_rules! s_encoder {
 #[derive(Debug)]
 pub struct KVEngine {
     /// the atomic table
-    table: Coremap<Data, Data>,
+    table: EngineTable<Versioned>,
     /// the encoding switch for the key
     encoded_k: bool,
-    /// the encoding switch for the value
-    encoded_v: bool,
+    /// the type of the values
+    v_type: ValueType,
+    /// the last version that was handed out to a value (see [`versioned`])
+    version: AtomicU64,
 }
 
 impl Default for KVEngine {
@@ -110,47 +247,107 @@ impl KVEngine {
     pub fn init(encoded_k: bool, encoded_v: bool) -> Self {
         Self::init_with_data(encoded_k, encoded_v, Coremap::new())
     }
-    pub fn init_with_data(encoded_k: bool, encoded_v: bool, table: Coremap<Data, Data>) -> Self {
+    pub fn init_with_data(
+        encoded_k: bool,
+        encoded_v: bool,
+        table: Coremap<Data, Versioned>,
+    ) -> Self {
+        let v_type = if encoded_v {
+            ValueType::Str
+        } else {
+            ValueType::Binstr
+        };
+        Self::init_typed_with_data(encoded_k, v_type, table)
+    }
+    /// Create a new in-memory KVEngine with the specified key encoding and value type
+    pub fn init_typed_with_data(
+        encoded_k: bool,
+        v_type: ValueType,
+        table: Coremap<Data, Versioned>,
+    ) -> Self {
+        let version = table
+            .iter()
+            .map(|kv| kv.value().version())
+            .max()
+            .unwrap_or(0);
         Self {
-            table,
+            table: EngineTable::new(table),
             encoded_k,
-            encoded_v,
+            v_type,
+            version: AtomicU64::new(version),
         }
     }
-    pub fn get_encoding(&self) -> (bool, bool) {
-        (self.encoded_k, self.encoded_v)
+    /// Record a write (see [`EngineTable::touch`])
+    pub fn touch(&self) {
+        self.table.touch();
+    }
+    /// Returns the total length (in bytes) of the values in this table
+    pub fn value_bytes(&self) -> usize {
+        self.table.value_bytes()
+    }
+    /// Returns the length of the value of a key, if it exists
+    pub fn value_len(&self, key: &[u8]) -> Option<usize> {
+        self.table.get(key).map(|value| value.len())
+    }
+    /// Returns the size of a write of alternating keys and values, taking the values that
+    /// they'd replace into account
+    pub fn write_size<'a>(&self, mut args: impl Iterator<Item = &'a [u8]>) -> WriteSize {
+        let mut size = WriteSize::default();
+        while let (Some(key), Some(value)) = (args.next(), args.next()) {
+            size = size.with_value(self.value_len(key), value.len());
+        }
+        size
+    }
+    /// Record that values of `added` bytes were written to the table and that values of
+    /// `removed` bytes left it (see [`EngineTable::account`])
+    pub fn account(&self, added: usize, removed: usize) {
+        self.table.account(added, removed);
+    }
+    /// Returns the key encoding and the type of the values
+    pub const fn get_types(&self) -> (bool, ValueType) {
+        (self.encoded_k, self.v_type)
     }
     /// Returns an encoder for the key and the value
     pub fn get_encoder(&self) -> DoubleEncoder {
-        match self.get_encoding() {
-            (true, true) => {
+        match self.get_types() {
+            (true, ValueType::Str) => {
                 // both k & v
                 fn is_okay(key: &[u8], value: &[u8]) -> bool {
                     encoding::is_utf8(key) && encoding::is_utf8(value)
                 }
                 d_encoder!(is_okay, TSYMBOL_UNICODE)
             }
-            (true, false) => {
+            (true, ValueType::Binstr) => {
                 // only k
                 fn is_okay(key: &[u8], _value: &[u8]) -> bool {
                     encoding::is_utf8(key)
                 }
                 d_encoder!(is_okay, TSYMBOL_BINARY)
             }
-            (false, false) => {
+            (false, ValueType::Binstr) => {
                 // none
                 fn is_okay(_k: &[u8], _v: &[u8]) -> bool {
                     true
                 }
                 d_encoder!(is_okay, TSYMBOL_BINARY)
             }
-            (false, true) => {
+            (false, ValueType::Str) => {
                 // only v
                 fn is_okay(_k: &[u8], v: &[u8]) -> bool {
                     encoding::is_utf8(v)
                 }
                 d_encoder!(is_okay, TSYMBOL_UNICODE)
             }
+            (encoded_k, ValueType::UInt) => typed_encoder!(types::is_uint, TSYMBOL_UINT, encoded_k),
+            (encoded_k, ValueType::SInt) => {
+                typed_encoder!(types::is_sint, TSYMBOL_UNICODE, encoded_k)
+            }
+            (encoded_k, ValueType::Float) => {
+                typed_encoder!(types::is_float, TSYMBOL_UNICODE, encoded_k)
+            }
+            (encoded_k, ValueType::Bool) => {
+                typed_encoder!(types::is_bool, TSYMBOL_UNICODE, encoded_k)
+            }
         }
     }
     /// Returns an encoder for the key
@@ -169,22 +366,12 @@ impl KVEngine {
     }
     /// Returns an encoder for the value
     pub fn get_value_encoder(&self) -> SingleEncoder {
-        if self.encoded_v {
-            fn e(inp: &[u8]) -> bool {
-                encoding::is_utf8(inp)
-            }
-            s_encoder!(e, TSYMBOL_UNICODE)
-        } else {
-            fn e(_inp: &[u8]) -> bool {
-                true
-            }
-            s_encoder!(e, TSYMBOL_BINARY)
-        }
+        s_encoder!(self.v_type.validator(), self.v_type.tsymbol())
     }
     pub fn len(&self) -> usize {
         self.table.len()
     }
-    pub fn __get_inner_ref(&self) -> &Coremap<Data, Data> {
+    pub fn __get_inner_ref(&self) -> &EngineTable<Versioned> {
         &self.table
     }
     /// Return an owned value of the key. In most cases, the reference count is just incremented
@@ -194,27 +381,23 @@ impl KVEngine {
         Data: Borrow<Q>,
         Q: AsRef<[u8]> + Hash + Eq + ?Sized,
     {
-        self.table.get(key).map(|v| v.clone())
+        self.table.get(key).map(|v| v.data().clone())
     }
     /// Truncate the table
     pub fn truncate_table(&self) {
-        self.table.clear()
+        self.table.truncate();
     }
     pub const fn needs_value_encoding(&self) -> bool {
-        self.encoded_v
+        self.v_type.needs_validation()
     }
     pub const fn needs_key_encoding(&self) -> bool {
         self.encoded_k
     }
     pub const fn needs_no_encoding(&self) -> bool {
-        !(self.encoded_k && self.encoded_v)
+        !(self.encoded_k || self.v_type.needs_validation())
     }
     pub const fn get_vt(&self) -> u8 {
-        if self.encoded_v {
-            TSYMBOL_UNICODE
-        } else {
-            TSYMBOL_BINARY
-        }
+        self.v_type.tsymbol()
     }
     pub const fn get_kt(&self) -> u8 {
         if self.encoded_k {
@@ -224,7 +407,7 @@ impl KVEngine {
         }
     }
     /// Get the value for a given key if it exists
-    pub fn get_with_tsymbol<Q>(&self, key: &Q) -> Result<(Option<Ref<Data, Data>>, u8), ()>
+    pub fn get_with_tsymbol<Q>(&self, key: &Q) -> Result<(Option<Ref<'_, Data, Versioned>>, u8), ()>
     where
         Data: Borrow<Q>,
         Q: AsRef<[u8]> + Hash + Eq + ?Sized,
@@ -233,7 +416,7 @@ impl KVEngine {
         Ok((self.table.get(key), self.get_vt()))
     }
     /// Get the value for a given key if it exists
-    pub fn get<Q>(&self, key: &Q) -> Result<Option<Ref<Data, Data>>, ()>
+    pub fn get<Q>(&self, key: &Q) -> Result<Option<Ref<'_, Data, Versioned>>, ()>
     where
         Data: Borrow<Q>,
         Q: AsRef<[u8]> + Hash + Eq + ?Sized,
@@ -248,14 +431,14 @@ impl KVEngine {
         Q: AsRef<[u8]> + Hash + Eq + ?Sized,
     {
         self._encode_key(key)?;
-        Ok(self.table.get_cloned(key))
+        Ok(self.get_cloned_unchecked(key))
     }
     pub fn get_cloned_unchecked<Q>(&self, key: &Q) -> Option<Data>
     where
         Data: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.table.get_cloned(key)
+        self.table.get(key).map(|v| v.data().clone())
     }
     /// Get the value for a given key if it exists, returning a cloned reference
     pub fn get_cloned_with_tsymbol<Q>(&self, key: &Q) -> Result<(Option<Data>, u8), ()>
@@ -264,7 +447,7 @@ impl KVEngine {
         Q: AsRef<[u8]> + Hash + Eq + ?Sized,
     {
         self._encode_key(key)?;
-        Ok((self.table.get_cloned(key), self.get_vt()))
+        Ok((self.get_cloned_unchecked(key), self.get_vt()))
     }
     pub fn exists<Q>(&self, key: &Q) -> Result<bool, ()>
     where
@@ -298,44 +481,88 @@ impl KVEngine {
             Ok(())
         }
     }
-    /// Check the unicode encoding of the given value, if the encoded_v flag is set
+    /// Check that the given value is valid for the type of the values
     fn _encode_value<T: AsRef<[u8]>>(&self, value: T) -> Result<(), ()> {
-        if self.encoded_v {
-            Self::_encode(value)
-        } else {
+        if (self.v_type.validator())(value.as_ref()) {
             Ok(())
+        } else {
+            Err(())
         }
     }
     /// Set the value of a non-existent key
     pub fn set(&self, key: Data, value: Data) -> Result<bool, ()> {
         self._encode_key(&key)?;
         self._encode_value(&value)?;
-        Ok(self.table.true_if_insert(key, value))
+        Ok(self.set_unchecked(key, value))
     }
     /// Set the value of a non-existent key
     pub fn set_unchecked(&self, key: Data, value: Data) -> bool {
-        self.table.true_if_insert(key, value)
+        let inserted = match self.table.entry(key) {
+            Entry::Vacant(ve) => {
+                self.account(value.len(), 0);
+                ve.insert(self.versioned(value));
+                true
+            }
+            Entry::Occupied(_) => false,
+        };
+        self.table.touch_if(inserted)
     }
     /// Update the value of an existing key
     pub fn update(&self, key: Data, value: Data) -> Result<bool, ()> {
         self._encode_key(&key)?;
         self._encode_value(&value)?;
-        Ok(self.table.true_if_update(key, value))
+        Ok(self.update_unchecked(key, value))
     }
     /// Update the value of an existing key
     pub fn update_unchecked(&self, key: Data, value: Data) -> bool {
-        self.table.true_if_update(key, value)
+        let updated = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                self.account(value.len(), oe.value().len());
+                oe.insert(self.versioned(value));
+                true
+            }
+            Entry::Vacant(_) => false,
+        };
+        self.table.touch_if(updated)
     }
     /// Update or insert the value of a key
     pub fn upsert(&self, key: Data, value: Data) -> Result<(), ()> {
         self._encode_key(&key)?;
         self._encode_value(&value)?;
-        self.table.upsert(key, value);
+        self.upsert_unchecked(key, value);
         Ok(())
     }
+    /// Update or insert the value of a key, returning the older value (if any)
+    pub fn getset(&self, key: Data, value: Data) -> Result<Option<Data>, ()> {
+        self._encode_key(&key)?;
+        self._encode_value(&value)?;
+        let older = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                self.account(value.len(), oe.value().len());
+                Some(oe.insert(self.versioned(value)).into_data())
+            }
+            Entry::Vacant(ve) => {
+                self.account(value.len(), 0);
+                ve.insert(self.versioned(value));
+                None
+            }
+        };
+        self.touch();
+        Ok(older)
+    }
     /// Update or insert the value of a key
     pub fn upsert_unchecked(&self, key: Data, value: Data) {
-        self.table.upsert(key, value);
+        match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                self.account(value.len(), oe.value().len());
+                oe.insert(self.versioned(value));
+            }
+            Entry::Vacant(ve) => {
+                self.account(value.len(), 0);
+                ve.insert(self.versioned(value));
+            }
+        }
+        self.touch();
     }
     /// Remove an existing key
     pub fn remove<Q>(&self, key: &Q) -> Result<bool, ()>
@@ -344,7 +571,7 @@ impl KVEngine {
         Q: AsRef<[u8]> + Hash + Eq + ?Sized,
     {
         self._encode_key(key)?;
-        Ok(self.table.true_if_removed(key))
+        Ok(self.remove_unchecked(key))
     }
     /// Remove an existing key
     pub fn remove_unchecked<Q>(&self, key: &Q) -> bool
@@ -352,7 +579,33 @@ impl KVEngine {
         Data: Borrow<Q>,
         Q: AsRef<[u8]> + Hash + Eq + ?Sized,
     {
-        self.table.true_if_removed(key)
+        self.pop_unchecked(key).is_some()
+    }
+    /// Returns the number of shards that the keys are spread over. Bulk operations like
+    /// [`Self::remove_matching`] work one shard at a time so that they never lock the
+    /// whole table
+    pub fn shard_count(&self) -> usize {
+        self.table.shard_count()
+    }
+    /// Count the keys of the given shard that match a pattern
+    pub fn count_matching(&self, shard: usize, pattern: &Pattern) -> usize {
+        self.table
+            .count_in_shard(shard, |key, _| pattern.matches(key))
+    }
+    /// Remove the keys of the given shard that match a pattern, returning the number of
+    /// removed keys
+    pub fn remove_matching(&self, shard: usize, pattern: &Pattern) -> usize {
+        let mut removed_bytes = 0;
+        let removed = self.table.remove_in_shard(shard, |key, value| {
+            let matches = pattern.matches(key);
+            if matches {
+                removed_bytes += value.len();
+            }
+            matches
+        });
+        self.account(0, removed_bytes);
+        self.table.touch_if(removed != 0);
+        removed
     }
     pub fn pop<Q>(&self, key: &Q) -> Result<Option<(Data, Data)>, ()>
     where
@@ -360,14 +613,19 @@ impl KVEngine {
         Q: AsRef<[u8]> + Hash + Eq + ?Sized,
     {
         self._encode_key(key)?;
-        Ok(self.table.remove(key))
+        Ok(self.pop_unchecked(key))
     }
     pub fn pop_unchecked<Q>(&self, key: &Q) -> Option<(Data, Data)>
     where
         Data: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.table.remove(key)
+        let popped = self.table.remove(key).map(|(key, value)| {
+            self.account(0, value.len());
+            (key, value.into_data())
+        });
+        self.table.touch_if(popped.is_some());
+        popped
     }
 }
 
@@ -404,6 +662,74 @@ fn test_bad_unicode_key_value() {
         .is_err());
 }
 
+#[test]
+fn test_remove_matching() {
+    let tbl = KVEngine::default();
+    for key in ["user:1", "user:2", "user:10", "session:1"] {
+        tbl.set_unchecked(Data::from(key), Data::from("value"));
+    }
+    let run = |pattern: &[u8], remove: bool| -> usize {
+        let pattern = Pattern::new(pattern).unwrap();
+        (0..tbl.shard_count())
+            .map(|shard| {
+                if remove {
+                    tbl.remove_matching(shard, &pattern)
+                } else {
+                    tbl.count_matching(shard, &pattern)
+                }
+            })
+            .sum()
+    };
+    assert_eq!(run(b"user:*", false), 3);
+    assert_eq!(run(b"*:1", false), 2);
+    assert_eq!(run(b"user:?", true), 2);
+    assert_eq!(tbl.len(), 2);
+    assert!(tbl.exists_unchecked(b"user:10".as_ref()));
+    assert_eq!(run(b"nothing*", true), 0);
+}
+
+#[test]
+fn test_last_modified() {
+    let tbl = KVEngine::default();
+    let table = tbl.__get_inner_ref();
+    assert_eq!(table.last_modified(), 0);
+    // writes that don't change anything aren't recorded
+    assert!(!tbl.update(Data::from("x"), Data::from("100")).unwrap());
+    assert!(tbl.pop(b"x".as_ref()).unwrap().is_none());
+    assert_eq!(table.last_modified(), 0);
+    assert!(tbl.set(Data::from("x"), Data::from("100")).unwrap());
+    assert_ne!(table.last_modified(), 0);
+}
+
+#[test]
+fn test_value_bytes() {
+    let tbl = KVEngine::default();
+    assert!(tbl.set(Data::from("a"), Data::from("hello")).unwrap());
+    assert!(tbl.set(Data::from("b"), Data::from("world!")).unwrap());
+    assert_eq!(tbl.value_bytes(), 11);
+    assert!(tbl.update(Data::from("a"), Data::from("hi")).unwrap());
+    assert_eq!(tbl.value_bytes(), 8);
+    assert_eq!(tbl.append(Data::from("b"), b"!!").unwrap(), 8);
+    assert_eq!(tbl.value_bytes(), 10);
+    assert!(tbl.remove(b"a".as_ref()).unwrap());
+    assert_eq!(tbl.value_bytes(), 8);
+    // the usage of a write depends on what it replaces
+    let args: [&[u8]; 4] = [b"b", b"1234567890", b"c", b"xyz"];
+    assert_eq!(
+        tbl.write_size(args.iter().copied()),
+        WriteSize::new(1, 5, 10)
+    );
+    tbl.truncate_table();
+    assert_eq!(tbl.value_bytes(), 0);
+    // restored tables start with the size of their values
+    let restored = KVEngine::init_with_data(false, false, {
+        let map = Coremap::new();
+        map.upsert(Data::from("x"), Versioned::new(Data::from("1234"), 1));
+        map
+    });
+    assert_eq!(restored.value_bytes(), 4);
+}
+
 #[test]
 fn test_with_bincode() {
     #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
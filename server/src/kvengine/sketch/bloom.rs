@@ -0,0 +1,284 @@
+/*
+ * Created on Fri Sep 24 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Bloom filters
+//!
+//! A Bloom filter tells if an item was (probably) added to it: it never misses an item that
+//! was added, but might claim that an item was added when it wasn't. The odds of the latter
+//! are set when the filter is reserved, along with the number of items it's meant to hold.
+//! A filter is a magic header, the number of hashes (1B), the number of bits (8B, little
+//! endian) and then the bits themselves
+
+use super::{murmur64a, SketchError, SketchResult};
+use crate::corestore::map::bref::Entry;
+use crate::corestore::Data;
+use crate::kvengine::KVEngine;
+use core::convert::TryInto;
+
+/// The magic header of a Bloom filter
+const MAGIC: &[u8] = b"SKYBLM01";
+/// The size of the header (the magic, the number of hashes and the number of bits)
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8;
+/// The largest number of bits in a filter (that's 128 MiB)
+const MAX_BITS: u64 = 1 << 30;
+/// The largest number of hashes per item
+const MAX_HASHES: u8 = 32;
+/// The seed for hashing the items
+const SEED: u64 = 0x5bd1_e995;
+
+/// The size of a Bloom filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterParams {
+    /// the number of hashes per item
+    hashes: u8,
+    /// the number of bits
+    bits: u64,
+}
+
+impl FilterParams {
+    /// The error rate of filters created by adding to a key that doesn't exist
+    pub const DEFAULT_ERROR_RATE: f64 = 0.01;
+    /// The capacity of filters created by adding to a key that doesn't exist
+    pub const DEFAULT_CAPACITY: u64 = 100;
+    /// Returns the size of a filter that holds `capacity` items with the given error rate,
+    /// or `None` if the error rate isn't between 0 and 1, the capacity is zero or if the
+    /// filter would be too large
+    pub fn new(error_rate: f64, capacity: u64) -> Option<Self> {
+        if !(error_rate > 0.0 && error_rate < 1.0) || capacity == 0 {
+            return None;
+        }
+        let ln2 = core::f64::consts::LN_2;
+        let bits = (-(capacity as f64) * error_rate.ln() / (ln2 * ln2)).ceil();
+        if bits > MAX_BITS as f64 {
+            return None;
+        }
+        let bits = bits as u64;
+        let hashes = (bits as f64 / capacity as f64 * ln2).round();
+        let hashes = hashes.max(1.0).min(MAX_HASHES as f64) as u8;
+        Some(Self { hashes, bits })
+    }
+    /// Returns the size (in bytes) of a filter with these parameters
+    pub fn filter_len(&self) -> usize {
+        HEADER_LEN + bytes_for(self.bits)
+    }
+    /// Returns the parameters of a filter, or `None` if it isn't one
+    fn of(blob: &[u8]) -> Option<Self> {
+        if blob.len() < HEADER_LEN || !blob.starts_with(MAGIC) {
+            return None;
+        }
+        let hashes = blob[MAGIC.len()];
+        let bits = u64::from_le_bytes(blob[MAGIC.len() + 1..HEADER_LEN].try_into().unwrap());
+        let is_valid = (1..=MAX_HASHES).contains(&hashes)
+            && (1..=MAX_BITS).contains(&bits)
+            && blob.len() == HEADER_LEN + bytes_for(bits);
+        if is_valid {
+            Some(Self { hashes, bits })
+        } else {
+            None
+        }
+    }
+    /// Returns an empty filter
+    fn empty_filter(&self) -> Vec<u8> {
+        let mut filter = Vec::with_capacity(self.filter_len());
+        filter.extend_from_slice(MAGIC);
+        filter.push(self.hashes);
+        filter.extend_from_slice(&self.bits.to_le_bytes());
+        filter.resize(self.filter_len(), 0);
+        filter
+    }
+    /// Returns the positions of the bits of an item
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item = usize> {
+        // double hashing, where every position is `h1 + i * h2`
+        let h1 = murmur64a(item, SEED);
+        let h2 = murmur64a(item, h1) | 1;
+        let bits = self.bits;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits) as usize)
+    }
+}
+
+impl Default for FilterParams {
+    fn default() -> Self {
+        // the default parameters are always valid
+        Self::new(Self::DEFAULT_ERROR_RATE, Self::DEFAULT_CAPACITY).unwrap()
+    }
+}
+
+/// Returns the number of bytes needed for the given number of bits
+fn bytes_for(bits: u64) -> usize {
+    bits.div_ceil(8) as usize
+}
+
+/// Check if the bit at the given position of a filter is set
+fn is_set(filter: &[u8], position: usize) -> bool {
+    filter[HEADER_LEN + position / 8] & (1 << (position % 8)) != 0
+}
+
+/// Check if an item was (probably) added to a filter
+fn contains(params: &FilterParams, filter: &[u8], item: &[u8]) -> bool {
+    params
+        .positions(item)
+        .all(|position| is_set(filter, position))
+}
+
+/// Add an item to a filter, returning true if any of its bits weren't set (that is, if the
+/// item definitely wasn't added before)
+fn add_item(params: &FilterParams, filter: &mut [u8], item: &[u8]) -> bool {
+    let mut modified = false;
+    for position in params.positions(item) {
+        if !is_set(filter, position) {
+            filter[HEADER_LEN + position / 8] |= 1 << (position % 8);
+            modified = true;
+        }
+    }
+    modified
+}
+
+impl KVEngine {
+    /// Create an empty Bloom filter of the given size for a key. Returns false if the key
+    /// already exists
+    pub fn bloom_reserve(&self, key: Data, params: FilterParams) -> SketchResult<bool> {
+        self._sketch_keys(&[&key])?;
+        let created = match self.table.fresh_entry(key) {
+            Some(ve) => {
+                let filter = params.empty_filter();
+                self.account(filter.len(), 0);
+                ve.insert(self.versioned(Data::from(filter)));
+                true
+            }
+            None => false,
+        };
+        Ok(self.table.touch_if(created))
+    }
+    /// Add an item to the Bloom filter of a key, creating a filter with the default
+    /// parameters if the key doesn't exist. Returns true if the item definitely wasn't
+    /// added before
+    pub fn bloom_add(&self, key: Data, item: &[u8]) -> SketchResult<bool> {
+        self._sketch_keys(&[&key])?;
+        let added = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                let params = FilterParams::of(oe.value()).ok_or(SketchError::WrongType)?;
+                if contains(&params, oe.value(), item) {
+                    // no need to copy the filter
+                    false
+                } else {
+                    let mut filter = oe.value().to_vec();
+                    add_item(&params, &mut filter, item);
+                    self.account(filter.len(), oe.value().len());
+                    *oe.value_mut() = self.versioned(Data::from(filter));
+                    true
+                }
+            }
+            Entry::Vacant(ve) => {
+                let params = FilterParams::default();
+                let mut filter = params.empty_filter();
+                add_item(&params, &mut filter, item);
+                self.account(filter.len(), 0);
+                ve.insert(self.versioned(Data::from(filter)));
+                true
+            }
+        };
+        Ok(self.table.touch_if(added))
+    }
+    /// Check if the given items were (probably) added to the Bloom filter of a key. If the
+    /// key doesn't exist, then none of them were
+    pub fn bloom_check(&self, key: &[u8], items: &[&[u8]]) -> SketchResult<Vec<bool>> {
+        self._sketch_keys(&[key])?;
+        let ret = match self.table.get(key) {
+            Some(filter) => {
+                let params = FilterParams::of(&filter).ok_or(SketchError::WrongType)?;
+                items
+                    .iter()
+                    .map(|item| contains(&params, &filter, item))
+                    .collect()
+            }
+            None => vec![false; items.len()],
+        };
+        Ok(ret)
+    }
+}
+
+#[test]
+fn test_filter_params() {
+    let params = FilterParams::new(0.01, 1000).unwrap();
+    // about 9.6 bits and 7 hashes per item
+    assert_eq!(params.bits, 9586);
+    assert_eq!(params.hashes, 7);
+    assert!(FilterParams::new(0.0, 1000).is_none());
+    assert!(FilterParams::new(1.0, 1000).is_none());
+    assert!(FilterParams::new(0.01, 0).is_none());
+    assert!(FilterParams::new(0.000_001, u64::MAX).is_none());
+    let filter = params.empty_filter();
+    assert_eq!(FilterParams::of(&filter), Some(params));
+    assert_eq!(FilterParams::of(&filter[..filter.len() - 1]), None);
+}
+
+#[test]
+fn test_bloom_filter() {
+    let kve = KVEngine::default();
+    assert!(kve
+        .bloom_reserve(Data::from("seen"), FilterParams::new(0.01, 1000).unwrap())
+        .unwrap());
+    assert!(!kve
+        .bloom_reserve(Data::from("seen"), FilterParams::default())
+        .unwrap());
+    let items: Vec<String> = (0..2000).map(|i| format!("id-{}", i)).collect();
+    for item in &items[..1000] {
+        kve.bloom_add(Data::from("seen"), item.as_bytes()).unwrap();
+    }
+    let added: Vec<&[u8]> = items[..1000].iter().map(|i| i.as_bytes()).collect();
+    let not_added: Vec<&[u8]> = items[1000..].iter().map(|i| i.as_bytes()).collect();
+    // no false negatives
+    assert!(kve
+        .bloom_check(b"seen", &added)
+        .unwrap()
+        .into_iter()
+        .all(|x| x));
+    // and about 1% false positives
+    let false_positives = kve
+        .bloom_check(b"seen", &not_added)
+        .unwrap()
+        .into_iter()
+        .filter(|x| *x)
+        .count();
+    assert!(false_positives < 30, "{} false positives", false_positives);
+    // adding an item twice is a no-op
+    assert!(!kve.bloom_add(Data::from("seen"), b"id-0").unwrap());
+    assert_eq!(kve.bloom_check(b"unseen", &[b"id-0"]).unwrap(), vec![false]);
+}
+
+#[test]
+fn test_bloom_wrong_type() {
+    let kve = KVEngine::default();
+    kve.hll_add(Data::from("visitors"), &[b"1"]).unwrap();
+    assert_eq!(
+        kve.bloom_add(Data::from("visitors"), b"1"),
+        Err(SketchError::WrongType)
+    );
+    assert_eq!(
+        kve.bloom_check(b"visitors", &[b"1"]),
+        Err(SketchError::WrongType)
+    );
+}
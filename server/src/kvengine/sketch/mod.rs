@@ -0,0 +1,112 @@
+/*
+ * Created on Fri Sep 24 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Probabilistic sketches
+//!
+//! Sketches are compact probabilistic structures (HyperLogLog counters and Bloom filters)
+//! that are stored as the values of keys in key/value tables with `binstr` values. Every
+//! sketch starts with a magic header identifying its type, so that they're persisted and
+//! restored like any other value while the sketch actions can still tell them apart from
+//! regular values (and from each other).
+//!
+//! Since sketches are persisted along with the hashes of what was added to them, they use
+//! their own hash function (MurmurHash64A) which, unlike the hasher of the standard library,
+//! is guaranteed to stay the same across platforms and releases
+
+use super::encoding;
+use super::KVEngine;
+use super::ValueType;
+use core::convert::TryInto;
+
+pub mod bloom;
+pub mod hyperloglog;
+
+/// An error while running a sketch operation
+#[derive(Debug, PartialEq)]
+pub enum SketchError {
+    /// the key isn't valid unicode while the table needs unicode keys
+    Encoding,
+    /// the value of the key isn't a sketch of the expected type
+    WrongType,
+    /// the table can't hold sketches (only tables with `binstr` values can)
+    WrongModel,
+}
+
+pub type SketchResult<T> = Result<T, SketchError>;
+
+/// MurmurHash64A by Austin Appleby
+pub fn murmur64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+impl KVEngine {
+    /// Check that this table can hold sketches and that the given keys are valid
+    fn _sketch_keys(&self, keys: &[&[u8]]) -> SketchResult<()> {
+        if self.v_type != ValueType::Binstr {
+            Err(SketchError::WrongModel)
+        } else if self.encoded_k && !keys.iter().all(encoding::is_utf8) {
+            Err(SketchError::Encoding)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn test_murmur64a() {
+    // the hashes have to stay the same forever, since they're persisted
+    assert_eq!(murmur64a(b"", 0), 0);
+    assert_eq!(murmur64a(b"hello", 0), 0x1e68_d17c_457b_f117);
+    assert_eq!(
+        murmur64a(b"Skytable is a NoSQL database", 42),
+        0x695d_a5fb_26e5_1d11
+    );
+    assert_ne!(murmur64a(b"hello", 0), murmur64a(b"hello", 1));
+    assert_ne!(murmur64a(b"hello", 0), murmur64a(b"hellp", 0));
+    assert_ne!(murmur64a(b"sky", 0), murmur64a(b"sky\0", 0));
+}
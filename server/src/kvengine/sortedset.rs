@@ -0,0 +1,614 @@
+/*
+ * Created on Thu Sep 23 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The sorted set engine
+//!
+//! The sorted set engine is the in-memory backing store for `sortedset` tables, where every
+//! key maps to a set of unique members, each with a (finite) score. Members are ranked by
+//! their scores (and members with equal scores by their bytes), which makes sorted sets a
+//! good fit for leaderboards, priority queues and time-ordered indices.
+//!
+//! Every set keeps its members twice: in a hash map (for looking up the score of a member)
+//! and in a ranking, which is a sorted list split into blocks of bounded size. Inserts and
+//! removals only shift the members of a single block, while rank lookups only need the
+//! lengths of the preceding blocks, so that even sets with millions of members stay cheap
+//! to update and query
+
+use super::encoding;
+use super::EngineTable;
+use super::Footprint;
+use crate::corestore::htable::Coremap;
+use crate::corestore::map::bref::Entry;
+use crate::corestore::quota::WriteSize;
+use crate::corestore::Data;
+use crate::resp::TSYMBOL_BINARY;
+use crate::resp::TSYMBOL_UNICODE;
+use core::cmp;
+use core::cmp::Ordering;
+use core::iter;
+use std::collections::HashMap;
+
+/// The largest number of members in a block of the ranking before it's split in two
+const MAX_BLOCK_LEN: usize = 512;
+
+/// Compare a ranked member against the given score and member
+fn cmp_entry(entry: &(f64, Data), score: f64, member: &[u8]) -> Ordering {
+    // scores are always finite, so they're always comparable
+    entry
+        .0
+        .partial_cmp(&score)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| entry.1.as_ref().cmp(member))
+}
+
+/// Resolve an inclusive range of ranks (where negative ranks are counted from the end) for
+/// a set with `len` members, returning `None` if the range is empty
+fn resolve_rank_range(len: usize, start: i64, stop: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { start + len } else { start };
+    let stop = if stop < 0 { stop + len } else { stop };
+    let (start, stop) = (cmp::max(start, 0), cmp::min(stop, len - 1));
+    if start > stop {
+        None
+    } else {
+        Some((start as usize, stop as usize))
+    }
+}
+
+/// The scored members of a key
+#[derive(Debug, Clone, Default)]
+pub struct ScoreSet {
+    /// the score of every member
+    scores: HashMap<Data, f64>,
+    /// the members, sorted by their scores and split into non-empty blocks
+    ranking: Vec<Vec<(f64, Data)>>,
+}
+
+impl PartialEq for ScoreSet {
+    fn eq(&self, other: &Self) -> bool {
+        // the blocks of the ranking depend on the order of the writes, so only compare
+        // the scores
+        self.scores == other.scores
+    }
+}
+
+/// Returns the footprint of a member, which is the member along with its score
+const fn member_footprint(member: &[u8]) -> usize {
+    member.len() + 8
+}
+
+impl Footprint for ScoreSet {
+    fn footprint(&self) -> usize {
+        self.scores
+            .keys()
+            .map(|member| member_footprint(member))
+            .sum()
+    }
+}
+
+impl ScoreSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Returns the number of members
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+    /// Returns the score of a member
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+    /// Set the score of a member, adding the member if it doesn't exist. Returns the older
+    /// score of the member, if any
+    pub fn insert(&mut self, member: Data, score: f64) -> Option<f64> {
+        let old = self.scores.insert(member.clone(), score);
+        match old {
+            Some(old) if old == score => {}
+            Some(old) => {
+                self.unrank(old, &member);
+                self.rank(score, member);
+            }
+            None => self.rank(score, member),
+        }
+        old
+    }
+    /// Remove a member, returning true if it existed
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => {
+                self.unrank(score, member);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Returns the (zero-based) rank of a member, counting from the lowest score
+    pub fn rank_of(&self, member: &[u8]) -> Option<usize> {
+        let score = self.score(member)?;
+        let idx = self.block_of(score, member);
+        let pos = self
+            .ranking
+            .get(idx)?
+            .binary_search_by(|entry| cmp_entry(entry, score, member))
+            .ok()?;
+        Some(self.ranking[..idx].iter().map(Vec::len).sum::<usize>() + pos)
+    }
+    /// Returns the members ranked from `start` to `stop` (both inclusive and in range)
+    pub fn range_by_rank(&self, start: usize, stop: usize) -> Vec<Data> {
+        let mut ret = Vec::with_capacity(stop - start + 1);
+        let (mut skip, mut take) = (start, stop - start + 1);
+        for block in &self.ranking {
+            if skip >= block.len() {
+                skip -= block.len();
+                continue;
+            }
+            let end = cmp::min(block.len(), skip + take);
+            ret.extend(block[skip..end].iter().map(|(_, member)| member.clone()));
+            take -= end - skip;
+            skip = 0;
+            if take == 0 {
+                break;
+            }
+        }
+        ret
+    }
+    /// Returns the members with a score between `min` and `max` (both inclusive), in the
+    /// order of their ranks
+    pub fn range_by_score(&self, min: f64, max: f64) -> Vec<Data> {
+        let idx = self
+            .ranking
+            .partition_point(|block| matches!(block.last(), Some((score, _)) if *score < min));
+        self.ranking[idx..]
+            .iter()
+            .flatten()
+            .skip_while(|(score, _)| *score < min)
+            .take_while(|(score, _)| *score <= max)
+            .map(|(_, member)| member.clone())
+            .collect()
+    }
+    /// Returns an iterator over the members and their scores, in the order of their ranks
+    pub fn iter(&self) -> impl Iterator<Item = (&Data, f64)> {
+        self.ranking
+            .iter()
+            .flatten()
+            .map(|(score, member)| (member, *score))
+    }
+    /// Returns the index of the block of the ranking where the given member belongs. This
+    /// is the number of blocks if it's beyond the last block
+    fn block_of(&self, score: f64, member: &[u8]) -> usize {
+        self.ranking.partition_point(|block| {
+            matches!(block.last(), Some(last) if cmp_entry(last, score, member) == Ordering::Less)
+        })
+    }
+    /// Add a member to the ranking
+    fn rank(&mut self, score: f64, member: Data) {
+        let idx = cmp::min(
+            self.block_of(score, &member),
+            self.ranking.len().saturating_sub(1),
+        );
+        match self.ranking.get_mut(idx) {
+            Some(block) => {
+                let pos = block
+                    .partition_point(|entry| cmp_entry(entry, score, &member) == Ordering::Less);
+                block.insert(pos, (score, member));
+                if block.len() > MAX_BLOCK_LEN {
+                    let upper = block.split_off(block.len() / 2);
+                    self.ranking.insert(idx + 1, upper);
+                }
+            }
+            None => self.ranking.push(vec![(score, member)]),
+        }
+    }
+    /// Remove a member from the ranking
+    fn unrank(&mut self, score: f64, member: &[u8]) {
+        let idx = self.block_of(score, member);
+        if let Some(block) = self.ranking.get_mut(idx) {
+            if let Ok(pos) = block.binary_search_by(|entry| cmp_entry(entry, score, member)) {
+                block.remove(pos);
+                if block.is_empty() {
+                    self.ranking.remove(idx);
+                }
+            }
+        }
+    }
+}
+
+/// The sorted set engine that acts as the in-memory backing store for sorted set tables
+#[derive(Debug)]
+pub struct SortedSetEngine {
+    /// the atomic table
+    table: EngineTable<ScoreSet>,
+    /// the encoding switch for the keys
+    encoded_k: bool,
+    /// the encoding switch for the members
+    encoded_m: bool,
+}
+
+impl Default for SortedSetEngine {
+    fn default() -> Self {
+        SortedSetEngine::init(false, false)
+    }
+}
+
+impl SortedSetEngine {
+    /// Create a new in-memory sorted set engine with the specified encoding schemes
+    pub fn init(encoded_k: bool, encoded_m: bool) -> Self {
+        Self::init_with_data(encoded_k, encoded_m, Coremap::new())
+    }
+    pub fn init_with_data(
+        encoded_k: bool,
+        encoded_m: bool,
+        table: Coremap<Data, ScoreSet>,
+    ) -> Self {
+        Self {
+            table: EngineTable::new(table),
+            encoded_k,
+            encoded_m,
+        }
+    }
+    /// Returns the encoding of the keys and the members
+    pub const fn get_encoding(&self) -> (bool, bool) {
+        (self.encoded_k, self.encoded_m)
+    }
+    /// Returns the tsymbol of the members
+    pub const fn get_mt(&self) -> u8 {
+        if self.encoded_m {
+            TSYMBOL_UNICODE
+        } else {
+            TSYMBOL_BINARY
+        }
+    }
+    /// Returns the number of keys
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+    pub fn __get_inner_ref(&self) -> &EngineTable<ScoreSet> {
+        &self.table
+    }
+    /// Check the encoding of the given key
+    fn _encode_key(&self, key: &[u8]) -> Result<(), ()> {
+        if !self.encoded_k || encoding::is_utf8(key) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+    /// Check the encoding of the given members
+    fn _encode_members<'a>(&self, mut members: impl Iterator<Item = &'a [u8]>) -> Result<(), ()> {
+        if !self.encoded_m || members.all(encoding::is_utf8) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+    /// Returns the size of a write of the given members to a key
+    pub fn write_size<'a>(&self, key: &[u8], members: impl Iterator<Item = &'a [u8]>) -> WriteSize {
+        let set = self.table.get(key);
+        let (mut bytes, mut largest) = (0, 0);
+        for member in members {
+            if !matches!(set.as_ref(), Some(set) if set.score(member).is_some()) {
+                bytes += member_footprint(member);
+            }
+            largest = largest.max(member.len());
+        }
+        WriteSize::new(set.is_none() as usize, bytes, largest)
+    }
+    /// Set the scores of the given members of a key, adding the members that don't exist
+    /// (and creating the key if it doesn't exist). Returns the number of members that were
+    /// added
+    pub fn add_members(&self, key: Data, members: Vec<(f64, Data)>) -> Result<usize, ()> {
+        self._encode_key(&key)?;
+        self._encode_members(members.iter().map(|(_, member)| member.as_ref()))?;
+        let (added, modified) = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                let set = oe.value_mut();
+                let (mut added, mut added_bytes, mut modified) = (0, 0, false);
+                for (score, member) in members {
+                    let footprint = member_footprint(&member);
+                    match set.insert(member, score) {
+                        Some(old) => modified |= old != score,
+                        None => {
+                            added += 1;
+                            added_bytes += footprint;
+                        }
+                    }
+                }
+                self.table.account(added_bytes, 0);
+                (added, modified)
+            }
+            Entry::Vacant(ve) => {
+                let mut set = ScoreSet::new();
+                for (score, member) in members {
+                    set.insert(member, score);
+                }
+                let added = set.len();
+                self.table.account(set.footprint(), 0);
+                ve.insert(set);
+                (added, true)
+            }
+        };
+        self.table.touch_if(added != 0 || modified);
+        Ok(added)
+    }
+    /// Add `by` to the score of a member of a key, adding the member with a score of `by`
+    /// if it doesn't exist. Returns the new score, or `None` (without changing anything) if
+    /// the new score wouldn't be finite
+    pub fn incr_score(&self, key: Data, member: Data, by: f64) -> Result<Option<f64>, ()> {
+        self._encode_key(&key)?;
+        self._encode_members(iter::once(member.as_ref()))?;
+        let score = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                let set = oe.value_mut();
+                let older = set.score(&member);
+                let score = older.unwrap_or(0.0) + by;
+                if !score.is_finite() {
+                    return Ok(None);
+                }
+                if older.is_none() {
+                    self.table.account(member_footprint(&member), 0);
+                }
+                set.insert(member, score);
+                score
+            }
+            Entry::Vacant(ve) => {
+                let mut set = ScoreSet::new();
+                self.table.account(member_footprint(&member), 0);
+                set.insert(member, by);
+                ve.insert(set);
+                by
+            }
+        };
+        self.table.touch();
+        Ok(Some(score))
+    }
+    /// Remove the given members from a key, returning the number of members that were
+    /// removed. The key is removed if it has no members left
+    pub fn remove_members(&self, key: &[u8], members: &[&[u8]]) -> Result<usize, ()> {
+        self._encode_key(key)?;
+        self._encode_members(members.iter().copied())?;
+        let removed = match self.table.get_mut(key) {
+            Some(mut set) => {
+                let set = set.value_mut();
+                let (mut removed, mut removed_bytes) = (0, 0);
+                for member in members {
+                    if set.remove(member) {
+                        removed += 1;
+                        removed_bytes += member_footprint(member);
+                    }
+                }
+                self.table.account(0, removed_bytes);
+                removed
+            }
+            None => 0,
+        };
+        // a concurrent write may have added members after we let go of the key, so only
+        // remove it if it's still empty
+        self.table.true_remove_if(key, |_, set| set.is_empty());
+        self.table.touch_if(removed != 0);
+        Ok(removed)
+    }
+    /// Returns the score of a member of a key
+    pub fn get_score(&self, key: &[u8], member: &[u8]) -> Result<Option<f64>, ()> {
+        self._encode_key(key)?;
+        self._encode_members(iter::once(member))?;
+        Ok(self.table.get(key).and_then(|set| set.score(member)))
+    }
+    /// Returns the (zero-based) rank of a member of a key, counting from the lowest score
+    /// (or from the highest score if `rev` is set)
+    pub fn get_rank(&self, key: &[u8], member: &[u8], rev: bool) -> Result<Option<usize>, ()> {
+        self._encode_key(key)?;
+        self._encode_members(iter::once(member))?;
+        let rank = self.table.get(key).and_then(|set| {
+            set.rank_of(member)
+                .map(|rank| if rev { set.len() - 1 - rank } else { rank })
+        });
+        Ok(rank)
+    }
+    /// Returns the number of members of a key, which is `0` if the key doesn't exist
+    pub fn count_members(&self, key: &[u8]) -> Result<usize, ()> {
+        self._encode_key(key)?;
+        Ok(self.table.get(key).map(|set| set.len()).unwrap_or(0))
+    }
+    /// Returns the members of a key ranked from `start` to `stop` (both inclusive, with
+    /// negative ranks counted from the end). If `rev` is set, the members are ranked from
+    /// the highest score instead
+    pub fn range_by_rank(
+        &self,
+        key: &[u8],
+        start: i64,
+        stop: i64,
+        rev: bool,
+    ) -> Result<Vec<Data>, ()> {
+        self._encode_key(key)?;
+        let set = match self.table.get(key) {
+            Some(set) => set,
+            None => return Ok(Vec::new()),
+        };
+        let ret = match resolve_rank_range(set.len(), start, stop) {
+            Some((start, stop)) if rev => {
+                let last = set.len() - 1;
+                let mut members = set.range_by_rank(last - stop, last - start);
+                members.reverse();
+                members
+            }
+            Some((start, stop)) => set.range_by_rank(start, stop),
+            None => Vec::new(),
+        };
+        Ok(ret)
+    }
+    /// Returns the members of a key with a score between `min` and `max` (both inclusive),
+    /// from the lowest score to the highest
+    pub fn range_by_score(&self, key: &[u8], min: f64, max: f64) -> Result<Vec<Data>, ()> {
+        self._encode_key(key)?;
+        let ret = match self.table.get(key) {
+            Some(set) => set.range_by_score(min, max),
+            None => Vec::new(),
+        };
+        Ok(ret)
+    }
+}
+
+#[test]
+fn test_score_set_ranking() {
+    let mut set = ScoreSet::new();
+    // enough members to span multiple blocks
+    let count = MAX_BLOCK_LEN * 4;
+    for i in (0..count).rev() {
+        assert_eq!(set.insert(Data::from(format!("m{:05}", i)), i as f64), None);
+    }
+    assert!(set.ranking.len() > 1);
+    assert_eq!(set.rank_of(b"m00000"), Some(0));
+    assert_eq!(set.rank_of(b"m01234"), Some(1234));
+    assert_eq!(
+        set.range_by_rank(1023, 1025),
+        vec![
+            Data::from("m01023"),
+            Data::from("m01024"),
+            Data::from("m01025")
+        ]
+    );
+    assert_eq!(
+        set.range_by_score(10.0, 12.5),
+        vec![
+            Data::from("m00010"),
+            Data::from("m00011"),
+            Data::from("m00012")
+        ]
+    );
+    // move the first member to the end
+    assert_eq!(set.insert(Data::from("m00000"), count as f64), Some(0.0));
+    assert_eq!(set.rank_of(b"m00000"), Some(count - 1));
+    assert_eq!(set.rank_of(b"m00001"), Some(0));
+    for i in 0..count {
+        assert!(set.remove(format!("m{:05}", i).as_bytes()));
+    }
+    assert!(set.is_empty());
+    assert!(set.ranking.is_empty());
+}
+
+#[test]
+fn test_equal_scores_rank_by_member() {
+    let mut set = ScoreSet::new();
+    set.insert(Data::from("b"), 1.0);
+    set.insert(Data::from("a"), 1.0);
+    set.insert(Data::from("c"), 0.5);
+    assert_eq!(
+        set.range_by_rank(0, 2),
+        vec![Data::from("c"), Data::from("a"), Data::from("b")]
+    );
+}
+
+#[test]
+fn test_sorted_set_engine() {
+    let zset = SortedSetEngine::default();
+    assert_eq!(
+        zset.add_members(
+            Data::from("board"),
+            vec![
+                (30.0, Data::from("alice")),
+                (10.0, Data::from("bob")),
+                (20.0, Data::from("carol"))
+            ]
+        )
+        .unwrap(),
+        3
+    );
+    assert_eq!(zset.__get_inner_ref().value_bytes(), 37);
+    assert_eq!(
+        zset.write_size(b"board", [&b"alice"[..], b"dave"].iter().copied()),
+        WriteSize::new(0, 12, 5)
+    );
+    assert_eq!(
+        zset.add_members(Data::from("board"), vec![(5.0, Data::from("alice"))])
+            .unwrap(),
+        0
+    );
+    assert_eq!(zset.__get_inner_ref().value_bytes(), 37);
+    assert_eq!(zset.get_score(b"board", b"alice").unwrap(), Some(5.0));
+    assert_eq!(zset.get_rank(b"board", b"alice", false).unwrap(), Some(0));
+    assert_eq!(zset.get_rank(b"board", b"alice", true).unwrap(), Some(2));
+    assert_eq!(
+        zset.incr_score(Data::from("board"), Data::from("alice"), 20.0)
+            .unwrap(),
+        Some(25.0)
+    );
+    assert_eq!(
+        zset.range_by_rank(b"board", 0, -1, true).unwrap(),
+        vec![Data::from("alice"), Data::from("carol"), Data::from("bob")]
+    );
+    assert_eq!(
+        zset.range_by_rank(b"board", -2, 100, false).unwrap(),
+        vec![Data::from("carol"), Data::from("alice")]
+    );
+    assert!(zset
+        .range_by_rank(b"board", 2, 1, false)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        zset.range_by_score(b"board", 10.0, 20.0).unwrap(),
+        vec![Data::from("bob"), Data::from("carol")]
+    );
+    // a score that isn't finite is refused
+    assert_eq!(
+        zset.incr_score(Data::from("board"), Data::from("bob"), f64::MAX)
+            .unwrap(),
+        Some(f64::MAX)
+    );
+    assert_eq!(
+        zset.incr_score(Data::from("board"), Data::from("bob"), f64::MAX)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        zset.remove_members(b"board", &[b"alice", b"bob", b"carol", b"dave"])
+            .unwrap(),
+        3
+    );
+    // the key goes away along with its last member
+    assert_eq!(zset.len(), 0);
+    assert_eq!(zset.__get_inner_ref().value_bytes(), 0);
+    assert_eq!(zset.get_rank(b"board", b"alice", false).unwrap(), None);
+}
+
+#[test]
+fn test_sorted_set_encoding() {
+    let bad_unicode = b"Hello \xF0\x90\x80World".to_vec();
+    let zset = SortedSetEngine::init(false, true);
+    assert!(zset
+        .add_members(
+            Data::from("board"),
+            vec![(1.0, Data::from(bad_unicode.clone()))]
+        )
+        .is_err());
+    let zset = SortedSetEngine::init(true, false);
+    assert!(zset
+        .incr_score(Data::from(bad_unicode.clone()), Data::from("alice"), 1.0)
+        .is_err());
+    assert_eq!(zset.len(), 0);
+}
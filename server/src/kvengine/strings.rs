@@ -0,0 +1,302 @@
+/*
+ * Created on Mon Sep 27 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # String operations
+//!
+//! Atomic operations on parts of the values of key/value tables with `str` or `binstr`
+//! values, which run while holding the lock on the shard of the key (so there's no window
+//! for another write between reading and writing the value). Offsets are byte offsets and
+//! for `str` tables, every operation makes sure that the value stays valid unicode without
+//! revalidating the whole value
+
+use super::encoding;
+use super::KVEngine;
+use super::ValueType;
+use crate::corestore::map::bref::Entry;
+use crate::corestore::Data;
+
+/// The maximum length (in bytes) that a value can grow to with string operations (512 MiB)
+pub const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+/// An error while running a string operation
+#[derive(Debug, PartialEq)]
+pub enum StringOpError {
+    /// the key or the bytes aren't valid unicode (or the operation would split a character)
+    /// while the table needs unicode keys or values
+    Encoding,
+    /// the table doesn't have string values
+    WrongType,
+    /// the value would grow beyond [`MAX_STRING_LEN`]
+    TooLarge,
+}
+
+pub type StringOpResult<T> = Result<T, StringOpError>;
+
+/// Check if the given index is at the start of a UTF-8 character (or right at the end) of
+/// valid UTF-8 bytes
+fn is_char_boundary(bytes: &[u8], idx: usize) -> bool {
+    // continuation bytes are 0b10xxxxxx
+    match bytes.get(idx) {
+        Some(byte) => (*byte as i8) >= -0x40,
+        None => true,
+    }
+}
+
+/// Resolve an inclusive range with negative indices (counted from the end) into a range of
+/// a value of the given length, returning `None` if it's empty
+fn resolve_range(len: usize, start: i64, end: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let resolve = |idx: i64| if idx < 0 { len + idx } else { idx };
+    let start = resolve(start).max(0);
+    let end = resolve(end).min(len - 1);
+    if start > end {
+        None
+    } else {
+        Some((start as usize, end as usize + 1))
+    }
+}
+
+impl KVEngine {
+    /// Check that the table has string values and that the key is valid
+    fn _string_op(&self, key: &[u8]) -> StringOpResult<()> {
+        if !self.v_type.is_string() {
+            Err(StringOpError::WrongType)
+        } else if self.encoded_k && !encoding::is_utf8(key) {
+            Err(StringOpError::Encoding)
+        } else {
+            Ok(())
+        }
+    }
+    /// Check that the given bytes can be written to a value
+    fn _string_op_bytes(&self, bytes: &[u8]) -> StringOpResult<()> {
+        if self.v_type == ValueType::Str && !encoding::is_utf8(bytes) {
+            Err(StringOpError::Encoding)
+        } else {
+            Ok(())
+        }
+    }
+    /// Append bytes to the value of a key, creating the key if it doesn't exist. Returns the
+    /// new length of the value
+    ///
+    /// Values are immutable shared buffers (readers may still hold on to the older value),
+    /// so this copies the whole value into a new one: every append is `O(n)` in the length
+    /// of the value, and building a value out of many small appends is quadratic
+    pub fn append(&self, key: Data, suffix: &[u8]) -> StringOpResult<usize> {
+        self._string_op(&key)?;
+        // two valid strings always make a valid string, so we only check the suffix
+        self._string_op_bytes(suffix)?;
+        let len = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                let value = oe.value_mut();
+                let len = value.len() + suffix.len();
+                if len > MAX_STRING_LEN {
+                    return Err(StringOpError::TooLarge);
+                }
+                let mut appended = Vec::with_capacity(len);
+                appended.extend_from_slice(value);
+                appended.extend_from_slice(suffix);
+                self.account(len, value.len());
+                *value = self.versioned(Data::from(appended));
+                len
+            }
+            Entry::Vacant(ve) => {
+                if suffix.len() > MAX_STRING_LEN {
+                    return Err(StringOpError::TooLarge);
+                }
+                self.account(suffix.len(), 0);
+                ve.insert(self.versioned(Data::copy_from_slice(suffix)));
+                suffix.len()
+            }
+        };
+        self.touch();
+        Ok(len)
+    }
+    /// Returns the length (in bytes) of the value of a key, or `None` if the key doesn't
+    /// exist
+    pub fn strlen(&self, key: &[u8]) -> StringOpResult<Option<usize>> {
+        self._string_op(key)?;
+        Ok(self.table.get(key).map(|value| value.len()))
+    }
+    /// Returns the bytes of the value of a key between `start` and `end` (both inclusive,
+    /// with negative offsets counted from the end), or `None` if the key doesn't exist. The
+    /// range is clamped to the value, so an out of range range returns an empty string
+    pub fn get_range(&self, key: &[u8], start: i64, end: i64) -> StringOpResult<Option<Data>> {
+        self._string_op(key)?;
+        let value = match self.table.get(key) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let range = match resolve_range(value.len(), start, end) {
+            Some((start, end)) => {
+                if self.v_type == ValueType::Str
+                    && !(is_char_boundary(&value, start) && is_char_boundary(&value, end))
+                {
+                    return Err(StringOpError::Encoding);
+                }
+                // no copying; this just holds another reference to the value
+                Data::from_blob(value.get_blob().slice(start..end))
+            }
+            None => Data::from(""),
+        };
+        Ok(Some(range))
+    }
+    /// Overwrite the bytes of the value of a key starting at `offset`, creating the key
+    /// if it doesn't exist. The value is padded with zero bytes if it's shorter than
+    /// `offset`. Returns the new length of the value
+    pub fn set_range(&self, key: Data, offset: usize, bytes: &[u8]) -> StringOpResult<usize> {
+        self._string_op(&key)?;
+        self._string_op_bytes(bytes)?;
+        let end = match offset.checked_add(bytes.len()) {
+            Some(end) if end <= MAX_STRING_LEN => end,
+            _ => return Err(StringOpError::TooLarge),
+        };
+        if bytes.is_empty() {
+            // nothing to write, so don't create the key either
+            return Ok(self.table.get(&key).map_or(0, |value| value.len()));
+        }
+        let len = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                let value = oe.value_mut();
+                if self.v_type == ValueType::Str
+                    && !(is_char_boundary(value, offset) && is_char_boundary(value, end))
+                {
+                    // we'd split a character
+                    return Err(StringOpError::Encoding);
+                }
+                let mut overwritten = Vec::with_capacity(value.len().max(end));
+                overwritten.extend_from_slice(value);
+                if overwritten.len() < end {
+                    overwritten.resize(end, 0);
+                }
+                overwritten[offset..end].copy_from_slice(bytes);
+                self.account(overwritten.len(), value.len());
+                *value = self.versioned(Data::from(overwritten));
+                value.len()
+            }
+            Entry::Vacant(ve) => {
+                let mut value = vec![0; end];
+                value[offset..].copy_from_slice(bytes);
+                self.account(end, 0);
+                ve.insert(self.versioned(Data::from(value)));
+                end
+            }
+        };
+        self.touch();
+        Ok(len)
+    }
+}
+
+#[test]
+fn test_resolve_range() {
+    assert_eq!(resolve_range(10, 0, 3), Some((0, 4)));
+    assert_eq!(resolve_range(10, -3, -1), Some((7, 10)));
+    assert_eq!(resolve_range(10, 0, 100), Some((0, 10)));
+    assert_eq!(resolve_range(10, -100, 1), Some((0, 2)));
+    assert_eq!(resolve_range(10, 5, 2), None);
+    assert_eq!(resolve_range(10, 10, 12), None);
+    assert_eq!(resolve_range(0, 0, -1), None);
+}
+
+#[test]
+fn test_append() {
+    let kve = KVEngine::init(true, true);
+    assert_eq!(kve.append(Data::from("log"), b"hello").unwrap(), 5);
+    assert_eq!(
+        kve.append(Data::from("log"), " wörld".as_bytes()).unwrap(),
+        12
+    );
+    assert_eq!(
+        kve.get_cloned(b"log".as_ref()).unwrap().unwrap(),
+        Data::from("hello wörld")
+    );
+    assert_eq!(
+        kve.append(Data::from("log"), b"\xF0\x90\x80"),
+        Err(StringOpError::Encoding)
+    );
+    let kve = KVEngine::init_typed_with_data(false, ValueType::UInt, Default::default());
+    assert_eq!(
+        kve.append(Data::from("log"), b"1"),
+        Err(StringOpError::WrongType)
+    );
+}
+
+#[test]
+fn test_strlen() {
+    let kve = KVEngine::init(false, true);
+    kve.set_unchecked(Data::from("greeting"), Data::from("hëllo"));
+    assert_eq!(kve.strlen(b"greeting").unwrap(), Some(6));
+    assert_eq!(kve.strlen(b"nogreeting").unwrap(), None);
+    let kve = KVEngine::init_typed_with_data(false, ValueType::UInt, Default::default());
+    assert_eq!(kve.strlen(b"x"), Err(StringOpError::WrongType));
+}
+
+#[test]
+fn test_get_range() {
+    let kve = KVEngine::init(false, true);
+    kve.set_unchecked(Data::from("greeting"), Data::from("hëllo"));
+    let get_range = |start, end| kve.get_range(b"greeting", start, end);
+    assert_eq!(get_range(0, 0).unwrap(), Some(Data::from("h")));
+    assert_eq!(get_range(-3, -1).unwrap(), Some(Data::from("llo")));
+    assert_eq!(get_range(1, 2).unwrap(), Some(Data::from("ë")));
+    assert_eq!(get_range(3, 1).unwrap(), Some(Data::from("")));
+    // that's half of the ë
+    assert_eq!(get_range(0, 1), Err(StringOpError::Encoding));
+    assert_eq!(kve.get_range(b"nogreeting", 0, 1).unwrap(), None);
+}
+
+#[test]
+fn test_set_range() {
+    let kve = KVEngine::init(false, false);
+    assert_eq!(kve.set_range(Data::from("buf"), 2, b"ab").unwrap(), 4);
+    assert_eq!(
+        kve.get_cloned(b"buf".as_ref()).unwrap().unwrap(),
+        Data::from(b"\0\0ab".to_vec())
+    );
+    assert_eq!(kve.set_range(Data::from("buf"), 0, b"xyz").unwrap(), 4);
+    assert_eq!(
+        kve.get_cloned(b"buf".as_ref()).unwrap().unwrap(),
+        Data::from("xyzb")
+    );
+    // empty writes don't create keys
+    assert_eq!(kve.set_range(Data::from("nobuf"), 5, b"").unwrap(), 0);
+    assert!(!kve.exists_unchecked(b"nobuf".as_ref()));
+    assert_eq!(
+        kve.set_range(Data::from("buf"), MAX_STRING_LEN, b"a"),
+        Err(StringOpError::TooLarge)
+    );
+    let kve = KVEngine::init(false, true);
+    kve.set_unchecked(Data::from("greeting"), Data::from("hëllo"));
+    // can't overwrite half of the ë
+    assert_eq!(
+        kve.set_range(Data::from("greeting"), 2, b"e"),
+        Err(StringOpError::Encoding)
+    );
+    assert_eq!(kve.set_range(Data::from("greeting"), 1, b"ey").unwrap(), 6);
+    assert_eq!(
+        kve.get_cloned(b"greeting".as_ref()).unwrap().unwrap(),
+        Data::from("heyllo")
+    );
+}
@@ -0,0 +1,415 @@
+/*
+ * Created on Sat Sep 25 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The time series engine
+//!
+//! The time series engine is the in-memory backing store for `timeseries` tables, where
+//! every key maps to a series: an append-only sequence of samples, each being a timestamp
+//! (a UNIX timestamp in milliseconds) and a (finite) float. Every sample has to be newer than
+//! the last one, so a series is always sorted by time and range queries are a binary search
+//! away. A series can have a retention period, in which case appending a sample drops the
+//! samples that are older than the new sample by more than the retention period
+
+use super::encoding;
+use super::EngineTable;
+use super::Footprint;
+use crate::corestore::htable::Coremap;
+use crate::corestore::map::bref::Entry;
+use crate::corestore::quota::WriteSize;
+use crate::corestore::Data;
+use std::collections::VecDeque;
+
+/// A sample: a timestamp (in milliseconds) and a value
+pub type Sample = (u64, f64);
+
+/// The samples of a key, oldest first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Series {
+    /// the retention period in milliseconds, where `0` means that samples are kept forever
+    retention: u64,
+    /// the samples
+    samples: VecDeque<Sample>,
+}
+
+/// The footprint of a sample, which is a timestamp and a value
+const SAMPLE_FOOTPRINT: usize = 16;
+
+impl Footprint for Series {
+    fn footprint(&self) -> usize {
+        self.samples.len() * SAMPLE_FOOTPRINT
+    }
+}
+
+impl Series {
+    /// Create an empty series with the given retention period
+    pub fn new(retention: u64) -> Self {
+        Self {
+            retention,
+            samples: VecDeque::new(),
+        }
+    }
+    /// Create a series with the given retention period and samples, returning `None` if
+    /// the samples aren't strictly ordered by time
+    pub fn with_samples(retention: u64, samples: VecDeque<Sample>) -> Option<Self> {
+        let is_ordered = samples
+            .iter()
+            .zip(samples.iter().skip(1))
+            .all(|(older, newer)| older.0 < newer.0);
+        if is_ordered {
+            Some(Self { retention, samples })
+        } else {
+            None
+        }
+    }
+    /// Returns the retention period in milliseconds (`0` if samples are kept forever)
+    pub const fn retention(&self) -> u64 {
+        self.retention
+    }
+    /// Returns the number of samples
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    /// Returns the newest sample
+    pub fn last(&self) -> Option<Sample> {
+        self.samples.back().copied()
+    }
+    /// Returns an iterator over the samples, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = Sample> + '_ {
+        self.samples.iter().copied()
+    }
+    /// Append a sample, returning false (without appending it) if it isn't newer than the
+    /// newest sample. Samples that fall out of the retention period are dropped
+    pub fn append(&mut self, timestamp: u64, value: f64) -> bool {
+        if matches!(self.last(), Some((newest, _)) if newest >= timestamp) {
+            return false;
+        }
+        self.samples.push_back((timestamp, value));
+        if self.retention != 0 {
+            let oldest = timestamp.saturating_sub(self.retention);
+            while matches!(self.samples.front(), Some((ts, _)) if *ts < oldest) {
+                self.samples.pop_front();
+            }
+        }
+        true
+    }
+    /// Returns an iterator over the samples between `from` and `to` (both inclusive),
+    /// oldest first
+    pub fn range(&self, from: u64, to: u64) -> impl Iterator<Item = Sample> + '_ {
+        let start = self.samples.partition_point(|(ts, _)| *ts < from);
+        self.samples
+            .range(start..)
+            .copied()
+            .take_while(move |(ts, _)| *ts <= to)
+    }
+}
+
+/// A function that aggregates the samples of a bucket into a single value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregation {
+    Min,
+    Max,
+    Avg,
+    Sum,
+}
+
+impl Aggregation {
+    /// Returns the aggregation with the given (lowercase) name
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        let aggregation = match name {
+            b"min" => Self::Min,
+            b"max" => Self::Max,
+            b"avg" => Self::Avg,
+            b"sum" => Self::Sum,
+            _ => return None,
+        };
+        Some(aggregation)
+    }
+}
+
+/// The running state of the aggregation of a bucket
+struct Accumulator {
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: usize,
+}
+
+impl Accumulator {
+    fn new(value: f64) -> Self {
+        Self {
+            min: value,
+            max: value,
+            sum: value,
+            count: 1,
+        }
+    }
+    fn push(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+    fn finish(&self, aggregation: Aggregation) -> f64 {
+        match aggregation {
+            Aggregation::Min => self.min,
+            Aggregation::Max => self.max,
+            Aggregation::Avg => self.sum / self.count as f64,
+            Aggregation::Sum => self.sum,
+        }
+    }
+}
+
+/// Aggregate the given samples (oldest first) into buckets of `bucket` milliseconds (which
+/// can't be zero), where every bucket starts at a multiple of `bucket`. Returns the start and
+/// the aggregated value of every bucket that has samples
+pub fn aggregate(
+    samples: impl Iterator<Item = Sample>,
+    aggregation: Aggregation,
+    bucket: u64,
+) -> Vec<Sample> {
+    let mut ret = Vec::new();
+    let mut current: Option<(u64, Accumulator)> = None;
+    for (timestamp, value) in samples {
+        let start = timestamp - timestamp % bucket;
+        if let Some((current_start, acc)) = current.as_mut() {
+            if *current_start == start {
+                acc.push(value);
+                continue;
+            }
+        }
+        if let Some((start, acc)) = current.replace((start, Accumulator::new(value))) {
+            ret.push((start, acc.finish(aggregation)));
+        }
+    }
+    if let Some((start, acc)) = current {
+        ret.push((start, acc.finish(aggregation)));
+    }
+    ret
+}
+
+/// The time series engine that acts as the in-memory backing store for time series tables
+#[derive(Debug)]
+pub struct TimeSeriesEngine {
+    /// the atomic table
+    table: EngineTable<Series>,
+    /// the encoding switch for the keys
+    encoded_k: bool,
+}
+
+impl Default for TimeSeriesEngine {
+    fn default() -> Self {
+        TimeSeriesEngine::init(false)
+    }
+}
+
+impl TimeSeriesEngine {
+    /// Create a new in-memory time series engine with the specified key encoding
+    pub fn init(encoded_k: bool) -> Self {
+        Self::init_with_data(encoded_k, Coremap::new())
+    }
+    pub fn init_with_data(encoded_k: bool, table: Coremap<Data, Series>) -> Self {
+        Self {
+            table: EngineTable::new(table),
+            encoded_k,
+        }
+    }
+    /// Returns the encoding of the keys
+    pub const fn get_encoding(&self) -> bool {
+        self.encoded_k
+    }
+    /// Returns the number of keys
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+    pub fn __get_inner_ref(&self) -> &EngineTable<Series> {
+        &self.table
+    }
+    /// Check the encoding of the given key
+    fn _encode_key(&self, key: &[u8]) -> Result<(), ()> {
+        if !self.encoded_k || encoding::is_utf8(key) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+    /// Returns the size of a write of the given number of samples to a key
+    pub fn write_size(&self, key: &[u8], samples: usize) -> WriteSize {
+        let keys = !self.table.contains_key(key) as usize;
+        WriteSize::new(keys, samples * SAMPLE_FOOTPRINT, 0)
+    }
+    /// Create an empty series for a key with the given retention period (in milliseconds,
+    /// where `0` keeps samples forever). Returns false if the key already exists
+    pub fn create(&self, key: Data, retention: u64) -> Result<bool, ()> {
+        self._encode_key(&key)?;
+        let created = match self.table.fresh_entry(key) {
+            Some(ve) => {
+                ve.insert(Series::new(retention));
+                true
+            }
+            None => false,
+        };
+        Ok(self.table.touch_if(created))
+    }
+    /// Append a sample to the series of a key, creating a series that keeps samples forever
+    /// if the key doesn't exist. Returns false if the sample isn't newer than the newest
+    /// sample of the series
+    pub fn add_sample(&self, key: Data, timestamp: u64, value: f64) -> Result<bool, ()> {
+        self._encode_key(&key)?;
+        let added = match self.table.entry(key) {
+            Entry::Occupied(mut oe) => {
+                let series = oe.value_mut();
+                let older = series.footprint();
+                let added = series.append(timestamp, value);
+                // the retention period may have dropped older samples
+                self.table.account(series.footprint(), older);
+                added
+            }
+            Entry::Vacant(ve) => {
+                let mut series = Series::new(0);
+                series.append(timestamp, value);
+                self.table.account(series.footprint(), 0);
+                ve.insert(series);
+                true
+            }
+        };
+        Ok(self.table.touch_if(added))
+    }
+    /// Returns the newest sample of a key
+    pub fn last_sample(&self, key: &[u8]) -> Result<Option<Sample>, ()> {
+        self._encode_key(key)?;
+        Ok(self.table.get(key).and_then(|series| series.last()))
+    }
+    /// Returns the number of samples of a key, which is `0` if the key doesn't exist
+    pub fn count_samples(&self, key: &[u8]) -> Result<usize, ()> {
+        self._encode_key(key)?;
+        Ok(self.table.get(key).map(|series| series.len()).unwrap_or(0))
+    }
+    /// Returns the samples of a key between `from` and `to` (both inclusive), oldest first
+    pub fn range(&self, key: &[u8], from: u64, to: u64) -> Result<Vec<Sample>, ()> {
+        self._encode_key(key)?;
+        let ret = match self.table.get(key) {
+            Some(series) => series.range(from, to).collect(),
+            None => Vec::new(),
+        };
+        Ok(ret)
+    }
+    /// Aggregate the samples of a key between `from` and `to` (both inclusive) into buckets
+    /// of `bucket` milliseconds (see [`aggregate`])
+    pub fn aggregate(
+        &self,
+        key: &[u8],
+        from: u64,
+        to: u64,
+        aggregation: Aggregation,
+        bucket: u64,
+    ) -> Result<Vec<Sample>, ()> {
+        self._encode_key(key)?;
+        let ret = match self.table.get(key) {
+            Some(series) => aggregate(series.range(from, to), aggregation, bucket),
+            None => Vec::new(),
+        };
+        Ok(ret)
+    }
+}
+
+#[test]
+fn test_series_append_and_range() {
+    let mut series = Series::new(0);
+    assert!(series.append(1000, 1.0));
+    assert!(series.append(2000, 2.0));
+    // samples are append-only
+    assert!(!series.append(2000, 3.0));
+    assert!(!series.append(1500, 3.0));
+    assert!(series.append(3000, 3.0));
+    assert_eq!(
+        series.range(1500, 3000).collect::<Vec<_>>(),
+        vec![(2000, 2.0), (3000, 3.0)]
+    );
+    assert_eq!(series.range(0, 999).count(), 0);
+    assert_eq!(series.last(), Some((3000, 3.0)));
+}
+
+#[test]
+fn test_series_retention() {
+    let mut series = Series::new(1000);
+    for ts in (0..=5000).step_by(500) {
+        series.append(ts, ts as f64);
+    }
+    // only the samples in the last second are kept
+    assert_eq!(
+        series.iter().map(|(ts, _)| ts).collect::<Vec<_>>(),
+        vec![4000, 4500, 5000]
+    );
+}
+
+#[test]
+fn test_aggregate() {
+    let samples = [(0, 1.0), (400, 3.0), (1000, 5.0), (2500, 2.0), (2999, 4.0)];
+    let run = |aggregation| aggregate(samples.iter().copied(), aggregation, 1000);
+    assert_eq!(
+        run(Aggregation::Min),
+        vec![(0, 1.0), (1000, 5.0), (2000, 2.0)]
+    );
+    assert_eq!(
+        run(Aggregation::Max),
+        vec![(0, 3.0), (1000, 5.0), (2000, 4.0)]
+    );
+    assert_eq!(
+        run(Aggregation::Avg),
+        vec![(0, 2.0), (1000, 5.0), (2000, 3.0)]
+    );
+    assert_eq!(
+        run(Aggregation::Sum),
+        vec![(0, 4.0), (1000, 5.0), (2000, 6.0)]
+    );
+}
+
+#[test]
+fn test_timeseries_engine() {
+    let ts = TimeSeriesEngine::default();
+    assert!(ts.create(Data::from("cpu"), 60_000).unwrap());
+    assert!(!ts.create(Data::from("cpu"), 0).unwrap());
+    assert!(ts.add_sample(Data::from("cpu"), 1000, 0.5).unwrap());
+    assert!(!ts.add_sample(Data::from("cpu"), 1000, 0.7).unwrap());
+    // a sample creates the key
+    assert!(ts.add_sample(Data::from("mem"), 1000, 0.25).unwrap());
+    assert_eq!(ts.len(), 2);
+    assert_eq!(ts.__get_inner_ref().value_bytes(), 32);
+    assert_eq!(ts.write_size(b"cpu", 1), WriteSize::new(0, 16, 0));
+    assert_eq!(ts.write_size(b"disk", 0), WriteSize::new(1, 0, 0));
+    // the sample at 1000 falls out of the retention period of a minute
+    assert!(ts.add_sample(Data::from("cpu"), 70_000, 0.5).unwrap());
+    assert_eq!(ts.__get_inner_ref().value_bytes(), 32);
+    assert_eq!(ts.count_samples(b"cpu").unwrap(), 1);
+    assert_eq!(ts.last_sample(b"mem").unwrap(), Some((1000, 0.25)));
+    assert_eq!(ts.last_sample(b"disk").unwrap(), None);
+    let bad_unicode = b"Hello \xF0\x90\x80World".to_vec();
+    let ts = TimeSeriesEngine::init(true);
+    assert!(ts.add_sample(Data::from(bad_unicode), 1000, 0.5).is_err());
+}
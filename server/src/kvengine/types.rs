@@ -0,0 +1,209 @@
+/*
+ * Created on Mon Sep 20 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Value types
+//!
+//! The values of a key/value table can either be strings (`binstr` or `str`) or one of the
+//! typed scalars (`uint`, `sint`, `float` or `bool`). Typed values are stored in their textual
+//! form, which is validated when they're written: integers are only accepted in their
+//! canonical form (no signs other than a `-` for negative numbers and no leading zeroes) so
+//! that equal numbers always have equal representations. Skyhash only has a type for
+//! unsigned integers, so `uint`s are returned as integers while the other typed scalars are
+//! returned as strings
+
+use crate::resp::{TSYMBOL_BINARY, TSYMBOL_UINT, TSYMBOL_UNICODE};
+
+/// The type of the values in a key/value table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// a binary string (anything goes)
+    Binstr,
+    /// a unicode string
+    Str,
+    /// a 64-bit unsigned integer
+    UInt,
+    /// a 64-bit signed integer
+    SInt,
+    /// a 64-bit float (`NaN` and infinities are not accepted)
+    Float,
+    /// `true` or `false`
+    Bool,
+}
+
+impl ValueType {
+    /// Returns the type for the given type name, as used in model expressions
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        let vtype = match name {
+            b"binstr" => Self::Binstr,
+            b"str" => Self::Str,
+            b"uint" => Self::UInt,
+            b"sint" => Self::SInt,
+            b"float" => Self::Float,
+            b"bool" => Self::Bool,
+            _ => return None,
+        };
+        Some(vtype)
+    }
+    /// Returns the name of the type, as used in model expressions
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Binstr => "binstr",
+            Self::Str => "str",
+            Self::UInt => "uint",
+            Self::SInt => "sint",
+            Self::Float => "float",
+            Self::Bool => "bool",
+        }
+    }
+    /// Returns the tsymbol used for values of this type in responses
+    pub const fn tsymbol(&self) -> u8 {
+        match self {
+            Self::Binstr => TSYMBOL_BINARY,
+            Self::UInt => TSYMBOL_UINT,
+            Self::Str | Self::SInt | Self::Float | Self::Bool => TSYMBOL_UNICODE,
+        }
+    }
+    /// Returns true if values of this type have to be validated
+    pub const fn needs_validation(&self) -> bool {
+        !matches!(self, Self::Binstr)
+    }
+    /// Returns true if values of this type are strings (`str` or `binstr`)
+    pub const fn is_string(&self) -> bool {
+        matches!(self, Self::Binstr | Self::Str)
+    }
+    /// Returns the validator for values of this type
+    pub fn validator(&self) -> fn(&[u8]) -> bool {
+        match self {
+            Self::Binstr => is_binstr,
+            Self::Str => is_str,
+            Self::UInt => is_uint,
+            Self::SInt => is_sint,
+            Self::Float => is_float,
+            Self::Bool => is_bool,
+        }
+    }
+}
+
+fn is_binstr(_: &[u8]) -> bool {
+    true
+}
+
+fn is_str(bytes: &[u8]) -> bool {
+    super::encoding::is_utf8(bytes)
+}
+
+/// Check if the bytes are a canonical unsigned integer that fits in 64 bits
+pub fn is_uint(bytes: &[u8]) -> bool {
+    let is_canonical = match bytes {
+        [] => false,
+        [b'0'] => true,
+        [b'0', ..] => false,
+        _ => bytes.iter().all(u8::is_ascii_digit),
+    };
+    // the digits are ASCII, so this is valid UTF-8
+    is_canonical
+        && unsafe { core::str::from_utf8_unchecked(bytes) }
+            .parse::<u64>()
+            .is_ok()
+}
+
+/// Check if the bytes are a canonical signed integer that fits in 64 bits
+pub fn is_sint(bytes: &[u8]) -> bool {
+    let is_canonical = match bytes {
+        // there's no negative zero
+        [b'-', b'0', ..] => false,
+        [b'-', magnitude @ ..] => is_uint(magnitude),
+        _ => is_uint(bytes),
+    };
+    // the sign and the digits are ASCII, so this is valid UTF-8
+    is_canonical
+        && unsafe { core::str::from_utf8_unchecked(bytes) }
+            .parse::<i64>()
+            .is_ok()
+}
+
+/// Check if the bytes are a finite float
+pub fn is_float(bytes: &[u8]) -> bool {
+    // only allow digits, signs, the decimal point and exponents (no `inf` or `NaN`)
+    let is_numeric = bytes
+        .iter()
+        .all(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E'));
+    // the bytes are ASCII, so this is valid UTF-8
+    is_numeric
+        && matches!(
+            unsafe { core::str::from_utf8_unchecked(bytes) }.parse::<f64>(),
+            Ok(float) if float.is_finite()
+        )
+}
+
+/// Check if the bytes are `true` or `false`
+pub fn is_bool(bytes: &[u8]) -> bool {
+    bytes == b"true" || bytes == b"false"
+}
+
+#[test]
+fn test_uint() {
+    for valid in ["0", "1", "100", "18446744073709551615"] {
+        assert!(is_uint(valid.as_bytes()), "{}", valid);
+    }
+    for invalid in ["", "-1", "+1", "01", "1.0", "18446744073709551616", "1a"] {
+        assert!(!is_uint(invalid.as_bytes()), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_sint() {
+    for valid in [
+        "0",
+        "-1",
+        "100",
+        "-9223372036854775808",
+        "9223372036854775807",
+    ] {
+        assert!(is_sint(valid.as_bytes()), "{}", valid);
+    }
+    for invalid in ["", "-", "-0", "+1", "-01", "9223372036854775808", "1.5"] {
+        assert!(!is_sint(invalid.as_bytes()), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_float() {
+    for valid in ["0", "-1.5", "3.14", "1e10", "2.5E-3"] {
+        assert!(is_float(valid.as_bytes()), "{}", valid);
+    }
+    for invalid in ["", "NaN", "inf", "-inf", "1e400", "1.0.0", "one"] {
+        assert!(!is_float(invalid.as_bytes()), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_bool() {
+    assert!(is_bool(b"true"));
+    assert!(is_bool(b"false"));
+    assert!(!is_bool(b"TRUE"));
+    assert!(!is_bool(b"1"));
+}
@@ -0,0 +1,233 @@
+/*
+ * Created on Tue Sep 28 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Versioned values
+//!
+//! Every value of a key/value table carries a version, which is handed out by the table
+//! from a counter that only ever moves forward. A write assigns a fresh version to the key
+//! while holding the lock on the shard of the key, so two different values of a key never
+//! share a version (not even if the key was removed and created again in between). This
+//! lets clients read the version along with the value and then make their writes conditional
+//! on the key still being at that version (optimistic concurrency control). The counter is
+//! persisted along with the table, so versions survive restarts
+
+use super::Footprint;
+use super::KVEngine;
+use crate::corestore::map::bref::Entry;
+use crate::corestore::Data;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use core::ops::Deref;
+use core::sync::atomic::Ordering;
+
+/// A value of a key/value table along with its version. Values that were restored from
+/// a table that was persisted before versions existed are at version `0`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Versioned {
+    data: Data,
+    version: u64,
+}
+
+impl Versioned {
+    pub const fn new(data: Data, version: u64) -> Self {
+        Self { data, version }
+    }
+    pub const fn data(&self) -> &Data {
+        &self.data
+    }
+    pub const fn version(&self) -> u64 {
+        self.version
+    }
+    pub fn into_data(self) -> Data {
+        self.data
+    }
+}
+
+impl Deref for Versioned {
+    type Target = Data;
+    fn deref(&self) -> &Data {
+        &self.data
+    }
+}
+
+impl Footprint for Versioned {
+    fn footprint(&self) -> usize {
+        self.data.len()
+    }
+}
+
+/// The outcome of a write that is conditional on the version of a key
+#[derive(Debug, PartialEq)]
+pub enum VersionCheck {
+    /// the key was at the expected version and the write went through
+    Okay,
+    /// the key is at another version, so nothing was written
+    Mismatch,
+    /// the key doesn't exist
+    Nil,
+}
+
+impl KVEngine {
+    /// Returns the last version that was handed out by this table
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+    /// Make sure that the versions handed out from now on are greater than `version`. This
+    /// is used to restore the counter of a table that was read from disk
+    pub fn restore_version(&self, version: u64) {
+        self.version.fetch_max(version, Ordering::AcqRel);
+    }
+    /// Returns the given value with a fresh version. Anyone who writes to the table directly
+    /// instead of going through the methods of the engine has to build the values that they
+    /// write with this, while holding the lock on the shard of the key
+    pub fn versioned(&self, value: Data) -> Versioned {
+        Versioned::new(value, self.version.fetch_add(1, Ordering::AcqRel) + 1)
+    }
+    /// Get the value of a key along with its version, if the key exists
+    pub fn get_versioned(&self, key: &[u8]) -> Result<Option<Versioned>, ()> {
+        self._encode_key(key)?;
+        Ok(self.table.get(key).map(|v| v.clone()))
+    }
+    /// Get the version of a key, if the key exists. Unlike the value, the version changes on
+    /// every write, even if the same value is written again
+    pub fn get_version<Q>(&self, key: &Q) -> Option<u64>
+    where
+        Data: Borrow<Q>,
+        Q: AsRef<[u8]> + Hash + Eq + ?Sized,
+    {
+        self.table.get(key).map(|v| v.version())
+    }
+    /// Update the value of a key if it is at the `expected` version
+    pub fn update_if_version(
+        &self,
+        key: Data,
+        value: Data,
+        expected: u64,
+    ) -> Result<VersionCheck, ()> {
+        self._encode_key(&key)?;
+        self._encode_value(&value)?;
+        let check = match self.table.entry(key) {
+            Entry::Occupied(mut oe) if oe.value().version() == expected => {
+                self.account(value.len(), oe.value().len());
+                oe.insert(self.versioned(value));
+                VersionCheck::Okay
+            }
+            Entry::Occupied(_) => VersionCheck::Mismatch,
+            Entry::Vacant(_) => VersionCheck::Nil,
+        };
+        self.table.touch_if(check == VersionCheck::Okay);
+        Ok(check)
+    }
+    /// Remove a key if it is at the `expected` version
+    pub fn remove_if_version(&self, key: Data, expected: u64) -> Result<VersionCheck, ()> {
+        self._encode_key(&key)?;
+        let check = match self.table.entry(key) {
+            Entry::Occupied(oe) if oe.value().version() == expected => {
+                self.account(0, oe.remove().len());
+                VersionCheck::Okay
+            }
+            Entry::Occupied(_) => VersionCheck::Mismatch,
+            Entry::Vacant(_) => VersionCheck::Nil,
+        };
+        self.table.touch_if(check == VersionCheck::Okay);
+        Ok(check)
+    }
+}
+
+#[test]
+fn test_versions_move_forward() {
+    let kve = KVEngine::init(false, false);
+    assert_eq!(kve.version(), 0);
+    assert!(kve.set_unchecked(Data::from("x"), Data::from("100")));
+    let first = kve.get_versioned(b"x".as_ref()).unwrap().unwrap().version();
+    assert!(kve.update_unchecked(Data::from("x"), Data::from("200")));
+    let second = kve.get_versioned(b"x".as_ref()).unwrap().unwrap().version();
+    assert!(second > first);
+    // a removed and recreated key doesn't get its older version back
+    assert!(kve.remove_unchecked(b"x".as_ref()));
+    assert!(kve.set_unchecked(Data::from("x"), Data::from("100")));
+    let third = kve.get_versioned(b"x".as_ref()).unwrap().unwrap().version();
+    assert!(third > second);
+    assert_eq!(kve.version(), third);
+}
+
+#[test]
+fn test_update_if_version() {
+    let kve = KVEngine::init(false, false);
+    assert_eq!(
+        kve.update_if_version(Data::from("x"), Data::from("100"), 0),
+        Ok(VersionCheck::Nil)
+    );
+    kve.set_unchecked(Data::from("x"), Data::from("100"));
+    let version = kve.get_versioned(b"x".as_ref()).unwrap().unwrap().version();
+    assert_eq!(
+        kve.update_if_version(Data::from("x"), Data::from("200"), version + 1),
+        Ok(VersionCheck::Mismatch)
+    );
+    assert_eq!(
+        kve.update_if_version(Data::from("x"), Data::from("200"), version),
+        Ok(VersionCheck::Okay)
+    );
+    // the version moved on, so the same check fails the second time
+    assert_eq!(
+        kve.update_if_version(Data::from("x"), Data::from("300"), version),
+        Ok(VersionCheck::Mismatch)
+    );
+    assert_eq!(
+        kve.get_cloned_unchecked(b"x".as_ref()),
+        Some(Data::from("200"))
+    );
+}
+
+#[test]
+fn test_remove_if_version() {
+    let kve = KVEngine::init(false, false);
+    kve.set_unchecked(Data::from("x"), Data::from("100"));
+    let version = kve.get_versioned(b"x".as_ref()).unwrap().unwrap().version();
+    assert_eq!(
+        kve.remove_if_version(Data::from("x"), version + 1),
+        Ok(VersionCheck::Mismatch)
+    );
+    assert!(kve.exists_unchecked(b"x".as_ref()));
+    assert_eq!(
+        kve.remove_if_version(Data::from("x"), version),
+        Ok(VersionCheck::Okay)
+    );
+    assert_eq!(
+        kve.remove_if_version(Data::from("x"), version),
+        Ok(VersionCheck::Nil)
+    );
+}
+
+#[test]
+fn test_restore_version() {
+    let kve = KVEngine::init(false, false);
+    kve.restore_version(100);
+    // the counter never moves back
+    kve.restore_version(10);
+    assert_eq!(kve.version(), 100);
+    assert_eq!(kve.versioned(Data::from("x")).version(), 101);
+}
@@ -39,6 +39,7 @@ use env_logger::Builder;
 use libsky::util::terminal;
 use libsky::URL;
 use libsky::VERSION;
+use log::LevelFilter;
 use std::env;
 use std::process;
 use std::thread;
@@ -59,6 +60,7 @@ mod protocol;
 mod queryengine;
 pub mod registry;
 mod resp;
+mod scripting;
 mod services;
 mod storage;
 #[cfg(test)]
@@ -86,9 +88,7 @@ const TEXT: &str = "
 type IoResult<T> = std::io::Result<T>;
 
 fn main() {
-    Builder::new()
-        .parse_filters(&env::var("SKY_LOG").unwrap_or_else(|_| "info".to_owned()))
-        .init();
+    init_logger();
     // Start the server which asynchronously waits for a CTRL+C signal
     // which will safely shut down the server
     let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -96,22 +96,13 @@ fn main() {
         .enable_all()
         .build()
         .unwrap();
-    let (ports, bgsave_config, snapshot_config, restore_filepath, maxcon) =
-        check_args_and_get_cfg();
+    let (cfg, restore_filepath) = check_args_and_get_cfg();
     // check if any other process is using the data directory and lock it if not (else error)
     // important: create the pid_file just here and nowhere else because check_args can also
     // involve passing --help or wrong arguments which can falsely create a PID file
     let pid_file = run_pre_startup_tasks();
-    let db: Result<corestore::Corestore, String> = runtime.block_on(async move {
-        arbiter::run(
-            ports,
-            bgsave_config,
-            snapshot_config,
-            restore_filepath,
-            maxcon,
-        )
-        .await
-    });
+    let db: Result<corestore::Corestore, String> =
+        runtime.block_on(async move { arbiter::run(cfg, restore_filepath).await });
     // Make sure all background workers terminate
     drop(runtime);
     let db = match db {
@@ -149,6 +140,15 @@ fn main() {
     terminal::write_info("Goodbye :)\n").unwrap();
 }
 
+/// Initialize the logger. `SKY_LOG` only sets the initial level: the logger itself accepts
+/// every level so that the level can be changed at runtime with `CONFIG SET loglevel`
+fn init_logger() {
+    let filters = env::var("SKY_LOG").unwrap_or_else(|_| "info".to_owned());
+    let level = Builder::new().parse_filters(&filters).build().filter();
+    Builder::new().filter_level(LevelFilter::Trace).init();
+    log::set_max_level(level);
+}
+
 pub fn pre_shutdown_cleanup(mut pid_file: FileLock, mr: Option<&Memstore>) {
     if let Err(e) = pid_file.unlock() {
         log::error!("Shutdown failure: Failed to unlock pid file: {}", e);
@@ -163,11 +163,11 @@ pub fn pre_shutdown_cleanup(mut pid_file: FileLock, mr: Option<&Memstore>) {
     }
 }
 
-use self::config::{BGSave, PortConfig, SnapshotConfig};
+use self::config::ParsedConfig;
 
 /// This function checks the command line arguments and either returns a config object
 /// or prints an error to `stderr` and terminates the server
-fn check_args_and_get_cfg() -> (PortConfig, BGSave, SnapshotConfig, Option<String>, usize) {
+fn check_args_and_get_cfg() -> (ParsedConfig, Option<String>) {
     let cfg = config::get_config_file_or_return_cfg();
     let binding_and_cfg = match cfg {
         Ok(config::ConfigType::Custom(cfg, file)) => {
@@ -177,12 +177,12 @@ fn check_args_and_get_cfg() -> (PortConfig, BGSave, SnapshotConfig, Option<Strin
                 println!("Skytable v{} | {}", VERSION, URL);
             }
             log::info!("Using settings from supplied configuration");
-            (cfg.ports, cfg.bgsave, cfg.snapshot, file, cfg.maxcon)
+            (cfg, file)
         }
         Ok(config::ConfigType::Def(cfg, file)) => {
             println!("Skytable v{} | {}\n{}", VERSION, URL, TEXT);
             log::warn!("No configuration file supplied. Using default settings");
-            (cfg.ports, cfg.bgsave, cfg.snapshot, file, cfg.maxcon)
+            (cfg, file)
         }
         Err(e) => {
             log::error!("{}", e);
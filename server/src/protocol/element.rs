@@ -78,7 +78,7 @@ impl UnsafeElement {
     pub unsafe fn to_owned_array(inner: &[Self]) -> Vec<OwnedElement> {
         inner
             .iter()
-            .map(|v| match &*v {
+            .map(|v| match v {
                 UnsafeElement::String(st) => {
                     OwnedElement::String(Bytes::copy_from_slice(st.as_slice()))
                 }
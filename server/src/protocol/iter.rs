@@ -38,6 +38,7 @@ use core::slice::Iter;
 /// left to the caller who has to guarantee:
 /// - Source pointers for the unsafe slice are valid
 /// - Source pointers exist as long as this iterator is used
+#[derive(Clone)]
 pub struct AnyArrayIter<'a> {
     iter: Iter<'a, UnsafeSlice>,
 }
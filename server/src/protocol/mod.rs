@@ -90,6 +90,8 @@ pub struct Parser<'a> {
     data_end_ptr: *const u8,
     /// the buffer
     buffer: &'a [u8],
+    /// the maximum number of elements in an array (or the maximum number of queries in a pipeline)
+    max_elements: usize,
 }
 
 #[derive(PartialEq)]
@@ -181,6 +183,8 @@ pub enum ParseError {
     ///
     /// The **parser will never return this**, but instead it is provided for convenience with [`dbnet`]
     Empty,
+    /// An array (or a pipeline) has more elements than permitted
+    TooManyElements,
 }
 
 #[derive(Debug, PartialEq)]
@@ -310,9 +314,24 @@ impl<'a> Parser<'a> {
                 cursor,
                 data_end_ptr,
                 buffer,
+                max_elements: usize::MAX,
             }
         }
     }
+    /// Reject any array (or pipeline) that has more than `max_elements` elements
+    pub fn with_max_elements(mut self, max_elements: usize) -> Self {
+        self.max_elements = max_elements;
+        self
+    }
+    /// Returns the element count if it's within the permitted limit. This should be checked
+    /// before we allocate anything for the elements
+    fn check_element_count(&self, count: usize) -> ParseResult<usize> {
+        if count > self.max_elements {
+            Err(ParseError::TooManyElements)
+        } else {
+            Ok(count)
+        }
+    }
     /// Returns what we have consumed
     /// ```text
     /// [*****************************]
@@ -483,7 +502,7 @@ impl<'a> Parser<'a> {
             unsafe {
                 let our_chunk = self.read_line()?;
                 if our_chunk.unsafe_eq(b'*', 0) {
-                    Ok(Self::parse_into_usize(
+                    self.check_element_count(Self::parse_into_usize(
                         our_chunk.into_slice_with_start_and_end(1, 1),
                     )?)
                 } else {
@@ -568,7 +587,7 @@ impl<'a> Parser<'a> {
     fn parse_next_any_array(&mut self) -> ParseResult<Box<[UnsafeSlice]>> {
         unsafe {
             let size_line = self.read_line()?;
-            let size = Self::parse_into_usize(size_line.into_slice())?;
+            let size = self.check_element_count(Self::parse_into_usize(size_line.into_slice())?)?;
             let mut array = Vec::with_capacity(size);
             for _ in 0..size {
                 array.push(self.parse_next_blob()?);
@@ -580,7 +599,8 @@ impl<'a> Parser<'a> {
     fn parse_next_flat_array(&mut self) -> ParseResult<Box<[UnsafeFlatElement]>> {
         unsafe {
             let flat_array_sizeline = self.read_line()?;
-            let array_size = Self::parse_into_usize(flat_array_sizeline.into_slice())?;
+            let array_size = self
+                .check_element_count(Self::parse_into_usize(flat_array_sizeline.into_slice())?)?;
             let mut array = Vec::with_capacity(array_size);
             for _ in 0..array_size {
                 if self.exhausted() {
@@ -603,7 +623,8 @@ impl<'a> Parser<'a> {
     fn parse_next_array(&mut self) -> ParseResult<Box<[UnsafeElement]>> {
         unsafe {
             let size_of_array_chunk = self.read_line()?;
-            let size_of_array = Self::parse_into_usize(size_of_array_chunk.into_slice())?;
+            let size_of_array = self
+                .check_element_count(Self::parse_into_usize(size_of_array_chunk.into_slice())?)?;
             let mut array = Vec::with_capacity(size_of_array);
             for _ in 0..size_of_array {
                 array.push(self.parse_next_element()?);
@@ -99,6 +99,56 @@ pub mod groups {
     pub const UNKNOWN_PROPERTY: &[u8] = "!16\nunknown-property\n".as_bytes();
     /// The keyspace is not empty and hence cannot be removed
     pub const KEYSPACE_NOT_EMPTY: &[u8] = "!18\nkeyspace-not-empty\n".as_bytes();
+    /// An unknown `SYS` query or section
+    pub const UNKNOWN_SYS_QUERY: &[u8] = "!17\nunknown-sys-query\n".as_bytes();
+    /// An unknown `SLOWLOG` query
+    pub const UNKNOWN_SLOWLOG_QUERY: &[u8] = "!21\nunknown-slowlog-query\n".as_bytes();
+    /// An unknown `CLIENT` query
+    pub const UNKNOWN_CLIENT_QUERY: &[u8] = "!20\nunknown-client-query\n".as_bytes();
+    /// A client name that is empty, has whitespace or is not valid unicode
+    pub const BAD_CLIENT_NAME: &[u8] = "!15\nbad-client-name\n".as_bytes();
+    /// The configuration couldn't be reloaded
+    pub const RELOAD_FAILED: &[u8] = "!13\nreload-failed\n".as_bytes();
+    /// An unknown `CONFIG` query
+    pub const UNKNOWN_CONFIG_QUERY: &[u8] = "!20\nunknown-config-query\n".as_bytes();
+    /// An unknown `CONFIG` key
+    pub const UNKNOWN_CONFIG_KEY: &[u8] = "!18\nunknown-config-key\n".as_bytes();
+    /// An invalid value for a `CONFIG` key
+    pub const BAD_CONFIG_VALUE: &[u8] = "!16\nbad-config-value\n".as_bytes();
+    /// The configuration file couldn't be rewritten
+    pub const REWRITE_FAILED: &[u8] = "!14\nrewrite-failed\n".as_bytes();
+    /// A query was queued in a transaction
+    pub const QUEUED: &[u8] = "+6\nQUEUED\n".as_bytes();
+    /// A watched key was changed, so the transaction wasn't run
+    pub const TXN_CONFLICT: &[u8] = "!12\ntxn-conflict\n".as_bytes();
+    /// A query failed to queue, so the transaction wasn't run
+    pub const TXN_ABORTED: &[u8] = "!11\ntxn-aborted\n".as_bytes();
+    /// `EXEC` or `DISCARD` without a `MULTI`
+    pub const NO_TRANSACTION: &[u8] = "!14\nno-transaction\n".as_bytes();
+    /// `MULTI` inside a transaction
+    pub const NESTED_TRANSACTION: &[u8] = "!18\nnested-transaction\n".as_bytes();
+    /// `WATCH` inside a transaction
+    pub const WATCH_IN_TRANSACTION: &[u8] = "!20\nwatch-in-transaction\n".as_bytes();
+    /// An action that can't be queued in a transaction
+    pub const UNSUPPORTED_IN_TRANSACTION: &[u8] = "!26\nunsupported-in-transaction\n".as_bytes();
+    /// The script ran for too many steps
+    pub const SCRIPT_STEP_LIMIT: &[u8] = "!17\nscript-step-limit\n".as_bytes();
+    /// The script ran for too long
+    pub const SCRIPT_TIMEOUT: &[u8] = "!14\nscript-timeout\n".as_bytes();
+    /// The script created too many strings and arrays
+    pub const SCRIPT_MEMORY_LIMIT: &[u8] = "!19\nscript-memory-limit\n".as_bytes();
+    /// No script with the given digest is cached
+    pub const SCRIPT_NOT_FOUND: &[u8] = "!16\nscript-not-found\n".as_bytes();
+    /// An unknown `SCRIPT` query
+    pub const UNKNOWN_SCRIPT_QUERY: &[u8] = "!20\nunknown-script-query\n".as_bytes();
+    /// An action that can't be run against another table with `ON`
+    pub const UNSUPPORTED_IN_SCOPE: &[u8] = "!20\nunsupported-in-scope\n".as_bytes();
+    /// A time series sample that isn't newer than the newest sample of the series
+    pub const OUT_OF_ORDER_SAMPLE: &[u8] = "!19\nout-of-order-sample\n".as_bytes();
+    /// A write that was conditional on the version of a key found it at another version
+    pub const VERSION_MISMATCH: &[u8] = "!16\nversion-mismatch\n".as_bytes();
+    /// A write or a DDL query that would go over one of the quotas of a keyspace
+    pub const QUOTA_EXCEEDED: &[u8] = "!14\nquota-exceeded\n".as_bytes();
 }
 
 pub mod full_responses {
@@ -144,4 +194,10 @@ pub mod full_responses {
     /// Pipelines are currently not supported
     // TODO(@ohsayan): Remove this once we implement pipelines
     pub const R_PIPELINE_UNSUPPORTED: &[u8] = "*1\n!26\npipeline-not-supported-yet".as_bytes();
+    /// The query is larger than the maximum query size
+    pub const R_QUERY_TOO_LARGE: &[u8] = "*1\n!15\nquery-too-large\n".as_bytes();
+    /// An array in the query has more elements than permitted
+    pub const R_TOO_MANY_ELEMENTS: &[u8] = "*1\n!17\ntoo-many-elements\n".as_bytes();
+    /// The connection was idle for too long
+    pub const R_IDLE_TIMEOUT: &[u8] = "*1\n!12\nidle-timeout\n".as_bytes();
 }
@@ -198,7 +198,7 @@ fn test_metaframe_parse() {
 
 #[test]
 fn test_cursor_next_char() {
-    let bytes = &[b'\n'];
+    let bytes = b"\n";
     assert!(Parser::new(&bytes[..])
         .will_cursor_give_char(b'\n', false)
         .unwrap());
@@ -565,3 +565,25 @@ fn test_parse_any_array() {
         ]))
     )
 }
+
+#[test]
+fn test_parse_too_many_elements() {
+    let anyarray = "*1\n~3\n3\nthe\n3\ncat\n6\nmeowed\n".as_bytes();
+    assert!(Parser::new(anyarray).with_max_elements(3).parse().is_ok());
+    let res = Parser::new(anyarray)
+        .with_max_elements(2)
+        .parse()
+        .unwrap_err();
+    assert_eq!(res, ParseError::TooManyElements);
+    // this should be rejected before we even have the elements
+    let bytes = "*1\n~18446744073709551615\n".as_bytes();
+    let res = Parser::new(bytes)
+        .with_max_elements(1024)
+        .parse()
+        .unwrap_err();
+    assert_eq!(res, ParseError::TooManyElements);
+    // pipelines too
+    let bytes = "*3\n".as_bytes();
+    let res = Parser::new(bytes).with_max_elements(2).parse().unwrap_err();
+    assert_eq!(res, ParseError::TooManyElements);
+}
@@ -28,6 +28,7 @@ use super::parser;
 use super::parser::VALID_CONTAINER_NAME;
 use crate::corestore::memstore::DdlError;
 use crate::corestore::memstore::ObjectID;
+use crate::corestore::quota::Quota;
 use crate::dbnet::connection::prelude::*;
 use crate::kvengine::encoding;
 use crate::registry;
@@ -36,6 +37,9 @@ use core::str;
 pub const TABLE: &[u8] = "TABLE".as_bytes();
 pub const KEYSPACE: &[u8] = "KEYSPACE".as_bytes();
 const VOLATILE: &[u8] = "volatile".as_bytes();
+const PERSISTENT: &[u8] = "persistent".as_bytes();
+const LIKE: &[u8] = "LIKE".as_bytes();
+const SYSTEM: &[u8] = "system".as_bytes();
 const FORCE_REMOVE: &[u8] = "force".as_bytes();
 
 action!(
@@ -79,9 +83,22 @@ action!(
 );
 
 action!(
-    /// We should have `<tableid> <model>(args)`
+    /// We should have `<tableid> <model>(args)` or `<tableid> like <other tableid>`
     fn create_table(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
         err_if_len_is!(con, act.len() > 3 || act.len() < 2);
+        let is_like = act.len() == 3
+            && matches!(act.clone().nth(1), Some(arg) if arg.eq_ignore_ascii_case(LIKE));
+        if is_like {
+            let (new, _, like) = unsafe {
+                // SAFETY: Already checked len
+                (
+                    act.next_unchecked(),
+                    act.next_unchecked(),
+                    act.next_unchecked(),
+                )
+            };
+            return conwrite!(con, create_table_like(handle, new, like, false));
+        }
         let (table_entity, model_code) = match parser::parse_table_args(&mut act) {
             Ok(v) => v,
             Err(e) => return con.write_response(e).await,
@@ -110,6 +127,10 @@ action!(
                 Err(DdlError::DefaultNotFound) => {
                     con.write_response(responses::groups::DEFAULT_UNSET).await?
                 }
+                Err(DdlError::QuotaExceeded) => {
+                    con.write_response(responses::groups::QUOTA_EXCEEDED)
+                        .await?
+                }
                 Err(_) => unsafe {
                     // we know that Corestore::create_table won't return anything else
                     impossible!()
@@ -128,7 +149,7 @@ action!(
         err_if_len_is!(act, con, not 1);
         match act.next() {
             Some(ksid) => {
-                if !encoding::is_utf8(&ksid) {
+                if !encoding::is_utf8(ksid) {
                     return con.write_response(responses::groups::ENCODING_ERROR).await;
                 }
                 let ksid_str = unsafe { str::from_utf8_unchecked(ksid) };
@@ -236,3 +257,207 @@ action! {
         Ok(())
     }
 }
+
+/// Validate the name of a container that is being renamed (or one that it is being renamed to)
+fn parse_container_name(name: &[u8]) -> Result<ObjectID, &'static [u8]> {
+    if !encoding::is_utf8(name) {
+        return Err(responses::groups::ENCODING_ERROR);
+    }
+    if name.len() > 64 {
+        return Err(responses::groups::CONTAINER_NAME_TOO_LONG);
+    }
+    if !VALID_CONTAINER_NAME.is_match(unsafe { str::from_utf8_unchecked(name) }) {
+        return Err(responses::groups::BAD_CONTAINER_NAME);
+    }
+    if name.eq(SYSTEM) {
+        return Err(responses::groups::PROTECTED_OBJECT);
+    }
+    Ok(unsafe {
+        // SAFETY: We just checked the length
+        ObjectID::from_slice(name)
+    })
+}
+
+action! {
+    /// Handle `alter table <entity> volatile`, `alter table <entity> persistent` and
+    /// `alter keyspace <ksid> <quota> <limit>` like queries
+    fn alter(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, lt 3);
+        let mut alter_what = unsafe { act.next().unsafe_unwrap() }.to_vec();
+        alter_what.make_ascii_uppercase();
+        let ret = match alter_what.as_ref() {
+            TABLE => {
+                err_if_len_is!(act, con, not 2);
+                alter_table(handle, &mut act)
+            }
+            KEYSPACE => {
+                err_if_len_is!(act, con, not 3);
+                alter_keyspace(handle, &mut act)
+            }
+            _ => responses::groups::UNKNOWN_DDL_QUERY,
+        };
+        conwrite!(con, ret)
+    }
+}
+
+/// Switch a table between being volatile and persistent (`<entity> <property>`)
+fn alter_table(handle: &Corestore, act: &mut ActionIter) -> &'static [u8] {
+    let entity = match parser::get_query_entity(unsafe { act.next_unchecked() }) {
+        Ok(e) => e,
+        Err(e) => return e,
+    };
+    let volatile = match unsafe { act.next_unchecked() } {
+        property if property.eq(VOLATILE) => true,
+        property if property.eq(PERSISTENT) => false,
+        _ => return responses::groups::UNKNOWN_PROPERTY,
+    };
+    if !registry::state_okay() {
+        return responses::groups::SERVER_ERR;
+    }
+    match handle.alter_table(entity, volatile) {
+        Ok(()) => responses::groups::OKAY,
+        Err(DdlError::DefaultNotFound) => responses::groups::DEFAULT_UNSET,
+        Err(DdlError::ObjectNotFound) => responses::groups::CONTAINER_NOT_FOUND,
+        Err(DdlError::DdlTransactionFailure) => responses::groups::DDL_TRANSACTIONAL_FAILURE,
+        Err(_) => unsafe {
+            // we know that Corestore::alter_table won't ever return anything else
+            impossible!()
+        },
+    }
+}
+
+/// Set the limit of a quota of a keyspace (`<ksid> <quota> <limit>`); a limit of `0`
+/// removes it
+fn alter_keyspace(handle: &Corestore, act: &mut ActionIter) -> &'static [u8] {
+    let (ksid, quota, limit) = unsafe {
+        (
+            act.next_unchecked(),
+            act.next_unchecked(),
+            act.next_unchecked(),
+        )
+    };
+    let ksid = match parse_container_name(ksid) {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    let quota = match Quota::from_name(quota) {
+        Some(quota) => quota,
+        None => return responses::groups::UNKNOWN_PROPERTY,
+    };
+    let limit = match String::from_utf8_lossy(limit).parse::<u64>() {
+        Ok(limit) => limit,
+        Err(_) => return responses::groups::WRONGTYPE_ERR,
+    };
+    if !registry::state_okay() {
+        return responses::groups::SERVER_ERR;
+    }
+    match handle.set_quota(&ksid, quota, limit) {
+        Ok(()) => responses::groups::OKAY,
+        Err(DdlError::ObjectNotFound) => responses::groups::CONTAINER_NOT_FOUND,
+        Err(DdlError::DdlTransactionFailure) => responses::groups::DDL_TRANSACTIONAL_FAILURE,
+        Err(_) => unsafe {
+            // we know that Corestore::set_quota won't ever return anything else
+            impossible!()
+        },
+    }
+}
+
+action! {
+    /// Handle `rename table <entity> <new tblid>` and `rename keyspace <ksid> <new ksid>`
+    /// like queries
+    fn rename(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let mut rename_what = unsafe { act.next().unsafe_unwrap() }.to_vec();
+        rename_what.make_ascii_uppercase();
+        let (from, to) = unsafe { (act.next().unsafe_unwrap(), act.next().unsafe_unwrap()) };
+        let new_id = match parse_container_name(to) {
+            Ok(id) => id,
+            Err(e) => return conwrite!(con, e),
+        };
+        let result = match rename_what.as_ref() {
+            TABLE => {
+                let entity = handle_entity!(con, from);
+                if !registry::state_okay() {
+                    return conwrite!(con, responses::groups::SERVER_ERR);
+                }
+                handle.rename_table(entity, new_id)
+            }
+            KEYSPACE => {
+                let ksid = match parse_container_name(from) {
+                    Ok(id) => id,
+                    Err(e) => return conwrite!(con, e),
+                };
+                if !registry::state_okay() {
+                    return conwrite!(con, responses::groups::SERVER_ERR);
+                }
+                handle.rename_keyspace(ksid, new_id)
+            }
+            _ => return conwrite!(con, responses::groups::UNKNOWN_DDL_QUERY),
+        };
+        let ret = match result {
+            Ok(()) => responses::groups::OKAY,
+            Err(DdlError::DefaultNotFound) => responses::groups::DEFAULT_UNSET,
+            Err(DdlError::ProtectedObject) => responses::groups::PROTECTED_OBJECT,
+            Err(DdlError::ObjectNotFound) => responses::groups::CONTAINER_NOT_FOUND,
+            Err(DdlError::AlreadyExists) => responses::groups::ALREADY_EXISTS,
+            Err(DdlError::StillInUse) => responses::groups::STILL_IN_USE,
+            Err(DdlError::DdlTransactionFailure) => responses::groups::DDL_TRANSACTIONAL_FAILURE,
+            Err(_) => unsafe {
+                // we know that Corestore::rename_* won't ever return anything else
+                impossible!()
+            },
+        };
+        conwrite!(con, ret)?;
+        Ok(())
+    }
+}
+
+/// Create the table `new` just like the table `like`, along with a copy of its data if
+/// `with_data` is set
+fn create_table_like(
+    handle: &Corestore,
+    new: &[u8],
+    like: &[u8],
+    with_data: bool,
+) -> &'static [u8] {
+    let new = match parser::get_query_entity(new) {
+        Ok(new) => unsafe {
+            // SAFETY: All sizes checked by the parser
+            new.into_owned()
+        },
+        Err(e) => return e,
+    };
+    let like = match parser::get_query_entity(like) {
+        Ok(like) => like,
+        Err(e) => return e,
+    };
+    if !registry::state_okay() {
+        return responses::groups::SERVER_ERR;
+    }
+    match handle.create_table_like(new, like, with_data) {
+        Ok(()) => responses::groups::OKAY,
+        Err(DdlError::AlreadyExists) => responses::groups::ALREADY_EXISTS,
+        Err(DdlError::DefaultNotFound) => responses::groups::DEFAULT_UNSET,
+        Err(DdlError::ObjectNotFound) => responses::groups::CONTAINER_NOT_FOUND,
+        Err(DdlError::QuotaExceeded) => responses::groups::QUOTA_EXCEEDED,
+        Err(_) => unsafe {
+            // we know that Corestore::create_table_like won't ever return anything else
+            impossible!()
+        },
+    }
+}
+
+action! {
+    /// Handle `clone table <tableid> <new tableid>` like queries, which create a copy of a
+    /// table along with its data
+    fn ddl_clone(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 3);
+        let mut clone_what = unsafe { act.next().unsafe_unwrap() }.to_vec();
+        clone_what.make_ascii_uppercase();
+        if clone_what != TABLE {
+            return conwrite!(con, responses::groups::UNKNOWN_DDL_QUERY);
+        }
+        let (like, new) = unsafe { (act.next_unchecked(), act.next_unchecked()) };
+        conwrite!(con, create_table_like(handle, new, like, true))
+    }
+}
@@ -25,16 +25,44 @@
 */
 
 use super::ddl::{KEYSPACE, TABLE};
-use crate::corestore::memstore::ObjectID;
+use crate::corestore::memstore::{DdlError, ObjectID};
+use crate::corestore::quota::Quota;
 use crate::dbnet::connection::prelude::*;
+use crate::registry::stats;
 use crate::resp::writer::TypedArrayWriter;
+use crate::storage::interface;
+use std::time::UNIX_EPOCH;
 
 const KEYSPACES: &[u8] = "KEYSPACES".as_bytes();
+const SERVER: &[u8] = "SERVER".as_bytes();
+const QUOTAS: &[u8] = "QUOTAS".as_bytes();
+
+/// Write an array of alternating property names and values, like
+/// `["model", "keymap", "keys", 10, ...]`
+macro_rules! write_properties {
+    ($con:expr, $($name:literal => $value:expr),* $(,)?) => {{
+        $con.write_array_length(2 * [$($name),*].len()).await?;
+        $(
+            conwrite!($con, $name)?;
+            conwrite!($con, $value)?;
+        )*
+    }};
+}
+
+const fn bool_name(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
 action! {
     /// Runs an inspect query:
     /// - `INSPECT KEYSPACES` is run by this function itself
     /// - `INSPECT TABLE <tblid>` is delegated to self::inspect_table
     /// - `INSPECT KEYSPACE <ksid>` is delegated to self::inspect_keyspace
+    /// - `INSPECT SERVER` is delegated to self::inspect_server
+    /// - `INSPECT QUOTAS <ksid>` is delegated to self::inspect_quotas
     fn inspect(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
         match act.next() {
             Some(inspect_what) => {
@@ -43,6 +71,8 @@ action! {
                 match inspect_what.as_ref() {
                     KEYSPACE => inspect_keyspace(handle, con, act).await?,
                     TABLE => inspect_table(handle, con, act).await?,
+                    SERVER => inspect_server(handle, con, act).await?,
+                    QUOTAS => inspect_quotas(handle, con, act).await?,
                     KEYSPACES => {
                         err_if_len_is!(act, con, not 0);
                         // let's return what all keyspaces exist
@@ -98,16 +128,115 @@ action! {
 }
 
 action! {
-    /// INSPECT a table. This should only have the table ID
+    /// INSPECT the quotas of a keyspace. This should only have the keyspace ID. Every limit
+    /// is `0` if it isn't set, and the usage is listed along with the limits
+    fn inspect_quotas(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 1);
+        let ksid = unsafe {
+            // SAFETY: Already checked len
+            act.next_unchecked()
+        };
+        if ksid.len() > 64 {
+            return conwrite!(con, responses::groups::BAD_CONTAINER_NAME);
+        }
+        let ks = match handle.get_keyspace(ksid) {
+            Some(kspace) => kspace,
+            None => return conwrite!(con, responses::groups::CONTAINER_NOT_FOUND),
+        };
+        let quotas = ks.quotas();
+        let (keys, bytes) = ks.usage();
+        write_properties!(
+            con,
+            "max_tables" => quotas.get(Quota::Tables),
+            "max_keys" => quotas.get(Quota::Keys),
+            "max_bytes" => quotas.get(Quota::Bytes),
+            "max_value_size" => quotas.get(Quota::ValueSize),
+            "tables" => ks.table_count(),
+            "keys" => keys,
+            "bytes" => bytes,
+        );
+        Ok(())
+    }
+}
+
+action! {
+    /// INSPECT a table. This should only have the table ID. The table is described by its
+    /// model, data types, volatility, statistics and the state of its data file
     fn inspect_table(handle: &Corestore, con: &'a mut T, mut act: ActionIter<'a>) {
         err_if_len_is!(act, con, not 1);
-        match act.next() {
-            Some(entity) => {
-                let entity = handle_entity!(con, entity);
-                conwrite!(con, get_tbl!(entity, handle, con).describe_self())?;
-            },
-            None => aerr!(con, aerr),
+        let entity = handle_entity!(con, unsafe {
+            // SAFETY: Already checked len
+            act.next_unchecked()
+        });
+        let (ksid, tblid, tbl) = match handle.get_table_with_ids(entity) {
+            Ok(found) => found,
+            Err(DdlError::DefaultNotFound) => return conwrite!(con, groups::DEFAULT_UNSET),
+            Err(DdlError::ObjectNotFound) => return conwrite!(con, groups::CONTAINER_NOT_FOUND),
+            Err(_) => unsafe { impossible!() },
+        };
+        // every flush rewrites the data file, so its modification time is the time of the
+        // last flush
+        let (disk_size, last_flush) = match interface::table_file_metadata(&ksid, &tblid) {
+            Ok(Some(metadata)) => {
+                let last_flush = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |since_epoch| since_epoch.as_secs());
+                (metadata.len(), last_flush)
+            }
+            Ok(None) => (0, 0),
+            Err(e) => {
+                log::error!("Failed to read the metadata of a table file: {}", e);
+                return conwrite!(con, groups::SERVER_ERR);
+            }
+        };
+        let (key_type, value_type) = tbl.data_types();
+        write_properties!(
+            con,
+            "model" => tbl.model_name(),
+            "key_type" => key_type,
+            "value_type" => value_type,
+            "volatile" => bool_name(tbl.is_volatile()),
+            "keys" => tbl.count(),
+            "used_memory_approx" => tbl.approx_mem_usage(),
+            "last_modified" => tbl.last_modified(),
+            "disk_size" => disk_size,
+            "last_flush" => last_flush,
+        );
+        Ok(())
+    }
+}
+
+action! {
+    /// INSPECT the server. This shouldn't have any arguments. The server is described by its
+    /// version, uptime, the number of containers and keys, clients and its persistence state
+    fn inspect_server(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 0);
+        let store = handle.get_store();
+        let (mut tables, mut keys, mut used_memory) = (0usize, 0usize, 0usize);
+        for ks in store.keyspaces.iter() {
+            for tbl in ks.value().tables.iter() {
+                tables += 1;
+                keys += tbl.value().count();
+                used_memory += tbl.value().approx_mem_usage();
+            }
         }
+        write_properties!(
+            con,
+            "version" => libsky::VERSION,
+            "started_at" => stats::start_time(),
+            "uptime" => stats::uptime(),
+            "keyspaces" => store.keyspaces.len(),
+            "tables" => tables,
+            "keys" => keys,
+            "used_memory_approx" => used_memory,
+            "connected_clients" => stats::active_connections(),
+            "commands_processed" => stats::commands_processed(),
+            "poisoned" => bool_name(!registry::state_okay()),
+            "bgsave_last_time" => stats::bgsave_last_time(),
+            "snapshot_last_time" => stats::snapshot_last_time(),
+        );
         Ok(())
     }
 }
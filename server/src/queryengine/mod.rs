@@ -33,18 +33,24 @@ use crate::protocol::element::UnsafeElement;
 use crate::protocol::iter::AnyArrayIter;
 use crate::protocol::responses;
 use crate::protocol::SimpleQuery;
+use crate::protocol::UnsafeSlice;
+use crate::registry::slowlog;
+use crate::registry::stats;
 use crate::{actions, admin};
 use core::hint::unreachable_unchecked;
+use std::time::Instant;
 mod ddl;
 mod inspect;
 pub mod parser;
+pub mod ql;
 #[cfg(test)]
 mod tests;
+pub mod txn;
 
 pub type ActionIter<'a> = AnyArrayIter<'a>;
 
 macro_rules! gen_constants_and_matches {
-    ($con:expr, $buf:ident, $db:ident, $($action:ident => $fns:expr),*) => {
+    ($con:expr, $buf:ident, $db:ident, $raw:ident, $($action:ident => $fns:expr),*) => {
         mod tags {
             //! This module is a collection of tags/strings used for evaluating queries
             //! and responses
@@ -58,7 +64,27 @@ macro_rules! gen_constants_and_matches {
         };
         match first.as_ref() {
             $(
-                tags::$action => $fns($db, $con, $buf).await?,
+                tags::$action => {
+                    let start = Instant::now();
+                    $fns($db, $con, $buf).await?;
+                    let elapsed = start.elapsed();
+                    stats::observe_action(stringify!($action), elapsed);
+                    $con.get_client().action_run(stringify!($action));
+                    if slowlog::is_slow(elapsed) {
+                        // skip the action itself
+                        let args = unsafe {
+                            // SAFETY: The source buffer is still valid (see execute_action)
+                            AnyArrayIter::new($raw.iter())
+                        }.skip(1);
+                        slowlog::record(
+                            stringify!($action),
+                            elapsed,
+                            args,
+                            $con.get_client().peer(),
+                            $db.get_centity_name(),
+                        );
+                    }
+                }
             )*
             _ => {
                 $con.write_response(responses::groups::UNKNOWN_ACTION).await?;
@@ -90,44 +116,132 @@ macro_rules! swap_entity {
     };
 }
 
+const ON: &[u8] = "ON".as_bytes();
+/// Actions that change the state of the connection itself, and hence can't be run with `ON`
+const UNSCOPED: [&[u8]; 6] = [
+    ON,
+    "USE".as_bytes(),
+    "MULTI".as_bytes(),
+    "EXEC".as_bytes(),
+    "DISCARD".as_bytes(),
+    "UNWATCH".as_bytes(),
+];
+
+/// Returns a copy of the store that runs the given action against the given entity, for an
+/// `ON <entity> <action> ...` query
+fn scoped_store(db: &Corestore, entity: &[u8], action: &[u8]) -> Result<Corestore, &'static [u8]> {
+    if UNSCOPED.contains(&action.to_ascii_uppercase().as_slice()) {
+        return Err(responses::groups::UNSUPPORTED_IN_SCOPE);
+    }
+    match db.with_table(parser::get_query_entity(entity)?) {
+        Ok(store) => Ok(store),
+        Err(DdlError::ObjectNotFound) => Err(responses::groups::CONTAINER_NOT_FOUND),
+        Err(DdlError::DefaultNotFound) => Err(responses::groups::DEFAULT_UNSET),
+        Err(_) => unsafe {
+            // we know Corestore::with_table doesn't return anything else
+            impossible!()
+        },
+    }
+}
+
 /// Execute a simple(*) query
-pub async fn execute_simple<'a, T: 'a, Strm>(
+pub async fn execute_simple<'a, T, Strm>(
     db: &mut Corestore,
     con: &'a mut T,
     buf: SimpleQuery,
 ) -> std::io::Result<()>
 where
-    T: ProtocolConnectionExt<Strm>,
+    T: 'a + ProtocolConnectionExt<Strm>,
     Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
 {
     if !buf.is_any_array() {
         return con.write_response(responses::groups::WRONGTYPE_ERR).await;
     }
-    let bufref;
-    let _rawiter;
-    let mut iter;
-    unsafe {
-        // this is the boxed slice
-        bufref = {
-            // SAFETY: execute_simple is called by execute_query which in turn is called
-            // by ConnnectionHandler::run(). In all cases, the `Con` remains valid
-            // ensuring that the source buffer exists as long as the connection does
-            // so this is safe.
-            match buf.into_inner() {
-                UnsafeElement::AnyArray(arr) => arr,
-                _ => unreachable_unchecked(),
-            }
+    let bufref = unsafe {
+        // SAFETY: execute_simple is called by execute_query which in turn is called
+        // by ConnnectionHandler::run(). In all cases, the `Con` remains valid
+        // ensuring that the source buffer exists as long as the connection does
+        // so this is safe.
+        match buf.into_inner() {
+            UnsafeElement::AnyArray(arr) => arr,
+            _ => unreachable_unchecked(),
+        }
+    };
+    if let [statement] = &*bufref {
+        let statement = unsafe {
+            // SAFETY: The source buffer is valid (see above)
+            statement.as_slice()
         };
-        _rawiter = bufref.iter();
-        // this is our final iter
-        iter = {
-            // SAFETY: Again, this is guaranteed to be valid because the `con` is valid
-            AnyArrayIter::new(_rawiter)
+        if ql::is_statement(statement) {
+            return match ql::parse(statement) {
+                Ok(statement) => {
+                    let args = statement.into_action();
+                    let slices: Vec<UnsafeSlice> = args
+                        .iter()
+                        .map(|arg| unsafe {
+                            // SAFETY: `args` outlives the slices
+                            UnsafeSlice::new(arg.as_ptr(), arg.len())
+                        })
+                        .collect();
+                    execute_action(db, con, &slices).await
+                }
+                Err(e) => con.write_response(e.to_response()).await,
+            };
+        }
+    }
+    execute_action(db, con, &bufref).await
+}
+
+/// Execute an action, with `bufref` being the action and its arguments
+async fn execute_action<'a, T, Strm>(
+    db: &mut Corestore,
+    con: &'a mut T,
+    bufref: &[UnsafeSlice],
+) -> std::io::Result<()>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    let mut scoped;
+    let (db, bufref) = match bufref {
+        [on, entity, action, ..] if unsafe { on.as_slice() }.eq_ignore_ascii_case(ON) => {
+            let scope = unsafe {
+                // SAFETY: The source buffer is valid as long as `bufref` is
+                scoped_store(db, entity.as_slice(), action.as_slice())
+            };
+            match scope {
+                Ok(store) => {
+                    scoped = store;
+                    (&mut scoped, &bufref[2..])
+                }
+                Err(e) => {
+                    con.get_txn_mut().abort();
+                    return con.write_response(e).await;
+                }
+            }
+        }
+        [on, ..] if unsafe { on.as_slice() }.eq_ignore_ascii_case(ON) => {
+            con.get_txn_mut().abort();
+            return con.write_response(responses::groups::ACTION_ERR).await;
+        }
+        _ => (db, bufref),
+    };
+    let mut iter = unsafe {
+        // SAFETY: The source buffer is valid as long as `bufref` is
+        AnyArrayIter::new(bufref.iter())
+    };
+    if con.get_txn().is_active() {
+        let act = unsafe {
+            // SAFETY: The source buffer is valid as long as `bufref` is
+            AnyArrayIter::new(bufref.iter())
         };
+        if txn::queue_query(db, con, act).await? {
+            return Ok(());
+        }
     }
     {
         gen_constants_and_matches!(
-            con, iter, db,
+            con, iter, db, bufref,
             GET => actions::get::get,
             SET => actions::set::set,
             UPDATE => actions::update::update,
@@ -149,9 +263,76 @@ where
             POP => actions::pop::pop,
             CREATE => ddl::create,
             DROP => ddl::ddl_drop,
+            ALTER => ddl::alter,
+            RENAME => ddl::rename,
+            CLONE => ddl::ddl_clone,
             USE => self::entity_swap,
             INSPECT => inspect::inspect,
-            MPOP => actions::mpop::mpop
+            MPOP => actions::mpop::mpop,
+            SYS => admin::sys::sys,
+            SLOWLOG => admin::slowlog::slowlog,
+            CLIENT => admin::client::client,
+            CONFIG => admin::config::config,
+            MULTI => txn::multi,
+            EXEC => txn::exec,
+            DISCARD => txn::discard,
+            WATCH => txn::watch,
+            UNWATCH => txn::unwatch,
+            EVAL => actions::eval::eval,
+            EVALSHA => actions::eval::evalsha,
+            SCRIPT => actions::eval::script,
+            COPY => actions::copy::copy,
+            MOVE => actions::copy::move_keys,
+            HSET => actions::hash::hset,
+            HGET => actions::hash::hget,
+            HMGET => actions::hash::hmget,
+            HDEL => actions::hash::hdel,
+            HGETALL => actions::hash::hgetall,
+            HLEN => actions::hash::hlen,
+            SADD => actions::sets::sadd,
+            SREM => actions::sets::srem,
+            SISMEMBER => actions::sets::sismember,
+            SCARD => actions::sets::scard,
+            SMEMBERS => actions::sets::smembers,
+            SUNION => actions::sets::sunion,
+            SINTER => actions::sets::sinter,
+            SDIFF => actions::sets::sdiff,
+            SUNIONSTORE => actions::sets::sunionstore,
+            SINTERSTORE => actions::sets::sinterstore,
+            SDIFFSTORE => actions::sets::sdiffstore,
+            ZADD => actions::sortedsets::zadd,
+            ZINCRBY => actions::sortedsets::zincrby,
+            ZREM => actions::sortedsets::zrem,
+            ZSCORE => actions::sortedsets::zscore,
+            ZRANK => actions::sortedsets::zrank,
+            ZREVRANK => actions::sortedsets::zrevrank,
+            ZCARD => actions::sortedsets::zcard,
+            ZRANGE => actions::sortedsets::zrange,
+            ZREVRANGE => actions::sortedsets::zrevrange,
+            ZRANGEBYSCORE => actions::sortedsets::zrangebyscore,
+            PFADD => actions::sketch::pfadd,
+            PFCOUNT => actions::sketch::pfcount,
+            PFMERGE => actions::sketch::pfmerge,
+            BFRESERVE => actions::sketch::bfreserve,
+            BFADD => actions::sketch::bfadd,
+            BFEXISTS => actions::sketch::bfexists,
+            BFMEXISTS => actions::sketch::bfmexists,
+            TSCREATE => actions::timeseries::tscreate,
+            TSADD => actions::timeseries::tsadd,
+            TSGET => actions::timeseries::tsget,
+            TSLEN => actions::timeseries::tslen,
+            TSRANGE => actions::timeseries::tsrange,
+            TSAGG => actions::timeseries::tsagg,
+            DELMATCH => actions::keymatch::delmatch,
+            COUNTMATCH => actions::keymatch::countmatch,
+            APPEND => actions::strings::append,
+            STRLEN => actions::strings::strlen,
+            GETRANGE => actions::strings::getrange,
+            SETRANGE => actions::strings::setrange,
+            GETSET => actions::strings::getset,
+            GETV => actions::versioned::getv,
+            UPDATEV => actions::versioned::updatev,
+            DELV => actions::versioned::delv
         );
     }
     Ok(())
@@ -25,9 +25,12 @@
 */
 
 use crate::corestore::lazy::Lazy;
+use crate::corestore::table;
 use crate::corestore::{BorrowedEntityGroup, OwnedEntityGroup};
 use crate::kvengine::encoding;
+use crate::kvengine::ValueType;
 use crate::protocol::responses;
+use crate::queryengine::ql::{self, ast::ModelExpr};
 use crate::queryengine::ActionIter;
 use crate::util::compiler;
 use crate::util::compiler::cold_err;
@@ -36,6 +39,11 @@ use core::str;
 use regex::Regex;
 
 const KEYMAP: &[u8] = "keymap".as_bytes();
+const HASHMAP: &[u8] = "hashmap".as_bytes();
+const SET: &[u8] = "set".as_bytes();
+const SORTEDSET: &[u8] = "sortedset".as_bytes();
+const TIMESERIES: &[u8] = "timeseries".as_bytes();
+const FLOAT: &[u8] = "float".as_bytes();
 const BINSTR: &[u8] = "binstr".as_bytes();
 const STR: &[u8] = "str".as_bytes();
 
@@ -47,77 +55,14 @@ pub(super) fn parse_table_args(
 ) -> Result<(OwnedEntityGroup, u8), &'static [u8]> {
     let table_name = unsafe { act.next().unsafe_unwrap() };
     let model_name = unsafe { act.next().unsafe_unwrap() };
-    if compiler::unlikely(!encoding::is_utf8(&table_name) || !encoding::is_utf8(&model_name)) {
+    if compiler::unlikely(!encoding::is_utf8(table_name) || !encoding::is_utf8(model_name)) {
         return Err(responses::groups::ENCODING_ERROR);
     }
     let model_name_str = unsafe { str::from_utf8_unchecked(model_name) };
 
     // get the entity group
     let entity_group = get_query_entity(table_name)?;
-    let splits: Vec<&str> = model_name_str.split('(').collect();
-    if compiler::unlikely(splits.len() != 2) {
-        return Err(responses::groups::BAD_EXPRESSION);
-    }
-    let model_name_split = unsafe { splits.get_unchecked(0) };
-    let model_args_split = unsafe { splits.get_unchecked(1) };
-
-    // model name has to have at least one char while model args should have
-    // atleast `)` 1 chars (for example if the model takes no arguments: `smh()`)
-    if compiler::unlikely(model_name_split.is_empty() || model_args_split.is_empty()) {
-        return Err(responses::groups::BAD_EXPRESSION);
-    }
-
-    // THIS IS WHERE WE HANDLE THE NEWER MODELS
-    if model_name_split.as_bytes() != KEYMAP {
-        return Err(responses::groups::UNKNOWN_MODEL);
-    }
-
-    let non_bracketed_end = unsafe {
-        *model_args_split
-            .as_bytes()
-            .get_unchecked(model_args_split.len() - 1)
-            != b')'
-    };
-
-    if compiler::unlikely(non_bracketed_end) {
-        return Err(responses::groups::BAD_EXPRESSION);
-    }
-
-    // should be (ty1, ty2)
-    let model_args: Vec<&str> = model_args_split[..model_args_split.len() - 1]
-        .split(',')
-        .map(|v| v.trim())
-        .collect();
-    if compiler::unlikely(model_args.len() != 2) {
-        // nope, someone had fun with commas or they added more args
-        // let's check if it was comma fun or if it was arg fun
-        return cold_err({
-            let all_nonzero = model_args.into_iter().all(|v| !v.is_empty());
-            if all_nonzero {
-                // arg fun
-                Err(responses::groups::TOO_MANY_ARGUMENTS)
-            } else {
-                // comma fun
-                Err(responses::groups::BAD_EXPRESSION)
-            }
-        });
-    }
-    let key_ty = unsafe { model_args.get_unchecked(0) };
-    let val_ty = unsafe { model_args.get_unchecked(1) };
-    if compiler::unlikely(
-        !VALID_CONTAINER_NAME.is_match(key_ty) || !VALID_CONTAINER_NAME.is_match(val_ty),
-    ) {
-        return Err(responses::groups::BAD_EXPRESSION);
-    }
-    let key_ty = key_ty.as_bytes();
-    let val_ty = val_ty.as_bytes();
-    let model_code: u8 = match (key_ty, val_ty) {
-        (BINSTR, BINSTR) => 0,
-        (BINSTR, STR) => 1,
-        (STR, STR) => 2,
-        (STR, BINSTR) => 3,
-        _ => return Err(responses::groups::UNKNOWN_DATA_TYPE),
-    };
+    let model_code = parse_model_code(model_name_str)?;
     Ok((
         unsafe {
             // SAFETY: All sizes checked here
@@ -140,70 +85,7 @@ pub(super) fn parse_table_args_test(
 
     // get the entity group
     let entity_group = get_query_entity(&table_name)?;
-    let splits: Vec<&str> = model_name_str.split('(').collect();
-    if compiler::unlikely(splits.len() != 2) {
-        return Err(responses::groups::BAD_EXPRESSION);
-    }
-    let model_name_split = unsafe { splits.get_unchecked(0) };
-    let model_args_split = unsafe { splits.get_unchecked(1) };
-
-    // model name has to have at least one char while model args should have
-    // atleast `)` 1 chars (for example if the model takes no arguments: `smh()`)
-    if compiler::unlikely(model_name_split.is_empty() || model_args_split.is_empty()) {
-        return Err(responses::groups::BAD_EXPRESSION);
-    }
-
-    // THIS IS WHERE WE HANDLE THE NEWER MODELS
-    if model_name_split.as_bytes() != KEYMAP {
-        return Err(responses::groups::UNKNOWN_MODEL);
-    }
-
-    let non_bracketed_end = unsafe {
-        *model_args_split
-            .as_bytes()
-            .get_unchecked(model_args_split.len() - 1)
-            != b')'
-    };
-
-    if compiler::unlikely(non_bracketed_end) {
-        return Err(responses::groups::BAD_EXPRESSION);
-    }
-
-    // should be (ty1, ty2)
-    let model_args: Vec<&str> = model_args_split[..model_args_split.len() - 1]
-        .split(',')
-        .map(|v| v.trim())
-        .collect();
-    if compiler::unlikely(model_args.len() != 2) {
-        // nope, someone had fun with commas or they added more args
-        // let's check if it was comma fun or if it was arg fun
-        return cold_err({
-            let all_nonzero = model_args.into_iter().all(|v| !v.is_empty());
-            if all_nonzero {
-                // arg fun
-                Err(responses::groups::TOO_MANY_ARGUMENTS)
-            } else {
-                // comma fun
-                Err(responses::groups::BAD_EXPRESSION)
-            }
-        });
-    }
-    let key_ty = unsafe { model_args.get_unchecked(0) };
-    let val_ty = unsafe { model_args.get_unchecked(1) };
-    if compiler::unlikely(
-        !VALID_CONTAINER_NAME.is_match(key_ty) || !VALID_CONTAINER_NAME.is_match(val_ty),
-    ) {
-        return Err(responses::groups::BAD_EXPRESSION);
-    }
-    let key_ty = key_ty.as_bytes();
-    let val_ty = val_ty.as_bytes();
-    let model_code: u8 = match (key_ty, val_ty) {
-        (BINSTR, BINSTR) => 0,
-        (BINSTR, STR) => 1,
-        (STR, STR) => 2,
-        (STR, BINSTR) => 3,
-        _ => return Err(responses::groups::UNKNOWN_DATA_TYPE),
-    };
+    let model_code = parse_model_code(model_name_str)?;
     Ok((
         unsafe {
             // SAFETY: All sizes checked here
@@ -212,7 +94,62 @@ pub(super) fn parse_table_args_test(
         model_code,
     ))
 }
-pub fn get_query_entity<'a>(input: &'a [u8]) -> Result<BorrowedEntityGroup, &'static [u8]> {
+/// Parse a model expression like `keymap(str, binstr)` and return the model code
+fn parse_model_code(model_expr: &str) -> Result<u8, &'static [u8]> {
+    let ModelExpr { model, args } = match ql::parse_model(model_expr.as_bytes()) {
+        Ok(expr) => expr,
+        Err(_) => return Err(responses::groups::BAD_EXPRESSION),
+    };
+    // THIS IS WHERE WE HANDLE THE NEWER MODELS
+    let model = model.as_bytes();
+    if model != KEYMAP
+        && model != HASHMAP
+        && model != SET
+        && model != SORTEDSET
+        && model != TIMESERIES
+    {
+        return Err(responses::groups::UNKNOWN_MODEL);
+    }
+    if compiler::unlikely(args.len() != 2) {
+        return cold_err(Err(responses::groups::TOO_MANY_ARGUMENTS));
+    }
+    // keys (and hash values or (sorted) set members) can only be strings, but keymap
+    // values can be typed (and time series samples are always floats)
+    let k_enc = parse_string_type(args[0].as_bytes())?;
+    if model == HASHMAP {
+        let v_enc = parse_string_type(args[1].as_bytes())?;
+        return Ok(table::hash_model_code(k_enc, v_enc));
+    }
+    if model == SET {
+        let m_enc = parse_string_type(args[1].as_bytes())?;
+        return Ok(table::set_model_code(k_enc, m_enc));
+    }
+    if model == SORTEDSET {
+        let m_enc = parse_string_type(args[1].as_bytes())?;
+        return Ok(table::sortedset_model_code(k_enc, m_enc));
+    }
+    if model == TIMESERIES {
+        if args[1].as_bytes() != FLOAT {
+            return Err(responses::groups::UNKNOWN_DATA_TYPE);
+        }
+        return Ok(table::timeseries_model_code(k_enc));
+    }
+    match ValueType::from_name(args[1].as_bytes()) {
+        Some(v_type) => Ok(table::kv_model_code(k_enc, v_type)),
+        None => Err(responses::groups::UNKNOWN_DATA_TYPE),
+    }
+}
+
+/// Parse a string type (`binstr` or `str`), returning its encoding
+fn parse_string_type(name: &[u8]) -> Result<bool, &'static [u8]> {
+    match name {
+        BINSTR => Ok(false),
+        STR => Ok(true),
+        _ => Err(responses::groups::UNKNOWN_DATA_TYPE),
+    }
+}
+
+pub fn get_query_entity(input: &[u8]) -> Result<BorrowedEntityGroup<'_>, &'static [u8]> {
     let y: Vec<&[u8]> = input.split(|v| *v == b':').collect();
     unsafe {
         if y.len() == 1 {
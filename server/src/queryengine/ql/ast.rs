@@ -0,0 +1,116 @@
+/*
+ * Created on Wed Sep 15 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The AST
+//!
+//! The typed statements produced by the [parser](super::parser)
+
+/// A keyspace or a table: either `name` or `keyspace.table` (`keyspace:table` is
+/// accepted too)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entity {
+    Single(String),
+    Full(String, String),
+}
+
+/// A model expression like `keymap(str, binstr)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelExpr {
+    pub model: String,
+    pub args: Vec<String>,
+}
+
+/// A column definition like `k str`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub ty: String,
+}
+
+/// What an `INSPECT` statement inspects
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inspect {
+    Keyspaces,
+    Keyspace(String),
+    Table(Entity),
+    Server,
+}
+
+pub type Key = Vec<u8>;
+pub type Pair = (Vec<u8>, Vec<u8>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /// `CREATE KEYSPACE <ks>`
+    CreateKeyspace(String),
+    /// `CREATE TABLE <entity> (<key column>, <value column>) [WITH volatile]`
+    CreateTable {
+        entity: Entity,
+        key: Column,
+        value: Column,
+        volatile: bool,
+    },
+    /// `CREATE TABLE <entity> LIKE <other entity>`
+    CreateTableLike { entity: Entity, like: Entity },
+    /// `CLONE TABLE <entity> TO <new entity>`
+    CloneTable { entity: Entity, to: Entity },
+    /// `DROP KEYSPACE <ks> [FORCE]`
+    DropKeyspace { keyspace: String, force: bool },
+    /// `DROP TABLE <entity>`
+    DropTable(Entity),
+    /// `ALTER TABLE <entity> WITH volatile` or `ALTER TABLE <entity> WITH persistent`
+    AlterTable { entity: Entity, volatile: bool },
+    /// `RENAME KEYSPACE <ks> TO <new ks>`
+    RenameKeyspace { keyspace: String, to: String },
+    /// `RENAME TABLE <entity> TO <new table>`
+    RenameTable { entity: Entity, to: String },
+    /// `USE <entity>`
+    Use(Entity),
+    /// `INSPECT KEYSPACES`, `INSPECT KEYSPACE <ks>`, `INSPECT TABLE <entity>` or
+    /// `INSPECT SERVER`
+    Inspect(Inspect),
+    /// `GET <key> [, <key> ...]`
+    Get(Vec<Key>),
+    /// `SET <key> = <value> [, <key> = <value> ...]`
+    Set(Vec<Pair>),
+    /// `UPDATE <key> = <value> [, <key> = <value> ...]`
+    Update(Vec<Pair>),
+    /// `UPSERT <key> = <value> [, <key> = <value> ...]`
+    Upsert(Vec<Pair>),
+    /// `DELETE <key> [, <key> ...]`
+    Delete(Vec<Key>),
+    /// `EXISTS <key> [, <key> ...]`
+    Exists(Vec<Key>),
+    /// `POP <key> [, <key> ...]`
+    Pop(Vec<Key>),
+    /// `COPY <key> [, <key> ...] TO <entity>`
+    Copy { keys: Vec<Key>, to: Entity },
+    /// `MOVE <key> [, <key> ...] TO <entity>`
+    Move { keys: Vec<Key>, to: Entity },
+    /// `ON <entity> <statement>`, which runs a `GET`, `SET`, `UPDATE`, `UPSERT`, `DELETE`,
+    /// `EXISTS`, `POP`, `COPY` or `MOVE` statement against the given table
+    On(Entity, Box<Statement>),
+}
@@ -0,0 +1,148 @@
+/*
+ * Created on Wed Sep 15 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The lexer
+//!
+//! Splits a statement into [`Token`]s, each of which remembers where it started so that
+//! errors can point at the exact position
+
+use super::{QlError, QlErrorKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// An identifier or a keyword (keywords are case-insensitive)
+    Ident(String),
+    /// A quoted string literal
+    Str(Vec<u8>),
+    /// An unsigned integer literal (kept as text since it's only ever sent as a value)
+    Number(String),
+    LParen,
+    RParen,
+    Comma,
+    Dot,
+    Colon,
+    Eq,
+    Semicolon,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// the byte offset of the start of the token
+    pub pos: usize,
+}
+
+impl TokenKind {
+    /// Returns a short description of the token for errors
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Ident(ident) => format!("'{}'", ident),
+            Self::Str(_) => "a string".to_owned(),
+            Self::Number(num) => format!("'{}'", num),
+            Self::LParen => "'('".to_owned(),
+            Self::RParen => "')'".to_owned(),
+            Self::Comma => "','".to_owned(),
+            Self::Dot => "'.'".to_owned(),
+            Self::Colon => "':'".to_owned(),
+            Self::Eq => "'='".to_owned(),
+            Self::Semicolon => "';'".to_owned(),
+        }
+    }
+}
+
+pub fn tokenize(src: &[u8]) -> Result<Vec<Token>, QlError> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < src.len() {
+        let pos = i;
+        let kind = match src[i] {
+            c if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                while i < src.len() && (src[i].is_ascii_alphanumeric() || src[i] == b'_') {
+                    i += 1;
+                }
+                // only ASCII here, so this is valid UTF-8
+                TokenKind::Ident(String::from_utf8_lossy(&src[pos..i]).into_owned())
+            }
+            c if c.is_ascii_digit() => {
+                while i < src.len() && src[i].is_ascii_digit() {
+                    i += 1;
+                }
+                if i < src.len() && (src[i].is_ascii_alphabetic() || src[i] == b'_') {
+                    return Err(QlError::new(i, QlErrorKind::UnexpectedChar(src[i])));
+                }
+                TokenKind::Number(String::from_utf8_lossy(&src[pos..i]).into_owned())
+            }
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                let mut string = Vec::new();
+                loop {
+                    match src.get(i) {
+                        Some(c) if *c == quote => break,
+                        Some(b'\\') => {
+                            let escaped = match src.get(i + 1) {
+                                Some(b'n') => b'\n',
+                                Some(b't') => b'\t',
+                                Some(b'\\') => b'\\',
+                                Some(b'\'') => b'\'',
+                                Some(b'"') => b'"',
+                                _ => return Err(QlError::new(i, QlErrorKind::BadEscape)),
+                            };
+                            string.push(escaped);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            string.push(*c);
+                            i += 1;
+                        }
+                        None => return Err(QlError::new(pos, QlErrorKind::UnterminatedString)),
+                    }
+                }
+                // skip the closing quote
+                i += 1;
+                TokenKind::Str(string)
+            }
+            c => {
+                i += 1;
+                match c {
+                    b'(' => TokenKind::LParen,
+                    b')' => TokenKind::RParen,
+                    b',' => TokenKind::Comma,
+                    b'.' => TokenKind::Dot,
+                    b':' => TokenKind::Colon,
+                    b'=' => TokenKind::Eq,
+                    b';' => TokenKind::Semicolon,
+                    c => return Err(QlError::new(pos, QlErrorKind::UnexpectedChar(c))),
+                }
+            }
+        };
+        tokens.push(Token { kind, pos });
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,370 @@
+/*
+ * Created on Wed Sep 15 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The query language
+//!
+//! Besides actions (where every argument is a separate element of the query), a query can
+//! be a single textual statement like:
+//! ```sql
+//! CREATE TABLE myks.mytbl (k str, v binstr) WITH volatile
+//! SET 'hello' = 'world', 'foo' = 'bar'
+//! INSPECT KEYSPACES
+//! ```
+//! Any query that has exactly one element with whitespace in it is parsed as a statement
+//! (no action has whitespace in its name). The statement is [tokenized](lexer), [parsed](parser)
+//! into a typed [`Statement`] and then lowered into the arguments of the equivalent action,
+//! so both paths share the same implementation (and the same responses).
+
+pub mod ast;
+mod lexer;
+mod parser;
+
+use self::ast::{Entity, Inspect, ModelExpr, Statement};
+use self::parser::Parser;
+use core::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum QlErrorKind {
+    UnexpectedChar(u8),
+    UnterminatedString,
+    BadEscape,
+    UnexpectedToken {
+        found: String,
+        expected: &'static str,
+    },
+    UnexpectedEnd {
+        expected: &'static str,
+    },
+    UnknownStatement(String),
+    UnknownProperty(String),
+}
+
+/// An error in a statement, along with the byte offset at which it was found
+#[derive(Debug, PartialEq)]
+pub struct QlError {
+    pub pos: usize,
+    pub kind: QlErrorKind,
+}
+
+impl QlError {
+    const fn new(pos: usize, kind: QlErrorKind) -> Self {
+        Self { pos, kind }
+    }
+    /// Returns the error response for this error, which carries the message
+    pub fn to_response(&self) -> Vec<u8> {
+        let message = format!("syntax-error: {}", self);
+        format!("!{}\n{}\n", message.len(), message).into_bytes()
+    }
+}
+
+impl fmt::Display for QlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            QlErrorKind::UnexpectedChar(c) => {
+                write!(f, "unexpected character {:?}", char::from(*c))?
+            }
+            QlErrorKind::UnterminatedString => write!(f, "unterminated string")?,
+            QlErrorKind::BadEscape => write!(f, "invalid escape sequence")?,
+            QlErrorKind::UnexpectedToken { found, expected } => {
+                write!(f, "expected {}, found {}", expected, found)?
+            }
+            QlErrorKind::UnexpectedEnd { expected } => {
+                write!(f, "expected {}, found the end of the query", expected)?
+            }
+            QlErrorKind::UnknownStatement(stmt) => write!(f, "unknown statement '{}'", stmt)?,
+            QlErrorKind::UnknownProperty(prop) => write!(f, "unknown property '{}'", prop)?,
+        }
+        // positions are 1-indexed for humans
+        write!(f, " at position {}", self.pos + 1)
+    }
+}
+
+/// Check if a single element query is a statement
+pub fn is_statement(element: &[u8]) -> bool {
+    element.iter().any(u8::is_ascii_whitespace)
+}
+
+/// Parse a statement
+pub fn parse(src: &[u8]) -> Result<Statement, QlError> {
+    Parser::new(lexer::tokenize(src)?, src.len()).parse_statement()
+}
+
+/// Parse a model expression, like `keymap(str, binstr)`
+pub fn parse_model(src: &[u8]) -> Result<ModelExpr, QlError> {
+    Parser::new(lexer::tokenize(src)?, src.len()).parse_model()
+}
+
+impl Entity {
+    /// Returns the entity in the `ks:tbl` form accepted by actions
+    fn into_arg(self) -> Vec<u8> {
+        match self {
+            Self::Single(name) => name.into_bytes(),
+            Self::Full(ks, tbl) => format!("{}:{}", ks, tbl).into_bytes(),
+        }
+    }
+}
+
+impl Statement {
+    /// Lower the statement into the arguments of the equivalent action (the first argument
+    /// being the name of the action)
+    pub fn into_action(self) -> Vec<Vec<u8>> {
+        fn action(name: &str, args: impl IntoIterator<Item = Vec<u8>>) -> Vec<Vec<u8>> {
+            let mut action = vec![name.as_bytes().to_vec()];
+            action.extend(args);
+            action
+        }
+        fn flatten(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> impl Iterator<Item = Vec<u8>> {
+            pairs.into_iter().flat_map(|(k, v)| [k, v])
+        }
+        fn word(word: &str) -> Vec<u8> {
+            word.as_bytes().to_vec()
+        }
+        match self {
+            Self::CreateKeyspace(ks) => action("CREATE", [word("KEYSPACE"), ks.into_bytes()]),
+            Self::CreateTable {
+                entity,
+                key,
+                value,
+                volatile,
+            } => {
+                let model = format!("keymap({},{})", key.ty, value.ty).into_bytes();
+                let mut args = vec![word("TABLE"), entity.into_arg(), model];
+                if volatile {
+                    args.push(word("volatile"));
+                }
+                action("CREATE", args)
+            }
+            Self::CreateTableLike { entity, like } => action(
+                "CREATE",
+                [
+                    word("TABLE"),
+                    entity.into_arg(),
+                    word("LIKE"),
+                    like.into_arg(),
+                ],
+            ),
+            Self::CloneTable { entity, to } => {
+                action("CLONE", [word("TABLE"), entity.into_arg(), to.into_arg()])
+            }
+            Self::DropKeyspace { keyspace, force } => {
+                let mut args = vec![word("KEYSPACE"), keyspace.into_bytes()];
+                if force {
+                    args.push(word("force"));
+                }
+                action("DROP", args)
+            }
+            Self::DropTable(entity) => action("DROP", [word("TABLE"), entity.into_arg()]),
+            Self::AlterTable { entity, volatile } => {
+                let property = if volatile { "volatile" } else { "persistent" };
+                action("ALTER", [word("TABLE"), entity.into_arg(), word(property)])
+            }
+            Self::RenameKeyspace { keyspace, to } => action(
+                "RENAME",
+                [word("KEYSPACE"), keyspace.into_bytes(), to.into_bytes()],
+            ),
+            Self::RenameTable { entity, to } => action(
+                "RENAME",
+                [word("TABLE"), entity.into_arg(), to.into_bytes()],
+            ),
+            Self::Use(entity) => action("USE", [entity.into_arg()]),
+            Self::Inspect(Inspect::Keyspaces) => action("INSPECT", [word("KEYSPACES")]),
+            Self::Inspect(Inspect::Keyspace(ks)) => {
+                action("INSPECT", [word("KEYSPACE"), ks.into_bytes()])
+            }
+            Self::Inspect(Inspect::Table(entity)) => {
+                action("INSPECT", [word("TABLE"), entity.into_arg()])
+            }
+            Self::Inspect(Inspect::Server) => action("INSPECT", [word("SERVER")]),
+            Self::Get(keys) if keys.len() == 1 => action("GET", keys),
+            Self::Get(keys) => action("MGET", keys),
+            Self::Set(pairs) if pairs.len() == 1 => action("SET", flatten(pairs)),
+            Self::Set(pairs) => action("MSET", flatten(pairs)),
+            Self::Update(pairs) if pairs.len() == 1 => action("UPDATE", flatten(pairs)),
+            Self::Update(pairs) => action("MUPDATE", flatten(pairs)),
+            Self::Upsert(pairs) => action("USET", flatten(pairs)),
+            Self::Delete(keys) => action("DEL", keys),
+            Self::Exists(keys) => action("EXISTS", keys),
+            Self::Pop(keys) if keys.len() == 1 => action("POP", keys),
+            Self::Pop(keys) => action("MPOP", keys),
+            Self::Copy { keys, to } => action("COPY", Some(to.into_arg()).into_iter().chain(keys)),
+            Self::Move { keys, to } => action("MOVE", Some(to.into_arg()).into_iter().chain(keys)),
+            Self::On(entity, statement) => action(
+                "ON",
+                Some(entity.into_arg())
+                    .into_iter()
+                    .chain(statement.into_action()),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ast::Column;
+    use super::*;
+
+    fn lower(src: &str) -> Vec<String> {
+        parse(src.as_bytes())
+            .unwrap()
+            .into_action()
+            .into_iter()
+            .map(|arg| String::from_utf8(arg).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_create_table() {
+        assert_eq!(
+            parse(b"create TABLE myks.mytbl (k str, v binstr) WITH volatile;").unwrap(),
+            Statement::CreateTable {
+                entity: Entity::Full("myks".to_owned(), "mytbl".to_owned()),
+                key: Column {
+                    name: "k".to_owned(),
+                    ty: "str".to_owned()
+                },
+                value: Column {
+                    name: "v".to_owned(),
+                    ty: "binstr".to_owned()
+                },
+                volatile: true
+            }
+        );
+        assert_eq!(
+            lower("CREATE TABLE mytbl (k str, v binstr)"),
+            ["CREATE", "TABLE", "mytbl", "keymap(str,binstr)"]
+        );
+    }
+
+    #[test]
+    fn test_parse_dml() {
+        assert_eq!(
+            parse(b"SET 'x' = \"it's\", 'y' = 100").unwrap(),
+            Statement::Set(vec![
+                (b"x".to_vec(), b"it's".to_vec()),
+                (b"y".to_vec(), b"100".to_vec())
+            ])
+        );
+        assert_eq!(lower("SET 'x' = 'y'"), ["SET", "x", "y"]);
+        assert_eq!(lower("GET 'x', 'y'"), ["MGET", "x", "y"]);
+        assert_eq!(lower("DELETE 'x'"), ["DEL", "x"]);
+        assert_eq!(lower("USE ks:tbl"), ["USE", "ks:tbl"]);
+        assert_eq!(
+            lower("ALTER TABLE ks.tbl WITH persistent"),
+            ["ALTER", "TABLE", "ks:tbl", "persistent"]
+        );
+        assert_eq!(
+            lower("RENAME TABLE ks.tbl TO newtbl"),
+            ["RENAME", "TABLE", "ks:tbl", "newtbl"]
+        );
+        assert_eq!(
+            lower("RENAME KEYSPACE ks TO newks"),
+            ["RENAME", "KEYSPACE", "ks", "newks"]
+        );
+        assert_eq!(
+            lower("ON ks.tbl GET 'x', 'y'"),
+            ["ON", "ks:tbl", "MGET", "x", "y"]
+        );
+        assert_eq!(lower("INSPECT KEYSPACE ks"), ["INSPECT", "KEYSPACE", "ks"]);
+        assert_eq!(lower("INSPECT SERVER"), ["INSPECT", "SERVER"]);
+        assert_eq!(
+            lower("CREATE TABLE ks.new LIKE ks.tbl"),
+            ["CREATE", "TABLE", "ks:new", "LIKE", "ks:tbl"]
+        );
+        assert_eq!(
+            lower("CLONE TABLE ks.tbl TO ks.new"),
+            ["CLONE", "TABLE", "ks:tbl", "ks:new"]
+        );
+        assert_eq!(
+            lower("ON ks.tbl MOVE 'x', 'y' TO ks.new"),
+            ["ON", "ks:tbl", "MOVE", "ks:new", "x", "y"]
+        );
+        assert_eq!(lower("COPY 'x' TO new"), ["COPY", "new", "x"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse(b"CREATE TABLE t (k str v str)").unwrap_err(),
+            QlError::new(
+                22,
+                QlErrorKind::UnexpectedToken {
+                    found: "'v'".to_owned(),
+                    expected: "','"
+                }
+            )
+        );
+        assert_eq!(
+            parse(b"GET 'x").unwrap_err(),
+            QlError::new(4, QlErrorKind::UnterminatedString)
+        );
+        assert_eq!(
+            parse(b"SET 'x' =").unwrap_err(),
+            QlError::new(
+                9,
+                QlErrorKind::UnexpectedEnd {
+                    expected: "a string or an integer"
+                }
+            )
+        );
+        assert_eq!(
+            parse(b"CREATE TABLE t (k str, v str) WITH durable").unwrap_err(),
+            QlError::new(35, QlErrorKind::UnknownProperty("durable".to_owned()))
+        );
+        assert_eq!(
+            parse(b"SELECT * FROM t").unwrap_err(),
+            QlError::new(7, QlErrorKind::UnexpectedChar(b'*'))
+        );
+        assert_eq!(
+            parse(b"ON tbl USE ks").unwrap_err(),
+            QlError::new(7, QlErrorKind::UnknownStatement("USE".to_owned()))
+        );
+        assert_eq!(
+            parse(b"RENAME TABLE tbl newtbl").unwrap_err().to_string(),
+            "expected TO, found 'newtbl' at position 18"
+        );
+        assert_eq!(
+            parse(b"FETCH 'x'").unwrap_err(),
+            QlError::new(0, QlErrorKind::UnknownStatement("FETCH".to_owned()))
+        );
+        assert_eq!(
+            parse(b"GET 'x' 'y'").unwrap_err().to_string(),
+            "expected the end of the statement, found a string at position 9"
+        );
+    }
+
+    #[test]
+    fn test_parse_model() {
+        assert_eq!(
+            parse_model(b"keymap(str, binstr)").unwrap(),
+            ModelExpr {
+                model: "keymap".to_owned(),
+                args: vec!["str".to_owned(), "binstr".to_owned()]
+            }
+        );
+        assert!(parse_model(b"keymap(str,)").is_err());
+        assert!(parse_model(b"keymap(str,str),").is_err());
+    }
+}
@@ -0,0 +1,383 @@
+/*
+ * Created on Wed Sep 15 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The parser
+//!
+//! A recursive descent parser that turns [`Token`]s into a [`Statement`]. Keywords are
+//! case-insensitive; keys and values have to be string or integer literals
+
+use super::ast::{Column, Entity, Inspect, Key, ModelExpr, Pair, Statement};
+use super::lexer::{Token, TokenKind};
+use super::{QlError, QlErrorKind};
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// the length of the source, which is where an unexpected end is reported
+    end: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>, src_len: usize) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            end: src_len,
+        }
+    }
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+    fn next(&mut self, expected: &'static str) -> Result<Token, QlError> {
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => Err(QlError::new(
+                self.end,
+                QlErrorKind::UnexpectedEnd { expected },
+            )),
+        }
+    }
+    fn unexpected(token: &Token, expected: &'static str) -> QlError {
+        QlError::new(
+            token.pos,
+            QlErrorKind::UnexpectedToken {
+                found: token.kind.describe(),
+                expected,
+            },
+        )
+    }
+    /// Check if the next token is the given keyword, consuming it if it is
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Ident(ident),
+                ..
+            }) if ident.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Consume the next token, which has to be the given keyword
+    fn expect_keyword(&mut self, keyword: &'static str) -> Result<(), QlError> {
+        let token = self.next(keyword)?;
+        match &token.kind {
+            TokenKind::Ident(ident) if ident.eq_ignore_ascii_case(keyword) => Ok(()),
+            _ => Err(Self::unexpected(&token, keyword)),
+        }
+    }
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        match self.peek() {
+            Some(token) if token.kind == kind => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+    fn expect(&mut self, kind: TokenKind, expected: &'static str) -> Result<(), QlError> {
+        let token = self.next(expected)?;
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err(Self::unexpected(&token, expected))
+        }
+    }
+    fn ident(&mut self, expected: &'static str) -> Result<String, QlError> {
+        let token = self.next(expected)?;
+        match token.kind {
+            TokenKind::Ident(ident) => Ok(ident),
+            _ => Err(Self::unexpected(&token, expected)),
+        }
+    }
+    /// Returns the (uppercased) keyword that starts a statement or a clause
+    fn keyword(&mut self, expected: &'static str) -> Result<(String, usize), QlError> {
+        let token = self.next(expected)?;
+        match token.kind {
+            TokenKind::Ident(ident) => Ok((ident.to_ascii_uppercase(), token.pos)),
+            _ => Err(Self::unexpected(&token, expected)),
+        }
+    }
+    /// Parse a complete statement, with an optional trailing `;`
+    pub fn parse_statement(mut self) -> Result<Statement, QlError> {
+        let (keyword, pos) = self.keyword("a statement")?;
+        let statement = match keyword.as_str() {
+            "CREATE" => self.create()?,
+            "DROP" => self.drop()?,
+            "ALTER" => self.alter()?,
+            "RENAME" => self.rename()?,
+            "CLONE" => {
+                self.expect_keyword("TABLE")?;
+                let entity = self.entity()?;
+                self.expect_keyword("TO")?;
+                Statement::CloneTable {
+                    entity,
+                    to: self.entity()?,
+                }
+            }
+            "USE" => Statement::Use(self.entity()?),
+            "INSPECT" => Statement::Inspect(self.inspect()?),
+            "ON" => {
+                let entity = self.entity()?;
+                let (keyword, pos) = self.keyword("a statement")?;
+                Statement::On(entity, Box::new(self.data_statement(keyword, pos)?))
+            }
+            _ => self.data_statement(keyword, pos)?,
+        };
+        self.finish()?;
+        Ok(statement)
+    }
+    /// Parse the rest of a statement that reads or writes keys, which can be run against
+    /// another table with `ON`
+    fn data_statement(&mut self, keyword: String, pos: usize) -> Result<Statement, QlError> {
+        let statement = match keyword.as_str() {
+            "GET" => Statement::Get(self.keys()?),
+            "SET" => Statement::Set(self.pairs()?),
+            "UPDATE" => Statement::Update(self.pairs()?),
+            "UPSERT" => Statement::Upsert(self.pairs()?),
+            "DELETE" => Statement::Delete(self.keys()?),
+            "EXISTS" => Statement::Exists(self.keys()?),
+            "POP" => Statement::Pop(self.keys()?),
+            "COPY" => {
+                let keys = self.keys()?;
+                self.expect_keyword("TO")?;
+                Statement::Copy {
+                    keys,
+                    to: self.entity()?,
+                }
+            }
+            "MOVE" => {
+                let keys = self.keys()?;
+                self.expect_keyword("TO")?;
+                Statement::Move {
+                    keys,
+                    to: self.entity()?,
+                }
+            }
+            _ => return Err(QlError::new(pos, QlErrorKind::UnknownStatement(keyword))),
+        };
+        Ok(statement)
+    }
+    /// Parse a complete model expression, like `keymap(str, binstr)`
+    pub fn parse_model(mut self) -> Result<ModelExpr, QlError> {
+        let model = self.ident("a model")?;
+        self.expect(TokenKind::LParen, "'('")?;
+        let mut args = Vec::new();
+        if !self.eat(TokenKind::RParen) {
+            loop {
+                args.push(self.ident("a type")?);
+                if self.eat(TokenKind::RParen) {
+                    break;
+                }
+                self.expect(TokenKind::Comma, "',' or ')'")?;
+            }
+        }
+        match self.peek() {
+            None => Ok(ModelExpr { model, args }),
+            Some(token) => Err(Self::unexpected(token, "the end of the expression")),
+        }
+    }
+    fn finish(&mut self) -> Result<(), QlError> {
+        self.eat(TokenKind::Semicolon);
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(Self::unexpected(token, "the end of the statement")),
+        }
+    }
+    fn create(&mut self) -> Result<Statement, QlError> {
+        let (keyword, pos) = self.keyword("KEYSPACE or TABLE")?;
+        match keyword.as_str() {
+            "KEYSPACE" => Ok(Statement::CreateKeyspace(self.ident("a keyspace name")?)),
+            "TABLE" => {
+                let entity = self.entity()?;
+                if self.eat_keyword("LIKE") {
+                    return Ok(Statement::CreateTableLike {
+                        entity,
+                        like: self.entity()?,
+                    });
+                }
+                self.expect(TokenKind::LParen, "'('")?;
+                let key = self.column()?;
+                self.expect(TokenKind::Comma, "','")?;
+                let value = self.column()?;
+                self.expect(TokenKind::RParen, "')'")?;
+                let mut volatile = false;
+                if self.eat_keyword("WITH") {
+                    loop {
+                        let (property, pos) = self.keyword("a property")?;
+                        match property.as_str() {
+                            "VOLATILE" => volatile = true,
+                            _ => {
+                                return Err(QlError::new(
+                                    pos,
+                                    QlErrorKind::UnknownProperty(property.to_ascii_lowercase()),
+                                ))
+                            }
+                        }
+                        if !self.eat(TokenKind::Comma) {
+                            break;
+                        }
+                    }
+                }
+                Ok(Statement::CreateTable {
+                    entity,
+                    key,
+                    value,
+                    volatile,
+                })
+            }
+            _ => Err(QlError::new(
+                pos,
+                QlErrorKind::UnexpectedToken {
+                    found: format!("'{}'", keyword),
+                    expected: "KEYSPACE or TABLE",
+                },
+            )),
+        }
+    }
+    fn drop(&mut self) -> Result<Statement, QlError> {
+        let (keyword, pos) = self.keyword("KEYSPACE or TABLE")?;
+        match keyword.as_str() {
+            "KEYSPACE" => {
+                let keyspace = self.ident("a keyspace name")?;
+                let force = self.eat_keyword("FORCE");
+                Ok(Statement::DropKeyspace { keyspace, force })
+            }
+            "TABLE" => Ok(Statement::DropTable(self.entity()?)),
+            _ => Err(QlError::new(
+                pos,
+                QlErrorKind::UnexpectedToken {
+                    found: format!("'{}'", keyword),
+                    expected: "KEYSPACE or TABLE",
+                },
+            )),
+        }
+    }
+    fn alter(&mut self) -> Result<Statement, QlError> {
+        self.expect_keyword("TABLE")?;
+        let entity = self.entity()?;
+        self.expect_keyword("WITH")?;
+        let (property, pos) = self.keyword("a property")?;
+        let volatile = match property.as_str() {
+            "VOLATILE" => true,
+            "PERSISTENT" => false,
+            _ => {
+                return Err(QlError::new(
+                    pos,
+                    QlErrorKind::UnknownProperty(property.to_ascii_lowercase()),
+                ))
+            }
+        };
+        Ok(Statement::AlterTable { entity, volatile })
+    }
+    fn rename(&mut self) -> Result<Statement, QlError> {
+        let (keyword, pos) = self.keyword("KEYSPACE or TABLE")?;
+        match keyword.as_str() {
+            "KEYSPACE" => {
+                let keyspace = self.ident("a keyspace name")?;
+                self.expect_keyword("TO")?;
+                let to = self.ident("a keyspace name")?;
+                Ok(Statement::RenameKeyspace { keyspace, to })
+            }
+            "TABLE" => {
+                let entity = self.entity()?;
+                self.expect_keyword("TO")?;
+                let to = self.ident("a table name")?;
+                Ok(Statement::RenameTable { entity, to })
+            }
+            _ => Err(QlError::new(
+                pos,
+                QlErrorKind::UnexpectedToken {
+                    found: format!("'{}'", keyword),
+                    expected: "KEYSPACE or TABLE",
+                },
+            )),
+        }
+    }
+    fn inspect(&mut self) -> Result<Inspect, QlError> {
+        let (keyword, pos) = self.keyword("KEYSPACES, KEYSPACE, TABLE or SERVER")?;
+        match keyword.as_str() {
+            "KEYSPACES" => Ok(Inspect::Keyspaces),
+            "KEYSPACE" => Ok(Inspect::Keyspace(self.ident("a keyspace name")?)),
+            "TABLE" => Ok(Inspect::Table(self.entity()?)),
+            "SERVER" => Ok(Inspect::Server),
+            _ => Err(QlError::new(
+                pos,
+                QlErrorKind::UnexpectedToken {
+                    found: format!("'{}'", keyword),
+                    expected: "KEYSPACES, KEYSPACE, TABLE or SERVER",
+                },
+            )),
+        }
+    }
+    fn entity(&mut self) -> Result<Entity, QlError> {
+        let first = self.ident("a keyspace or table name")?;
+        if self.eat(TokenKind::Dot) || self.eat(TokenKind::Colon) {
+            Ok(Entity::Full(first, self.ident("a table name")?))
+        } else {
+            Ok(Entity::Single(first))
+        }
+    }
+    fn column(&mut self) -> Result<Column, QlError> {
+        let name = self.ident("a column name")?;
+        let ty = self.ident("a type")?;
+        Ok(Column { name, ty })
+    }
+    fn literal(&mut self) -> Result<Vec<u8>, QlError> {
+        const EXPECTED: &str = "a string or an integer";
+        let token = self.next(EXPECTED)?;
+        match token.kind {
+            TokenKind::Str(string) => Ok(string),
+            TokenKind::Number(num) => Ok(num.into_bytes()),
+            _ => Err(Self::unexpected(&token, EXPECTED)),
+        }
+    }
+    /// Parse `<key> [, <key> ...]`
+    fn keys(&mut self) -> Result<Vec<Key>, QlError> {
+        let mut keys = vec![self.literal()?];
+        while self.eat(TokenKind::Comma) {
+            keys.push(self.literal()?);
+        }
+        Ok(keys)
+    }
+    /// Parse `<key> = <value> [, <key> = <value> ...]`
+    fn pairs(&mut self) -> Result<Vec<Pair>, QlError> {
+        let mut pairs = Vec::new();
+        loop {
+            let key = self.literal()?;
+            self.expect(TokenKind::Eq, "'='")?;
+            pairs.push((key, self.literal()?));
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        Ok(pairs)
+    }
+}
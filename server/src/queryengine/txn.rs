@@ -0,0 +1,523 @@
+/*
+ * Created on Mon Sep 13 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Transactions
+//!
+//! A connection can open a transaction with `MULTI`. Until the transaction is closed with
+//! `EXEC` (or `DISCARD`), every supported query is validated and queued instead of being run,
+//! bound to the table that was in use when it was queued. On `EXEC`, every shard that the
+//! queued queries (and any keys watched with `WATCH`) touch is write-locked, and the queue is
+//! run as a unit; no other writer can interleave with it. Writes are checked against the
+//! quotas of their keyspace when they're queued and again (under the locks) when they're run,
+//! in which case a write that would go over a limit gets a `quota-exceeded` response.
+//!
+//! Locks are always taken in the same order: the quota locks of keyspaces with limits by their
+//! address first, then tables by their address and shards by their index in the table
+//! (see [`Skymap::lock_shards_for`](crate::corestore::map::Skymap::lock_shards_for)),
+//! so two transactions touching overlapping keys will never deadlock.
+
+use crate::corestore::map::LockedShards;
+use crate::corestore::memstore::Keyspace;
+use crate::corestore::quota::{QuotaTracker, WriteSize};
+use crate::corestore::table::Table;
+use crate::corestore::Data;
+use crate::dbnet::connection::prelude::*;
+use crate::kvengine::KVEngine;
+use crate::kvengine::Versioned;
+use core::hash::BuildHasher;
+use core::mem;
+use core::ptr;
+use std::sync::Arc;
+
+const GET: &[u8] = "GET".as_bytes();
+const SET: &[u8] = "SET".as_bytes();
+const UPDATE: &[u8] = "UPDATE".as_bytes();
+const USET: &[u8] = "USET".as_bytes();
+const DEL: &[u8] = "DEL".as_bytes();
+const EXISTS: &[u8] = "EXISTS".as_bytes();
+const POP: &[u8] = "POP".as_bytes();
+const MULTI: &[u8] = "MULTI".as_bytes();
+const EXEC: &[u8] = "EXEC".as_bytes();
+const DISCARD: &[u8] = "DISCARD".as_bytes();
+const WATCH: &[u8] = "WATCH".as_bytes();
+const UNWATCH: &[u8] = "UNWATCH".as_bytes();
+const USE: &[u8] = "USE".as_bytes();
+
+/// The actions that can be queued in a transaction (or called from a script)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TxnOp {
+    Get,
+    Set,
+    Update,
+    Uset,
+    Del,
+    Exists,
+    Pop,
+}
+
+/// The response of a [`TxnOp`]
+#[derive(Debug, PartialEq)]
+pub(crate) enum Reply {
+    /// A response group, like [`groups::OKAY`]
+    Code(&'static [u8]),
+    /// A value, with its tsymbol
+    Mono(u8, Data),
+    Int(usize),
+}
+
+impl Reply {
+    /// Serialize the response into `out`
+    pub(crate) fn write_to(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Code(code) => out.extend_from_slice(code),
+            Self::Mono(tsymbol, val) => push_mono(out, *tsymbol, val),
+            Self::Int(int) => push_mono(out, b':', int.to_string().as_bytes()),
+        }
+    }
+}
+
+impl TxnOp {
+    pub(crate) fn from_action(action: &[u8]) -> Option<Self> {
+        let op = match action {
+            GET => Self::Get,
+            SET => Self::Set,
+            UPDATE => Self::Update,
+            USET => Self::Uset,
+            DEL => Self::Del,
+            EXISTS => Self::Exists,
+            POP => Self::Pop,
+            _ => return None,
+        };
+        Some(op)
+    }
+    /// Check if the action can be run with `len` arguments
+    pub(crate) const fn arity_okay(&self, len: usize) -> bool {
+        match self {
+            Self::Get | Self::Pop => len == 1,
+            Self::Set | Self::Update => len == 2,
+            Self::Uset => len != 0 && len & 1 == 0,
+            Self::Del | Self::Exists => len != 0,
+        }
+    }
+    /// Returns true if the action writes to the table (removing keys included)
+    pub(crate) const fn is_write(&self) -> bool {
+        !matches!(self, Self::Get | Self::Exists)
+    }
+    /// Returns true if the action can make the table grow, which is what quotas are checked
+    /// for
+    pub(crate) const fn grows_table(&self) -> bool {
+        matches!(self, Self::Set | Self::Update | Self::Uset)
+    }
+    /// Every `n`th argument of the action is a key
+    pub(crate) const fn key_step(&self) -> usize {
+        match self {
+            Self::Set | Self::Update | Self::Uset => 2,
+            _ => 1,
+        }
+    }
+    /// Returns the size of the write that the action (which has to be one that grows the
+    /// table) would do on the locked shards, taking the values that it'd replace into account
+    pub(crate) fn write_size<S>(
+        &self,
+        shards: &LockedShards<'_, Data, Versioned, S>,
+        args: &[Data],
+    ) -> WriteSize
+    where
+        S: BuildHasher + Clone,
+    {
+        args.chunks_exact(2).fold(WriteSize::default(), |size, kv| {
+            size.with_value(shards.get(&kv[0]).map(|older| older.len()), kv[1].len())
+        })
+    }
+    /// Run the action on the locked shards. All the keys in `args` should belong to
+    /// the locked shards
+    pub(crate) fn run<S>(
+        &self,
+        kve: &KVEngine,
+        shards: &mut LockedShards<'_, Data, Versioned, S>,
+        args: &[Data],
+        writable: bool,
+    ) -> Reply
+    where
+        S: BuildHasher + Clone,
+    {
+        let key_encoder = kve.get_key_encoder();
+        let encoder = kve.get_encoder();
+        let encoding_is_okay = match self {
+            Self::Set | Self::Update | Self::Uset => {
+                args.chunks_exact(2).all(|kv| encoder.is_ok(&kv[0], &kv[1]))
+            }
+            _ => args.iter().all(|k| key_encoder.is_ok(k)),
+        };
+        if self.is_write() && !writable {
+            return Reply::Code(groups::SERVER_ERR);
+        }
+        if !encoding_is_okay {
+            return Reply::Code(groups::ENCODING_ERROR);
+        }
+        match self {
+            Self::Get => match shards.get(&args[0]) {
+                Some(val) => Reply::Mono(kve.get_vt(), val.data().clone()),
+                None => Reply::Code(groups::NIL),
+            },
+            Self::Set => {
+                if shards.get(&args[0]).is_some() {
+                    Reply::Code(groups::OVERWRITE_ERR)
+                } else {
+                    kve.account(args[1].len(), 0);
+                    shards.insert(args[0].clone(), kve.versioned(args[1].clone()));
+                    kve.touch();
+                    Reply::Code(groups::OKAY)
+                }
+            }
+            Self::Update => {
+                if let Some(older) = shards.get(&args[0]).map(|older| older.len()) {
+                    kve.account(args[1].len(), older);
+                    shards.insert(args[0].clone(), kve.versioned(args[1].clone()));
+                    kve.touch();
+                    Reply::Code(groups::OKAY)
+                } else {
+                    Reply::Code(groups::NIL)
+                }
+            }
+            Self::Uset => {
+                args.chunks_exact(2).for_each(|kv| {
+                    let older = shards.get(&kv[0]).map_or(0, |older| older.len());
+                    kve.account(kv[1].len(), older);
+                    shards.insert(kv[0].clone(), kve.versioned(kv[1].clone()));
+                });
+                kve.touch();
+                Reply::Int(args.len() / 2)
+            }
+            Self::Del => {
+                let done_howmany = args
+                    .iter()
+                    .filter(|key| match shards.remove(*key) {
+                        Some((_, older)) => {
+                            kve.account(0, older.len());
+                            true
+                        }
+                        None => false,
+                    })
+                    .count();
+                if done_howmany != 0 {
+                    kve.touch();
+                }
+                Reply::Int(done_howmany)
+            }
+            Self::Exists => {
+                let how_many_of_them_exist =
+                    args.iter().filter(|key| shards.get(*key).is_some()).count();
+                Reply::Int(how_many_of_them_exist)
+            }
+            Self::Pop => match shards.remove(&args[0]) {
+                Some((_key, val)) => {
+                    kve.account(0, val.len());
+                    kve.touch();
+                    Reply::Mono(kve.get_vt(), val.into_data())
+                }
+                None => Reply::Code(groups::NIL),
+            },
+        }
+    }
+}
+
+/// A queued action, bound to the table it was queued against
+struct QueuedAction {
+    op: TxnOp,
+    table: Arc<Table>,
+    /// the keyspace of the table, whose quotas the action is checked against
+    keyspace: Option<Arc<Keyspace>>,
+    args: Vec<Data>,
+}
+
+impl QueuedAction {
+    fn keys(&self) -> impl Iterator<Item = &Data> {
+        self.args.iter().step_by(self.op.key_step())
+    }
+}
+
+/// A key watched with `WATCH`, along with the version it had at the time
+struct WatchedKey {
+    table: Arc<Table>,
+    key: Data,
+    version: Option<u64>,
+}
+
+/// The transaction state of a connection
+#[derive(Default)]
+pub struct Transaction {
+    /// whether `MULTI` was run
+    active: bool,
+    /// whether a query failed to queue, in which case `EXEC` will refuse to run
+    aborted: bool,
+    queue: Vec<QueuedAction>,
+    watched: Vec<WatchedKey>,
+}
+
+impl Transaction {
+    /// Returns true if a transaction is open
+    pub const fn is_active(&self) -> bool {
+        self.active
+    }
+    /// Doom the open transaction (if any), so that `EXEC` refuses to run it
+    pub fn abort(&mut self) {
+        if self.active {
+            self.aborted = true;
+        }
+    }
+    /// Close the transaction (if any) and forget all watched keys, returning the older state
+    fn reset(&mut self) -> Self {
+        mem::take(self)
+    }
+    /// Run the queued actions as a unit, returning the array of responses or `None` if any of
+    /// the watched keys were changed since they were watched
+    fn execute(self) -> Option<Vec<u8>> {
+        let Self { queue, watched, .. } = self;
+        let writable = registry::state_okay();
+        // the usage can't be looked up once the shards are locked, and the quota locks are
+        // taken in the order of the addresses of the keyspaces, just like the tables below
+        let mut keyspaces: Vec<&Keyspace> = queue
+            .iter()
+            .filter_map(|action| action.keyspace.as_deref())
+            .collect();
+        keyspaces.sort_unstable_by_key(|keyspace| *keyspace as *const Keyspace);
+        keyspaces.dedup_by(|a, b| ptr::eq(*a, *b));
+        let mut quotas: Vec<(&Keyspace, QuotaTracker<'_>)> = keyspaces
+            .into_iter()
+            .filter_map(|keyspace| Some((keyspace, keyspace.quota_tracker()?)))
+            .collect();
+        // lock the tables in the order of their addresses; never in the order of the queue
+        let mut tables: Vec<&Arc<Table>> = queue
+            .iter()
+            .map(|action| &action.table)
+            .chain(watched.iter().map(|watched| &watched.table))
+            .collect();
+        tables.sort_unstable_by_key(|table| Arc::as_ptr(table));
+        tables.dedup_by_key(|table| Arc::as_ptr(*table));
+        let mut locks: Vec<_> = tables
+            .iter()
+            .map(|table| {
+                let keys = queue
+                    .iter()
+                    .filter(|action| Arc::ptr_eq(&action.table, table))
+                    .flat_map(QueuedAction::keys)
+                    .chain(
+                        watched
+                            .iter()
+                            .filter(|watched| Arc::ptr_eq(&watched.table, table))
+                            .map(|watched| &watched.key),
+                    );
+                let kve = kvstore(table);
+                (kve, kve.__get_inner_ref().inner.lock_shards_for(keys))
+            })
+            .collect();
+        let position = |table: &Arc<Table>| {
+            tables
+                .binary_search_by_key(&Arc::as_ptr(table), |table| Arc::as_ptr(table))
+                .unwrap_or_else(|_| unreachable!("table of a queued action wasn't locked"))
+        };
+        let conflict = watched.iter().any(|watched| {
+            let (_, shards) = &locks[position(&watched.table)];
+            shards.get(&watched.key).map(Versioned::version) != watched.version
+        });
+        if conflict {
+            return None;
+        }
+        let mut out = Vec::new();
+        out.push(b'&');
+        out.extend_from_slice(queue.len().to_string().as_bytes());
+        out.push(b'\n');
+        for action in queue.iter() {
+            let (kve, shards) = &mut locks[position(&action.table)];
+            let quota = action.keyspace.as_deref().and_then(|keyspace| {
+                quotas
+                    .iter_mut()
+                    .find(|(ks, _)| ptr::eq(*ks, keyspace))
+                    .map(|(_, quota)| quota)
+            });
+            let allowed = match quota {
+                Some(quota) if action.op.grows_table() => {
+                    quota.allow(action.op.write_size(shards, &action.args))
+                }
+                _ => true,
+            };
+            let reply = if allowed {
+                action.op.run(kve, shards, &action.args, writable)
+            } else {
+                Reply::Code(groups::QUOTA_EXCEEDED)
+            };
+            reply.write_to(&mut out);
+        }
+        Some(out)
+    }
+}
+
+/// Returns the key/value store of a table that has already been checked to be one
+fn kvstore(table: &Table) -> &KVEngine {
+    match table.get_kvstore() {
+        Ok(kve) => kve,
+        Err(_) => unreachable!("only key/value tables are bound to transactions"),
+    }
+}
+
+fn push_mono(out: &mut Vec<u8>, tsymbol: u8, payload: &[u8]) {
+    out.push(tsymbol);
+    out.extend_from_slice(payload.len().to_string().as_bytes());
+    out.push(b'\n');
+    out.extend_from_slice(payload);
+    out.push(b'\n');
+}
+
+/// If a transaction is open on this connection, validate and queue the query instead of
+/// running it. Returns true if the query was consumed (and a response was written)
+pub async fn queue_query<'a, T, Strm>(
+    handle: &Corestore,
+    con: &'a mut T,
+    mut act: ActionIter<'a>,
+) -> std::io::Result<bool>
+where
+    T: 'a + ProtocolConnectionExt<Strm>,
+    Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
+{
+    if !con.get_txn().is_active() {
+        return Ok(false);
+    }
+    let action = match act.next_uppercase() {
+        Some(action) => action,
+        None => return Ok(false),
+    };
+    let op = match action.as_ref() {
+        MULTI | EXEC | DISCARD | WATCH | UNWATCH | USE => return Ok(false),
+        action => TxnOp::from_action(action),
+    };
+    let resp = match (op, handle.get_ctable()) {
+        (None, _) => groups::UNSUPPORTED_IN_TRANSACTION,
+        (Some(op), _) if !op.arity_okay(act.len()) => groups::ACTION_ERR,
+        (Some(_), None) => groups::DEFAULT_UNSET,
+        (Some(_), Some(table)) if table.get_kvstore().is_err() => groups::WRONG_MODEL,
+        (Some(op), Some(table))
+            if op.grows_table()
+                && !handle.quota_allows(|| kvstore(&table).write_size(act.clone())) =>
+        {
+            groups::QUOTA_EXCEEDED
+        }
+        (Some(op), Some(table)) => {
+            con.get_txn_mut().queue.push(QueuedAction {
+                op,
+                table,
+                keyspace: handle.get_ctable_keyspace(),
+                args: act.map(Data::copy_from_slice).collect(),
+            });
+            groups::QUEUED
+        }
+    };
+    if resp != groups::QUEUED {
+        // a query that can't be queued dooms the whole transaction
+        con.get_txn_mut().abort();
+    }
+    conwrite!(con, resp)?;
+    Ok(true)
+}
+
+action! {
+    /// Run a `MULTI` query: open a transaction on this connection
+    fn multi(_handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 0);
+        if con.get_txn().is_active() {
+            conwrite!(con, groups::NESTED_TRANSACTION)
+        } else {
+            con.get_txn_mut().active = true;
+            conwrite!(con, groups::OKAY)
+        }
+    }
+}
+
+action! {
+    /// Run an `EXEC` query: run all the queued queries as a unit and return an array with
+    /// the response of each one
+    fn exec(_handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 0);
+        if !con.get_txn().is_active() {
+            return conwrite!(con, groups::NO_TRANSACTION);
+        }
+        let txn = con.get_txn_mut().reset();
+        if txn.aborted {
+            return conwrite!(con, groups::TXN_ABORTED);
+        }
+        match txn.execute() {
+            Some(responses) => conwrite!(con, responses),
+            None => conwrite!(con, groups::TXN_CONFLICT),
+        }
+    }
+}
+
+action! {
+    /// Run a `DISCARD` query: throw away all the queued queries and close the transaction
+    fn discard(_handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 0);
+        if con.get_txn().is_active() {
+            con.get_txn_mut().reset();
+            conwrite!(con, groups::OKAY)
+        } else {
+            conwrite!(con, groups::NO_TRANSACTION)
+        }
+    }
+}
+
+action! {
+    /// Run a `WATCH` query: the next transaction will fail to `EXEC` if any of the keys
+    /// (in the current table) are changed before then
+    fn watch(handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        err_if_len_is!(act, con, eq 0);
+        if con.get_txn().is_active() {
+            return conwrite!(con, groups::WATCH_IN_TRANSACTION);
+        }
+        let kve = kve!(con, handle);
+        let table = match handle.get_ctable() {
+            Some(table) => table,
+            None => return conwrite!(con, groups::DEFAULT_UNSET),
+        };
+        let watched: Vec<WatchedKey> = act
+            .map(|key| WatchedKey {
+                table: table.clone(),
+                key: Data::copy_from_slice(key),
+                version: kve.get_version(key),
+            })
+            .collect();
+        con.get_txn_mut().watched.extend(watched);
+        conwrite!(con, groups::OKAY)
+    }
+}
+
+action! {
+    /// Run an `UNWATCH` query: forget all the watched keys
+    fn unwatch(_handle: &Corestore, con: &'a mut T, act: ActionIter<'a>) {
+        err_if_len_is!(act, con, not 0);
+        con.get_txn_mut().watched.clear();
+        conwrite!(con, groups::OKAY)
+    }
+}
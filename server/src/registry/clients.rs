@@ -0,0 +1,218 @@
+/*
+ * Created on Thu Sep 09 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The client registry
+//!
+//! Every live connection is tracked here with a [`ClientInfo`] object. A client is registered
+//! when its [`ConnectionHandler`](crate::dbnet::connection::ConnectionHandler) is created and
+//! is removed when the handler is dropped. Each client also has its own kill switch which
+//! a connection handler listens to with a [`Terminator`](crate::dbnet::Terminator)
+
+use crate::corestore::entity_name;
+use crate::corestore::htable::Coremap;
+use crate::corestore::lazy::Lazy;
+use crate::corestore::memstore::DEFAULT;
+use crate::corestore::OwnedEntityGroup;
+use crate::registry::stats;
+use core::fmt;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use parking_lot::Mutex;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+const ORD_RLX: Ordering = Ordering::Relaxed;
+
+/// The ID of the next client
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// All the live clients
+static CLIENTS: Lazy<ClientMap, fn() -> ClientMap> = Lazy::new(Coremap::new);
+
+type ClientMap = Coremap<u64, Arc<ClientInfo>>;
+
+/// Information about a connected client
+pub struct ClientInfo {
+    /// a unique ID for this client
+    id: u64,
+    /// the address of the remote socket
+    peer: SocketAddr,
+    /// whether the client is connected over TLS
+    tls: bool,
+    /// the subject of the verified TLS client certificate, if one was presented
+    cert_subject: Option<String>,
+    /// the UNIX timestamp at which the client connected
+    connected_at: u64,
+    /// the name set by the client
+    name: Mutex<Option<String>>,
+    /// the entity the client is connected to
+    entity: Mutex<Arc<OwnedEntityGroup>>,
+    /// the last action run by the client
+    last_action: Mutex<Option<&'static str>>,
+    /// the UNIX timestamp of the last action
+    last_action_at: AtomicU64,
+    /// the bytes read from this client
+    bytes_in: AtomicU64,
+    /// the bytes written to this client
+    bytes_out: AtomicU64,
+    /// the kill switch
+    kill: broadcast::Sender<()>,
+}
+
+impl ClientInfo {
+    /// Create a new `ClientInfo` object for a fresh connection. This doesn't register
+    /// the client
+    pub fn new(peer: SocketAddr, tls: bool, cert_subject: Option<String>) -> Arc<Self> {
+        let (kill, _) = broadcast::channel(1);
+        let now = stats::unix_now();
+        Arc::new(Self {
+            id: NEXT_ID.fetch_add(1, ORD_RLX),
+            peer,
+            tls,
+            cert_subject,
+            connected_at: now,
+            name: Mutex::new(None),
+            entity: Mutex::new(Arc::new((Some(DEFAULT), Some(DEFAULT)))),
+            last_action: Mutex::new(None),
+            last_action_at: AtomicU64::new(now),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            kill,
+        })
+    }
+    pub const fn id(&self) -> u64 {
+        self.id
+    }
+    pub const fn peer(&self) -> SocketAddr {
+        self.peer
+    }
+    /// Returns the subject of the verified TLS client certificate (if any)
+    pub fn cert_subject(&self) -> Option<&str> {
+        self.cert_subject.as_deref()
+    }
+    pub fn set_name(&self, name: String) {
+        *self.name.lock() = Some(name);
+    }
+    pub fn set_entity(&self, entity: Arc<OwnedEntityGroup>) {
+        *self.entity.lock() = entity;
+    }
+    /// Record that the client ran `action`
+    pub fn action_run(&self, action: &'static str) {
+        *self.last_action.lock() = Some(action);
+        self.last_action_at.store(stats::unix_now(), ORD_RLX);
+    }
+    pub fn add_bytes_in(&self, count: usize) {
+        self.bytes_in.fetch_add(count as u64, ORD_RLX);
+    }
+    pub fn add_bytes_out(&self, count: usize) {
+        self.bytes_out.fetch_add(count as u64, ORD_RLX);
+    }
+    /// Returns a receiver for this client's kill switch
+    pub fn subscribe_kill(&self) -> broadcast::Receiver<()> {
+        self.kill.subscribe()
+    }
+}
+
+impl fmt::Display for ClientInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let now = stats::unix_now();
+        write!(
+            f,
+            "id={} addr={} tls={} name={} entity={} age={} idle={} lastcmd={} bytes_in={} bytes_out={} subject={}",
+            self.id,
+            self.peer,
+            self.tls,
+            self.name.lock().as_deref().unwrap_or(""),
+            entity_name(&self.entity.lock()),
+            now.saturating_sub(self.connected_at),
+            now.saturating_sub(self.last_action_at.load(ORD_RLX)),
+            self.last_action.lock().unwrap_or(""),
+            self.bytes_in.load(ORD_RLX),
+            self.bytes_out.load(ORD_RLX),
+            self.cert_subject().unwrap_or("")
+        )
+    }
+}
+
+/// Add a client to the registry
+pub fn register(client: Arc<ClientInfo>) {
+    let _ = CLIENTS.true_if_insert(client.id, client);
+}
+
+/// Remove a client from the registry
+pub fn deregister(id: u64) {
+    let _ = CLIENTS.true_if_removed(&id);
+}
+
+/// Returns all the live clients, ordered by their IDs
+pub fn list() -> Vec<Arc<ClientInfo>> {
+    let mut clients: Vec<Arc<ClientInfo>> = CLIENTS
+        .iter()
+        .map(|client| client.value().clone())
+        .collect();
+    clients.sort_unstable_by_key(|client| client.id);
+    clients
+}
+
+/// Signal the client with the given `id` to disconnect. Returns false if no such client
+/// exists
+pub fn kill(id: u64) -> bool {
+    match CLIENTS.get(&id) {
+        Some(client) => {
+            // the receiver may have already gone away if the connection is closing
+            let _ = client.value().kill.send(());
+            true
+        }
+        None => false,
+    }
+}
+
+#[test]
+fn test_client_registry() {
+    let peer: SocketAddr = "127.0.0.1:2003".parse().unwrap();
+    let client = ClientInfo::new(peer, false, None);
+    let id = client.id();
+    let mut kill_switch = client.subscribe_kill();
+    register(client.clone());
+    client.set_name("cyan".to_owned());
+    client.action_run("GET");
+    client.add_bytes_in(10);
+    let listing = list()
+        .into_iter()
+        .find(|client| client.id() == id)
+        .unwrap()
+        .to_string();
+    assert!(listing.starts_with(&format!(
+        "id={} addr=127.0.0.1:2003 tls=false name=cyan",
+        id
+    )));
+    assert!(listing.contains("entity=default:default"));
+    assert!(listing.contains("lastcmd=GET bytes_in=10 bytes_out=0"));
+    assert!(kill(id));
+    assert!(kill_switch.try_recv().is_ok());
+    deregister(id);
+    assert!(!kill(id));
+}
@@ -33,6 +33,10 @@ use crate::corestore::lock::{QLGuard, QuickLock};
 use core::sync::atomic::AtomicBool;
 use core::sync::atomic::Ordering;
 
+pub mod clients;
+pub mod slowlog;
+pub mod stats;
+
 const ORD_ACQ: Ordering = Ordering::Acquire;
 const ORD_REL: Ordering = Ordering::Release;
 const ORD_SEQ: Ordering = Ordering::SeqCst;
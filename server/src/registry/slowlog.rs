@@ -0,0 +1,207 @@
+/*
+ * Created on Wed Sep 08 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The slow query log
+//!
+//! Every action dispatched by the query engine is timed and if it takes longer than the
+//! configured threshold, an entry is recorded in a bounded in-memory ring. Once the ring
+//! is full, the oldest entries are evicted. The log is read with the `SLOWLOG` action
+
+use crate::config::SlowlogConfig;
+use crate::corestore::lazy::Lazy;
+use crate::registry::stats;
+use core::fmt;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+const ORD_RLX: Ordering = Ordering::Relaxed;
+/// The maximum number of arguments (excluding the action) recorded for an entry
+pub const MAX_ARGS: usize = 16;
+/// The maximum number of bytes recorded for a single argument
+pub const MAX_ARG_LEN: usize = 128;
+
+/// The threshold in microseconds
+static THRESHOLD: AtomicU64 = AtomicU64::new(SlowlogConfig::default().threshold);
+/// The maximum number of entries
+static MAXLEN: AtomicUsize = AtomicUsize::new(SlowlogConfig::default().maxlen);
+/// Whether entries should also be sent to the server log
+static LOG: AtomicBool = AtomicBool::new(SlowlogConfig::default().log);
+/// The ring of entries
+type SlowlogRing = Mutex<VecDeque<SlowlogEntry>>;
+
+/// The ID of the next entry
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+/// The entries, with the most recent entry at the front
+static ENTRIES: Lazy<SlowlogRing, fn() -> SlowlogRing> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// A slowlog entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowlogEntry {
+    /// a unique, increasing ID
+    pub id: u64,
+    /// the UNIX timestamp at which the entry was recorded
+    pub timestamp: u64,
+    /// how long the action took, in microseconds
+    pub duration: u64,
+    /// the action
+    pub action: &'static str,
+    /// the (possibly truncated) arguments
+    pub args: Vec<String>,
+    /// the address of the client
+    pub client: SocketAddr,
+    /// the entity that the client was connected to
+    pub entity: String,
+}
+
+impl fmt::Display for SlowlogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "id={} time={} duration={}us action={} entity={} client={} args={:?}",
+            self.id,
+            self.timestamp,
+            self.duration,
+            self.action,
+            self.entity,
+            self.client,
+            self.args
+        )
+    }
+}
+
+/// Apply the slowlog configuration
+pub fn init(cfg: &SlowlogConfig) {
+    THRESHOLD.store(cfg.threshold, ORD_RLX);
+    MAXLEN.store(cfg.maxlen, ORD_RLX);
+    LOG.store(cfg.log, ORD_RLX);
+}
+
+/// Returns true if an action that took `duration` should be recorded
+pub fn is_slow(duration: Duration) -> bool {
+    MAXLEN.load(ORD_RLX) != 0 && duration.as_micros() as u64 >= THRESHOLD.load(ORD_RLX)
+}
+
+/// Record an entry. The caller should check [`is_slow`] first
+pub fn record<'a>(
+    action: &'static str,
+    duration: Duration,
+    args: impl ExactSizeIterator<Item = &'a [u8]>,
+    client: SocketAddr,
+    entity: String,
+) {
+    let argc = args.len();
+    let mut recorded_args: Vec<String> = args
+        .take(MAX_ARGS)
+        .map(|arg| {
+            if arg.len() > MAX_ARG_LEN {
+                format!(
+                    "{}... ({} more bytes)",
+                    String::from_utf8_lossy(&arg[..MAX_ARG_LEN]),
+                    arg.len() - MAX_ARG_LEN
+                )
+            } else {
+                String::from_utf8_lossy(arg).into_owned()
+            }
+        })
+        .collect();
+    if argc > MAX_ARGS {
+        recorded_args.push(format!("... ({} more arguments)", argc - MAX_ARGS));
+    }
+    let entry = SlowlogEntry {
+        id: NEXT_ID.fetch_add(1, ORD_RLX),
+        timestamp: stats::unix_now(),
+        duration: duration.as_micros() as u64,
+        action,
+        args: recorded_args,
+        client,
+        entity,
+    };
+    if LOG.load(ORD_RLX) {
+        log::warn!("Slow query: {}", entry);
+    }
+    let maxlen = MAXLEN.load(ORD_RLX);
+    let mut entries = ENTRIES.lock();
+    entries.push_front(entry);
+    entries.truncate(maxlen);
+}
+
+/// Returns (at most) `count` of the most recent entries, newest first
+pub fn get(count: usize) -> Vec<SlowlogEntry> {
+    ENTRIES.lock().iter().take(count).cloned().collect()
+}
+
+/// Returns the number of entries in the slowlog
+pub fn len() -> usize {
+    ENTRIES.lock().len()
+}
+
+/// Remove all the entries from the slowlog
+pub fn reset() {
+    ENTRIES.lock().clear()
+}
+
+#[test]
+fn test_slowlog_record() {
+    let client: SocketAddr = "127.0.0.1:2003".parse().unwrap();
+    let args: Vec<&[u8]> = vec![b"x", b"y"];
+    reset();
+    record(
+        "SET",
+        Duration::from_millis(20),
+        args.into_iter(),
+        client,
+        "default:default".to_owned(),
+    );
+    let bigarg = vec![b'a'; MAX_ARG_LEN + 10];
+    let args: Vec<&[u8]> = (0..MAX_ARGS + 2).map(|_| bigarg.as_slice()).collect();
+    record(
+        "MSET",
+        Duration::from_millis(30),
+        args.into_iter(),
+        client,
+        "default".to_owned(),
+    );
+    let entries = get(10);
+    assert_eq!(entries.len(), 2);
+    // newest first
+    assert_eq!(entries[0].action, "MSET");
+    assert_eq!(entries[0].args.len(), MAX_ARGS + 1);
+    assert!(entries[0].args[0].ends_with("... (10 more bytes)"));
+    assert_eq!(entries[0].args[MAX_ARGS], "... (2 more arguments)");
+    assert_eq!(entries[1].action, "SET");
+    assert_eq!(entries[1].args, vec!["x".to_owned(), "y".to_owned()]);
+    assert_eq!(entries[1].duration, 20_000);
+    assert!(entries[0].id > entries[1].id);
+    assert_eq!(get(1).len(), 1);
+    reset();
+    assert_eq!(len(), 0);
+}
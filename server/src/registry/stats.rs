@@ -0,0 +1,339 @@
+/*
+ * Created on Mon Sep 06 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Server statistics
+//!
+//! This module holds global counters that are collected by the networking layer and
+//! the background services and are reported by `SYS INFO` and the metrics exporter. All
+//! counters are _relaxed_ atomics since we only care about eventual consistency for these numbers
+
+use crate::corestore::htable::Coremap;
+use crate::corestore::lazy::Lazy;
+use chrono::prelude::Utc;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
+use std::time::Duration;
+
+const ORD_RLX: Ordering = Ordering::Relaxed;
+
+/// Upper bounds (in microseconds) of the buckets used for action latencies
+pub const ACTION_LATENCY_BUCKETS: [u64; 12] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 1_000_000,
+];
+/// Upper bounds (in microseconds) of the buckets used for BGSAVE and snapshot durations
+pub const PERSIST_LATENCY_BUCKETS: [u64; 7] = [
+    1_000,
+    10_000,
+    100_000,
+    1_000_000,
+    5_000_000,
+    30_000_000,
+    120_000_000,
+];
+
+/// A fixed bucket histogram of durations
+pub struct Histogram<const N: usize> {
+    /// the upper bounds (in microseconds) of each bucket
+    bounds: [u64; N],
+    /// the number of observations in each bucket (not cumulative)
+    buckets: [AtomicU64; N],
+    /// the total number of observations
+    count: AtomicU64,
+    /// the sum of all observations, in microseconds
+    sum: AtomicU64,
+}
+
+impl<const N: usize> Histogram<N> {
+    pub const fn new(bounds: [u64; N]) -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        Self {
+            bounds,
+            buckets: [ZERO; N],
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+        }
+    }
+    /// Record an observation
+    pub fn observe(&self, duration: Duration) {
+        let micros = duration.as_micros() as u64;
+        if let Some(pos) = self.bounds.iter().position(|bound| micros <= *bound) {
+            self.buckets[pos].fetch_add(1, ORD_RLX);
+        }
+        self.count.fetch_add(1, ORD_RLX);
+        self.sum.fetch_add(micros, ORD_RLX);
+    }
+    /// Returns `(upper bound in microseconds, cumulative count)` for every bucket
+    pub fn cumulative_buckets(&self) -> Vec<(u64, u64)> {
+        let mut cumulative = 0;
+        self.bounds
+            .iter()
+            .zip(self.buckets.iter())
+            .map(|(bound, bucket)| {
+                cumulative += bucket.load(ORD_RLX);
+                (*bound, cumulative)
+            })
+            .collect()
+    }
+    /// Returns the total number of observations
+    pub fn count(&self) -> u64 {
+        self.count.load(ORD_RLX)
+    }
+    /// Returns the sum of all observations in microseconds
+    pub fn sum_micros(&self) -> u64 {
+        self.sum.load(ORD_RLX)
+    }
+}
+
+pub type ActionHistogram = Histogram<12>;
+pub type PersistHistogram = Histogram<7>;
+/// Latency histograms keyed by the action name
+pub type ActionLatencies = Coremap<&'static str, ActionHistogram>;
+
+/// Latency histograms for every action that has been run at least once
+static ACTION_LATENCIES: Lazy<ActionLatencies, fn() -> ActionLatencies> = Lazy::new(Coremap::new);
+/// BGSAVE durations
+static BGSAVE_LATENCY: PersistHistogram = Histogram::new(PERSIST_LATENCY_BUCKETS);
+/// Snapshot durations
+static SNAPSHOT_LATENCY: PersistHistogram = Histogram::new(PERSIST_LATENCY_BUCKETS);
+
+/// The time (UNIX timestamp in seconds) at which the server was started
+static START_TIME: AtomicU64 = AtomicU64::new(0);
+/// The maximum number of clients that can be connected at once
+static MAX_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+/// The number of currently connected clients
+static ACTIVE_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+/// The total number of connections accepted since startup
+static TOTAL_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+/// The total number of queries processed since startup
+static COMMANDS_PROCESSED: AtomicU64 = AtomicU64::new(0);
+/// The total number of bytes read from clients
+static BYTES_IN: AtomicU64 = AtomicU64::new(0);
+/// The total number of bytes written to clients
+static BYTES_OUT: AtomicU64 = AtomicU64::new(0);
+/// The outcome of the last BGSAVE
+static BGSAVE_LAST_OKAY: AtomicBool = AtomicBool::new(true);
+/// The time (UNIX timestamp in seconds) of the last BGSAVE. `0` if it never ran
+static BGSAVE_LAST_TIME: AtomicU64 = AtomicU64::new(0);
+/// The total number of BGSAVE runs
+static BGSAVE_RUNS: AtomicU64 = AtomicU64::new(0);
+/// The total number of failed BGSAVE runs
+static BGSAVE_FAILURES: AtomicU64 = AtomicU64::new(0);
+/// The total number of snapshots created
+static SNAPSHOTS_CREATED: AtomicU64 = AtomicU64::new(0);
+/// The total number of snapshots that failed
+static SNAPSHOTS_FAILED: AtomicU64 = AtomicU64::new(0);
+/// The time (UNIX timestamp in seconds) of the last successful snapshot. `0` if none
+static SNAPSHOT_LAST_TIME: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the current UNIX timestamp in seconds
+pub fn unix_now() -> u64 {
+    Utc::now().timestamp() as u64
+}
+
+/// Returns the current UNIX timestamp in milliseconds
+pub fn unix_now_millis() -> u64 {
+    Utc::now().timestamp_millis() as u64
+}
+
+/// Record the server startup time and the connection limit
+pub fn init(maxcon: usize) {
+    START_TIME.store(unix_now(), ORD_RLX);
+    MAX_CONNECTIONS.store(maxcon, ORD_RLX);
+}
+
+/// Record a change in the connection limit
+pub fn set_max_connections(maxcon: usize) {
+    MAX_CONNECTIONS.store(maxcon, ORD_RLX);
+}
+
+/// Returns the uptime in seconds
+pub fn uptime() -> u64 {
+    unix_now().saturating_sub(START_TIME.load(ORD_RLX))
+}
+
+/// Returns the startup time as a UNIX timestamp
+pub fn start_time() -> u64 {
+    START_TIME.load(ORD_RLX)
+}
+
+/// Returns the configured connection limit
+pub fn max_connections() -> usize {
+    MAX_CONNECTIONS.load(ORD_RLX)
+}
+
+/// Record a new connection
+pub fn connection_opened() {
+    ACTIVE_CONNECTIONS.fetch_add(1, ORD_RLX);
+    TOTAL_CONNECTIONS.fetch_add(1, ORD_RLX);
+}
+
+/// Record a closed connection
+pub fn connection_closed() {
+    ACTIVE_CONNECTIONS.fetch_sub(1, ORD_RLX);
+}
+
+/// Returns the number of currently connected clients
+pub fn active_connections() -> usize {
+    ACTIVE_CONNECTIONS.load(ORD_RLX)
+}
+
+/// Returns the total number of connections accepted since startup
+pub fn total_connections() -> u64 {
+    TOTAL_CONNECTIONS.load(ORD_RLX)
+}
+
+/// Record a processed query
+pub fn command_processed() {
+    COMMANDS_PROCESSED.fetch_add(1, ORD_RLX);
+}
+
+/// Returns the number of queries processed since startup
+pub fn commands_processed() -> u64 {
+    COMMANDS_PROCESSED.load(ORD_RLX)
+}
+
+/// Record `count` bytes read from a client
+pub fn add_bytes_in(count: usize) {
+    BYTES_IN.fetch_add(count as u64, ORD_RLX);
+}
+
+/// Record `count` bytes written to a client
+pub fn add_bytes_out(count: usize) {
+    BYTES_OUT.fetch_add(count as u64, ORD_RLX);
+}
+
+/// Returns the total number of bytes read from clients
+pub fn bytes_in() -> u64 {
+    BYTES_IN.load(ORD_RLX)
+}
+
+/// Returns the total number of bytes written to clients
+pub fn bytes_out() -> u64 {
+    BYTES_OUT.load(ORD_RLX)
+}
+
+/// Record the latency of an action
+pub fn observe_action(action: &'static str, duration: Duration) {
+    match ACTION_LATENCIES.get(action) {
+        Some(histogram) => histogram.observe(duration),
+        None => {
+            let _ = ACTION_LATENCIES.true_if_insert(action, Histogram::new(ACTION_LATENCY_BUCKETS));
+            if let Some(histogram) = ACTION_LATENCIES.get(action) {
+                histogram.observe(duration);
+            }
+        }
+    }
+}
+
+/// Returns a static reference to the action latency histograms
+pub fn action_latencies() -> &'static ActionLatencies {
+    &ACTION_LATENCIES
+}
+
+/// Record the outcome of a BGSAVE
+pub fn bgsave_finished(okay: bool, duration: Duration) {
+    BGSAVE_LATENCY.observe(duration);
+    BGSAVE_RUNS.fetch_add(1, ORD_RLX);
+    if !okay {
+        BGSAVE_FAILURES.fetch_add(1, ORD_RLX);
+    }
+    BGSAVE_LAST_OKAY.store(okay, ORD_RLX);
+    BGSAVE_LAST_TIME.store(unix_now(), ORD_RLX);
+}
+
+/// Returns true if the last BGSAVE succeeded (or if it never ran)
+pub fn bgsave_last_okay() -> bool {
+    BGSAVE_LAST_OKAY.load(ORD_RLX)
+}
+
+/// Returns the time of the last BGSAVE, or `0` if it never ran
+pub fn bgsave_last_time() -> u64 {
+    BGSAVE_LAST_TIME.load(ORD_RLX)
+}
+
+/// Returns the total number of BGSAVE runs
+pub fn bgsave_runs() -> u64 {
+    BGSAVE_RUNS.load(ORD_RLX)
+}
+
+/// Returns the total number of failed BGSAVE runs
+pub fn bgsave_failures() -> u64 {
+    BGSAVE_FAILURES.load(ORD_RLX)
+}
+
+/// Returns a static reference to the BGSAVE duration histogram
+pub fn bgsave_latency() -> &'static PersistHistogram {
+    &BGSAVE_LATENCY
+}
+
+/// Record the outcome of a local snapshot
+pub fn snapshot_finished(okay: bool, duration: Duration) {
+    SNAPSHOT_LATENCY.observe(duration);
+    if okay {
+        SNAPSHOTS_CREATED.fetch_add(1, ORD_RLX);
+        SNAPSHOT_LAST_TIME.store(unix_now(), ORD_RLX);
+    } else {
+        SNAPSHOTS_FAILED.fetch_add(1, ORD_RLX);
+    }
+}
+
+/// Returns the total number of snapshots created
+pub fn snapshots_created() -> u64 {
+    SNAPSHOTS_CREATED.load(ORD_RLX)
+}
+
+/// Returns the total number of failed snapshots
+pub fn snapshots_failed() -> u64 {
+    SNAPSHOTS_FAILED.load(ORD_RLX)
+}
+
+/// Returns the time of the last successful snapshot, or `0` if none was created
+pub fn snapshot_last_time() -> u64 {
+    SNAPSHOT_LAST_TIME.load(ORD_RLX)
+}
+
+/// Returns a static reference to the snapshot duration histogram
+pub fn snapshot_latency() -> &'static PersistHistogram {
+    &SNAPSHOT_LATENCY
+}
+
+#[test]
+fn test_histogram_buckets() {
+    let histogram = Histogram::new([10, 100, 1000]);
+    histogram.observe(Duration::from_micros(5));
+    histogram.observe(Duration::from_micros(50));
+    histogram.observe(Duration::from_micros(70));
+    histogram.observe(Duration::from_micros(5000));
+    assert_eq!(
+        histogram.cumulative_buckets(),
+        vec![(10, 1), (100, 3), (1000, 3)]
+    );
+    assert_eq!(histogram.count(), 4);
+    assert_eq!(histogram.sum_micros(), 5125);
+}
@@ -39,6 +39,7 @@ pub mod writer;
 
 pub const TSYMBOL_BINARY: u8 = b'?';
 pub const TSYMBOL_UNICODE: u8 = b'+';
+pub const TSYMBOL_UINT: u8 = b':';
 
 /// # The `Writable` trait
 /// All trait implementors are given access to an asynchronous stream to which
@@ -98,7 +99,7 @@ impl Writable for Vec<u8> {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         async fn write_bytes(con: &mut impl IsConnection, resp: Vec<u8>) -> Result<(), IoError> {
             con.write_lowlevel(&resp).await?;
             Ok(())
@@ -111,7 +112,7 @@ impl<const N: usize> Writable for [u8; N] {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         Box::pin(async move { con.write_lowlevel(&self).await })
     }
 }
@@ -120,7 +121,7 @@ impl Writable for &'static [u8] {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         async fn write_bytes(con: &mut impl IsConnection, resp: &[u8]) -> Result<(), IoError> {
             con.write_lowlevel(resp).await?;
             Ok(())
@@ -133,23 +134,23 @@ impl Writable for &'static str {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         async fn write_bytes(con: &mut impl IsConnection, bytes: &str) -> Result<(), IoError> {
             // First write a `+` character to the stream since this is a
             // string (we represent `String`s as `Byte` objects internally)
             // and since `Bytes` are effectively `String`s we will append the
             // type operator `+` to the stream
-            con.write_lowlevel(&[b'+']).await?;
+            con.write_lowlevel(b"+").await?;
             // Now get the size of the Bytes object as bytes
             let size = Integer64::from(bytes.len());
             // Write this to the stream
             con.write_lowlevel(&size).await?;
             // Now write a LF character
-            con.write_lowlevel(&[b'\n']).await?;
+            con.write_lowlevel(b"\n").await?;
             // Now write the REAL bytes (of the object)
             con.write_lowlevel(bytes.as_bytes()).await?;
             // Now write another LF
-            con.write_lowlevel(&[b'\n']).await?;
+            con.write_lowlevel(b"\n").await?;
             Ok(())
         }
         Box::pin(write_bytes(con, self))
@@ -160,23 +161,23 @@ impl Writable for BytesWrapper {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         async fn write_bytes(con: &mut impl IsConnection, bytes: Bytes) -> Result<(), IoError> {
             // First write a `+` character to the stream since this is a
             // string (we represent `String`s as `Byte` objects internally)
             // and since `Bytes` are effectively `String`s we will append the
             // type operator `+` to the stream
-            con.write_lowlevel(&[b'+']).await?;
+            con.write_lowlevel(b"+").await?;
             // Now get the size of the Bytes object as bytes
             let size = Integer64::from(bytes.len());
             // Write this to the stream
             con.write_lowlevel(&size).await?;
             // Now write a LF character
-            con.write_lowlevel(&[b'\n']).await?;
+            con.write_lowlevel(b"\n").await?;
             // Now write the REAL bytes (of the object)
             con.write_lowlevel(&bytes).await?;
             // Now write another LF
-            con.write_lowlevel(&[b'\n']).await?;
+            con.write_lowlevel(b"\n").await?;
             Ok(())
         }
         Box::pin(write_bytes(con, self.finish_into_bytes()))
@@ -187,12 +188,12 @@ impl Writable for RespCode {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         async fn write_bytes(con: &mut impl IsConnection, code: RespCode) -> Result<(), IoError> {
             if let RespCode::ErrorString(e) = code {
                 // Since this is an other error which contains a description
                 // we'll write !<no_of_bytes> followed by the string
-                con.write_lowlevel(&[b'!']).await?;
+                con.write_lowlevel(b"!").await?;
                 // Convert the string into a vector of bytes
                 let e = e.to_string().into_bytes();
                 // Now get the length of the byte vector and turn it into
@@ -201,25 +202,25 @@ impl Writable for RespCode {
                 // Write the length
                 con.write_lowlevel(&len_as_bytes).await?;
                 // Then an LF
-                con.write_lowlevel(&[b'\n']).await?;
+                con.write_lowlevel(b"\n").await?;
                 // Then the error string
                 con.write_lowlevel(&e).await?;
                 // Then another LF
-                con.write_lowlevel(&[b'\n']).await?;
+                con.write_lowlevel(b"\n").await?;
                 // And now we're done
                 return Ok(());
             }
             // Self's tsymbol is !
             // The length of the response code is 1
             // And we need a newline
-            con.write_lowlevel(&[b'!', b'1', b'\n']).await?;
+            con.write_lowlevel(b"!1\n").await?;
             // We need to get the u8 version of the response code
             let code: u8 = code.into();
             // We need the UTF8 equivalent of the response code
             let code_bytes = code.to_string().into_bytes();
             con.write_lowlevel(&code_bytes).await?;
             // Now append a newline
-            con.write_lowlevel(&[b'\n']).await?;
+            con.write_lowlevel(b"\n").await?;
             Ok(())
         }
         Box::pin(write_bytes(con, self))
@@ -230,7 +231,7 @@ impl Writable for usize {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         async fn write_bytes(con: &mut impl IsConnection, val: usize) -> Result<(), IoError> {
             con.write_lowlevel(b":").await?;
             let usize_bytes = val.to_string().into_bytes();
@@ -249,7 +250,7 @@ impl Writable for u64 {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         async fn write_bytes(con: &mut impl IsConnection, val: u64) -> Result<(), IoError> {
             con.write_lowlevel(b":").await?;
             let usize_bytes = val.to_string().into_bytes();
@@ -268,23 +269,23 @@ impl Writable for ObjectID {
     fn write<'s>(
         self,
         con: &'s mut impl IsConnection,
-    ) -> Pin<Box<(dyn Future<Output = Result<(), IoError>> + Send + Sync + 's)>> {
+    ) -> Pin<Box<dyn Future<Output = Result<(), IoError>> + Send + Sync + 's>> {
         async fn write_bytes(con: &mut impl IsConnection, bytes: ObjectID) -> Result<(), IoError> {
             // First write a `+` character to the stream since this is a
             // string (we represent `String`s as `Byte` objects internally)
             // and since `Bytes` are effectively `String`s we will append the
             // type operator `+` to the stream
-            con.write_lowlevel(&[b'+']).await?;
+            con.write_lowlevel(b"+").await?;
             // Now get the size of the Bytes object as bytes
             let size = Integer64::from(bytes.len());
             // Write this to the stream
             con.write_lowlevel(&size).await?;
             // Now write a LF character
-            con.write_lowlevel(&[b'\n']).await?;
+            con.write_lowlevel(b"\n").await?;
             // Now write the REAL bytes (of the object)
             con.write_lowlevel(&bytes).await?;
             // Now write another LF
-            con.write_lowlevel(&[b'\n']).await?;
+            con.write_lowlevel(b"\n").await?;
             Ok(())
         }
         Box::pin(write_bytes(con, self))
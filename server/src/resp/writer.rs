@@ -47,9 +47,9 @@ where
     raw_stream.write_all(&[tsymbol; 1]).await?; // first write tsymbol
     let bytes = Integer64::from(payload.len());
     raw_stream.write_all(&bytes).await?; // then len
-    raw_stream.write_all(&[b'\n']).await?; // LF
+    raw_stream.write_all(b"\n").await?; // LF
     raw_stream.write_all(payload).await?; // payload
-    raw_stream.write_all(&[b'\n']).await?; // final LF
+    raw_stream.write_all(b"\n").await?; // final LF
     Ok(())
 }
 
@@ -77,12 +77,12 @@ where
         {
             let stream = unsafe { con.raw_stream() };
             // first write _
-            stream.write_all(&[b'_']).await?;
+            stream.write_all(b"_").await?;
             let bytes = Integer64::from(len);
             // now write len
             stream.write_all(&bytes).await?;
             // first LF
-            stream.write_all(&[b'\n']).await?;
+            stream.write_all(b"\n").await?;
         }
         Ok(Self {
             con,
@@ -100,11 +100,11 @@ where
         let len = Integer64::from(bytes.len());
         stream.write_all(&len).await?;
         // now LF
-        stream.write_all(&[b'\n']).await?;
+        stream.write_all(b"\n").await?;
         // now element
         stream.write_all(bytes).await?;
         // now final LF
-        stream.write_all(&[b'\n']).await?;
+        stream.write_all(b"\n").await?;
         Ok(())
     }
     /// Write the NIL response code
@@ -149,7 +149,7 @@ where
             // now write len
             stream.write_all(&bytes).await?;
             // first LF
-            stream.write_all(&[b'\n']).await?;
+            stream.write_all(b"\n").await?;
         }
         Ok(Self {
             con,
@@ -164,17 +164,17 @@ where
         let len = Integer64::from(bytes.len());
         stream.write_all(&len).await?;
         // now LF
-        stream.write_all(&[b'\n']).await?;
+        stream.write_all(b"\n").await?;
         // now element
         stream.write_all(bytes).await?;
         // now final LF
-        stream.write_all(&[b'\n']).await?;
+        stream.write_all(b"\n").await?;
         Ok(())
     }
     /// Write a null
     pub async fn write_null(&mut self) -> IoResult<()> {
         let stream = unsafe { self.con.raw_stream() };
-        stream.write_all(&[b'\0', b'\n']).await?;
+        stream.write_all(b"\0\n").await?;
         Ok(())
     }
 }
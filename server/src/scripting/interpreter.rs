@@ -0,0 +1,458 @@
+/*
+ * Created on Tue Sep 14 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The interpreter
+//!
+//! A tree-walking interpreter for a parsed script. Every statement and expression that is
+//! evaluated counts as a step; a script is stopped once it runs for more than
+//! [`Limits::max_steps`] steps or past [`Limits::deadline`]. Since values are copied when
+//! they're evaluated, the size of every string or array that an expression evaluates to is
+//! charged to the script, which is stopped once it was charged more than
+//! [`Limits::max_memory`] bytes
+
+use super::parser::{BinOp, Expr, Stmt, UnOp};
+use super::ScriptError;
+use std::time::Instant;
+
+/// The maximum length of a string or an array created by a script
+pub const MAX_VALUE_LEN: usize = 16 * 1024 * 1024;
+/// The clock is only checked once every these many steps (has to be a power of two)
+const CLOCK_INTERVAL: u64 = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Str(Vec<u8>),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    const fn is_truthy(&self) -> bool {
+        !matches!(self, Self::Nil | Self::Bool(false))
+    }
+    const fn type_name(&self) -> &'static str {
+        match self {
+            Self::Nil => "nil",
+            Self::Bool(_) => "boolean",
+            Self::Int(_) => "integer",
+            Self::Str(_) => "string",
+            Self::Array(_) => "array",
+        }
+    }
+    /// Returns the number of bytes that the strings and arrays in this value take up
+    fn footprint(&self) -> usize {
+        match self {
+            Self::Str(string) => string.len(),
+            Self::Array(items) => {
+                items.len() * std::mem::size_of::<Self>()
+                    + items.iter().map(Self::footprint).sum::<usize>()
+            }
+            Self::Nil | Self::Bool(_) | Self::Int(_) => 0,
+        }
+    }
+    /// Returns the value as a string if it is a string or an integer
+    fn to_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            Self::Str(string) => Some(string.clone()),
+            Self::Int(int) => Some(int.to_string().into_bytes()),
+            _ => None,
+        }
+    }
+}
+
+/// What a script can do with the outside world
+pub trait Host {
+    /// Run an action, with `args[0]` being the name of the action
+    fn call(&mut self, args: Vec<Vec<u8>>) -> Result<Value, String>;
+}
+
+pub struct Limits {
+    pub max_steps: u64,
+    pub deadline: Instant,
+    pub max_memory: usize,
+}
+
+/// How a block finished running
+enum Flow {
+    Normal,
+    Break,
+    Return(Value),
+}
+
+pub struct Interpreter<'a, H> {
+    host: &'a mut H,
+    limits: Limits,
+    steps: u64,
+    /// the number of bytes of strings and arrays created so far
+    memory: usize,
+    /// the variables in scope, innermost last. The first scope holds the globals
+    scopes: Vec<Vec<(String, Value)>>,
+}
+
+impl<'a, H: Host> Interpreter<'a, H> {
+    pub fn new(host: &'a mut H, limits: Limits, globals: Vec<(String, Value)>) -> Self {
+        Self {
+            host,
+            limits,
+            steps: 0,
+            memory: 0,
+            scopes: vec![globals],
+        }
+    }
+    /// Run the script, returning the value it returned (or nil)
+    pub fn run(mut self, block: &[Stmt]) -> Result<Value, ScriptError> {
+        match self.block(block)? {
+            Flow::Return(value) => Ok(value),
+            Flow::Normal => Ok(Value::Nil),
+            Flow::Break => Err(ScriptError::runtime("'break' outside of a loop")),
+        }
+    }
+    fn step(&mut self) -> Result<(), ScriptError> {
+        self.steps += 1;
+        if self.steps > self.limits.max_steps {
+            return Err(ScriptError::TooManySteps);
+        }
+        if self.steps & (CLOCK_INTERVAL - 1) == 0 && Instant::now() > self.limits.deadline {
+            return Err(ScriptError::Timeout);
+        }
+        Ok(())
+    }
+    /// Charge the script for `bytes` bytes of strings or arrays that were just created
+    fn charge(&mut self, bytes: usize) -> Result<(), ScriptError> {
+        self.memory = self.memory.saturating_add(bytes);
+        if self.memory > self.limits.max_memory {
+            return Err(ScriptError::OutOfMemory);
+        }
+        Ok(())
+    }
+    fn lookup(&mut self, name: &str) -> Option<&mut Value> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|(var, _)| var == name)
+            .map(|(_, value)| value)
+    }
+    fn block(&mut self, block: &[Stmt]) -> Result<Flow, ScriptError> {
+        self.scopes.push(Vec::new());
+        let mut flow = Flow::Normal;
+        for stmt in block {
+            flow = self.stmt(stmt)?;
+            if !matches!(flow, Flow::Normal) {
+                break;
+            }
+        }
+        self.scopes.pop();
+        Ok(flow)
+    }
+    fn stmt(&mut self, stmt: &Stmt) -> Result<Flow, ScriptError> {
+        self.step()?;
+        match stmt {
+            Stmt::Local(name, expr) => {
+                let value = self.expr(expr)?;
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.push((name.clone(), value));
+                }
+            }
+            Stmt::Assign(name, expr) => {
+                let value = self.expr(expr)?;
+                match self.lookup(name) {
+                    Some(var) => *var = value,
+                    None => self.scopes[0].push((name.clone(), value)),
+                }
+            }
+            Stmt::SetIndex(name, index, expr) => {
+                let index = self.expr(index)?;
+                let value = self.expr(expr)?;
+                let array = match self.lookup(name) {
+                    Some(Value::Array(array)) => array,
+                    Some(other) => {
+                        return Err(ScriptError::runtime(format!(
+                            "attempt to index a {} value ('{}')",
+                            other.type_name(),
+                            name
+                        )))
+                    }
+                    None => {
+                        return Err(ScriptError::runtime(format!(
+                            "attempt to index an undefined variable ('{}')",
+                            name
+                        )))
+                    }
+                };
+                match index {
+                    Value::Int(i) if i >= 1 && (i as usize) <= array.len() => {
+                        array[i as usize - 1] = value
+                    }
+                    Value::Int(i) if i as usize == array.len() + 1 => {
+                        if array.len() == MAX_VALUE_LEN {
+                            return Err(ScriptError::runtime("array is too large"));
+                        }
+                        array.push(value);
+                        // the value itself was charged when it was evaluated, but not the
+                        // slot it takes up in the array
+                        self.charge(std::mem::size_of::<Value>())?;
+                    }
+                    _ => return Err(ScriptError::runtime("array index out of bounds")),
+                }
+            }
+            Stmt::If(branches, otherwise) => {
+                for (cond, body) in branches {
+                    if self.expr(cond)?.is_truthy() {
+                        return self.block(body);
+                    }
+                }
+                if let Some(body) = otherwise {
+                    return self.block(body);
+                }
+            }
+            Stmt::While(cond, body) => {
+                while self.expr(cond)?.is_truthy() {
+                    match self.block(body)? {
+                        Flow::Normal => {}
+                        Flow::Break => break,
+                        ret @ Flow::Return(_) => return Ok(ret),
+                    }
+                }
+            }
+            Stmt::Break => return Ok(Flow::Break),
+            Stmt::Return(expr) => {
+                let value = match expr {
+                    Some(expr) => self.expr(expr)?,
+                    None => Value::Nil,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::Call(expr) => {
+                self.expr(expr)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+    fn expr(&mut self, expr: &Expr) -> Result<Value, ScriptError> {
+        self.step()?;
+        let value = match expr {
+            Expr::Nil => Value::Nil,
+            Expr::Bool(b) => Value::Bool(*b),
+            Expr::Int(int) => Value::Int(*int),
+            Expr::Str(string) => Value::Str(string.clone()),
+            Expr::Var(name) => self.lookup(name).map(|v| v.clone()).unwrap_or(Value::Nil),
+            Expr::Index(array, index) => match (self.expr(array)?, self.expr(index)?) {
+                (Value::Array(array), Value::Int(i)) => {
+                    if i >= 1 {
+                        array.get(i as usize - 1).cloned().unwrap_or(Value::Nil)
+                    } else {
+                        Value::Nil
+                    }
+                }
+                (Value::Array(_), index) => {
+                    return Err(ScriptError::runtime(format!(
+                        "attempt to index an array with a {} value",
+                        index.type_name()
+                    )))
+                }
+                (other, _) => {
+                    return Err(ScriptError::runtime(format!(
+                        "attempt to index a {} value",
+                        other.type_name()
+                    )))
+                }
+            },
+            Expr::Call(name, args, line) => {
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(self.expr(arg)?);
+                }
+                self.call(name, values)
+                    .map_err(|e| ScriptError::runtime(format!("line {}: {}", line, e)))?
+            }
+            Expr::Array(items) => {
+                let mut values = Vec::with_capacity(items.len());
+                for item in items {
+                    values.push(self.expr(item)?);
+                }
+                Value::Array(values)
+            }
+            Expr::Unary(op, expr) => {
+                let value = self.expr(expr)?;
+                unary(*op, value)?
+            }
+            Expr::Binary(BinOp::And, lhs, rhs) => {
+                let lhs = self.expr(lhs)?;
+                if lhs.is_truthy() {
+                    self.expr(rhs)?
+                } else {
+                    lhs
+                }
+            }
+            Expr::Binary(BinOp::Or, lhs, rhs) => {
+                let lhs = self.expr(lhs)?;
+                if lhs.is_truthy() {
+                    lhs
+                } else {
+                    self.expr(rhs)?
+                }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.expr(lhs)?;
+                let rhs = self.expr(rhs)?;
+                binary(*op, lhs, rhs)?
+            }
+        };
+        self.charge(value.footprint())?;
+        Ok(value)
+    }
+    /// Call a builtin function
+    fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, String> {
+        match (name, args.as_slice()) {
+            ("sky", [_, ..]) => {
+                let mut strings = Vec::with_capacity(args.len());
+                for arg in args {
+                    match arg.to_bytes() {
+                        Some(string) => strings.push(string),
+                        None => {
+                            return Err(format!(
+                                "sky() only accepts strings and integers, got {}",
+                                arg.type_name()
+                            ))
+                        }
+                    }
+                }
+                self.host.call(strings)
+            }
+            ("tostring", [value]) => Ok(match value {
+                Value::Nil | Value::Bool(_) | Value::Array(_) => {
+                    Value::Str(value.type_name().as_bytes().to_vec())
+                }
+                Value::Int(int) => Value::Str(int.to_string().into_bytes()),
+                Value::Str(_) => value.clone(),
+            }),
+            ("tonumber", [value]) => Ok(match value {
+                Value::Int(_) => value.clone(),
+                Value::Str(string) => std::str::from_utf8(string)
+                    .ok()
+                    .and_then(|string| string.trim().parse().ok())
+                    .map(Value::Int)
+                    .unwrap_or(Value::Nil),
+                _ => Value::Nil,
+            }),
+            ("type", [value]) => Ok(Value::Str(value.type_name().as_bytes().to_vec())),
+            ("error", [message]) => Err(match message.to_bytes() {
+                Some(message) => String::from_utf8_lossy(&message).into_owned(),
+                None => message.type_name().to_owned(),
+            }),
+            ("sky" | "tostring" | "tonumber" | "type" | "error", _) => {
+                Err(format!("wrong number of arguments to '{}'", name))
+            }
+            _ => Err(format!("attempt to call an unknown function ('{}')", name)),
+        }
+    }
+}
+
+fn unary(op: UnOp, value: Value) -> Result<Value, ScriptError> {
+    let value = match (op, value) {
+        (UnOp::Not, value) => Value::Bool(!value.is_truthy()),
+        (UnOp::Neg, Value::Int(int)) => Value::Int(
+            int.checked_neg()
+                .ok_or_else(|| ScriptError::runtime("integer overflow"))?,
+        ),
+        (UnOp::Len, Value::Str(string)) => Value::Int(string.len() as i64),
+        (UnOp::Len, Value::Array(array)) => Value::Int(array.len() as i64),
+        (op, value) => {
+            return Err(ScriptError::runtime(format!(
+                "attempt to perform {:?} on a {} value",
+                op,
+                value.type_name()
+            )))
+        }
+    };
+    Ok(value)
+}
+
+fn binary(op: BinOp, lhs: Value, rhs: Value) -> Result<Value, ScriptError> {
+    let value = match (op, lhs, rhs) {
+        (BinOp::Eq, lhs, rhs) => Value::Bool(lhs == rhs),
+        (BinOp::Ne, lhs, rhs) => Value::Bool(lhs != rhs),
+        (BinOp::Concat, lhs, rhs) => match (lhs.to_bytes(), rhs.to_bytes()) {
+            (Some(mut lhs), Some(rhs)) => {
+                if lhs.len() + rhs.len() > MAX_VALUE_LEN {
+                    return Err(ScriptError::runtime("string is too large"));
+                }
+                lhs.extend(rhs);
+                Value::Str(lhs)
+            }
+            _ => {
+                return Err(ScriptError::runtime(
+                    "attempt to concatenate a value that isn't a string or an integer",
+                ))
+            }
+        },
+        (op @ (BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge), lhs, rhs) => {
+            let ordering = match (&lhs, &rhs) {
+                (Value::Int(lhs), Value::Int(rhs)) => lhs.cmp(rhs),
+                (Value::Str(lhs), Value::Str(rhs)) => lhs.cmp(rhs),
+                _ => {
+                    return Err(ScriptError::runtime(format!(
+                        "attempt to compare a {} value with a {} value",
+                        lhs.type_name(),
+                        rhs.type_name()
+                    )))
+                }
+            };
+            Value::Bool(match op {
+                BinOp::Lt => ordering.is_lt(),
+                BinOp::Le => ordering.is_le(),
+                BinOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+        (op, Value::Int(lhs), Value::Int(rhs)) => {
+            let result = match op {
+                BinOp::Add => lhs.checked_add(rhs),
+                BinOp::Sub => lhs.checked_sub(rhs),
+                BinOp::Mul => lhs.checked_mul(rhs),
+                BinOp::Div | BinOp::Mod if rhs == 0 => {
+                    return Err(ScriptError::runtime("attempt to divide by zero"))
+                }
+                BinOp::Div => lhs.checked_div_euclid(rhs),
+                BinOp::Mod => lhs.checked_rem_euclid(rhs),
+                _ => unreachable!("logical operators are evaluated by the interpreter"),
+            };
+            Value::Int(result.ok_or_else(|| ScriptError::runtime("integer overflow"))?)
+        }
+        (op, lhs, rhs) => {
+            return Err(ScriptError::runtime(format!(
+                "attempt to perform {:?} on a {} value and a {} value",
+                op,
+                lhs.type_name(),
+                rhs.type_name()
+            )))
+        }
+    };
+    Ok(value)
+}
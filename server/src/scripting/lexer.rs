@@ -0,0 +1,257 @@
+/*
+ * Created on Tue Sep 14 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The lexer
+//!
+//! Turns the source of a script into a stream of [`Token`]s
+
+use super::ScriptError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Name(String),
+    Int(i64),
+    Str(Vec<u8>),
+    // keywords
+    Local,
+    If,
+    Then,
+    Elseif,
+    Else,
+    End,
+    While,
+    Do,
+    Break,
+    Return,
+    And,
+    Or,
+    Not,
+    Nil,
+    True,
+    False,
+    // symbols
+    Assign,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Concat,
+    Hash,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Semicolon,
+}
+
+impl Token {
+    fn keyword(name: &str) -> Option<Self> {
+        let kw = match name {
+            "local" => Self::Local,
+            "if" => Self::If,
+            "then" => Self::Then,
+            "elseif" => Self::Elseif,
+            "else" => Self::Else,
+            "end" => Self::End,
+            "while" => Self::While,
+            "do" => Self::Do,
+            "break" => Self::Break,
+            "return" => Self::Return,
+            "and" => Self::And,
+            "or" => Self::Or,
+            "not" => Self::Not,
+            "nil" => Self::Nil,
+            "true" => Self::True,
+            "false" => Self::False,
+            _ => return None,
+        };
+        Some(kw)
+    }
+}
+
+/// A token along with the line it was found on
+pub type Spanned = (Token, usize);
+
+/// Split the source into tokens
+pub fn tokenize(src: &[u8]) -> Result<Vec<Spanned>, ScriptError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < src.len() {
+        let c = src[i];
+        let peek = src.get(i + 1).copied();
+        let token = match c {
+            b'\n' => {
+                line += 1;
+                i += 1;
+                continue;
+            }
+            c if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'-' if peek == Some(b'-') => {
+                // a comment; skip till the end of the line
+                while i < src.len() && src[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < src.len() && src[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let int = std::str::from_utf8(&src[start..i])
+                    .ok()
+                    .and_then(|int| int.parse().ok())
+                    .ok_or_else(|| ScriptError::compile(line, "integer literal is too large"))?;
+                tokens.push((Token::Int(int), line));
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = i;
+                while i < src.len() && (src[i].is_ascii_alphanumeric() || src[i] == b'_') {
+                    i += 1;
+                }
+                // only ASCII so far, so this is valid UTF-8
+                let name = String::from_utf8_lossy(&src[start..i]).into_owned();
+                let token = Token::keyword(&name).unwrap_or(Token::Name(name));
+                tokens.push((token, line));
+                continue;
+            }
+            b'"' | b'\'' => {
+                let (string, next) = string_literal(src, i, line)?;
+                tokens.push((Token::Str(string), line));
+                i = next;
+                continue;
+            }
+            b'=' if peek == Some(b'=') => Token::Eq,
+            b'~' if peek == Some(b'=') => Token::Ne,
+            b'<' if peek == Some(b'=') => Token::Le,
+            b'>' if peek == Some(b'=') => Token::Ge,
+            b'.' if peek == Some(b'.') => Token::Concat,
+            b'=' => Token::Assign,
+            b'<' => Token::Lt,
+            b'>' => Token::Gt,
+            b'+' => Token::Plus,
+            b'-' => Token::Minus,
+            b'*' => Token::Star,
+            b'/' => Token::Slash,
+            b'%' => Token::Percent,
+            b'#' => Token::Hash,
+            b'(' => Token::LParen,
+            b')' => Token::RParen,
+            b'[' => Token::LBracket,
+            b']' => Token::RBracket,
+            b'{' => Token::LBrace,
+            b'}' => Token::RBrace,
+            b',' => Token::Comma,
+            b';' => Token::Semicolon,
+            c => {
+                return Err(ScriptError::compile(
+                    line,
+                    format!("unexpected character {:?}", char::from(c)),
+                ))
+            }
+        };
+        i += match token {
+            Token::Eq | Token::Ne | Token::Le | Token::Ge | Token::Concat => 2,
+            _ => 1,
+        };
+        tokens.push((token, line));
+    }
+    Ok(tokens)
+}
+
+/// Read the string literal starting at `start`, returning the string and the position
+/// right after the closing quote
+fn string_literal(src: &[u8], start: usize, line: usize) -> Result<(Vec<u8>, usize), ScriptError> {
+    let quote = src[start];
+    let mut string = Vec::new();
+    let mut i = start + 1;
+    loop {
+        match src.get(i) {
+            Some(c) if *c == quote => return Ok((string, i + 1)),
+            Some(b'\\') => {
+                let escaped = match src.get(i + 1) {
+                    Some(b'n') => b'\n',
+                    Some(b't') => b'\t',
+                    Some(b'r') => b'\r',
+                    Some(b'0') => b'\0',
+                    Some(b'\\') => b'\\',
+                    Some(b'"') => b'"',
+                    Some(b'\'') => b'\'',
+                    _ => return Err(ScriptError::compile(line, "invalid escape sequence")),
+                };
+                string.push(escaped);
+                i += 2;
+            }
+            Some(b'\n') | None => {
+                return Err(ScriptError::compile(line, "unfinished string"));
+            }
+            Some(c) => {
+                string.push(*c);
+                i += 1;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tokenize() {
+    let tokens: Vec<Token> = tokenize(b"local x = 'he\\'y' .. 10 -- comment\nreturn #x ~= 2")
+        .unwrap()
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Local,
+            Token::Name("x".to_owned()),
+            Token::Assign,
+            Token::Str(b"he'y".to_vec()),
+            Token::Concat,
+            Token::Int(10),
+            Token::Return,
+            Token::Hash,
+            Token::Name("x".to_owned()),
+            Token::Ne,
+            Token::Int(2)
+        ]
+    );
+}
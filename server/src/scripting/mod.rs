@@ -0,0 +1,433 @@
+/*
+ * Created on Tue Sep 14 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Scripting
+//!
+//! Scripts are written in a small, Lua-like language (see [`parser`] for the grammar) and
+//! are run with `EVAL`/`EVALSHA`. Like with Redis, the keys that a script will touch have to
+//! be declared up front: they are available to the script as `KEYS` and the remaining
+//! arguments as `ARGV` (both 1-indexed). A script can run `GET`, `SET`, `UPDATE`, `USET`,
+//! `DEL`, `EXISTS` and `POP` on the declared keys of the current table with
+//! `sky('ACTION', ...)`.
+//!
+//! While a script runs, all the shards that the declared keys belong to are write-locked,
+//! so no other writer sees the keys halfway through the script (which runs on a thread that
+//! is allowed to block, so that the other connections of the worker keep being served). To
+//! make sure that a bad script can't hold on to the locks for long, a script is stopped
+//! after [`MAX_STEPS`] steps or [`MAX_RUNTIME`], whichever comes first, and once the strings
+//! and arrays it created add up to more than [`MAX_MEMORY`] bytes. Writes aren't undone
+//! when a script fails or is stopped, so scripts are isolated but not atomic.
+//!
+//! Compiled scripts are cached by the SHA1 digest of their source. At most
+//! [`MAX_CACHED_SCRIPTS`] scripts are cached; once the cache is full, an arbitrary script
+//! is evicted to make room for a new one (and has to be loaded again before `EVALSHA` can
+//! run it)
+
+mod interpreter;
+mod lexer;
+mod parser;
+
+pub use self::interpreter::Value;
+use self::interpreter::{Host, Interpreter, Limits};
+use self::parser::{Block, Parser};
+
+/// A compiled script
+pub type Script = Block;
+use crate::corestore::htable::Coremap;
+use crate::corestore::lazy::Lazy;
+use crate::corestore::map::LockedShards;
+use crate::corestore::memstore::Keyspace;
+use crate::corestore::quota::QuotaTracker;
+use crate::corestore::Data;
+use crate::kvengine::KVEngine;
+use crate::kvengine::Versioned;
+use crate::protocol::responses::groups;
+use crate::queryengine::txn::{Reply, TxnOp};
+use crate::registry;
+use core::fmt;
+use core::hash::BuildHasher;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The maximum number of steps a script can run for
+pub const MAX_STEPS: u64 = 100_000;
+/// The maximum time a script can run for
+pub const MAX_RUNTIME: Duration = Duration::from_millis(50);
+/// The maximum number of bytes of strings and arrays that a script can create in total
+pub const MAX_MEMORY: usize = 64 * 1024 * 1024;
+/// The maximum number of compiled scripts that are cached
+pub const MAX_CACHED_SCRIPTS: usize = 1024;
+
+type ScriptCache = Coremap<String, Arc<Script>>;
+
+/// The compiled scripts, by the hex encoded SHA1 digest of their source
+static SCRIPTS: Lazy<ScriptCache, fn() -> ScriptCache> = Lazy::new(Coremap::new);
+
+#[derive(Debug, PartialEq)]
+pub enum ScriptError {
+    /// The script couldn't be compiled (line, message)
+    Compile(usize, String),
+    /// The script raised an error while running
+    Runtime(String),
+    /// The script ran for more than [`MAX_STEPS`] steps
+    TooManySteps,
+    /// The script ran for longer than [`MAX_RUNTIME`]
+    Timeout,
+    /// The script created more than [`MAX_MEMORY`] bytes of strings and arrays
+    OutOfMemory,
+}
+
+impl ScriptError {
+    fn compile(line: usize, message: impl Into<String>) -> Self {
+        Self::Compile(line, message.into())
+    }
+    fn runtime(message: impl Into<String>) -> Self {
+        Self::Runtime(message.into())
+    }
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compile(line, message) => write!(f, "line {}: {}", line, message),
+            Self::Runtime(message) => write!(f, "{}", message),
+            Self::TooManySteps => write!(f, "script ran for more than {} steps", MAX_STEPS),
+            Self::Timeout => write!(f, "script ran for more than {:?}", MAX_RUNTIME),
+            Self::OutOfMemory => write!(f, "script used more than {} bytes", MAX_MEMORY),
+        }
+    }
+}
+
+/// Returns the hex encoded SHA1 digest of a script
+pub fn digest(src: &[u8]) -> String {
+    openssl::sha::sha1(src)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Compile a script and cache it, returning its digest
+pub fn load(src: &[u8]) -> Result<(String, Arc<Script>), ScriptError> {
+    let sha = digest(src);
+    if let Some(block) = SCRIPTS.get_cloned(&sha) {
+        return Ok((sha, block));
+    }
+    let block = Arc::new(Parser::new(lexer::tokenize(src)?).parse()?);
+    cache(&SCRIPTS, sha.clone(), block.clone());
+    Ok((sha, block))
+}
+
+/// Add a script to `cache`, evicting an arbitrary script if the cache is full
+fn cache(cache: &ScriptCache, sha: String, block: Arc<Script>) {
+    if cache.len() >= MAX_CACHED_SCRIPTS {
+        // the read lock on the shard has to be dropped before the script can be removed
+        let victim = cache.iter().next().map(|kv| kv.key().clone());
+        if let Some(victim) = victim {
+            cache.true_if_removed(&victim);
+        }
+    }
+    cache.upsert(sha, block);
+}
+
+/// Returns a cached script
+pub fn get(sha: &str) -> Option<Arc<Script>> {
+    SCRIPTS.get_cloned(&sha.to_ascii_lowercase())
+}
+
+/// Check if a script is cached
+pub fn exists(sha: &str) -> bool {
+    SCRIPTS.contains_key(&sha.to_ascii_lowercase())
+}
+
+/// Remove all the cached scripts
+pub fn flush() {
+    SCRIPTS.clear()
+}
+
+/// Run a script against a key/value table, locking the shards of all the `keys`. Writes
+/// are checked against the quotas of `keyspace`, the keyspace of the table
+pub fn run(
+    kve: &KVEngine,
+    keyspace: Option<&Keyspace>,
+    script: &Script,
+    keys: Vec<Data>,
+    argv: Vec<Data>,
+) -> Result<Value, ScriptError> {
+    let globals = vec![
+        ("KEYS".to_owned(), to_array(&keys)),
+        ("ARGV".to_owned(), to_array(&argv)),
+    ];
+    // the usage can't be looked up once the shards are locked
+    let quota = keyspace.and_then(Keyspace::quota_tracker);
+    let mut host = KvHost {
+        kve,
+        quota,
+        shards: kve.__get_inner_ref().inner.lock_shards_for(keys.iter()),
+        keys: &keys,
+        writable: registry::state_okay(),
+    };
+    let limits = Limits {
+        max_steps: MAX_STEPS,
+        deadline: Instant::now() + MAX_RUNTIME,
+        max_memory: MAX_MEMORY,
+    };
+    Interpreter::new(&mut host, limits, globals).run(script)
+}
+
+fn to_array(items: &[Data]) -> Value {
+    Value::Array(items.iter().map(|item| Value::Str(item.to_vec())).collect())
+}
+
+/// Runs the actions called by a script on the locked shards of a table
+struct KvHost<'a, S> {
+    kve: &'a KVEngine,
+    quota: Option<QuotaTracker<'a>>,
+    shards: LockedShards<'a, Data, Versioned, S>,
+    /// the declared keys
+    keys: &'a [Data],
+    writable: bool,
+}
+
+impl<'a, S: BuildHasher + Clone> Host for KvHost<'a, S> {
+    fn call(&mut self, args: Vec<Vec<u8>>) -> Result<Value, String> {
+        let mut args = args.into_iter();
+        let action = match args.next() {
+            Some(action) => action.to_ascii_uppercase(),
+            None => return Err("no action".to_owned()),
+        };
+        let name = String::from_utf8_lossy(&action).into_owned();
+        let op = TxnOp::from_action(&action)
+            .ok_or_else(|| format!("action '{}' can't be called from a script", name))?;
+        let args: Vec<Data> = args.map(Data::from).collect();
+        if !op.arity_okay(args.len()) {
+            return Err(format!("wrong number of arguments for '{}'", name));
+        }
+        if let Some(key) = args
+            .iter()
+            .step_by(op.key_step())
+            .find(|key| !self.keys.contains(key))
+        {
+            // the key's shard might not be locked
+            return Err(format!(
+                "key '{}' wasn't declared",
+                String::from_utf8_lossy(key)
+            ));
+        }
+        if let (true, Some(quota)) = (op.grows_table(), &mut self.quota) {
+            if !quota.allow(op.write_size(&self.shards, &args)) {
+                return Err(format!("'{}' failed: quota exceeded", name));
+            }
+        }
+        match op.run(self.kve, &mut self.shards, &args, self.writable) {
+            Reply::Code(groups::OKAY) => Ok(Value::Bool(true)),
+            Reply::Code(groups::NIL) => Ok(Value::Nil),
+            Reply::Code(groups::OVERWRITE_ERR) => Ok(Value::Bool(false)),
+            Reply::Code(groups::ENCODING_ERROR) => Err(format!("'{}' failed: bad encoding", name)),
+            Reply::Code(_) => Err(format!("'{}' failed: server error", name)),
+            Reply::Mono(_, value) => Ok(Value::Str(value.to_vec())),
+            Reply::Int(int) => Ok(Value::Int(int as i64)),
+        }
+    }
+}
+
+/// Serialize the value returned by a script into a response
+pub fn to_response(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Nil | Value::Bool(false) => out.extend_from_slice(groups::NIL),
+        Value::Bool(true) => out.extend_from_slice(groups::OKAY),
+        Value::Int(int) if *int >= 0 => Reply::Int(*int as usize).write_to(out),
+        Value::Int(int) => {
+            // there are no signed integers in Skyhash
+            Reply::Mono(crate::resp::TSYMBOL_UNICODE, Data::from(int.to_string())).write_to(out)
+        }
+        Value::Str(string) => {
+            let tsymbol = if std::str::from_utf8(string).is_ok() {
+                crate::resp::TSYMBOL_UNICODE
+            } else {
+                crate::resp::TSYMBOL_BINARY
+            };
+            Reply::Mono(tsymbol, Data::copy_from_slice(string)).write_to(out)
+        }
+        Value::Array(items) => {
+            out.push(b'&');
+            out.extend_from_slice(items.len().to_string().as_bytes());
+            out.push(b'\n');
+            items.iter().for_each(|item| to_response(item, out));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corestore::Data;
+
+    fn eval(src: &str, keys: &[&str], argv: &[&str]) -> Result<Value, ScriptError> {
+        let kve = KVEngine::init(false, false);
+        eval_with(&kve, src, keys, argv)
+    }
+    fn eval_with(
+        kve: &KVEngine,
+        src: &str,
+        keys: &[&str],
+        argv: &[&str],
+    ) -> Result<Value, ScriptError> {
+        let (_, script) = load(src.as_bytes())?;
+        let data = |items: &[&str]| items.iter().map(|s| Data::from(s.to_string())).collect();
+        run(kve, None, &script, data(keys), data(argv))
+    }
+
+    #[test]
+    fn test_script_arithmetic_and_control_flow() {
+        let src = "
+            local total = 0
+            local i = 1
+            while true do
+                if i > 10 then break end
+                if i % 2 == 0 then total = total + i end
+                i = i + 1
+            end
+            return {total, 'x' .. total, #ARGV, ARGV[1]}
+        ";
+        assert_eq!(
+            eval(src, &[], &["a"]).unwrap(),
+            Value::Array(vec![
+                Value::Int(30),
+                Value::Str(b"x30".to_vec()),
+                Value::Int(1),
+                Value::Str(b"a".to_vec())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_script_calls_actions() {
+        let kve = KVEngine::init(false, false);
+        let src = "
+            local old = sky('GET', KEYS[1])
+            if old == nil then
+                sky('SET', KEYS[1], ARGV[1])
+                return 0
+            end
+            sky('UPDATE', KEYS[1], tonumber(old) + tonumber(ARGV[1]))
+            return tonumber(old)
+        ";
+        assert_eq!(
+            eval_with(&kve, src, &["counter"], &["5"]).unwrap(),
+            Value::Int(0)
+        );
+        assert_eq!(
+            eval_with(&kve, src, &["counter"], &["5"]).unwrap(),
+            Value::Int(5)
+        );
+        assert_eq!(
+            kve.get_cloned(b"counter".as_ref()).unwrap().unwrap(),
+            Data::from("10")
+        );
+    }
+
+    #[test]
+    fn test_script_quotas() {
+        use crate::corestore::memstore::DEFAULT;
+        use crate::corestore::quota::Quota;
+        let ks = Keyspace::empty_default();
+        ks.quotas().set(Quota::Keys, 1);
+        let table = ks.get_table_atomic_ref(&DEFAULT).unwrap();
+        let kve = table.get_kvstore().unwrap();
+        let (_, script) =
+            load(b"sky('SET', KEYS[1], 'a') return sky('SET', KEYS[2], 'b')").unwrap();
+        let keys = vec![Data::from("x"), Data::from("y")];
+        assert!(matches!(
+            run(kve, Some(&ks), &script, keys, vec![]),
+            Err(ScriptError::Runtime(_))
+        ));
+        // the writes before the one that went over the quota are kept
+        assert_eq!(kve.len(), 1);
+    }
+
+    #[test]
+    fn test_script_undeclared_key() {
+        assert!(matches!(
+            eval("return sky('GET', 'x')", &[], &[]),
+            Err(ScriptError::Runtime(_))
+        ));
+    }
+
+    #[test]
+    fn test_script_limits() {
+        assert_eq!(
+            eval("while true do end", &[], &[]),
+            Err(ScriptError::TooManySteps)
+        );
+        // the copies add up to the memory budget before the string gets too large
+        assert_eq!(
+            eval("local s = 'xx' while true do s = s .. s end", &[], &[]),
+            Err(ScriptError::OutOfMemory)
+        );
+    }
+
+    #[test]
+    fn test_script_memory_limit() {
+        // none of the strings is large, but every concatenation creates a new 2 MB one
+        let src = "
+            local s = 'x'
+            while #s < 1024 * 1024 do s = s .. s end
+            while true do local t = s .. s end
+        ";
+        assert_eq!(eval(src, &[], &[]), Err(ScriptError::OutOfMemory));
+    }
+
+    #[test]
+    fn test_script_memory_limit_arrays() {
+        // an array that keeps growing is stopped just like a string that does
+        let src = "
+            local s = 'x'
+            while #s < 64 * 1024 do s = s .. s end
+            local t = {}
+            while true do t[#t + 1] = s end
+        ";
+        assert_eq!(eval(src, &[], &[]), Err(ScriptError::OutOfMemory));
+    }
+
+    #[test]
+    fn test_script_cache() {
+        let src = b"return 'cached'";
+        let (sha, _) = load(src).unwrap();
+        assert_eq!(sha, digest(src));
+        assert!(exists(&sha.to_ascii_uppercase()));
+        assert!(get(&sha).is_some());
+    }
+
+    #[test]
+    fn test_script_cache_is_bounded() {
+        let scripts = ScriptCache::new();
+        for i in 0..MAX_CACHED_SCRIPTS + 10 {
+            cache(&scripts, i.to_string(), Arc::new(Vec::new()));
+        }
+        assert_eq!(scripts.len(), MAX_CACHED_SCRIPTS);
+        assert!(scripts.contains_key(&(MAX_CACHED_SCRIPTS + 9).to_string()));
+    }
+}
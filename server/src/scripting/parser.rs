@@ -0,0 +1,483 @@
+/*
+ * Created on Tue Sep 14 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The parser
+//!
+//! A recursive descent parser that turns [`Token`]s into a [`Block`]. The grammar is a
+//! small subset of Lua's:
+//! ```text
+//! block := { stmt [';'] }
+//! stmt  := 'local' NAME '=' expr | NAME '=' expr | NAME '[' expr ']' '=' expr
+//!        | 'if' expr 'then' block { 'elseif' expr 'then' block } [ 'else' block ] 'end'
+//!        | 'while' expr 'do' block 'end' | 'break' | 'return' [ expr ] | call
+//! expr  := the usual binary and unary operators over:
+//!          nil | true | false | INT | STRING | NAME | call | expr '[' expr ']'
+//!        | '{' [ expr { ',' expr } ] '}' | '(' expr ')'
+//! call  := NAME '(' [ expr { ',' expr } ] ')'
+//! ```
+
+use super::lexer::{Spanned, Token};
+use super::ScriptError;
+
+pub type Block = Vec<Stmt>;
+
+#[derive(Debug, PartialEq)]
+pub enum Stmt {
+    Local(String, Expr),
+    Assign(String, Expr),
+    /// `name[index] = value`
+    SetIndex(String, Expr, Expr),
+    If(Vec<(Expr, Block)>, Option<Block>),
+    While(Expr, Block),
+    Break,
+    Return(Option<Expr>),
+    Call(Expr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Concat,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnOp {
+    Not,
+    Neg,
+    Len,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Expr {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Str(Vec<u8>),
+    Var(String),
+    Index(Box<Expr>, Box<Expr>),
+    /// A call to a builtin function, along with the line it was made on
+    Call(String, Vec<Expr>, usize),
+    Array(Vec<Expr>),
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl BinOp {
+    /// Returns the binding power of the operator (the higher, the tighter)
+    const fn precedence(&self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge => 3,
+            Self::Concat => 4,
+            Self::Add | Self::Sub => 5,
+            Self::Mul | Self::Div | Self::Mod => 6,
+        }
+    }
+    const fn is_right_assoc(&self) -> bool {
+        matches!(self, Self::Concat)
+    }
+    fn from_token(token: &Token) -> Option<Self> {
+        let op = match token {
+            Token::Or => Self::Or,
+            Token::And => Self::And,
+            Token::Eq => Self::Eq,
+            Token::Ne => Self::Ne,
+            Token::Lt => Self::Lt,
+            Token::Le => Self::Le,
+            Token::Gt => Self::Gt,
+            Token::Ge => Self::Ge,
+            Token::Concat => Self::Concat,
+            Token::Plus => Self::Add,
+            Token::Minus => Self::Sub,
+            Token::Star => Self::Mul,
+            Token::Slash => Self::Div,
+            Token::Percent => Self::Mod,
+            _ => return None,
+        };
+        Some(op)
+    }
+}
+
+/// The binding power of unary operators; tighter than all binary operators
+const UNARY_PRECEDENCE: u8 = 7;
+/// The maximum nesting depth of blocks and expressions, so that a script can't overflow
+/// the parser's (or the interpreter's) stack. Chains of operators or indexing nest one level
+/// deeper with every operand
+const MAX_DEPTH: usize = 128;
+
+pub struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Spanned>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            depth: 0,
+        }
+    }
+    /// Parse the whole script
+    pub fn parse(mut self) -> Result<Block, ScriptError> {
+        let block = self.block()?;
+        match self.peek() {
+            None => Ok(block),
+            Some(token) => Err(self.unexpected(token)),
+        }
+    }
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+    fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n).map(|(token, _)| token)
+    }
+    /// The line of the current token (or the last line, at the end of the script)
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map(|(_, line)| *line)
+            .unwrap_or(1)
+    }
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+    fn unexpected(&self, token: &Token) -> ScriptError {
+        ScriptError::compile(self.line(), format!("unexpected {:?}", token))
+    }
+    fn expect(&mut self, expected: Token) -> Result<(), ScriptError> {
+        match self.peek() {
+            Some(token) if *token == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(token) => Err(self.unexpected(token)),
+            None => Err(ScriptError::compile(
+                self.line(),
+                format!("expected {:?}, but the script ended", expected),
+            )),
+        }
+    }
+    fn name(&mut self) -> Result<String, ScriptError> {
+        match self.next() {
+            Some(Token::Name(name)) => Ok(name),
+            Some(token) => {
+                self.pos -= 1;
+                Err(self.unexpected(&token))
+            }
+            None => Err(ScriptError::compile(
+                self.line(),
+                "expected a name, but the script ended",
+            )),
+        }
+    }
+    fn enter(&mut self) -> Result<(), ScriptError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            Err(ScriptError::compile(
+                self.line(),
+                "script is nested too deeply",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+    fn block(&mut self) -> Result<Block, ScriptError> {
+        self.enter()?;
+        let mut block = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(Token::End | Token::Else | Token::Elseif) => break,
+                Some(Token::Semicolon) => self.pos += 1,
+                Some(_) => {
+                    let stmt = self.stmt()?;
+                    let is_return = matches!(stmt, Stmt::Return(_));
+                    block.push(stmt);
+                    if is_return {
+                        // like Lua, return has to be the last statement in a block
+                        if let Some(Token::Semicolon) = self.peek() {
+                            self.pos += 1;
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        self.depth -= 1;
+        Ok(block)
+    }
+    fn stmt(&mut self) -> Result<Stmt, ScriptError> {
+        let stmt = match self.peek() {
+            Some(Token::Local) => {
+                self.pos += 1;
+                let name = self.name()?;
+                self.expect(Token::Assign)?;
+                Stmt::Local(name, self.expr()?)
+            }
+            Some(Token::If) => {
+                self.pos += 1;
+                let mut branches = Vec::new();
+                let cond = self.expr()?;
+                self.expect(Token::Then)?;
+                branches.push((cond, self.block()?));
+                let mut otherwise = None;
+                loop {
+                    match self.next() {
+                        Some(Token::Elseif) => {
+                            let cond = self.expr()?;
+                            self.expect(Token::Then)?;
+                            branches.push((cond, self.block()?));
+                        }
+                        Some(Token::Else) => {
+                            otherwise = Some(self.block()?);
+                            self.expect(Token::End)?;
+                            break;
+                        }
+                        Some(Token::End) => break,
+                        _ => {
+                            return Err(ScriptError::compile(self.line(), "expected 'end'"));
+                        }
+                    }
+                }
+                Stmt::If(branches, otherwise)
+            }
+            Some(Token::While) => {
+                self.pos += 1;
+                let cond = self.expr()?;
+                self.expect(Token::Do)?;
+                let body = self.block()?;
+                self.expect(Token::End)?;
+                Stmt::While(cond, body)
+            }
+            Some(Token::Break) => {
+                self.pos += 1;
+                Stmt::Break
+            }
+            Some(Token::Return) => {
+                self.pos += 1;
+                match self.peek() {
+                    None | Some(Token::End | Token::Else | Token::Elseif | Token::Semicolon) => {
+                        Stmt::Return(None)
+                    }
+                    Some(_) => Stmt::Return(Some(self.expr()?)),
+                }
+            }
+            Some(Token::Name(_)) => match self.peek_nth(1) {
+                Some(Token::Assign) => {
+                    let name = self.name()?;
+                    self.pos += 1;
+                    Stmt::Assign(name, self.expr()?)
+                }
+                Some(Token::LBracket) => {
+                    let name = self.name()?;
+                    self.pos += 1;
+                    let index = self.expr()?;
+                    self.expect(Token::RBracket)?;
+                    self.expect(Token::Assign)?;
+                    Stmt::SetIndex(name, index, self.expr()?)
+                }
+                Some(Token::LParen) => Stmt::Call(self.primary()?),
+                Some(token) => {
+                    let token = token.clone();
+                    self.pos += 1;
+                    return Err(self.unexpected(&token));
+                }
+                None => {
+                    return Err(ScriptError::compile(self.line(), "unfinished statement"));
+                }
+            },
+            Some(token) => return Err(self.unexpected(token)),
+            None => unreachable!("block() doesn't parse statements at the end of the script"),
+        };
+        Ok(stmt)
+    }
+    fn expr(&mut self) -> Result<Expr, ScriptError> {
+        self.binary(0)
+    }
+    /// Parse a binary expression where all operators bind tighter than `min_precedence`
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, ScriptError> {
+        self.enter()?;
+        let depth = self.depth;
+        let mut lhs = self.unary()?;
+        while let Some(op) = self.peek().and_then(BinOp::from_token) {
+            let precedence = op.precedence();
+            if precedence <= min_precedence {
+                break;
+            }
+            self.pos += 1;
+            // every operand that is folded into `lhs` nests it one level deeper
+            self.enter()?;
+            let rhs = if op.is_right_assoc() {
+                self.binary(precedence - 1)?
+            } else {
+                self.binary(precedence)?
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        self.depth = depth - 1;
+        Ok(lhs)
+    }
+    fn unary(&mut self) -> Result<Expr, ScriptError> {
+        let op = match self.peek() {
+            Some(Token::Not) => UnOp::Not,
+            Some(Token::Minus) => UnOp::Neg,
+            Some(Token::Hash) => UnOp::Len,
+            _ => return self.postfix(),
+        };
+        self.pos += 1;
+        Ok(Expr::Unary(op, Box::new(self.binary(UNARY_PRECEDENCE)?)))
+    }
+    fn postfix(&mut self) -> Result<Expr, ScriptError> {
+        let depth = self.depth;
+        let mut expr = self.primary()?;
+        while let Some(Token::LBracket) = self.peek() {
+            self.pos += 1;
+            self.enter()?;
+            let index = self.expr()?;
+            self.expect(Token::RBracket)?;
+            expr = Expr::Index(Box::new(expr), Box::new(index));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+    fn primary(&mut self) -> Result<Expr, ScriptError> {
+        let line = self.line();
+        let expr = match self.next() {
+            Some(Token::Nil) => Expr::Nil,
+            Some(Token::True) => Expr::Bool(true),
+            Some(Token::False) => Expr::Bool(false),
+            Some(Token::Int(int)) => Expr::Int(int),
+            Some(Token::Str(string)) => Expr::Str(string),
+            Some(Token::Name(name)) => {
+                if let Some(Token::LParen) = self.peek() {
+                    self.pos += 1;
+                    Expr::Call(name, self.list(Token::RParen)?, line)
+                } else {
+                    Expr::Var(name)
+                }
+            }
+            Some(Token::LBrace) => Expr::Array(self.list(Token::RBrace)?),
+            Some(Token::LParen) => {
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                expr
+            }
+            Some(token) => {
+                self.pos -= 1;
+                return Err(self.unexpected(&token));
+            }
+            None => {
+                return Err(ScriptError::compile(
+                    line,
+                    "expected an expression, but the script ended",
+                ))
+            }
+        };
+        Ok(expr)
+    }
+    /// Parse a comma separated list of expressions, up to (and including) `close`
+    fn list(&mut self, close: Token) -> Result<Vec<Expr>, ScriptError> {
+        let mut list = Vec::new();
+        if self.peek() == Some(&close) {
+            self.pos += 1;
+            return Ok(list);
+        }
+        loop {
+            list.push(self.expr()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(token) if token == close => return Ok(list),
+                _ => {
+                    return Err(ScriptError::compile(
+                        self.line(),
+                        format!("expected ',' or {:?}", close),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(src: &str) -> Result<Block, ScriptError> {
+    Parser::new(super::lexer::tokenize(src.as_bytes())?).parse()
+}
+
+#[test]
+fn test_parse_precedence() {
+    let block = parse("return 1 + 2 * 3 .. 'x' .. 'y'").unwrap();
+    let mul = Expr::Binary(BinOp::Mul, Box::new(Expr::Int(2)), Box::new(Expr::Int(3)));
+    let add = Expr::Binary(BinOp::Add, Box::new(Expr::Int(1)), Box::new(mul));
+    let tail = Expr::Binary(
+        BinOp::Concat,
+        Box::new(Expr::Str(b"x".to_vec())),
+        Box::new(Expr::Str(b"y".to_vec())),
+    );
+    assert_eq!(
+        block,
+        vec![Stmt::Return(Some(Expr::Binary(
+            BinOp::Concat,
+            Box::new(add),
+            Box::new(tail)
+        )))]
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(parse("if x then return 1").is_err());
+    assert!(parse("local = 1").is_err());
+    assert!(parse("x + 1").is_err());
+    assert!(parse(&"(".repeat(1000)).is_err());
+}
+
+#[test]
+fn test_parse_long_chains() {
+    // left associative chains nest as deeply as they're long
+    let long_sum = format!("return 1{}", " + 1".repeat(100_000));
+    assert!(parse(&long_sum).is_err());
+    let long_index = format!("return x{}", "[1]".repeat(100_000));
+    assert!(parse(&long_index).is_err());
+    let short_sum = format!("return 1{}", " + 1".repeat(100));
+    assert!(parse(&short_sum).is_ok());
+}
@@ -25,25 +25,75 @@
 */
 
 use crate::config::BGSave;
+use crate::corestore::lazy::Lazy;
 use crate::corestore::Corestore;
 use crate::dbnet::Terminator;
 use crate::registry;
+use crate::registry::stats;
 use crate::storage;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
 use libsky::TResult;
+use std::time::Instant;
+use tokio::sync::Notify;
 use tokio::time::{self, Duration};
 
+/// Whether BGSAVE is enabled
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// The BGSAVE interval in seconds. This is kept even if BGSAVE is disabled
+static EVERY: AtomicU64 = AtomicU64::new(BGSAVE_DEFAULT_EVERY);
+/// Wakes up the scheduler when the BGSAVE configuration is changed
+static RECONFIGURED: Lazy<Notify, fn() -> Notify> = Lazy::new(Notify::new);
+
+const ORD_RLX: Ordering = Ordering::Relaxed;
+const BGSAVE_DEFAULT_EVERY: u64 = 120;
+
+/// Set the BGSAVE configuration. If the scheduler is running, it will pick up the
+/// new configuration immediately
+pub fn configure(bgsave_cfg: &BGSave) {
+    match bgsave_cfg {
+        BGSave::Enabled(every) => {
+            EVERY.store(*every, ORD_RLX);
+            ENABLED.store(true, ORD_RLX);
+        }
+        BGSave::Disabled => ENABLED.store(false, ORD_RLX),
+    }
+    RECONFIGURED.notify_one();
+}
+
+/// Returns the current BGSAVE configuration
+pub fn config() -> BGSave {
+    if ENABLED.load(ORD_RLX) {
+        BGSave::Enabled(EVERY.load(ORD_RLX))
+    } else {
+        BGSave::Disabled
+    }
+}
+
+/// Set the BGSAVE interval without enabling or disabling BGSAVE
+pub fn set_every(every: u64) {
+    EVERY.store(every, ORD_RLX);
+    RECONFIGURED.notify_one();
+}
+
+/// Returns the BGSAVE interval in seconds (even if BGSAVE is disabled)
+pub fn every() -> u64 {
+    EVERY.load(ORD_RLX)
+}
+
 /// The bgsave_scheduler calls the bgsave task in `Corestore` after `every` seconds
 ///
 /// The time after which the scheduler will wake up the BGSAVE task is determined by
-/// `bgsave_cfg` which is to be passed as an argument. If BGSAVE is disabled, this function
-/// immediately returns
-pub async fn bgsave_scheduler(handle: Corestore, bgsave_cfg: BGSave, mut terminator: Terminator) {
-    match bgsave_cfg {
-        BGSave::Enabled(duration) => {
-            // If we're here - the user doesn't trust his power supply or just values
-            // his data - which is good! So we'll turn this into a `Duration`
-            let duration = Duration::from_secs(duration);
-            loop {
+/// the configuration set with [`configure`]. If BGSAVE is disabled, the scheduler just
+/// waits until it is either enabled or the server shuts down
+pub async fn bgsave_scheduler(handle: Corestore, mut terminator: Terminator) {
+    loop {
+        match config() {
+            BGSave::Enabled(duration) => {
+                // If we're here - the user doesn't trust his power supply or just values
+                // his data - which is good! So we'll turn this into a `Duration`
+                let duration = Duration::from_secs(duration);
                 tokio::select! {
                     // Sleep until `duration` from the current time instant
                     _ = time::sleep_until(time::Instant::now() + duration) => {
@@ -55,6 +105,8 @@ pub async fn bgsave_scheduler(handle: Corestore, bgsave_cfg: BGSave, mut termina
                             let _ = bgsave_blocking_section(owned_handle);
                         }).await.expect("Something caused the background service to panic");
                     }
+                    // the configuration changed, so start over with the new interval
+                    _ = RECONFIGURED.notified() => {}
                     // Otherwise wait for a notification
                     _ = terminator.receive_signal() => {
                         // we got a notification to quit; so break out
@@ -62,9 +114,14 @@ pub async fn bgsave_scheduler(handle: Corestore, bgsave_cfg: BGSave, mut termina
                     }
                 }
             }
-        }
-        BGSave::Disabled => {
-            // the user doesn't bother about his data; cool, let's not bother about it either
+            BGSave::Disabled => {
+                // the user doesn't bother about his data; cool, let's not bother about it either
+                // unless they change their mind
+                tokio::select! {
+                    _ = RECONFIGURED.notified() => {}
+                    _ = terminator.receive_signal() => break,
+                }
+            }
         }
     }
     log::info!("BGSAVE service has exited");
@@ -79,16 +136,20 @@ pub fn run_bgsave(handle: &Corestore) -> TResult<()> {
 
 /// This just wraps around [`_bgsave_blocking_section`] and prints nice log messages depending on the outcome
 fn bgsave_blocking_section(handle: Corestore) -> bool {
-    registry::lock_flush_state();
+    // hold the flush lock for the entire flush, so that DDL queries wait for us
+    let _flush_lock = registry::lock_flush_state();
+    let start = Instant::now();
     match run_bgsave(&handle) {
         Ok(_) => {
             log::info!("BGSAVE completed successfully");
             registry::unpoison();
+            stats::bgsave_finished(true, start.elapsed());
             true
         }
         Err(e) => {
             log::error!("BGSAVE failed with error: {}", e);
             registry::poison();
+            stats::bgsave_finished(false, start.elapsed());
             false
         }
     }
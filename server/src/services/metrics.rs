@@ -0,0 +1,404 @@
+/*
+ * Created on Tue Sep 07 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # The metrics exporter
+//!
+//! This service serves the server statistics in the Prometheus text exposition format
+//! over plain HTTP on a local port. Every scrape renders a fresh set of values
+
+use crate::config::MetricsConfig;
+use crate::corestore::Corestore;
+use crate::dbnet::Terminator;
+use crate::registry;
+use crate::registry::stats::{self, Histogram};
+use core::fmt::Display;
+use core::fmt::Write;
+use std::io::Result as IoResult;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{self, Duration};
+
+/// The exporter is only ever bound to the loopback interface
+const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+/// We won't read more than this for the request head
+const MAX_REQUEST_SIZE: usize = 4096;
+/// A client that takes longer than this to be served is dropped
+const SERVE_TIMEOUT: Duration = Duration::from_secs(5);
+const NOT_FOUND: &[u8] =
+    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".as_bytes();
+
+/// Bind to the metrics port if the exporter is enabled
+pub async fn bind(metrics_cfg: &MetricsConfig) -> IoResult<Option<TcpListener>> {
+    match metrics_cfg {
+        MetricsConfig::Enabled(port) => {
+            let listener = TcpListener::bind(SocketAddr::new(LOCALHOST, *port)).await?;
+            log::info!("Serving metrics on http://{}:{}/metrics", LOCALHOST, port);
+            Ok(Some(listener))
+        }
+        MetricsConfig::Disabled => Ok(None),
+    }
+}
+
+/// The metrics service
+///
+/// Scrapes are served one at a time (they're infrequent anyway) so that no copy of the
+/// [`Corestore`] handle outlives this service once the termination signal is received
+pub async fn metrics_service(
+    handle: Corestore,
+    listener: Option<TcpListener>,
+    mut terminator: Terminator,
+) {
+    let listener = match listener {
+        Some(listener) => listener,
+        None => {
+            // the exporter is disabled
+            return;
+        }
+    };
+    loop {
+        tokio::select! {
+            con = listener.accept() => {
+                if let Ok((stream, _)) = con {
+                    match time::timeout(SERVE_TIMEOUT, serve(&handle, stream)).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => log::warn!("Failed to serve metrics: {}", e),
+                        Err(_) => log::warn!("Metrics client timed out"),
+                    }
+                }
+            }
+            _ = terminator.receive_signal() => {
+                break;
+            }
+        }
+    }
+    log::info!("Metrics service has exited");
+}
+
+/// Read the request head and respond with the metrics (or a 404)
+async fn serve(handle: &Corestore, mut stream: TcpStream) -> IoResult<()> {
+    let mut request = Vec::with_capacity(512);
+    let mut chunk = [0u8; 512];
+    loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&chunk[..read]);
+        if request.windows(4).any(|w| w == b"\r\n\r\n") || request.len() > MAX_REQUEST_SIZE {
+            break;
+        }
+    }
+    if request.starts_with(b"GET /metrics") {
+        let body = render(handle);
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+    } else {
+        stream.write_all(NOT_FOUND).await?;
+    }
+    stream.shutdown().await
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+fn histogram<const N: usize>(out: &mut String, name: &str, labels: &str, hist: &Histogram<N>) {
+    let sep = if labels.is_empty() { "" } else { "," };
+    for (bound, count) in hist.cumulative_buckets() {
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}{}le=\"{}\"}} {}",
+            name,
+            labels,
+            sep,
+            bound as f64 / 1_000_000.0,
+            count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{}_bucket{{{}{}le=\"+Inf\"}} {}",
+        name,
+        labels,
+        sep,
+        hist.count()
+    );
+    sample(
+        out,
+        &format!("{}_sum", name),
+        labels,
+        hist.sum_micros() as f64 / 1_000_000.0,
+    );
+    sample(out, &format!("{}_count", name), labels, hist.count());
+}
+
+/// Render all the metrics in the Prometheus text format
+fn render(handle: &Corestore) -> String {
+    let mut out = String::new();
+    header(
+        &mut out,
+        "skytable_uptime_seconds",
+        "gauge",
+        "Seconds since the server started",
+    );
+    sample(&mut out, "skytable_uptime_seconds", "", stats::uptime());
+    header(
+        &mut out,
+        "skytable_poisoned",
+        "gauge",
+        "Whether writes are disabled due to a persistence failure",
+    );
+    sample(
+        &mut out,
+        "skytable_poisoned",
+        "",
+        !registry::state_okay() as u8,
+    );
+
+    // clients
+    header(
+        &mut out,
+        "skytable_connections",
+        "gauge",
+        "Number of connected clients",
+    );
+    sample(
+        &mut out,
+        "skytable_connections",
+        "",
+        stats::active_connections(),
+    );
+    header(
+        &mut out,
+        "skytable_max_connections",
+        "gauge",
+        "Maximum number of clients (maxcon)",
+    );
+    sample(
+        &mut out,
+        "skytable_max_connections",
+        "",
+        stats::max_connections(),
+    );
+    header(
+        &mut out,
+        "skytable_connections_total",
+        "counter",
+        "Number of connections accepted",
+    );
+    sample(
+        &mut out,
+        "skytable_connections_total",
+        "",
+        stats::total_connections(),
+    );
+    header(
+        &mut out,
+        "skytable_commands_processed_total",
+        "counter",
+        "Number of queries processed",
+    );
+    sample(
+        &mut out,
+        "skytable_commands_processed_total",
+        "",
+        stats::commands_processed(),
+    );
+    header(
+        &mut out,
+        "skytable_bytes_in_total",
+        "counter",
+        "Bytes read from clients",
+    );
+    sample(&mut out, "skytable_bytes_in_total", "", stats::bytes_in());
+    header(
+        &mut out,
+        "skytable_bytes_out_total",
+        "counter",
+        "Bytes written to clients",
+    );
+    sample(&mut out, "skytable_bytes_out_total", "", stats::bytes_out());
+
+    // actions
+    let actions: Vec<_> = stats::action_latencies().iter().collect();
+    header(
+        &mut out,
+        "skytable_action_total",
+        "counter",
+        "Number of times an action was run",
+    );
+    for action in actions.iter() {
+        let labels = format!("action=\"{}\"", action.key());
+        sample(
+            &mut out,
+            "skytable_action_total",
+            &labels,
+            action.value().count(),
+        );
+    }
+    header(
+        &mut out,
+        "skytable_action_duration_seconds",
+        "histogram",
+        "Action latencies",
+    );
+    for action in actions.iter() {
+        let labels = format!("action=\"{}\"", action.key());
+        histogram(
+            &mut out,
+            "skytable_action_duration_seconds",
+            &labels,
+            action.value(),
+        );
+    }
+    drop(actions);
+
+    // persistence
+    header(
+        &mut out,
+        "skytable_bgsave_total",
+        "counter",
+        "Number of BGSAVE runs",
+    );
+    sample(&mut out, "skytable_bgsave_total", "", stats::bgsave_runs());
+    header(
+        &mut out,
+        "skytable_bgsave_failures_total",
+        "counter",
+        "Number of failed BGSAVE runs",
+    );
+    sample(
+        &mut out,
+        "skytable_bgsave_failures_total",
+        "",
+        stats::bgsave_failures(),
+    );
+    header(
+        &mut out,
+        "skytable_bgsave_duration_seconds",
+        "histogram",
+        "BGSAVE durations",
+    );
+    histogram(
+        &mut out,
+        "skytable_bgsave_duration_seconds",
+        "",
+        stats::bgsave_latency(),
+    );
+    header(
+        &mut out,
+        "skytable_snapshot_total",
+        "counter",
+        "Number of snapshots created",
+    );
+    sample(
+        &mut out,
+        "skytable_snapshot_total",
+        "",
+        stats::snapshots_created(),
+    );
+    header(
+        &mut out,
+        "skytable_snapshot_failures_total",
+        "counter",
+        "Number of failed snapshots",
+    );
+    sample(
+        &mut out,
+        "skytable_snapshot_failures_total",
+        "",
+        stats::snapshots_failed(),
+    );
+    header(
+        &mut out,
+        "skytable_snapshot_duration_seconds",
+        "histogram",
+        "Snapshot durations",
+    );
+    histogram(
+        &mut out,
+        "skytable_snapshot_duration_seconds",
+        "",
+        stats::snapshot_latency(),
+    );
+
+    // keyspaces and tables
+    header(
+        &mut out,
+        "skytable_table_keys",
+        "gauge",
+        "Number of keys in a table",
+    );
+    for ks in handle.get_store().keyspaces.iter() {
+        let ksid = String::from_utf8_lossy(ks.key());
+        for tbl in ks.value().tables.iter() {
+            let labels = format!(
+                "keyspace=\"{}\",table=\"{}\"",
+                ksid,
+                String::from_utf8_lossy(tbl.key())
+            );
+            sample(
+                &mut out,
+                "skytable_table_keys",
+                &labels,
+                tbl.value().count(),
+            );
+        }
+    }
+    out
+}
+
+#[test]
+fn test_render_metrics() {
+    use crate::corestore::memstore::Memstore;
+    use crate::storage::sengine::SnapshotEngine;
+    use std::sync::Arc;
+    let handle = Corestore::default_with_store(
+        Memstore::new_default(),
+        Arc::new(SnapshotEngine::new_disabled()),
+    );
+    stats::observe_action("HEYA", Duration::from_micros(150));
+    let rendered = render(&handle);
+    assert!(rendered.contains("# TYPE skytable_action_duration_seconds histogram\n"));
+    assert!(rendered
+        .contains("skytable_action_duration_seconds_bucket{action=\"HEYA\",le=\"0.00025\"} "));
+    assert!(rendered.contains("skytable_bgsave_duration_seconds_bucket{le=\"+Inf\"} "));
+    assert!(rendered.contains("skytable_table_keys{keyspace=\"default\",table=\"default\"} 0\n"));
+}
@@ -25,4 +25,6 @@
 */
 
 pub mod bgsave;
+pub mod metrics;
+pub mod reload;
 pub mod snapshot;
@@ -0,0 +1,142 @@
+/*
+ * Created on Sat Sep 11 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Configuration reloads
+//!
+//! The configuration file can be re-read while the server is running, either by sending
+//! `SIGHUP` to `skyd` or by running `SYS RELOAD`. Only the runtime-safe settings are applied:
+//! the TLS certificates, BGSAVE, the snapshot interval, retention and failsafe, the connection
+//! limits and the slowlog. Existing connections are kept; changes to anything else (like the
+//! host or the ports) need a restart
+
+use crate::config::{self, ParsedConfig, PortConfig, SnapshotConfig};
+use crate::corestore::Corestore;
+use crate::dbnet::{limits, tls, Terminator};
+use crate::registry::slowlog;
+use crate::services::{bgsave, snapshot};
+
+/// Re-read the configuration file and apply the runtime-safe settings. If the file
+/// cannot be read or is invalid, nothing is changed
+pub fn reload(handle: &Corestore) -> Result<(), String> {
+    match config::config_file() {
+        Some(filename) => reload_from(handle, &filename),
+        None => Err("the server wasn't started with a configuration file".to_owned()),
+    }
+}
+
+/// Read the configuration file at `filename` and apply the runtime-safe settings
+fn reload_from(handle: &Corestore, filename: &str) -> Result<(), String> {
+    let ParsedConfig {
+        bgsave: bgsave_cfg,
+        snapshot: snapshot_cfg,
+        ports,
+        maxcon,
+        slowlog: slowlog_cfg,
+        limits: limits_cfg,
+        ..
+    } = config::parse_config_file(filename).map_err(|e| e.to_string().trim().to_owned())?;
+    // reload the certificates first because this is the only thing that can fail
+    match ports {
+        PortConfig::SecureOnly { ssl, .. } | PortConfig::Multi { ssl, .. } => {
+            let reloaded = tls::reload_certificates(&ssl)
+                .map_err(|e| format!("failed to reload TLS certificates: {}", e))?;
+            if !reloaded {
+                log::warn!("TLS wasn't enabled on startup. Restart the server to enable it");
+            }
+        }
+        PortConfig::InsecureOnly { .. } => {}
+    }
+    bgsave::configure(&bgsave_cfg);
+    let engine = handle.get_engine();
+    match snapshot_cfg {
+        SnapshotConfig::Enabled(pref) if engine.is_enabled() => snapshot::configure(engine, &pref),
+        SnapshotConfig::Enabled(_) => {
+            log::warn!("Snapshots weren't enabled on startup. Restart the server to enable them")
+        }
+        SnapshotConfig::Disabled if engine.is_enabled() => {
+            log::warn!("Snapshots were enabled on startup. Restart the server to disable them")
+        }
+        SnapshotConfig::Disabled => {}
+    }
+    limits::set_maxcon(maxcon);
+    limits::init(&limits_cfg);
+    slowlog::init(&slowlog_cfg);
+    log::info!("Reloaded configuration from {}", filename);
+    Ok(())
+}
+
+#[cfg(unix)]
+/// Reload the configuration whenever the server receives a `SIGHUP`
+pub async fn sighup_service(handle: Corestore, mut terminator: Terminator) {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => sighup,
+        Err(e) => {
+            log::error!("Failed to bind to SIGHUP with: {}", e);
+            return;
+        }
+    };
+    loop {
+        tokio::select! {
+            _ = sighup.recv() => {
+                if let Err(e) = reload(&handle) {
+                    log::error!("Failed to reload configuration: {}", e);
+                }
+            }
+            _ = terminator.receive_signal() => break,
+        }
+    }
+    log::info!("Reload service has exited");
+}
+
+#[test]
+fn test_reload_from() {
+    use crate::config::runtime;
+    use crate::corestore::memstore::Memstore;
+    use crate::storage::sengine::SnapshotEngine;
+    use std::{env, fs, sync::Arc};
+    let handle = Corestore::default_with_store(
+        Memstore::new_default(),
+        Arc::new(SnapshotEngine::new_disabled()),
+    );
+    let mut filename = env::temp_dir();
+    filename.push("skyd-test-reload.toml");
+    let filename = filename.to_str().unwrap().to_owned();
+    let server = "[server]\nhost = \"127.0.0.1\"\nport = 2003\n";
+    fs::write(&filename, format!("{}[bgsave]\nevery = 321\n", server)).unwrap();
+    let reloaded = reload_from(&handle, &filename);
+    // an invalid file changes nothing
+    fs::write(&filename, format!("{}[bgsave]\nevery = 0\n", server)).unwrap();
+    let rejected = reload_from(&handle, &filename);
+    fs::remove_file(&filename).unwrap();
+    reloaded.unwrap();
+    assert!(rejected.is_err());
+    // the new interval is what `CONFIG GET` returns
+    assert_eq!(
+        runtime::get(handle.get_engine(), "bgsave.every").unwrap(),
+        "321"
+    );
+}
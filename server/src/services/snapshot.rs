@@ -25,13 +25,50 @@
 */
 
 use crate::config::SnapshotConfig;
+use crate::config::SnapshotPref;
+use crate::corestore::lazy::Lazy;
 use crate::corestore::Corestore;
 use crate::dbnet::Terminator;
 use crate::registry;
 use crate::storage::sengine::SnapshotEngine;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
 use std::sync::Arc;
+use tokio::sync::Notify;
 use tokio::time::{self, Duration};
 
+const ORD_RLX: Ordering = Ordering::Relaxed;
+
+/// The snapshot interval in seconds
+static EVERY: AtomicU64 = AtomicU64::new(0);
+/// Whether the database should be poisoned if snapshotting fails
+static FAILSAFE: AtomicBool = AtomicBool::new(true);
+/// Wakes up the snapshot service when the configuration is changed
+static RECONFIGURED: Lazy<Notify, fn() -> Notify> = Lazy::new(Notify::new);
+
+/// Update the snapshot interval, the failsafe and the number of snapshots that the
+/// engine keeps. If the service is running, it will pick up the new interval immediately
+pub fn configure(engine: &SnapshotEngine, pref: &SnapshotPref) {
+    EVERY.store(pref.every, ORD_RLX);
+    FAILSAFE.store(pref.poison, ORD_RLX);
+    engine.set_maxlen(pref.atmost);
+    RECONFIGURED.notify_one();
+}
+
+/// Returns the current snapshot configuration
+pub fn config(engine: &SnapshotEngine) -> SnapshotConfig {
+    if engine.is_enabled() {
+        SnapshotConfig::Enabled(SnapshotPref::new(
+            EVERY.load(ORD_RLX),
+            engine.maxlen(),
+            FAILSAFE.load(ORD_RLX),
+        ))
+    } else {
+        SnapshotConfig::Disabled
+    }
+}
+
 /// The snapshot service
 ///
 /// This service calls `SnapEngine::mksnap()` periodically to create snapshots. Whenever
@@ -52,19 +89,24 @@ pub async fn snapshot_service(
         }
         SnapshotConfig::Enabled(configuration) => {
             let (duration, _, failsafe) = configuration.decompose();
-            let duration = Duration::from_secs(duration);
+            EVERY.store(duration, ORD_RLX);
+            FAILSAFE.store(failsafe, ORD_RLX);
             loop {
+                // the configuration may be changed at runtime, so load it on every iteration
+                let duration = Duration::from_secs(EVERY.load(ORD_RLX));
                 tokio::select! {
                     _ = time::sleep_until(time::Instant::now() + duration) => {
                         if engine.mksnap(handle.clone_store()).await == 0 {
                             // it passed, so unpoison the handle
                             registry::unpoison();
-                        } else if failsafe {
+                        } else if FAILSAFE.load(ORD_RLX) {
                             // mksnap returned false and we are set to stop writes if snapshotting failed
                             // so let's poison the handle
                             registry::poison();
                         }
                     },
+                    // the configuration changed, so start over with the new interval
+                    _ = RECONFIGURED.notified() => {},
                     _ = termination_signal.receive_signal() => {
                         // time to terminate; goodbye!
                         break;
@@ -40,6 +40,50 @@ pub const BYTEMARK_MODEL_KV_BIN_STR: u8 = 1;
 pub const BYTEMARK_MODEL_KV_STR_STR: u8 = 2;
 /// KVE model bytemark with key:str, val:bin
 pub const BYTEMARK_MODEL_KV_STR_BIN: u8 = 3;
+/// KVE model bytemark with key:bin, val:uint
+pub const BYTEMARK_MODEL_KV_BIN_UINT: u8 = 4;
+/// KVE model bytemark with key:bin, val:sint
+pub const BYTEMARK_MODEL_KV_BIN_SINT: u8 = 5;
+/// KVE model bytemark with key:bin, val:float
+pub const BYTEMARK_MODEL_KV_BIN_FLOAT: u8 = 6;
+/// KVE model bytemark with key:bin, val:bool
+pub const BYTEMARK_MODEL_KV_BIN_BOOL: u8 = 7;
+/// KVE model bytemark with key:str, val:uint
+pub const BYTEMARK_MODEL_KV_STR_UINT: u8 = 8;
+/// KVE model bytemark with key:str, val:sint
+pub const BYTEMARK_MODEL_KV_STR_SINT: u8 = 9;
+/// KVE model bytemark with key:str, val:float
+pub const BYTEMARK_MODEL_KV_STR_FLOAT: u8 = 10;
+/// KVE model bytemark with key:str, val:bool
+pub const BYTEMARK_MODEL_KV_STR_BOOL: u8 = 11;
+/// Hash model bytemark with key+field:bin, val:bin
+pub const BYTEMARK_MODEL_HASH_BIN_BIN: u8 = 12;
+/// Hash model bytemark with key+field:bin, val:str
+pub const BYTEMARK_MODEL_HASH_BIN_STR: u8 = 13;
+/// Hash model bytemark with key+field:str, val:str
+pub const BYTEMARK_MODEL_HASH_STR_STR: u8 = 14;
+/// Hash model bytemark with key+field:str, val:bin
+pub const BYTEMARK_MODEL_HASH_STR_BIN: u8 = 15;
+/// Set model bytemark with key:bin, member:bin
+pub const BYTEMARK_MODEL_SET_BIN_BIN: u8 = 16;
+/// Set model bytemark with key:bin, member:str
+pub const BYTEMARK_MODEL_SET_BIN_STR: u8 = 17;
+/// Set model bytemark with key:str, member:str
+pub const BYTEMARK_MODEL_SET_STR_STR: u8 = 18;
+/// Set model bytemark with key:str, member:bin
+pub const BYTEMARK_MODEL_SET_STR_BIN: u8 = 19;
+/// Sorted set model bytemark with key:bin, member:bin
+pub const BYTEMARK_MODEL_SORTEDSET_BIN_BIN: u8 = 20;
+/// Sorted set model bytemark with key:bin, member:str
+pub const BYTEMARK_MODEL_SORTEDSET_BIN_STR: u8 = 21;
+/// Sorted set model bytemark with key:str, member:str
+pub const BYTEMARK_MODEL_SORTEDSET_STR_STR: u8 = 22;
+/// Sorted set model bytemark with key:str, member:bin
+pub const BYTEMARK_MODEL_SORTEDSET_STR_BIN: u8 = 23;
+/// Time series model bytemark with key:bin
+pub const BYTEMARK_MODEL_TIMESERIES_BIN: u8 = 24;
+/// Time series model bytemark with key:str
+pub const BYTEMARK_MODEL_TIMESERIES_STR: u8 = 25;
 
 // storage bym
 /// Persistent storage bytemark
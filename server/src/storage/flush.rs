@@ -33,6 +33,7 @@ use super::interface;
 use crate::corestore::memstore::Keyspace;
 use crate::corestore::memstore::Memstore;
 use crate::corestore::memstore::ObjectID;
+use crate::corestore::table::Table;
 use crate::registry;
 use crate::IoResult;
 
@@ -59,6 +60,69 @@ pub fn flush_full(store: &Memstore) -> IoResult<()> {
     Ok(())
 }
 
+/// Persist a renamed table. The table is written under its new name before the partmap is
+/// switched over to it and only then is the older file removed, so that a crash at any point
+/// leaves behind a partmap that only points to complete files
+pub fn flush_renamed_table(
+    ksid: &ObjectID,
+    keyspace: &Keyspace,
+    old_tblid: &ObjectID,
+    new_tblid: &ObjectID,
+) -> IoResult<()> {
+    interface::create_keyspace_tree(ksid)?;
+    if let Some(table) = keyspace.get_table_atomic_ref(new_tblid) {
+        self::oneshot::flush_table(new_tblid, ksid, &table)?;
+    }
+    self::oneshot::flush_partmap(ksid, keyspace)?;
+    interface::remove_table_file(ksid, old_tblid)
+}
+
+/// Persist a renamed keyspace. The keyspace (along with its partmap) is written under its
+/// new name before the preload is switched over to it and only then is the older directory
+/// removed
+pub fn flush_renamed_keyspace(
+    store: &Memstore,
+    old_ksid: &ObjectID,
+    new_ksid: &ObjectID,
+) -> IoResult<()> {
+    if let Some(keyspace) = store.get_keyspace_atomic_ref(new_ksid) {
+        interface::create_keyspace_tree(new_ksid)?;
+        self::oneshot::flush_keyspace(new_ksid, &keyspace)?;
+        self::oneshot::flush_partmap(new_ksid, &keyspace)?;
+    }
+    interface::create_tree(store)?;
+    self::oneshot::flush_preload(store)?;
+    interface::remove_keyspace_tree(old_ksid)
+}
+
+/// Persist a table whose volatility was changed. A table that is now persistent is written
+/// before the partmap is updated, while the file of a table that is now volatile is only
+/// removed after the partmap is updated
+pub fn flush_altered_table(
+    ksid: &ObjectID,
+    keyspace: &Keyspace,
+    tblid: &ObjectID,
+    table: &Table,
+) -> IoResult<()> {
+    interface::create_keyspace_tree(ksid)?;
+    // this is a no-op for volatile tables
+    self::oneshot::flush_table(tblid, ksid, table)?;
+    self::oneshot::flush_partmap(ksid, keyspace)?;
+    if table.is_volatile() {
+        interface::remove_table_file(ksid, tblid)?;
+    }
+    Ok(())
+}
+
+/// Persist the quotas of a keyspace, which are stored in its partmap. The tables of the
+/// keyspace are written first, so that the partmap never points to a table that isn't on
+/// disk yet
+pub fn flush_keyspace_quotas(ksid: &ObjectID, keyspace: &Keyspace) -> IoResult<()> {
+    interface::create_keyspace_tree(ksid)?;
+    self::oneshot::flush_keyspace(ksid, keyspace)?;
+    self::oneshot::flush_partmap(ksid, keyspace)
+}
+
 pub fn snap_flush_keyspace_full(
     snapdir: &str,
     snapid: &str,
@@ -127,7 +191,30 @@ pub mod oneshot {
                     DataModel::KV(kve) => super::interface::serialize_map_into_slow_buffer(
                         &mut file,
                         kve.__get_inner_ref(),
+                        kve.version(),
+                    )?,
+                    DataModel::Hash(hash) => {
+                        super::interface::serialize_nested_map_into_slow_buffer(
+                            &mut file,
+                            hash.__get_inner_ref(),
+                        )?
+                    }
+                    DataModel::Set(set) => super::interface::serialize_nested_set_into_slow_buffer(
+                        &mut file,
+                        set.__get_inner_ref(),
                     )?,
+                    DataModel::SortedSet(zset) => {
+                        super::interface::serialize_nested_sortedset_into_slow_buffer(
+                            &mut file,
+                            zset.__get_inner_ref(),
+                        )?
+                    }
+                    DataModel::TimeSeries(ts) => {
+                        super::interface::serialize_nested_timeseries_into_slow_buffer(
+                            &mut file,
+                            ts.__get_inner_ref(),
+                        )?
+                    }
                 }
                 file.sync_all()?;
                 fs::rename(&$path, &$path[..$path.len() - 1])
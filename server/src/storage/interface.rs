@@ -30,11 +30,17 @@ use crate::corestore::htable::Coremap;
 use crate::corestore::htable::Data;
 use crate::corestore::memstore::Keyspace;
 use crate::corestore::memstore::Memstore;
+use crate::corestore::memstore::ObjectID;
+use crate::kvengine::hashmap::FieldMap;
+use crate::kvengine::hashset::MemberSet;
+use crate::kvengine::sortedset::ScoreSet;
+use crate::kvengine::timeseries::Series;
+use crate::kvengine::Versioned;
 use crate::registry;
 use crate::IoResult;
 use std::collections::HashSet;
 use std::fs;
-use std::io::{BufWriter, Write};
+use std::io::{BufWriter, ErrorKind, Write};
 
 pub const DIR_KSROOT: &str = "data/ks";
 pub const DIR_SNAPROOT: &str = "data/snaps";
@@ -70,6 +76,43 @@ pub fn create_tree(memroot: &Memstore) -> IoResult<()> {
     Ok(())
 }
 
+/// Create the directory of a single keyspace, ignoring it if it exists
+pub fn create_keyspace_tree(ksid: &ObjectID) -> IoResult<()> {
+    unsafe { try_dir_ignore_existing!(concat_path!(DIR_KSROOT, ksid.as_str())) }
+}
+
+/// Remove the directory of a keyspace along with all of its files, ignoring it if it
+/// doesn't exist (say, because the keyspace was never flushed)
+pub fn remove_keyspace_tree(ksid: &ObjectID) -> IoResult<()> {
+    let ks_path = unsafe { concat_path!(DIR_KSROOT, ksid.as_str()) };
+    ignore_not_found(fs::remove_dir_all(ks_path))
+}
+
+/// Remove the data file of a table, ignoring it if it doesn't exist (say, because the
+/// table is volatile)
+pub fn remove_table_file(ksid: &ObjectID, tblid: &ObjectID) -> IoResult<()> {
+    let tbl_path = unsafe { concat_path!(DIR_KSROOT, ksid.as_str(), tblid.as_str()) };
+    ignore_not_found(fs::remove_file(tbl_path))
+}
+
+/// Returns the metadata of the data file of a table, or `None` if it doesn't exist (say,
+/// because the table is volatile or was never flushed)
+pub fn table_file_metadata(ksid: &ObjectID, tblid: &ObjectID) -> IoResult<Option<fs::Metadata>> {
+    let tbl_path = unsafe { concat_path!(DIR_KSROOT, ksid.as_str(), tblid.as_str()) };
+    match fs::metadata(tbl_path) {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn ignore_not_found(result: IoResult<()>) -> IoResult<()> {
+    match result {
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
 pub fn snap_create_tree(snapdir: &str, snapid: &str, memroot: &Memstore) -> IoResult<()> {
     for ks in memroot.keyspaces.iter() {
         unsafe {
@@ -126,10 +169,59 @@ pub fn cleanup_tree(memroot: &Memstore) -> IoResult<()> {
 /// is important that you fsync yourself!
 pub fn serialize_map_into_slow_buffer<T: Write>(
     buffer: &mut T,
-    map: &Coremap<Data, Data>,
+    map: &Coremap<Data, Versioned>,
+    version: u64,
+) -> IoResult<()> {
+    let mut buffer = BufWriter::new(buffer);
+    super::se::raw_serialize_versioned_map(map, version, &mut buffer)?;
+    buffer.flush()?;
+    Ok(())
+}
+
+/// Same as [`serialize_map_into_slow_buffer`], but for a map of maps (like the one of a hash
+/// table)
+pub fn serialize_nested_map_into_slow_buffer<T: Write>(
+    buffer: &mut T,
+    map: &Coremap<Data, FieldMap>,
+) -> IoResult<()> {
+    let mut buffer = BufWriter::new(buffer);
+    super::se::raw_serialize_nested_map(map, &mut buffer)?;
+    buffer.flush()?;
+    Ok(())
+}
+
+/// Same as [`serialize_map_into_slow_buffer`], but for a map of sets (like the one of a set
+/// table)
+pub fn serialize_nested_set_into_slow_buffer<T: Write>(
+    buffer: &mut T,
+    map: &Coremap<Data, MemberSet>,
+) -> IoResult<()> {
+    let mut buffer = BufWriter::new(buffer);
+    super::se::raw_serialize_nested_set(map, &mut buffer)?;
+    buffer.flush()?;
+    Ok(())
+}
+
+/// Same as [`serialize_map_into_slow_buffer`], but for a map of sorted sets (like the one of
+/// a sorted set table)
+pub fn serialize_nested_sortedset_into_slow_buffer<T: Write>(
+    buffer: &mut T,
+    map: &Coremap<Data, ScoreSet>,
+) -> IoResult<()> {
+    let mut buffer = BufWriter::new(buffer);
+    super::se::raw_serialize_nested_sortedset(map, &mut buffer)?;
+    buffer.flush()?;
+    Ok(())
+}
+
+/// Same as [`serialize_map_into_slow_buffer`], but for a map of time series (like the one of
+/// a time series table)
+pub fn serialize_nested_timeseries_into_slow_buffer<T: Write>(
+    buffer: &mut T,
+    map: &Coremap<Data, Series>,
 ) -> IoResult<()> {
     let mut buffer = BufWriter::new(buffer);
-    super::se::raw_serialize_map(map, &mut buffer)?;
+    super::se::raw_serialize_nested_timeseries(map, &mut buffer)?;
     buffer.flush()?;
     Ok(())
 }
@@ -45,17 +45,25 @@
 //! ## Safety
 //!
 //! > Trust me, all methods are bombingly unsafe. They do such crazy things that you might not
-//! think of using them anywhere outside. This is a specialized parser built for the database.
-//! -- Sayan (July 2021)
+//! > think of using them anywhere outside. This is a specialized parser built for the database.
+//! > -- Sayan (July 2021)
 
 use crate::corestore::array::Array;
 use crate::corestore::htable::Coremap;
+use crate::corestore::quota::{Quotas, QUOTA_COUNT};
 use crate::corestore::Data;
+use crate::kvengine::hashmap::FieldMap;
+use crate::kvengine::hashset::MemberSet;
+use crate::kvengine::sortedset::ScoreSet;
+use crate::kvengine::timeseries::Series;
+use crate::kvengine::Versioned;
+use core::cmp;
 use core::hash::Hash;
 use core::mem;
 use core::ptr;
 use core::slice;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::io::Write;
 // for some astronomical reasons do not mess with this
 #[macro_use]
@@ -71,6 +79,11 @@ pub mod unflush;
 #[cfg(test)]
 mod tests;
 
+/// The magic that the files of key/value tables start with, since values have versions.
+/// Older files start with the number of entries instead, which is never anywhere close to
+/// the integer that these bytes make up
+const VERSIONED_MAP_MAGIC: [u8; 8] = *b"SKYKVV01";
+
 /*
     Endian and pointer "appendix":
     We assume a fixed size of 1 for all the cases. All sizes don't hit over isize::MAX as
@@ -175,7 +188,7 @@ mod tests;
 /// DISCLAIMER: THIS FUNCTION CAN DO TERRIBLE THINGS
 unsafe fn raw_byte_repr<'a, T: 'a>(len: &'a T) -> &'a [u8] {
     {
-        let ptr: *const u8 = mem::transmute(len);
+        let ptr = len as *const T as *const u8;
         slice::from_raw_parts::<'a>(ptr, mem::size_of::<T>())
     }
 }
@@ -184,29 +197,49 @@ mod se {
     use super::*;
     use crate::corestore::memstore::Keyspace;
     #[cfg(test)]
-    /// Serialize a map into a _writable_ thing
+    /// Serialize a map into a _writable_ thing. This is the format of key/value tables
+    /// before values had versions, which we still need to be able to read
     pub fn serialize_map(map: &Coremap<Data, Data>) -> Result<Vec<u8>, std::io::Error> {
         /*
         [LEN:8B][KLEN:8B|VLEN:8B][K][V][KLEN:8B][VLEN:8B]...
         */
         // write the len header first
         let mut w = Vec::with_capacity(128);
-        self::raw_serialize_map(map, &mut w)?;
+        unsafe {
+            w.write_all(raw_byte_repr(&to_64bit_little_endian!(map.len())))?;
+            // now the keys and values
+            for kv in map.iter() {
+                let (k, v) = (kv.key(), kv.value());
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(k.len())))?;
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(v.len())))?;
+                w.write_all(k)?;
+                w.write_all(v)?;
+            }
+        }
         Ok(w)
     }
 
-    /// Serialize a map and write it to a provided buffer
-    pub fn raw_serialize_map<W: Write>(
-        map: &Coremap<Data, Data>,
+    /// Serialize a map of versioned values (like the one of a key/value table) along with
+    /// the last version handed out by the table, and write it to a provided buffer. Since
+    /// the map may be written to while we serialize it, some values can have versions that
+    /// are newer than `version`
+    pub fn raw_serialize_versioned_map<W: Write>(
+        map: &Coremap<Data, Versioned>,
+        version: u64,
         w: &mut W,
     ) -> std::io::Result<()> {
+        /*
+        [MAGIC:8B][VERSION:8B][LEN:8B]([KLEN:8B][VLEN:8B][VVERSION:8B][K][V])*
+        */
         unsafe {
+            w.write_all(&VERSIONED_MAP_MAGIC)?;
+            w.write_all(&version.to_le_bytes())?;
             w.write_all(raw_byte_repr(&to_64bit_little_endian!(map.len())))?;
-            // now the keys and values
             for kv in map.iter() {
                 let (k, v) = (kv.key(), kv.value());
                 w.write_all(raw_byte_repr(&to_64bit_little_endian!(k.len())))?;
                 w.write_all(raw_byte_repr(&to_64bit_little_endian!(v.len())))?;
+                w.write_all(&v.version().to_le_bytes())?;
                 w.write_all(k)?;
                 w.write_all(v)?;
             }
@@ -214,6 +247,129 @@ mod se {
         Ok(())
     }
 
+    /// Serialize a map of maps (like the one of a hash table) and write it to a provided
+    /// buffer
+    pub fn raw_serialize_nested_map<W: Write>(
+        map: &Coremap<Data, FieldMap>,
+        w: &mut W,
+    ) -> std::io::Result<()> {
+        /*
+        [LEN:8B]([KLEN:8B][K][FLEN:8B]([FKLEN:8B][FVLEN:8B][FK][FV])*)*
+        */
+        unsafe {
+            w.write_all(raw_byte_repr(&to_64bit_little_endian!(map.len())))?;
+            for kv in map.iter() {
+                let (k, fields) = (kv.key(), kv.value());
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(k.len())))?;
+                w.write_all(k)?;
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(fields.len())))?;
+                for (field, value) in fields.iter() {
+                    w.write_all(raw_byte_repr(&to_64bit_little_endian!(field.len())))?;
+                    w.write_all(raw_byte_repr(&to_64bit_little_endian!(value.len())))?;
+                    w.write_all(field)?;
+                    w.write_all(value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize a map of sets (like the one of a set table) and write it to a provided
+    /// buffer
+    pub fn raw_serialize_nested_set<W: Write>(
+        map: &Coremap<Data, MemberSet>,
+        w: &mut W,
+    ) -> std::io::Result<()> {
+        /*
+        [LEN:8B]([KLEN:8B][K][MLEN:8B]([MKLEN:8B][MK])*)*
+        */
+        unsafe {
+            w.write_all(raw_byte_repr(&to_64bit_little_endian!(map.len())))?;
+            for kv in map.iter() {
+                let (k, members) = (kv.key(), kv.value());
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(k.len())))?;
+                w.write_all(k)?;
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(members.len())))?;
+                for member in members.iter() {
+                    w.write_all(raw_byte_repr(&to_64bit_little_endian!(member.len())))?;
+                    w.write_all(member)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize a map of sorted sets (like the one of a sorted set table) and write it to
+    /// a provided buffer. Scores are written as the little endian bytes of their `f64`
+    /// representation
+    pub fn raw_serialize_nested_sortedset<W: Write>(
+        map: &Coremap<Data, ScoreSet>,
+        w: &mut W,
+    ) -> std::io::Result<()> {
+        /*
+        [LEN:8B]([KLEN:8B][K][MLEN:8B]([SCORE:8B][MKLEN:8B][MK])*)*
+        */
+        unsafe {
+            w.write_all(raw_byte_repr(&to_64bit_little_endian!(map.len())))?;
+            for kv in map.iter() {
+                let (k, members) = (kv.key(), kv.value());
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(k.len())))?;
+                w.write_all(k)?;
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(members.len())))?;
+                for (member, score) in members.iter() {
+                    w.write_all(&score.to_le_bytes())?;
+                    w.write_all(raw_byte_repr(&to_64bit_little_endian!(member.len())))?;
+                    w.write_all(member)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize a map of time series (like the one of a time series table) and write it to
+    /// a provided buffer. Since samples are ordered by time, only the first timestamp and the
+    /// difference of every other timestamp to the one before it are written, as LEB128
+    /// varints (so that a sample every few seconds costs a couple of bytes instead of 8),
+    /// while values are written as the little endian bytes of their `f64` representation
+    pub fn raw_serialize_nested_timeseries<W: Write>(
+        map: &Coremap<Data, Series>,
+        w: &mut W,
+    ) -> std::io::Result<()> {
+        /*
+        [LEN:8B]([KLEN:8B][K][RETENTION:8B][SLEN:8B]([TSDELTA:VARINT][VALUE:8B])*)*
+        */
+        unsafe {
+            w.write_all(raw_byte_repr(&to_64bit_little_endian!(map.len())))?;
+            for kv in map.iter() {
+                let (k, series) = (kv.key(), kv.value());
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(k.len())))?;
+                w.write_all(k)?;
+                w.write_all(&series.retention().to_le_bytes())?;
+                w.write_all(raw_byte_repr(&to_64bit_little_endian!(series.len())))?;
+                let mut previous = 0;
+                for (timestamp, value) in series.iter() {
+                    write_varint(w, timestamp - previous)?;
+                    w.write_all(&value.to_le_bytes())?;
+                    previous = timestamp;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write an unsigned integer as an LEB128 varint: seven bits at a time, least significant
+    /// bits first, with the high bit of every byte but the last one set
+    fn write_varint<W: Write>(w: &mut W, mut int: u64) -> std::io::Result<()> {
+        loop {
+            let byte = (int & 0x7F) as u8;
+            int >>= 7;
+            if int == 0 {
+                return w.write_all(&[byte]);
+            }
+            w.write_all(&[byte | 0x80])?;
+        }
+    }
+
     /// Serialize a set and write it to a provided buffer
     pub fn raw_serialize_set<W, K, V>(map: &Coremap<K, V>, w: &mut W) -> std::io::Result<()>
     where
@@ -234,8 +390,12 @@ mod se {
 
     /// Generate a partition map for the given keyspace
     /// ```text
-    /// [8B: EXTENT]([8B: LEN][?B: PARTITION ID][1B: Storage type][1B: Model type])*
+    /// [8B: EXTENT]([8B: LEN][?B: PARTITION ID][1B: Storage type][1B: Model type])*[QUOTAS]?
     /// ```
+    /// If any of the quotas of the keyspace has a limit, then the limits follow the
+    /// partitions as `[8B: LIMIT]` in the order of [`Quota`](crate::corestore::quota::Quota).
+    /// Otherwise they are left out, so that keyspaces without quotas have the same partmap
+    /// as they had before quotas existed
     pub fn raw_serialize_partmap<W: Write>(w: &mut W, keyspace: &Keyspace) -> std::io::Result<()> {
         unsafe {
             // extent
@@ -252,6 +412,12 @@ mod se {
                 // now model type
                 w.write_all(raw_byte_repr(&table.get_model_code()))?;
             }
+            let quotas = keyspace.quotas();
+            if !quotas.is_unlimited() {
+                for limit in quotas.to_array().iter() {
+                    w.write_all(raw_byte_repr(&to_64bit_little_endian!(*limit)))?;
+                }
+            }
         }
         Ok(())
     }
@@ -261,6 +427,9 @@ mod de {
     use super::*;
     use std::collections::HashMap;
 
+    /// A set with a 2x1B _bytemark_ for every entry
+    type BytemarkSet<T> = HashMap<T, (u8, u8)>;
+
     pub trait DeserializeFrom {
         fn is_expected_len(clen: usize) -> bool;
         fn from_slice(slice: &[u8]) -> Self;
@@ -328,8 +497,35 @@ mod de {
         }
     }
 
-    /// Deserializes a map-like set which has an 2x1B _bytemark_ for every entry
-    pub fn deserialize_set_ctype_bytemark<T>(data: &[u8]) -> Option<HashMap<T, (u8, u8)>>
+    /// Deserializes a partmap: the partitions (see
+    /// [`deserialize_set_ctype_bytemark_prefix`]) followed by the limits of the quotas, if
+    /// the keyspace has any
+    pub fn deserialize_partmap<T>(data: &[u8]) -> Option<(BytemarkSet<T>, Quotas)>
+    where
+        T: DeserializeFrom + Eq + Hash,
+    {
+        let (set, consumed) = self::deserialize_set_ctype_bytemark_prefix(data)?;
+        let rest = &data[consumed..];
+        if rest.is_empty() {
+            // no limits
+            return Some((set, Quotas::new()));
+        }
+        if rest.len() != QUOTA_COUNT * 8 {
+            return None;
+        }
+        let mut limits = [0u64; QUOTA_COUNT];
+        for (limit, raw) in limits.iter_mut().zip(rest.chunks_exact(8)) {
+            *limit = u64::from_le_bytes(unsafe {
+                // SAFETY: We just checked that every chunk has 8 bytes
+                ptr::read_unaligned(raw.as_ptr().cast())
+            });
+        }
+        Some((set, Quotas::from_array(limits)))
+    }
+
+    /// Deserializes a map-like set which has an 2x1B _bytemark_ for every entry from the
+    /// start of `data`, returning the set along with the number of bytes that it took up
+    fn deserialize_set_ctype_bytemark_prefix<T>(data: &[u8]) -> Option<(BytemarkSet<T>, usize)>
     where
         T: DeserializeFrom + Eq + Hash,
     {
@@ -375,12 +571,7 @@ mod de {
                         return None;
                     }
                 }
-                if ptr == end_ptr {
-                    Some(set)
-                } else {
-                    // nope, someone gave us more data
-                    None
-                }
+                Some((set, ptr as usize - data.as_ptr() as usize))
             }
         }
     }
@@ -441,6 +632,337 @@ mod de {
         }
     }
 
+    /// Deserialize a file that contains a serialized map of versioned values (see
+    /// [`se::raw_serialize_versioned_map`]), returning the map along with the last version
+    /// handed out by the table (which is atleast the version of every value). Files written before values had versions are read with
+    /// [`deserialize_map`] and all their values are at version `0`
+    pub fn deserialize_versioned_map(data: Vec<u8>) -> Option<(Coremap<Data, Versioned>, u64)> {
+        if !data.starts_with(&VERSIONED_MAP_MAGIC) {
+            let hm = deserialize_map(data)?
+                .into_iter()
+                .map(|(key, value)| (key, Versioned::new(value, 0)))
+                .collect();
+            return Some((hm, 0));
+        }
+        if data.len() < 24 {
+            // no version or length header
+            None
+        } else {
+            unsafe {
+                // UNSAFE(@ohsayan): See deserialize_map; we never read beyond end_ptr
+                let mut ptr = data.as_ptr().add(8);
+                let mut version = u64::from_le_bytes(ptr::read_unaligned(ptr.cast()));
+                ptr = ptr.add(8);
+                let len = transmute_len(ptr);
+                ptr = ptr.add(8);
+                let hm = Coremap::with_capacity(len);
+                let end_ptr = data.as_ptr().add(data.len());
+                for _ in 0..len {
+                    // this is what we have: [KLEN:8B][VLEN:8B][VVERSION:8B]
+                    if (ptr.add(24)) > end_ptr {
+                        return None;
+                    }
+                    let lenkey = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    let lenval = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    let vversion = u64::from_le_bytes(ptr::read_unaligned(ptr.cast()));
+                    ptr = ptr.add(8);
+                    if (ptr.add(lenkey + lenval)) > end_ptr {
+                        // not enough data left
+                        return None;
+                    }
+                    let key = Data::copy_from_slice(slice::from_raw_parts(ptr, lenkey));
+                    ptr = ptr.add(lenkey);
+                    let val = Data::copy_from_slice(slice::from_raw_parts(ptr, lenval));
+                    ptr = ptr.add(lenval);
+                    version = cmp::max(version, vversion);
+                    hm.upsert(key, Versioned::new(val, vversion));
+                }
+                if ptr == end_ptr {
+                    Some((hm, version))
+                } else {
+                    // nope, someone gave us more data
+                    None
+                }
+            }
+        }
+    }
+
+    /// Deserialize a file that contains a serialized map of maps (see
+    /// [`se::raw_serialize_nested_map`])
+    pub fn deserialize_nested_map(data: Vec<u8>) -> Option<Coremap<Data, FieldMap>> {
+        if data.len() < 8 {
+            // no length header
+            None
+        } else {
+            unsafe {
+                // UNSAFE(@ohsayan): See deserialize_map; we never read beyond end_ptr
+                let mut ptr = data.as_ptr();
+                let len = transmute_len(ptr);
+                ptr = ptr.add(8);
+                let hm = Coremap::with_capacity(len);
+                let end_ptr = data.as_ptr().add(data.len());
+                for _ in 0..len {
+                    // this is what we have: [KLEN:8B][K][FLEN:8B]
+                    if (ptr.add(8)) > end_ptr {
+                        return None;
+                    }
+                    let lenkey = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    if (ptr.add(lenkey + 8)) > end_ptr {
+                        return None;
+                    }
+                    let key = Data::copy_from_slice(slice::from_raw_parts(ptr, lenkey));
+                    ptr = ptr.add(lenkey);
+                    let lenfields = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    // every field needs atleast 16B, so don't trust a corrupted length
+                    let mut fields = FieldMap::with_capacity(cmp::min(
+                        lenfields,
+                        end_ptr.offset_from(ptr) as usize / 16,
+                    ));
+                    // and then the fields: ([FKLEN:8B][FVLEN:8B][FK][FV])*
+                    for _ in 0..lenfields {
+                        if (ptr.add(16)) > end_ptr {
+                            return None;
+                        }
+                        let lenfield = transmute_len(ptr);
+                        ptr = ptr.add(8);
+                        let lenval = transmute_len(ptr);
+                        ptr = ptr.add(8);
+                        if (ptr.add(lenfield + lenval)) > end_ptr {
+                            return None;
+                        }
+                        let field = Data::copy_from_slice(slice::from_raw_parts(ptr, lenfield));
+                        ptr = ptr.add(lenfield);
+                        let val = Data::copy_from_slice(slice::from_raw_parts(ptr, lenval));
+                        ptr = ptr.add(lenval);
+                        fields.insert(field, val);
+                    }
+                    hm.upsert(key, fields);
+                }
+                if ptr == end_ptr {
+                    Some(hm)
+                } else {
+                    // nope, someone gave us more data
+                    None
+                }
+            }
+        }
+    }
+
+    /// Deserialize a file that contains a serialized map of sets (see
+    /// [`se::raw_serialize_nested_set`])
+    pub fn deserialize_nested_set(data: Vec<u8>) -> Option<Coremap<Data, MemberSet>> {
+        if data.len() < 8 {
+            // no length header
+            None
+        } else {
+            unsafe {
+                // UNSAFE(@ohsayan): See deserialize_map; we never read beyond end_ptr
+                let mut ptr = data.as_ptr();
+                let len = transmute_len(ptr);
+                ptr = ptr.add(8);
+                let hm = Coremap::with_capacity(len);
+                let end_ptr = data.as_ptr().add(data.len());
+                for _ in 0..len {
+                    // this is what we have: [KLEN:8B][K][MLEN:8B]
+                    if (ptr.add(8)) > end_ptr {
+                        return None;
+                    }
+                    let lenkey = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    if (ptr.add(lenkey + 8)) > end_ptr {
+                        return None;
+                    }
+                    let key = Data::copy_from_slice(slice::from_raw_parts(ptr, lenkey));
+                    ptr = ptr.add(lenkey);
+                    let lenmembers = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    // every member needs atleast 8B, so don't trust a corrupted length
+                    let mut members = MemberSet::with_capacity(cmp::min(
+                        lenmembers,
+                        end_ptr.offset_from(ptr) as usize / 8,
+                    ));
+                    // and then the members: ([MKLEN:8B][MK])*
+                    for _ in 0..lenmembers {
+                        if (ptr.add(8)) > end_ptr {
+                            return None;
+                        }
+                        let lenmember = transmute_len(ptr);
+                        ptr = ptr.add(8);
+                        if (ptr.add(lenmember)) > end_ptr {
+                            return None;
+                        }
+                        let member = Data::copy_from_slice(slice::from_raw_parts(ptr, lenmember));
+                        ptr = ptr.add(lenmember);
+                        if !members.insert(member) {
+                            // repeat?; that's not what we wanted
+                            return None;
+                        }
+                    }
+                    hm.upsert(key, members);
+                }
+                if ptr == end_ptr {
+                    Some(hm)
+                } else {
+                    // nope, someone gave us more data
+                    None
+                }
+            }
+        }
+    }
+
+    /// Deserialize a file that contains a serialized map of sorted sets (see
+    /// [`se::raw_serialize_nested_sortedset`])
+    pub fn deserialize_nested_sortedset(data: Vec<u8>) -> Option<Coremap<Data, ScoreSet>> {
+        if data.len() < 8 {
+            // no length header
+            None
+        } else {
+            unsafe {
+                // UNSAFE(@ohsayan): See deserialize_map; we never read beyond end_ptr
+                let mut ptr = data.as_ptr();
+                let len = transmute_len(ptr);
+                ptr = ptr.add(8);
+                let hm = Coremap::with_capacity(len);
+                let end_ptr = data.as_ptr().add(data.len());
+                for _ in 0..len {
+                    // this is what we have: [KLEN:8B][K][MLEN:8B]
+                    if (ptr.add(8)) > end_ptr {
+                        return None;
+                    }
+                    let lenkey = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    if (ptr.add(lenkey + 8)) > end_ptr {
+                        return None;
+                    }
+                    let key = Data::copy_from_slice(slice::from_raw_parts(ptr, lenkey));
+                    ptr = ptr.add(lenkey);
+                    let lenmembers = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    let mut members = ScoreSet::new();
+                    // and then the members: ([SCORE:8B][MKLEN:8B][MK])*
+                    for _ in 0..lenmembers {
+                        if (ptr.add(16)) > end_ptr {
+                            return None;
+                        }
+                        let score = f64::from_le_bytes(ptr::read_unaligned(ptr.cast()));
+                        ptr = ptr.add(8);
+                        let lenmember = transmute_len(ptr);
+                        ptr = ptr.add(8);
+                        if (ptr.add(lenmember)) > end_ptr {
+                            return None;
+                        }
+                        let member = Data::copy_from_slice(slice::from_raw_parts(ptr, lenmember));
+                        ptr = ptr.add(lenmember);
+                        if !score.is_finite() || members.insert(member, score).is_some() {
+                            // a bad score or a repeat?; that's not what we wanted
+                            return None;
+                        }
+                    }
+                    hm.upsert(key, members);
+                }
+                if ptr == end_ptr {
+                    Some(hm)
+                } else {
+                    // nope, someone gave us more data
+                    None
+                }
+            }
+        }
+    }
+
+    /// Deserialize a file that contains a serialized map of time series (see
+    /// [`se::raw_serialize_nested_timeseries`])
+    pub fn deserialize_nested_timeseries(data: Vec<u8>) -> Option<Coremap<Data, Series>> {
+        if data.len() < 8 {
+            // no length header
+            None
+        } else {
+            unsafe {
+                // UNSAFE(@ohsayan): See deserialize_map; we never read beyond end_ptr
+                let mut ptr = data.as_ptr();
+                let len = transmute_len(ptr);
+                ptr = ptr.add(8);
+                let hm = Coremap::with_capacity(len);
+                let end_ptr = data.as_ptr().add(data.len());
+                for _ in 0..len {
+                    // this is what we have: [KLEN:8B][K][RETENTION:8B][SLEN:8B]
+                    if (ptr.add(8)) > end_ptr {
+                        return None;
+                    }
+                    let lenkey = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    if (ptr.add(lenkey + 16)) > end_ptr {
+                        return None;
+                    }
+                    let key = Data::copy_from_slice(slice::from_raw_parts(ptr, lenkey));
+                    ptr = ptr.add(lenkey);
+                    let retention = u64::from_le_bytes(ptr::read_unaligned(ptr.cast()));
+                    ptr = ptr.add(8);
+                    let lensamples = transmute_len(ptr);
+                    ptr = ptr.add(8);
+                    // every sample needs atleast 9B, so don't trust a corrupted length
+                    let mut samples = VecDeque::with_capacity(cmp::min(
+                        lensamples,
+                        end_ptr.offset_from(ptr) as usize / 9,
+                    ));
+                    // and then the samples: ([TSDELTA:VARINT][VALUE:8B])*
+                    let mut timestamp: u64 = 0;
+                    for i in 0..lensamples {
+                        let (delta, next) = read_varint(ptr, end_ptr)?;
+                        ptr = next;
+                        if (ptr.add(8)) > end_ptr {
+                            return None;
+                        }
+                        let value = f64::from_le_bytes(ptr::read_unaligned(ptr.cast()));
+                        ptr = ptr.add(8);
+                        if (i != 0 && delta == 0) || !value.is_finite() {
+                            // out of order or a bad value?; that's not what we wanted
+                            return None;
+                        }
+                        timestamp = timestamp.checked_add(delta)?;
+                        samples.push_back((timestamp, value));
+                    }
+                    hm.upsert(key, Series::with_samples(retention, samples)?);
+                }
+                if ptr == end_ptr {
+                    Some(hm)
+                } else {
+                    // nope, someone gave us more data
+                    None
+                }
+            }
+        }
+    }
+
+    /// Read an LEB128 varint (see `se::write_varint`) starting at `ptr`, returning the
+    /// integer and a pointer to the byte right after it. Returns `None` if the varint
+    /// doesn't end before `end_ptr` or doesn't fit in 64 bits
+    unsafe fn read_varint(mut ptr: *const u8, end_ptr: *const u8) -> Option<(u64, *const u8)> {
+        let mut int: u64 = 0;
+        let mut shift = 0;
+        loop {
+            if ptr >= end_ptr || shift > 63 {
+                return None;
+            }
+            let byte = *ptr;
+            ptr = ptr.add(1);
+            let bits = (byte & 0x7F) as u64;
+            if shift == 63 && bits > 1 {
+                // that's more than 64 bits
+                return None;
+            }
+            int |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Some((int, ptr));
+            }
+            shift += 7;
+        }
+    }
+
     #[allow(clippy::needless_return)] // Clippy really misunderstands this
     pub(super) unsafe fn transmute_len(start_ptr: *const u8) -> usize {
         little_endian!({
@@ -34,6 +34,7 @@
 
 use crate::corestore::memstore::Memstore;
 use crate::corestore::memstore::ObjectID;
+use crate::corestore::quota::Quotas;
 use crate::IoResult;
 use core::ptr;
 use std::collections::HashMap;
@@ -88,9 +89,9 @@ pub(super) fn read_preload_raw(preload: Vec<u8>) -> IoResult<HashSet<ObjectID>>
     }
 }
 
-/// Reads the partfile and returns a set
-pub fn read_partfile_raw(partfile: Vec<u8>) -> IoResult<LoadedPartfile> {
-    match super::de::deserialize_set_ctype_bytemark(&partfile) {
+/// Reads the partfile and returns a set along with the quotas of the keyspace
+pub fn read_partfile_raw(partfile: Vec<u8>) -> IoResult<(LoadedPartfile, Quotas)> {
+    match super::de::deserialize_partmap(&partfile) {
         Some(s) => Ok(s),
         None => Err(IoError::from(ErrorKind::InvalidData)),
     }
@@ -29,16 +29,20 @@ use super::interface::DIR_SNAPROOT;
 use crate::corestore::iarray::IArray;
 use crate::corestore::lazy::Lazy;
 use crate::corestore::lock::QuickLock;
+use crate::registry::stats;
 use crate::storage::interface::DIR_RSNAPROOT;
 use crate::Memstore;
 use bytes::Bytes;
 use chrono::prelude::Utc;
 use core::fmt;
 use core::str;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
 use regex::Regex;
 use std::fs;
 use std::io::Error as IoError;
 use std::sync::Arc;
+use std::time::Instant;
 
 type QStore = IArray<[String; 64]>;
 type SnapshotResult<T> = Result<T, SnapshotEngineError>;
@@ -84,10 +88,25 @@ impl fmt::Display for SnapshotEngineError {
     }
 }
 
+/// The state of the local snapshot queue, as reported by `SYS INFO`
+pub struct QueueState {
+    /// whether local snapshots are enabled
+    pub enabled: bool,
+    /// whether a snapshot is currently being created
+    pub busy: bool,
+    /// the number of snapshots in the queue (unknown if busy)
+    pub len: usize,
+    /// the maximum number of snapshots to keep (`0` means unlimited)
+    pub maxlen: usize,
+}
+
 /// The snapshot engine
 #[derive(Debug)]
 pub struct SnapshotEngine {
     local_enabled: bool,
+    /// the maximum number of local snapshots to keep. This is applied to the queue when
+    /// the next snapshot is created
+    local_maxlen: AtomicUsize,
     /// the local snapshot queue
     local_queue: QuickLock<Queue>,
     /// the remote snapshot lock
@@ -118,6 +137,7 @@ impl SnapshotEngine {
     pub const fn new(maxlen: usize) -> Self {
         Self {
             local_enabled: true,
+            local_maxlen: AtomicUsize::new(maxlen),
             local_queue: QuickLock::new(Queue::new(maxlen, maxlen == 0)),
             remote_lock: QuickLock::new(()),
         }
@@ -125,10 +145,42 @@ impl SnapshotEngine {
     pub const fn new_disabled() -> Self {
         Self {
             local_enabled: false,
+            local_maxlen: AtomicUsize::new(0),
             local_queue: QuickLock::new(Queue::new(0, true)),
             remote_lock: QuickLock::new(()),
         }
     }
+    /// Returns true if local snapshots are enabled
+    pub const fn is_enabled(&self) -> bool {
+        self.local_enabled
+    }
+    /// Returns the maximum number of local snapshots to keep (`0` means unlimited)
+    pub fn maxlen(&self) -> usize {
+        self.local_maxlen.load(Ordering::Relaxed)
+    }
+    /// Set the maximum number of local snapshots to keep. Older snapshots beyond the new
+    /// limit are removed when the next snapshot is created
+    pub fn set_maxlen(&self, maxlen: usize) {
+        self.local_maxlen.store(maxlen, Ordering::Relaxed)
+    }
+    /// Returns the current state of the local snapshot queue. This never blocks: if a
+    /// snapshot is in progress, the queue is reported as busy
+    pub fn queue_state(&self) -> QueueState {
+        match self.local_queue.try_lock() {
+            Some(queue) => QueueState {
+                enabled: self.local_enabled,
+                busy: false,
+                len: queue.len(),
+                maxlen: self.maxlen(),
+            },
+            None => QueueState {
+                enabled: self.local_enabled,
+                busy: true,
+                len: 0,
+                maxlen: 0,
+            },
+        }
+    }
     pub fn parse_dir(&self) -> SnapshotResult<()> {
         parse_dir!(self.local_queue, DIR_SNAPROOT);
         Ok(())
@@ -159,7 +211,10 @@ impl SnapshotEngine {
             };
             let name = self.get_snapname();
             let nameclone = name.clone();
-            let todel = queue.add_new(name);
+            // apply the current limit first, in case it was lowered
+            let mut todel = queue.set_maxlen(self.maxlen());
+            todel.extend(queue.add_new(name));
+            let start = Instant::now();
             let snap_create_result = tokio::task::spawn_blocking(move || {
                 Self::_mksnap_blocking_section(&store, &nameclone)
             })
@@ -170,22 +225,26 @@ impl SnapshotEngine {
             match snap_create_result {
                 Ok(_) => {
                     log::info!("Successfully created snapshot");
+                    stats::snapshot_finished(true, start.elapsed());
                 }
                 Err(e) => {
                     log::info!("Failed to create snapshot with error: {}", e);
+                    stats::snapshot_finished(false, start.elapsed());
                     // so it failed, remove it from queue
                     let _ = queue.pop_last().unwrap();
                     return 1;
                 }
             }
 
-            // Now delete the older snap (if any)
-            if let Some(snap) = todel {
+            // Now delete the older snaps (if any)
+            if !todel.is_empty() {
                 tokio::task::spawn_blocking(move || {
-                    if let Err(e) = fs::remove_dir_all(concat_path!(DIR_SNAPROOT, snap)) {
-                        log::warn!("Failed to remove older snapshot (ignored): {}", e);
-                    } else {
-                        log::info!("Successfully removed older snapshot");
+                    for snap in todel {
+                        if let Err(e) = fs::remove_dir_all(concat_path!(DIR_SNAPROOT, snap)) {
+                            log::warn!("Failed to remove older snapshot (ignored): {}", e);
+                        } else {
+                            log::info!("Successfully removed older snapshot");
+                        }
                     }
                 })
                 .await
@@ -200,6 +259,7 @@ impl SnapshotEngine {
     /// - `0` => Okay
     /// - `1` => Error
     /// - `3` => Busy
+    ///
     /// (consistent with mksnap)
     pub async fn mkrsnap(&self, name: Bytes, store: Arc<Memstore>) -> u8 {
         let _lck = match self.remote_lock.try_lock() {
@@ -246,6 +306,23 @@ mod queue {
                 dontpop,
             }
         }
+        /// Returns the number of items in the queue
+        pub fn len(&self) -> usize {
+            self.queue.len()
+        }
+        /// Set the maximum number of items (`0` if unbounded), returning the oldest items
+        /// that no longer fit in the queue
+        pub fn set_maxlen(&mut self, maxlen: usize) -> Vec<String> {
+            self.maxlen = maxlen;
+            self.dontpop = maxlen == 0;
+            let mut removed = Vec::new();
+            if !self.dontpop {
+                while self.queue.len() > maxlen {
+                    removed.push(self.pop().unwrap());
+                }
+            }
+            removed
+        }
         pub fn push(&mut self, item: String) {
             self.queue.push(item)
         }
@@ -315,4 +392,26 @@ mod queue {
         assert!(q.add_new(String::from("snap5")).is_none());
         assert!(q.add_new(String::from("snap6")).is_none());
     }
+
+    #[test]
+    fn test_queue_set_maxlen() {
+        let mut q = Queue::new(4, false);
+        assert!(q.add_new(String::from("snap1")).is_none());
+        assert!(q.add_new(String::from("snap2")).is_none());
+        assert!(q.add_new(String::from("snap3")).is_none());
+        assert!(q.add_new(String::from("snap4")).is_none());
+        // lowering the limit gives back the oldest snapshots
+        assert_eq!(
+            q.set_maxlen(2),
+            vec![String::from("snap1"), String::from("snap2")]
+        );
+        assert_eq!(
+            q.add_new(String::from("snap5")),
+            Some(String::from("snap3"))
+        );
+        // and an unlimited queue never pops
+        assert!(q.set_maxlen(0).is_empty());
+        assert!(q.add_new(String::from("snap6")).is_none());
+        assert_eq!(q.len(), 3);
+    }
 }
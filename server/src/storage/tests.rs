@@ -60,6 +60,168 @@ fn test_ser_de_few_elements() {
         .all(|kv| cmap.get(kv.key()).unwrap().eq(kv.value())));
 }
 
+#[test]
+fn test_ser_de_nested_map() {
+    let cmap: Coremap<Data, FieldMap> = Coremap::new();
+    let mut fields = FieldMap::new();
+    fields.insert(Data::from("name"), Data::from("sayan"));
+    fields.insert(Data::from("lang"), Data::from("rust"));
+    cmap.upsert(Data::from("sayan"), fields);
+    cmap.upsert(Data::from("nobody"), FieldMap::new());
+    let mut ser = Vec::new();
+    se::raw_serialize_nested_map(&cmap, &mut ser).unwrap();
+    let de = de::deserialize_nested_map(ser.clone()).unwrap();
+    assert_eq!(de.len(), cmap.len());
+    assert!(de
+        .iter()
+        .all(|kv| cmap.get(kv.key()).unwrap().eq(kv.value())));
+    // corrupted
+    ser.pop();
+    assert!(de::deserialize_nested_map(ser.clone()).is_none());
+    // excess bytes
+    ser.extend_from_slice(b"t!");
+    assert!(de::deserialize_nested_map(ser).is_none());
+}
+
+#[test]
+fn test_ser_de_nested_set() {
+    let cmap: Coremap<Data, MemberSet> = Coremap::new();
+    let mut members = MemberSet::new();
+    members.insert(Data::from("rust"));
+    members.insert(Data::from(""));
+    cmap.upsert(Data::from("tags"), members);
+    cmap.upsert(Data::from("notags"), MemberSet::new());
+    let mut ser = Vec::new();
+    se::raw_serialize_nested_set(&cmap, &mut ser).unwrap();
+    let de = de::deserialize_nested_set(ser.clone()).unwrap();
+    assert_eq!(de.len(), cmap.len());
+    assert!(de
+        .iter()
+        .all(|kv| cmap.get(kv.key()).unwrap().eq(kv.value())));
+    // corrupted
+    ser.pop();
+    assert!(de::deserialize_nested_set(ser.clone()).is_none());
+    // excess bytes
+    ser.extend_from_slice(b"t!");
+    assert!(de::deserialize_nested_set(ser).is_none());
+}
+
+#[test]
+fn test_ser_de_nested_sortedset() {
+    let cmap: Coremap<Data, ScoreSet> = Coremap::new();
+    let mut members = ScoreSet::new();
+    members.insert(Data::from("alice"), 25.5);
+    members.insert(Data::from("bob"), -3.0);
+    members.insert(Data::from(""), 0.0);
+    cmap.upsert(Data::from("board"), members);
+    cmap.upsert(Data::from("noboard"), ScoreSet::new());
+    let mut ser = Vec::new();
+    se::raw_serialize_nested_sortedset(&cmap, &mut ser).unwrap();
+    let de = de::deserialize_nested_sortedset(ser.clone()).unwrap();
+    assert_eq!(de.len(), cmap.len());
+    assert!(de
+        .iter()
+        .all(|kv| cmap.get(kv.key()).unwrap().eq(kv.value())));
+    assert_eq!(
+        de.get("board".as_bytes()).unwrap().rank_of(b"alice"),
+        Some(2)
+    );
+    // corrupted
+    ser.pop();
+    assert!(de::deserialize_nested_sortedset(ser.clone()).is_none());
+    // excess bytes
+    ser.extend_from_slice(b"t!");
+    assert!(de::deserialize_nested_sortedset(ser).is_none());
+}
+
+#[test]
+fn test_ser_de_nested_timeseries() {
+    let cmap: Coremap<Data, Series> = Coremap::new();
+    let mut series = Series::new(3_600_000);
+    for (timestamp, value) in [(1_632_528_000_000, 0.5), (1_632_528_000_250, -1.25)] {
+        series.append(timestamp, value);
+    }
+    // a large gap
+    series.append(1_632_528_100_000, 1e10);
+    cmap.upsert(Data::from("cpu"), series);
+    cmap.upsert(Data::from("empty"), Series::new(0));
+    let mut ser = Vec::new();
+    se::raw_serialize_nested_timeseries(&cmap, &mut ser).unwrap();
+    let de = de::deserialize_nested_timeseries(ser.clone()).unwrap();
+    assert_eq!(de.len(), cmap.len());
+    assert!(de
+        .iter()
+        .all(|kv| cmap.get(kv.key()).unwrap().eq(kv.value())));
+    // the deltas are varints: 8 (len) + 8+3 (key) + 8+8 (retention, samples) + 6 + 2 + 3
+    // (deltas) + 3*8 (values) + 8+5+8+8 (the empty series) bytes
+    assert_eq!(ser.len(), 99);
+    // corrupted
+    ser.pop();
+    assert!(de::deserialize_nested_timeseries(ser.clone()).is_none());
+    // excess bytes
+    ser.extend_from_slice(b"t!");
+    assert!(de::deserialize_nested_timeseries(ser).is_none());
+}
+
+#[test]
+fn test_de_nested_timeseries_out_of_order() {
+    let cmap: Coremap<Data, Series> = Coremap::new();
+    let mut series = Series::new(0);
+    series.append(0, 1.0);
+    series.append(1, 2.0);
+    cmap.upsert(Data::from("cpu"), series);
+    let mut ser = Vec::new();
+    se::raw_serialize_nested_timeseries(&cmap, &mut ser).unwrap();
+    assert!(de::deserialize_nested_timeseries(ser.clone()).is_some());
+    // make the second delta zero, so that both samples have the same timestamp
+    let second_delta = ser.len() - 9;
+    assert_eq!(ser[second_delta], 1);
+    ser[second_delta] = 0;
+    assert!(de::deserialize_nested_timeseries(ser).is_none());
+}
+
+#[test]
+fn test_ser_de_versioned_map() {
+    let cmap: Coremap<Data, Versioned> = Coremap::new();
+    cmap.upsert(
+        Data::from("sayan"),
+        Versioned::new(Data::from("writes code"), 3),
+    );
+    cmap.upsert(Data::from(""), Versioned::new(Data::from(""), 7));
+    let mut ser = Vec::new();
+    se::raw_serialize_versioned_map(&cmap, 10, &mut ser).unwrap();
+    let (de, version) = de::deserialize_versioned_map(ser.clone()).unwrap();
+    assert_eq!(version, 10);
+    assert_eq!(de.len(), cmap.len());
+    assert!(de
+        .iter()
+        .all(|kv| cmap.get(kv.key()).unwrap().eq(kv.value())));
+    // values written while serializing can be newer than the version in the header
+    let mut ser_newer = Vec::new();
+    se::raw_serialize_versioned_map(&cmap, 5, &mut ser_newer).unwrap();
+    assert_eq!(de::deserialize_versioned_map(ser_newer).unwrap().1, 7);
+    // truncated
+    assert!(de::deserialize_versioned_map(ser[..ser.len() - 1].to_owned()).is_none());
+    assert!(de::deserialize_versioned_map(ser[..20].to_owned()).is_none());
+    // excess bytes
+    ser.extend_from_slice(b"t!");
+    assert!(de::deserialize_versioned_map(ser).is_none());
+}
+
+#[test]
+fn test_de_versioned_map_from_legacy_map() {
+    let cmap: Coremap<Data, Data> = Coremap::new();
+    cmap.upsert(Data::from("sayan"), Data::from("writes code"));
+    cmap.upsert(Data::from("supersayan"), Data::from("writes super code"));
+    let ser = se::serialize_map(&cmap).unwrap();
+    let (de, version) = de::deserialize_versioned_map(ser).unwrap();
+    assert_eq!(version, 0);
+    assert_eq!(de.len(), cmap.len());
+    assert!(de
+        .iter()
+        .all(|kv| kv.value().version() == 0 && cmap.get(kv.key()).unwrap().eq(kv.value().data())));
+}
+
 cfg_test!(
     use libstress::utils::generate_random_string_vector;
     use rand::thread_rng;
@@ -125,7 +287,7 @@ cfg_test!(
             .collect();
         let mut se = se::serialize_map(&cmap).unwrap();
         // random patch
-        let patch: Vec<u8> = (0u16..500u16).into_iter().map(|v| (v >> 7) as u8).collect();
+        let patch: Vec<u8> = (0u16..500u16).map(|v| (v >> 7) as u8).collect();
         se.extend(patch);
         assert!(de::deserialize_map(se).is_none());
     }
@@ -198,7 +360,7 @@ mod bytemark_set_tests {
         let ks = Keyspace::empty_default();
         let mut v = Vec::new();
         se::raw_serialize_partmap(&mut v, &ks).unwrap();
-        let ret: HashMap<ObjectID, (u8, u8)> = de::deserialize_set_ctype_bytemark(&v).unwrap();
+        let ret: HashMap<ObjectID, (u8, u8)> = de::deserialize_partmap(&v).unwrap().0;
         let mut expected = HashMap::new();
         unsafe {
             expected.insert(
@@ -226,7 +388,7 @@ mod bytemark_set_tests {
         }
         let mut v = Vec::new();
         se::raw_serialize_partmap(&mut v, &ks).unwrap();
-        let ret: HashMap<ObjectID, (u8, u8)> = de::deserialize_set_ctype_bytemark(&v).unwrap();
+        let ret: HashMap<ObjectID, (u8, u8)> = de::deserialize_partmap(&v).unwrap().0;
         let mut expected = HashMap::new();
         unsafe {
             // our cache is volatile
@@ -248,6 +410,29 @@ mod bytemark_set_tests {
         }
         assert_hmeq!(expected, ret);
     }
+    #[test]
+    fn test_partmap_quotas() {
+        use crate::corestore::quota::Quota;
+        let ks = Keyspace::empty_default();
+        let mut legacy = Vec::new();
+        se::raw_serialize_partmap(&mut legacy, &ks).unwrap();
+        // no limits, so nothing follows the partitions
+        let (ret, quotas): (HashMap<ObjectID, (u8, u8)>, _) =
+            de::deserialize_partmap(&legacy).unwrap();
+        assert_eq!(ret.len(), 1);
+        assert!(quotas.is_unlimited());
+        ks.quotas().set(Quota::Keys, 100);
+        ks.quotas().set(Quota::ValueSize, 1024);
+        let mut v = Vec::new();
+        se::raw_serialize_partmap(&mut v, &ks).unwrap();
+        assert_eq!(v.len(), legacy.len() + 32);
+        let (ret, quotas): (HashMap<ObjectID, (u8, u8)>, _) = de::deserialize_partmap(&v).unwrap();
+        assert_eq!(ret.len(), 1);
+        assert_eq!(quotas.to_array(), [0, 100, 0, 1024]);
+        // a partial trailer is corrupted data
+        v.pop();
+        assert!(de::deserialize_partmap::<ObjectID>(&v).is_none());
+    }
 }
 
 mod flush_routines {
@@ -276,11 +461,150 @@ mod flush_routines {
                 .get(&Data::from("hello"))
                 .unwrap()
                 .unwrap()
+                .data()
                 .clone(),
             Data::from("world")
         );
     }
     #[test]
+    fn test_flush_unflush_hash_table() {
+        use crate::corestore::table;
+        let code = table::hash_model_code(true, false);
+        let tbl = Table::from_model_code(code, false).unwrap();
+        tbl.get_hashstore()
+            .unwrap()
+            .set_fields("sayan".into(), vec![("lang".into(), "rust".into())])
+            .unwrap();
+        let tblid = unsafe { ObjectID::from_slice("myhashtbl1") };
+        let ksid = unsafe { ObjectID::from_slice("myks1") };
+        fs::create_dir_all("data/ks/myks1").unwrap();
+        super::flush::oneshot::flush_table(&tblid, &ksid, &tbl).unwrap();
+        let ret = super::unflush::read_table(&ksid, &tblid, false, code).unwrap();
+        assert_eq!(ret.get_model_code(), code);
+        assert_eq!(
+            ret.get_hashstore()
+                .unwrap()
+                .get_field(b"sayan", b"lang")
+                .unwrap()
+                .unwrap(),
+            Data::from("rust")
+        );
+    }
+    #[test]
+    fn test_flush_unflush_set_table() {
+        use crate::corestore::table;
+        let code = table::set_model_code(false, true);
+        let tbl = Table::from_model_code(code, false).unwrap();
+        tbl.get_setstore()
+            .unwrap()
+            .add_members("tags".into(), vec!["rust".into(), "db".into()])
+            .unwrap();
+        let tblid = unsafe { ObjectID::from_slice("mysettbl1") };
+        let ksid = unsafe { ObjectID::from_slice("myks1") };
+        fs::create_dir_all("data/ks/myks1").unwrap();
+        super::flush::oneshot::flush_table(&tblid, &ksid, &tbl).unwrap();
+        let ret = super::unflush::read_table(&ksid, &tblid, false, code).unwrap();
+        assert_eq!(ret.get_model_code(), code);
+        let set = ret.get_setstore().unwrap();
+        assert_eq!(set.count_members(b"tags").unwrap(), 2);
+        assert!(set.is_member(b"tags", b"db").unwrap());
+    }
+    #[test]
+    fn test_flush_unflush_sortedset_table() {
+        use crate::corestore::table;
+        let code = table::sortedset_model_code(true, true);
+        let tbl = Table::from_model_code(code, false).unwrap();
+        tbl.get_sortedsetstore()
+            .unwrap()
+            .add_members(
+                "board".into(),
+                vec![(10.0, "alice".into()), (20.0, "bob".into())],
+            )
+            .unwrap();
+        let tblid = unsafe { ObjectID::from_slice("myzsettbl1") };
+        let ksid = unsafe { ObjectID::from_slice("myks1") };
+        fs::create_dir_all("data/ks/myks1").unwrap();
+        super::flush::oneshot::flush_table(&tblid, &ksid, &tbl).unwrap();
+        let ret = super::unflush::read_table(&ksid, &tblid, false, code).unwrap();
+        assert_eq!(ret.get_model_code(), code);
+        let zset = ret.get_sortedsetstore().unwrap();
+        assert_eq!(zset.get_score(b"board", b"bob").unwrap(), Some(20.0));
+        assert_eq!(zset.get_rank(b"board", b"bob", true).unwrap(), Some(0));
+    }
+    #[test]
+    fn test_flush_unflush_timeseries_table() {
+        use crate::corestore::table;
+        use crate::kvengine::timeseries::Aggregation;
+        let code = table::timeseries_model_code(true);
+        let tbl = Table::from_model_code(code, false).unwrap();
+        let ts = tbl.get_timeseriesstore().unwrap();
+        ts.create("cpu".into(), 10_000).unwrap();
+        for (timestamp, value) in [(1000, 0.5), (2000, 0.75), (3000, 0.25)] {
+            ts.add_sample("cpu".into(), timestamp, value).unwrap();
+        }
+        let tblid = unsafe { ObjectID::from_slice("mytstbl1") };
+        let ksid = unsafe { ObjectID::from_slice("myks1") };
+        fs::create_dir_all("data/ks/myks1").unwrap();
+        super::flush::oneshot::flush_table(&tblid, &ksid, &tbl).unwrap();
+        let ret = super::unflush::read_table(&ksid, &tblid, false, code).unwrap();
+        assert_eq!(ret.get_model_code(), code);
+        let ts = ret.get_timeseriesstore().unwrap();
+        assert_eq!(ts.count_samples(b"cpu").unwrap(), 3);
+        assert_eq!(
+            ts.aggregate(b"cpu", 0, u64::MAX, Aggregation::Max, 10_000)
+                .unwrap(),
+            vec![(0, 0.75)]
+        );
+        // the retention period survives too
+        assert!(ts.add_sample("cpu".into(), 12_500, 1.0).unwrap());
+        assert_eq!(ts.range(b"cpu", 0, 2500).unwrap(), vec![]);
+    }
+    #[test]
+    fn test_flush_unflush_sketches() {
+        use crate::kvengine::sketch::bloom::FilterParams;
+        let tbl = Table::new_default_kve();
+        let kve = tbl.get_kvstore().unwrap();
+        kve.hll_add("visitors".into(), &[b"sayan", b"ferris"])
+            .unwrap();
+        kve.bloom_reserve("seen".into(), FilterParams::new(0.01, 1000).unwrap())
+            .unwrap();
+        kve.bloom_add("seen".into(), b"sayan").unwrap();
+        let tblid = unsafe { ObjectID::from_slice("mysketchtbl1") };
+        let ksid = unsafe { ObjectID::from_slice("myks1") };
+        fs::create_dir_all("data/ks/myks1").unwrap();
+        super::flush::oneshot::flush_table(&tblid, &ksid, &tbl).unwrap();
+        let ret = super::unflush::read_table(&ksid, &tblid, false, 0).unwrap();
+        let kve = ret.get_kvstore().unwrap();
+        assert_eq!(kve.hll_count(&[b"visitors"]).unwrap(), 2);
+        assert_eq!(
+            kve.bloom_check(b"seen", &[b"sayan", b"ferris"]).unwrap(),
+            vec![true, false]
+        );
+    }
+    #[test]
+    fn test_flush_unflush_versions() {
+        let tbl = Table::new_default_kve();
+        let kve = tbl.get_kvstore().unwrap();
+        kve.set("hello".into(), "world".into()).unwrap();
+        kve.set("removed".into(), "soon".into()).unwrap();
+        kve.remove(b"removed".as_ref()).unwrap();
+        let version = kve.get_versioned(b"hello").unwrap().unwrap().version();
+        let tblid = unsafe { ObjectID::from_slice("myversionedtbl1") };
+        let ksid = unsafe { ObjectID::from_slice("myks1") };
+        fs::create_dir_all("data/ks/myks1").unwrap();
+        super::flush::oneshot::flush_table(&tblid, &ksid, &tbl).unwrap();
+        let ret = super::unflush::read_table(&ksid, &tblid, false, 0).unwrap();
+        let ret = ret.get_kvstore().unwrap();
+        assert_eq!(
+            ret.get_versioned(b"hello").unwrap().unwrap().version(),
+            version
+        );
+        // the version of the removed key isn't handed out again either
+        assert_eq!(ret.version(), kve.version());
+        ret.set("removed".into(), "again".into()).unwrap();
+        assert!(ret.get_versioned(b"removed").unwrap().unwrap().version() > kve.version());
+    }
+    #[test]
     fn test_flush_unflush_keyspace() {
         // create the temp dir for this test
         fs::create_dir_all("data/ks/myks_1").unwrap();
@@ -299,7 +623,7 @@ mod flush_routines {
         // and a volatile table
         ks.create_table(tbl2.clone(), Table::new_kve_with_volatile(true));
         super::flush::flush_keyspace_full(&ksid, &ks).unwrap();
-        let ret = super::unflush::read_keyspace(&ksid).unwrap();
+        let ret = super::unflush::read_keyspace_with_quotas(&ksid).unwrap().0;
         let tbl1_ret = ret.get(&tbl1).unwrap();
         let tbl2_ret = ret.get(&tbl2).unwrap();
         assert_eq!(
@@ -309,9 +633,91 @@ mod flush_routines {
                 .get(&Data::from("hello"))
                 .unwrap()
                 .unwrap()
+                .data()
                 .clone(),
             Data::from("world")
         );
         assert!(tbl2_ret.get_kvstore().unwrap().len() == 0);
     }
+    #[test]
+    fn test_flush_renamed_table() {
+        fs::create_dir_all("data/ks/myks_rename").unwrap();
+        let ksid = unsafe { ObjectID::from_slice("myks_rename") };
+        let tblid = unsafe { ObjectID::from_slice("mytbl") };
+        let new_tblid = unsafe { ObjectID::from_slice("mynewtbl") };
+        let ks = Keyspace::empty();
+        let mytbl = Table::new_default_kve();
+        mytbl
+            .get_kvstore()
+            .unwrap()
+            .set("hello".into(), "world".into())
+            .unwrap();
+        ks.create_table(tblid.clone(), mytbl);
+        super::flush::flush_keyspace_full(&ksid, &ks).unwrap();
+        ks.rename_table(&tblid, new_tblid.clone()).unwrap();
+        super::flush::flush_renamed_table(&ksid, &ks, &tblid, &new_tblid).unwrap();
+        // the older file is gone and the partmap only knows about the newer name
+        assert!(!std::path::Path::new("data/ks/myks_rename/mytbl").exists());
+        let ret = super::unflush::read_keyspace_with_quotas(&ksid).unwrap().0;
+        assert!(ret.get(&tblid).is_none());
+        assert_eq!(
+            ret.get(&new_tblid)
+                .unwrap()
+                .get_kvstore()
+                .unwrap()
+                .get(&Data::from("hello"))
+                .unwrap()
+                .unwrap()
+                .data()
+                .clone(),
+            Data::from("world")
+        );
+    }
+    #[test]
+    fn test_flush_altered_table() {
+        fs::create_dir_all("data/ks/myks_alter").unwrap();
+        let ksid = unsafe { ObjectID::from_slice("myks_alter") };
+        let tblid = unsafe { ObjectID::from_slice("mytbl") };
+        let ks = Keyspace::empty();
+        ks.create_table(tblid.clone(), Table::new_kve_with_volatile(true));
+        super::flush::flush_keyspace_full(&ksid, &ks).unwrap();
+        let table = ks.get_table_atomic_ref(&tblid).unwrap();
+        table
+            .get_kvstore()
+            .unwrap()
+            .set("hello".into(), "world".into())
+            .unwrap();
+        // now make it persistent
+        table.set_volatile(false);
+        super::flush::flush_altered_table(&ksid, &ks, &tblid, &table).unwrap();
+        let ret = super::unflush::read_keyspace_with_quotas(&ksid).unwrap().0;
+        let tbl_ret = ret.get(&tblid).unwrap();
+        assert!(!tbl_ret.is_volatile());
+        assert_eq!(tbl_ret.get_kvstore().unwrap().len(), 1);
+        // and back to being volatile
+        table.set_volatile(true);
+        super::flush::flush_altered_table(&ksid, &ks, &tblid, &table).unwrap();
+        assert!(!std::path::Path::new("data/ks/myks_alter/mytbl").exists());
+        let ret = super::unflush::read_keyspace_with_quotas(&ksid).unwrap().0;
+        assert!(ret.get(&tblid).unwrap().is_volatile());
+    }
+    #[test]
+    fn test_flush_keyspace_quotas() {
+        use crate::corestore::quota::Quota;
+        let ksid = unsafe { ObjectID::from_slice("myks_quotas") };
+        let tblid = unsafe { ObjectID::from_slice("mytbl") };
+        let ks = Keyspace::empty();
+        ks.create_table(tblid.clone(), Table::new_default_kve());
+        ks.quotas().set(Quota::Tables, 4);
+        // the keyspace tree is created if it doesn't exist yet
+        super::flush::flush_keyspace_quotas(&ksid, &ks).unwrap();
+        let (tables, quotas) = super::unflush::read_keyspace_with_quotas(&ksid).unwrap();
+        assert!(tables.get(&tblid).is_some());
+        assert_eq!(quotas.to_array(), [4, 0, 0, 0]);
+        // removing the limit drops the quotas from the partmap
+        ks.quotas().set(Quota::Tables, 0);
+        super::flush::flush_keyspace_quotas(&ksid, &ks).unwrap();
+        let (_, quotas) = super::unflush::read_keyspace_with_quotas(&ksid).unwrap();
+        assert!(quotas.is_unlimited());
+    }
 }
@@ -32,7 +32,8 @@ use super::bytemarks;
 use crate::corestore::memstore::Keyspace;
 use crate::corestore::memstore::Memstore;
 use crate::corestore::memstore::ObjectID;
-use crate::corestore::table::Table;
+use crate::corestore::quota::Quotas;
+use crate::corestore::table::{self, Table};
 use crate::storage::interface::DIR_KSROOT;
 use crate::storage::preload::LoadedPartfile;
 use crate::storage::Coremap;
@@ -57,35 +58,67 @@ pub fn read_table(
     model_code: u8,
 ) -> IoResult<Table> {
     let filepath = unsafe { concat_path!(DIR_KSROOT, ksid.as_str(), tblid.as_str()) };
-    let data = if volatile {
+    if let Some((k_enc, v_enc)) = table::hash_encoding(model_code) {
+        let data = if volatile {
+            Coremap::new()
+        } else {
+            let f = fs::read(filepath)?;
+            super::de::deserialize_nested_map(f).ok_or_else(|| bad_data!())?
+        };
+        return Ok(Table::new_hash_with_data(data, volatile, k_enc, v_enc));
+    }
+    if let Some((k_enc, m_enc)) = table::set_encoding(model_code) {
+        let data = if volatile {
+            Coremap::new()
+        } else {
+            let f = fs::read(filepath)?;
+            super::de::deserialize_nested_set(f).ok_or_else(|| bad_data!())?
+        };
+        return Ok(Table::new_set_with_data(data, volatile, k_enc, m_enc));
+    }
+    if let Some((k_enc, m_enc)) = table::sortedset_encoding(model_code) {
+        let data = if volatile {
+            Coremap::new()
+        } else {
+            let f = fs::read(filepath)?;
+            super::de::deserialize_nested_sortedset(f).ok_or_else(|| bad_data!())?
+        };
+        return Ok(Table::new_sortedset_with_data(data, volatile, k_enc, m_enc));
+    }
+    if let Some(k_enc) = table::timeseries_encoding(model_code) {
+        let data = if volatile {
+            Coremap::new()
+        } else {
+            let f = fs::read(filepath)?;
+            super::de::deserialize_nested_timeseries(f).ok_or_else(|| bad_data!())?
+        };
+        return Ok(Table::new_timeseries_with_data(data, volatile, k_enc));
+    }
+    let (data, version) = if volatile {
         // no need to read anything; table is volatile and has no file
-        Coremap::new()
+        (Coremap::new(), 0)
     } else {
         // not volatile, so read this in
         let f = fs::read(filepath)?;
-        super::de::deserialize_map(f).ok_or_else(|| bad_data!())?
+        super::de::deserialize_versioned_map(f).ok_or_else(|| bad_data!())?
     };
-    let tbl = match model_code {
-        bytemarks::BYTEMARK_MODEL_KV_BIN_BIN => {
-            Table::new_kve_with_data(data, volatile, false, false)
-        }
-        bytemarks::BYTEMARK_MODEL_KV_BIN_STR => {
-            Table::new_kve_with_data(data, volatile, false, true)
-        }
-        bytemarks::BYTEMARK_MODEL_KV_STR_STR => {
-            Table::new_kve_with_data(data, volatile, true, true)
-        }
-        bytemarks::BYTEMARK_MODEL_KV_STR_BIN => {
-            Table::new_kve_with_data(data, volatile, true, false)
-        }
-        _ => return Err(IoError::from(ErrorKind::Unsupported)),
+    let tbl = match Table::from_model_code_with_data(model_code, volatile, data) {
+        Some(tbl) => tbl,
+        None => return Err(IoError::from(ErrorKind::Unsupported)),
     };
+    if let Ok(kve) = tbl.get_kvstore() {
+        // so that versions of removed keys aren't handed out again
+        kve.restore_version(version);
+    }
     Ok(tbl)
 }
 
-/// Read an entire keyspace into a Coremap. You'll need to initialize the rest
-pub fn read_keyspace(ksid: &ObjectID) -> IoResult<Coremap<ObjectID, Arc<Table>>> {
-    let partmap = self::read_partmap(ksid)?;
+/// Read an entire keyspace into a Coremap along with its quotas. You'll need to initialize
+/// the rest
+pub fn read_keyspace_with_quotas(
+    ksid: &ObjectID,
+) -> IoResult<(Coremap<ObjectID, Arc<Table>>, Quotas)> {
+    let (partmap, quotas) = self::read_partmap(ksid)?;
     let ks: Coremap<ObjectID, Arc<Table>> = Coremap::with_capacity(partmap.len());
     for (tableid, (table_storage_type, model_code)) in partmap.into_iter() {
         if table_storage_type > 1 {
@@ -95,11 +128,11 @@ pub fn read_keyspace(ksid: &ObjectID) -> IoResult<Coremap<ObjectID, Arc<Table>>>
         let tbl = self::read_table(ksid, &tableid, is_volatile, model_code)?;
         ks.true_if_insert(tableid, Arc::new(tbl));
     }
-    Ok(ks)
+    Ok((ks, quotas))
 }
 
-/// Read the `PARTMAP` for a given keyspace
-pub fn read_partmap(ksid: &ObjectID) -> IoResult<LoadedPartfile> {
+/// Read the `PARTMAP` for a given keyspace (along with its quotas)
+pub fn read_partmap(ksid: &ObjectID) -> IoResult<(LoadedPartfile, Quotas)> {
     let filepath = unsafe { concat_path!(DIR_KSROOT, ksid.as_str(), "PARTMAP") };
     super::preload::read_partfile_raw(fs::read(filepath)?)
 }
@@ -139,7 +172,8 @@ pub fn read_full() -> IoResult<Memstore> {
     let preload = self::read_preload()?;
     let ksmap = Coremap::with_capacity(preload.len());
     for ksid in preload {
-        let ks = Keyspace::init_with_all_def_strategy(self::read_keyspace(&ksid)?);
+        let (tables, quotas) = self::read_keyspace_with_quotas(&ksid)?;
+        let ks = Keyspace::init_with_quotas(tables, quotas);
         ksmap.upsert(ksid, Arc::new(ks));
     }
     Ok(Memstore::init_with_all(ksmap))
@@ -0,0 +1,117 @@
+/*
+ * Created on Thu Sep 09 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(skip = "find_client")]
+mod __private {
+    use skytable::{types::Array, Element, RespCode};
+    /// Returns the `CLIENT LIST` line of the client named `name`
+    async fn find_client(con: &mut skytable::AsyncConnection, name: &str) -> Option<String> {
+        let query = skytable::query!("CLIENT", "LIST");
+        match con.run_simple_query(&query).await.unwrap() {
+            Element::Array(Array::Str(lines)) => lines
+                .into_iter()
+                .flatten()
+                .find(|line| line.contains(&format!(" name={} ", name))),
+            _ => panic!("Bad response for CLIENT LIST"),
+        }
+    }
+    async fn test_client_setname_and_list() {
+        query.push("CLIENT");
+        query.push("SETNAME");
+        query.push("supercyan");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let line = find_client(&mut con, "supercyan").await.unwrap();
+        assert!(line.starts_with("id="));
+        assert!(line.contains(" tls=false "));
+        assert!(line.contains(" lastcmd=CLIENT "));
+    }
+    async fn test_client_setname_bad_name() {
+        query.push("CLIENT");
+        query.push("SETNAME");
+        query.push("super cyan");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("bad-client-name".to_owned()))
+        );
+    }
+    async fn test_client_kill() {
+        let mut victim = skytable::AsyncConnection::new("127.0.0.1", 2003)
+            .await
+            .unwrap();
+        let setname = skytable::query!("CLIENT", "SETNAME", "victim");
+        assert_eq!(
+            victim.run_simple_query(&setname).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let line = find_client(&mut con, "victim").await.unwrap();
+        let id = line
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .trim_start_matches("id=")
+            .to_owned();
+        query.push("CLIENT");
+        query.push("KILL");
+        query.push(id);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        assert!(victim
+            .run_simple_query(&skytable::query!("HEYA"))
+            .await
+            .is_err());
+    }
+    async fn test_client_kill_nonexistent() {
+        query.push("CLIENT");
+        query.push("KILL");
+        query.push("0");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::NotFound)
+        );
+    }
+    async fn test_client_kill_bad_id() {
+        query.push("CLIENT");
+        query.push("KILL");
+        query.push("notanid");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Wrongtype)
+        );
+    }
+    async fn test_client_unknown_query() {
+        query.push("CLIENT");
+        query.push("blahblah");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("unknown-client-query".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * Created on Sun Sep 12 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use skytable::{Element, RespCode};
+    async fn test_config_get_maxcon() {
+        query.push("CONFIG");
+        query.push("GET");
+        query.push("maxcon");
+        match con.run_simple_query(&query).await.unwrap() {
+            Element::String(maxcon) => assert!(maxcon.parse::<usize>().unwrap() > 0),
+            _ => panic!("Bad response for CONFIG GET"),
+        }
+    }
+    async fn test_config_set_loglevel() {
+        let query = skytable::query!("CONFIG", "GET", "loglevel");
+        let level = match con.run_simple_query(&query).await.unwrap() {
+            Element::String(level) => level,
+            _ => panic!("Bad response for CONFIG GET"),
+        };
+        // set it to the same level so that we don't affect the other tests
+        let query = skytable::query!("CONFIG", "SET", "loglevel", level);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+    }
+    async fn test_config_unknown_key() {
+        query.push("CONFIG");
+        query.push("GET");
+        query.push("blahblah");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("unknown-config-key".to_owned()))
+        );
+    }
+    async fn test_config_set_bad_value() {
+        query.push("CONFIG");
+        query.push("SET");
+        query.push("bgsave.every");
+        query.push("0");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("bad-config-value".to_owned()))
+        );
+    }
+    async fn test_config_unknown_query() {
+        query.push("CONFIG");
+        query.push("RESETSTAT");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("unknown-config-query".to_owned()))
+        );
+    }
+    async fn test_config_syntax_error() {
+        query.push("CONFIG");
+        query.push("SET");
+        query.push("maxcon");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+    }
+}
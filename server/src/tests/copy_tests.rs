@@ -0,0 +1,234 @@
+/*
+ * Created on Sun Sep 19 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use skytable::types::{Array, RawString};
+    use skytable::{Element, RespCode};
+    async fn test_copy_keys() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,str)");
+        setkeys! {
+            con,
+            "x": "100",
+            "y": "200"
+        }
+        push!(query, "COPY", &tblname, "x", "y", "z");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(2)
+        );
+        let query = skytable::query!("ON", &tblname, "GET", "y");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("200".to_owned())
+        );
+        // the keys are still in the current table
+        let query = skytable::query!("EXISTS", "x", "y");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(2)
+        );
+    }
+    async fn test_copy_skips_existing_keys() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,str)");
+        setkeys! {
+            con,
+            "x": "100"
+        }
+        let query = skytable::query!("ON", &tblname, "SET", "x", "200");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("COPY", &tblname, "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(0)
+        );
+        let query = skytable::query!("ON", &tblname, "GET", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("200".to_owned())
+        );
+    }
+    async fn test_copy_bad_encoding() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,binstr)");
+        push!(query, "ON", &tblname, "SET", "x");
+        query.push(RawString::from(b"Hello \xF0\x90\x80World".to_vec()));
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        // the current table has str values
+        let query = skytable::query!("ON", &tblname, "MOVE", &__MYENTITY__, "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::EncodingError)
+        );
+        // nothing was moved
+        let query = skytable::query!("ON", &tblname, "EXISTS", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(1)
+        );
+    }
+    async fn test_copy_to_nonexistent_table() {
+        push!(query, "COPY", "thisdoesnotexist:atall", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("container-not-found".to_owned()))
+        );
+    }
+    async fn test_copy_syntax_error() {
+        push!(query, "COPY", &__MYENTITY__);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+    }
+    async fn test_move_keys() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,str)");
+        setkeys! {
+            con,
+            "x": "100",
+            "y": "200"
+        }
+        push!(query, "MOVE", &tblname, "x", "y");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(2)
+        );
+        let query = skytable::query!("EXISTS", "x", "y");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(0)
+        );
+        let query = skytable::query!("ON", &tblname, "GET", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("100".to_owned())
+        );
+    }
+    async fn test_move_statement() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,str)");
+        setkeys! {
+            con,
+            "x": "100"
+        }
+        query.push(format!("MOVE 'x' TO {}", tblname));
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(1)
+        );
+        let query = skytable::query!("ON", &tblname, "EXISTS", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(1)
+        );
+    }
+    async fn test_create_table_like() {
+        setkeys! {
+            con,
+            "x": "100"
+        }
+        let tblname = new_table_name!(__MYENTITY__);
+        push!(query, "CREATE", "TABLE", &tblname, "LIKE", &__MYENTITY__);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        // only the settings are copied
+        let query = skytable::query!("ON", &tblname, "EXISTS", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(0)
+        );
+        for (property, value) in [
+            ("key_type", "str"),
+            ("value_type", "str"),
+            ("volatile", "true"),
+        ] {
+            assert_eq!(
+                inspect_table_property!(con, &tblname, property),
+                Element::String(value.to_owned())
+            );
+        }
+    }
+    async fn test_create_table_like_already_exists() {
+        push!(
+            query,
+            "CREATE",
+            "TABLE",
+            &__MYENTITY__,
+            "LIKE",
+            &__MYENTITY__
+        );
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("err-already-exists".to_owned()))
+        );
+    }
+    async fn test_clone_table() {
+        setkeys! {
+            con,
+            "x": "100",
+            "y": "200"
+        }
+        let tblname = new_table_name!(__MYENTITY__);
+        push!(query, "CLONE", "TABLE", &__MYENTITY__, &tblname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("ON", &tblname, "MGET", "x", "y");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::Array(Array::Str(vec![
+                Some("100".to_owned()),
+                Some("200".to_owned())
+            ]))
+        );
+        // the clone doesn't share data with the original table
+        let query = skytable::query!("DEL", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(1)
+        );
+        let query = skytable::query!("ON", &tblname, "EXISTS", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(1)
+        );
+    }
+    async fn test_clone_nonexistent_table() {
+        let tblname = new_table_name!(__MYENTITY__);
+        push!(query, "CLONE", "TABLE", "thisdoesnotexist:atall", &tblname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("container-not-found".to_owned()))
+        );
+    }
+}
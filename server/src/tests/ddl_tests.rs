@@ -169,4 +169,163 @@ mod __private {
             Element::RespCode(RespCode::ActionError)
         )
     }
+    async fn test_rename_table() {
+        let mykeyspace: &str = __MYENTITY__.split(':').collect::<Vec<&str>>()[0];
+        let mut rng = rand::thread_rng();
+        let tblname = utils::rand_alphastring(10, &mut rng);
+        let newname = utils::rand_alphastring(10, &mut rng);
+        query.push("create");
+        query.push("table");
+        query.push(mykeyspace.to_owned() + ":" + &tblname);
+        query.push("keymap(str,str)");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let mut query = Query::new();
+        query.push("rename");
+        query.push("table");
+        query.push(mykeyspace.to_owned() + ":" + &tblname);
+        query.push(&newname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let mut query = Query::new();
+        query.push("drop");
+        query.push("table");
+        query.push(mykeyspace.to_owned() + ":" + &tblname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("container-not-found".to_owned()))
+        );
+        let mut query = Query::new();
+        query.push("drop");
+        query.push("table");
+        query.push(mykeyspace.to_owned() + ":" + &newname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+    }
+    async fn test_rename_table_in_use() {
+        let mut rng = rand::thread_rng();
+        let newname = utils::rand_alphastring(10, &mut rng);
+        query.push("rename");
+        query.push("table");
+        query.push(&__MYENTITY__);
+        query.push(newname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("still-in-use".to_owned()))
+        );
+    }
+    async fn test_rename_table_already_exists() {
+        let mykeyspace: &str = __MYENTITY__.split(':').collect::<Vec<&str>>()[0];
+        let my_table: &str = __MYENTITY__.split(':').collect::<Vec<&str>>()[1];
+        let mut rng = rand::thread_rng();
+        let tblname = utils::rand_alphastring(10, &mut rng);
+        query.push("create");
+        query.push("table");
+        query.push(mykeyspace.to_owned() + ":" + &tblname);
+        query.push("keymap(str,str)");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let mut query = Query::new();
+        query.push("rename");
+        query.push("table");
+        query.push(mykeyspace.to_owned() + ":" + &tblname);
+        query.push(my_table);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("err-already-exists".to_owned()))
+        );
+    }
+    async fn test_rename_keyspace() {
+        let mut rng = rand::thread_rng();
+        let ksname = utils::rand_alphastring(10, &mut rng);
+        let newname = utils::rand_alphastring(10, &mut rng);
+        query.push("create");
+        query.push("keyspace");
+        query.push(&ksname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let mut query = Query::new();
+        query.push("rename");
+        query.push("keyspace");
+        query.push(&ksname);
+        query.push(&newname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let mut query = Query::new();
+        query.push("drop");
+        query.push("keyspace");
+        query.push(newname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+    }
+    async fn test_rename_keyspace_in_use() {
+        let mykeyspace: &str = __MYENTITY__.split(':').collect::<Vec<&str>>()[0];
+        let mut rng = rand::thread_rng();
+        let newname = utils::rand_alphastring(10, &mut rng);
+        query.push("rename");
+        query.push("keyspace");
+        query.push(mykeyspace);
+        query.push(newname);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("still-in-use".to_owned()))
+        );
+    }
+    async fn test_rename_protected_keyspace() {
+        query.push("rename");
+        query.push("keyspace");
+        query.push("default");
+        query.push("mydefault");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("err-protected-object".to_owned()))
+        );
+    }
+    async fn test_alter_table() {
+        query.push("alter");
+        query.push("table");
+        query.push(&__MYENTITY__);
+        query.push("persistent");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "volatile"),
+            Element::String("false".to_owned())
+        );
+        let mut query = Query::new();
+        query.push("alter");
+        query.push("table");
+        query.push(&__MYENTITY__);
+        query.push("volatile");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+    }
+    async fn test_alter_table_unknown_property() {
+        query.push("alter");
+        query.push("table");
+        query.push(&__MYENTITY__);
+        query.push("durable");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("unknown-property".to_owned()))
+        );
+    }
 }
@@ -0,0 +1,151 @@
+/*
+ * Created on Tue Sep 14 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use skytable::{types::Array, Element, RespCode};
+    const INCR: &str = "
+        local old = tonumber(sky('GET', KEYS[1]))
+        if old == nil then
+            sky('SET', KEYS[1], ARGV[1])
+            return tonumber(ARGV[1])
+        end
+        sky('UPDATE', KEYS[1], old + tonumber(ARGV[1]))
+        return old + tonumber(ARGV[1])
+    ";
+    async fn test_eval() {
+        query.push("EVAL");
+        query.push(INCR);
+        query.push("1");
+        query.push("counter");
+        query.push("10");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(10)
+        );
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(20)
+        );
+        let query = skytable::query!("GET", "counter");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("20".to_owned())
+        );
+    }
+    async fn test_eval_returns_array() {
+        query.push("EVAL");
+        query.push("return {KEYS[1], ARGV[1], nil}");
+        query.push("1");
+        query.push("x");
+        query.push("y");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::Array(Array::Recursive(vec![
+                Element::String("x".to_owned()),
+                Element::String("y".to_owned()),
+                Element::RespCode(RespCode::NotFound)
+            ]))
+        );
+    }
+    async fn test_evalsha() {
+        query.push("SCRIPT");
+        query.push("LOAD");
+        query.push(INCR);
+        let sha = match con.run_simple_query(&query).await.unwrap() {
+            Element::String(sha) => sha,
+            _ => panic!("Bad response for SCRIPT LOAD"),
+        };
+        assert_eq!(sha.len(), 40);
+        let query = skytable::query!("SCRIPT", "EXISTS", sha.clone(), "blah");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(1)
+        );
+        let query = skytable::query!("EVALSHA", sha, "1", "counter", "5");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(5)
+        );
+    }
+    async fn test_evalsha_not_found() {
+        query.push("EVALSHA");
+        query.push("da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        query.push("0");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("script-not-found".to_owned()))
+        );
+    }
+    async fn test_eval_undeclared_key() {
+        query.push("EVAL");
+        query.push("return sky('GET', 'x')");
+        query.push("0");
+        match con.run_simple_query(&query).await.unwrap() {
+            Element::RespCode(RespCode::ErrorString(e)) => assert!(e.starts_with("script-error")),
+            _ => panic!("Bad response for EVAL"),
+        }
+    }
+    async fn test_eval_compile_error() {
+        query.push("EVAL");
+        query.push("if true then");
+        query.push("0");
+        match con.run_simple_query(&query).await.unwrap() {
+            Element::RespCode(RespCode::ErrorString(e)) => {
+                assert!(e.starts_with("script-compile-error"))
+            }
+            _ => panic!("Bad response for EVAL"),
+        }
+    }
+    async fn test_eval_step_limit() {
+        query.push("EVAL");
+        query.push("while true do end");
+        query.push("0");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("script-step-limit".to_owned()))
+        );
+    }
+    async fn test_eval_memory_limit() {
+        query.push("EVAL");
+        query.push("local s = 'xx' while true do s = s .. s end");
+        query.push("0");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("script-memory-limit".to_owned()))
+        );
+    }
+    async fn test_eval_syntax_error() {
+        query.push("EVAL");
+        query.push("return 1");
+        query.push("2");
+        query.push("x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+    }
+}
@@ -0,0 +1,137 @@
+/*
+ * Created on Tue Sep 21 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(table = "hashmap(str,str)")]
+mod __private {
+    use skytable::types::{Array, RawString};
+    use skytable::{Element, RespCode};
+    async fn test_hset_hget() {
+        push!(query, "HSET", "sayan", "lang", "rust", "city", "kolkata");
+        runeq!(con, query, Element::UnsignedInt(2));
+        // only new fields are counted
+        let query = skytable::query!("HSET", "sayan", "lang", "c", "os", "linux");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("HGET", "sayan", "lang");
+        runeq!(con, query, Element::String("c".to_owned()));
+        let query = skytable::query!("HGET", "sayan", "age");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+        let query = skytable::query!("HGET", "nobody", "lang");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+    }
+    async fn test_hset_syntax_error() {
+        push!(query, "HSET", "sayan", "lang");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+        let query = skytable::query!("HSET", "sayan", "lang", "rust", "city");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+    }
+    async fn test_hmget() {
+        push!(query, "HSET", "sayan", "lang", "rust", "city", "kolkata");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("HMGET", "sayan", "city", "age", "lang");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Str(vec![
+                Some("kolkata".to_owned()),
+                None,
+                Some("rust".to_owned())
+            ]))
+        );
+        let query = skytable::query!("HMGET", "nobody", "city");
+        runeq!(con, query, Element::Array(Array::Str(vec![None])));
+    }
+    async fn test_hdel_hlen() {
+        push!(query, "HSET", "sayan", "lang", "rust", "city", "kolkata");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("HLEN", "sayan");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("HDEL", "sayan", "lang", "age");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("HLEN", "sayan");
+        runeq!(con, query, Element::UnsignedInt(1));
+        // the key goes away along with its last field
+        let query = skytable::query!("HDEL", "sayan", "city");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("HLEN", "sayan");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_hgetall() {
+        push!(query, "HSET", "sayan", "lang", "rust");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("HGETALL", "sayan");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Recursive(vec![
+                Element::Array(Array::Str(vec![Some("lang".to_owned())])),
+                Element::Array(Array::Str(vec![Some("rust".to_owned())]))
+            ]))
+        );
+        let query = skytable::query!("HGETALL", "nobody");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Recursive(vec![
+                Element::Array(Array::Str(vec![])),
+                Element::Array(Array::Str(vec![]))
+            ]))
+        );
+    }
+    async fn test_hash_bad_encoding() {
+        let mut query = skytable::Query::new();
+        query.push("HSET");
+        query.push("sayan");
+        query.push("lang");
+        query.push(RawString::from(b"Hello \xF0\x90\x80World".to_vec()));
+        runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+    }
+    async fn test_wrong_model() {
+        push!(query, "GET", "sayan");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("wrong-model".to_owned()))
+        );
+        let query = skytable::query!("SET", "sayan", "rust");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("wrong-model".to_owned()))
+        );
+    }
+    async fn test_typed_hash_values_are_unsupported() {
+        push!(query, "CREATE", "TABLE", "typedhash", "hashmap(str,uint)");
+        runeq!(con, query, Element::RespCode(RespCode::UnknownDataType));
+    }
+    async fn test_inspect_hash_table() {
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "model"),
+            Element::String("hashmap".to_owned())
+        );
+    }
+}
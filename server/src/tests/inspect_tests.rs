@@ -51,21 +51,77 @@ mod __private {
         query.push("TABLE");
         query.push(my_table);
         match con.run_simple_query(&query).await.unwrap() {
-            Element::String(st) => {
-                assert_eq!(st, "Keymap { data:(str,str), volatile:true }".to_owned())
+            Element::Array(Array::Recursive(properties)) => {
+                assert_eq!(
+                    &properties[..8],
+                    [
+                        Element::String("model".to_owned()),
+                        Element::String("keymap".to_owned()),
+                        Element::String("key_type".to_owned()),
+                        Element::String("str".to_owned()),
+                        Element::String("value_type".to_owned()),
+                        Element::String("str".to_owned()),
+                        Element::String("volatile".to_owned()),
+                        Element::String("true".to_owned()),
+                    ]
+                )
             }
             _ => panic!("Bad response for inspect table"),
         }
     }
     async fn test_inspect_table_fully_qualified_entity() {
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "model"),
+            Element::String("keymap".to_owned())
+        );
+    }
+    async fn test_inspect_table_statistics() {
+        setkeys! {
+            con,
+            "x": "100",
+            "y": "200"
+        }
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "keys"),
+            Element::UnsignedInt(2)
+        );
+        assert!(matches!(
+            inspect_table_property!(con, &__MYENTITY__, "last_modified"),
+            Element::UnsignedInt(time) if time > 0
+        ));
+        assert!(matches!(
+            inspect_table_property!(con, &__MYENTITY__, "used_memory_approx"),
+            Element::UnsignedInt(used) if used > 0
+        ));
+        // the table is volatile, so it's never flushed
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "disk_size"),
+            Element::UnsignedInt(0)
+        );
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "last_flush"),
+            Element::UnsignedInt(0)
+        );
+    }
+    async fn test_inspect_nonexistent_table() {
         query.push("INSPECT");
         query.push("TABLE");
-        query.push(__MYENTITY__);
+        query.push("thisdoesnotexist:atall");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("container-not-found".to_owned()))
+        );
+    }
+    async fn test_inspect_server() {
+        query.push("INSPECT");
+        query.push("SERVER");
         match con.run_simple_query(&query).await.unwrap() {
-            Element::String(st) => {
-                assert_eq!(st, "Keymap { data:(str,str), volatile:true }".to_owned())
+            Element::Array(Array::Recursive(properties)) => {
+                assert_eq!(properties[0], Element::String("version".to_owned()));
+                assert!(properties.contains(&Element::String("keyspaces".to_owned())));
+                assert!(properties.contains(&Element::String("poisoned".to_owned())));
             }
-            _ => panic!("Bad response for inspect table"),
+            _ => panic!("Bad response for inspect server"),
         }
     }
     async fn test_inspect_keyspaces_syntax_error() {
@@ -87,6 +143,15 @@ mod __private {
             Element::RespCode(RespCode::ActionError)
         );
     }
+    async fn test_inspect_server_syntax_error() {
+        query.push("INSPECT");
+        query.push("SERVER");
+        query.push("oijfwirfjwo");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+    }
     async fn test_inspect_table_syntax_error() {
         query.push("INSPECT");
         query.push("TABLE");
@@ -0,0 +1,78 @@
+/*
+ * Created on Sun Sep 26 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use skytable::{Element, RespCode};
+    async fn test_countmatch() {
+        setkeys! {
+            con,
+            "user:1": "sayan",
+            "user:2": "joel",
+            "user:10": "nandan",
+            "session:1": "token"
+        }
+        push!(query, "COUNTMATCH", "user:*");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("COUNTMATCH", "user:?");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("COUNTMATCH", "*:1");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("COUNTMATCH", "[su]*:1[0-9]");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("COUNTMATCH", "admin:*");
+        runeq!(con, query, Element::UnsignedInt(0));
+        // counting doesn't remove anything
+        let query = skytable::query!("DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(4));
+    }
+    async fn test_delmatch() {
+        setkeys! {
+            con,
+            "user:1": "sayan",
+            "user:2": "joel",
+            "session:1": "token"
+        }
+        push!(query, "DELMATCH", "user:*");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("DELMATCH", "user:*");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("EXISTS", "session:1");
+        runeq!(con, query, Element::UnsignedInt(1));
+    }
+    async fn test_delmatch_bad_pattern() {
+        push!(query, "DELMATCH", "user:[12");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("malformed-expression".to_owned()))
+        );
+        let query = skytable::query!("COUNTMATCH");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+    }
+}
@@ -66,3 +66,26 @@ mod __private {
         runeq!(con, query, Element::RespCode(RespCode::EncodingError));
     }
 }
+
+/// Tests for tables where only the values are unicode strings
+mod binstr_str {
+    #[sky_macros::dbtest(table = "keymap(binstr,str)")]
+    mod __private {
+        use skytable::types::RawString;
+        use skytable::{Element, RespCode};
+        async fn test_bad_encoding_str_value_multi() {
+            // only the values are validated in this table, so MSET, USET and MUPDATE can't skip
+            // the validation
+            push!(query, "SET", RawString::from(b"x\xF0".to_vec()), "100");
+            runeq!(con, query, Element::RespCode(RespCode::Okay));
+            for action in ["MSET", "USET", "MUPDATE"] {
+                let query = skytable::query!(
+                    action,
+                    RawString::from(b"x\xF0".to_vec()),
+                    RawString::from(b"Hello \xF0\x90\x80World".to_vec())
+                );
+                runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+/*
+ * Created on Mon Sep 20 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(table = "keymap(str,uint)")]
+mod __private {
+    use skytable::{Element, RespCode};
+    async fn test_uint_set_get() {
+        push!(query, "SET", "x", "100");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("GET", "x");
+        runeq!(con, query, Element::UnsignedInt(100));
+    }
+    async fn test_uint_bad_values() {
+        for bad in ["-1", "1.5", "007", "hello", "18446744073709551616"] {
+            let query = skytable::query!("SET", "x", bad);
+            runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+        }
+        let query = skytable::query!("EXISTS", "x");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_uint_bad_value_mset() {
+        push!(query, "MSET", "x", "100", "y", "two hundred");
+        runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+    }
+    async fn test_uint_bad_value_update() {
+        setkeys! {
+            con,
+            "x": "100"
+        }
+        push!(query, "UPDATE", "x", "-100");
+        runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+    }
+    async fn test_sint_values() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,sint)");
+        push!(query, "ON", &tblname, "MSET", "x", "-100", "y", "100");
+        runeq!(con, query, Element::UnsignedInt(2));
+        // Skyhash has no type for these, so they're returned as strings
+        let query = skytable::query!("ON", &tblname, "GET", "x");
+        runeq!(con, query, Element::String("-100".to_owned()));
+        for bad in ["-0", "+1", "1.5", "9223372036854775808"] {
+            let query = skytable::query!("ON", &tblname, "SET", "z", bad);
+            runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+        }
+    }
+    async fn test_float_values() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,float)");
+        push!(query, "ON", &tblname, "MSET", "x", "-1.5", "y", "2e10");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("ON", &tblname, "GET", "x");
+        runeq!(con, query, Element::String("-1.5".to_owned()));
+        for bad in ["NaN", "inf", "1.0.0", "pi"] {
+            let query = skytable::query!("ON", &tblname, "SET", "z", bad);
+            runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+        }
+    }
+    async fn test_bool_values() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(binstr,bool)");
+        push!(query, "ON", &tblname, "MSET", "x", "true", "y", "false");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("ON", &tblname, "GET", "y");
+        runeq!(con, query, Element::String("false".to_owned()));
+        for bad in ["TRUE", "1", "yes"] {
+            let query = skytable::query!("ON", &tblname, "SET", "z", bad);
+            runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+        }
+    }
+    async fn test_typed_keys_are_unsupported() {
+        let query = skytable::query!("CREATE", "TABLE", "typedkeys", "keymap(uint,str)");
+        runeq!(con, query, Element::RespCode(RespCode::UnknownDataType));
+    }
+    async fn test_inspect_typed_table() {
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "value_type"),
+            Element::String("uint".to_owned())
+        );
+    }
+}
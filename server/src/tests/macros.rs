@@ -54,3 +54,44 @@ macro_rules! runeq {
         assert_eq!($con.run_simple_query(&$query).await.unwrap(), $eq)
     };
 }
+
+macro_rules! inspect_table_property {
+    ($con:expr, $entity:expr, $property:expr) => {{
+        let query = skytable::query!("INSPECT", "TABLE", $entity);
+        match $con.run_simple_query(&query).await.unwrap() {
+            skytable::Element::Array(skytable::types::Array::Recursive(properties)) => {
+                // the properties are alternating names and values
+                let mut properties = properties.into_iter();
+                let mut found = None;
+                while let (Some(name), Some(value)) = (properties.next(), properties.next()) {
+                    if name == skytable::Element::String($property.to_owned()) {
+                        found = Some(value);
+                    }
+                }
+                found.expect("property not found")
+            }
+            x => panic!("Bad response for INSPECT TABLE: {:?}", x),
+        }
+    }};
+}
+
+macro_rules! new_table_name {
+    ($entity:expr) => {{
+        // the table goes into the keyspace of the test suite
+        let mykeyspace: &str = $entity.split(':').collect::<Vec<&str>>()[0];
+        let mut rng = rand::thread_rng();
+        mykeyspace.to_owned() + ":" + &libstress::utils::rand_alphastring(10, &mut rng)
+    }};
+}
+
+macro_rules! create_table {
+    ($con:expr, $entity:expr, $model:expr) => {{
+        let tblname = new_table_name!($entity);
+        let query = skytable::query!("CREATE", "TABLE", &tblname, $model);
+        assert_eq!(
+            $con.run_simple_query(&query).await.unwrap(),
+            skytable::Element::RespCode(skytable::RespCode::Okay)
+        );
+        tblname
+    }};
+}
@@ -28,10 +28,29 @@
 
 #[macro_use]
 mod macros;
+mod client_tests;
+mod config_tests;
+mod copy_tests;
 mod ddl_tests;
+mod eval_tests;
+mod hash_tests;
 mod inspect_tests;
+mod keymatch_tests;
 mod kvengine;
 mod kvengine_encoding;
+mod kvengine_types;
+mod ql_tests;
+mod quota_tests;
+mod scope_tests;
+mod set_tests;
+mod sketch_tests;
+mod slowlog_tests;
+mod sortedset_tests;
+mod strings_tests;
+mod sys_tests;
+mod timeseries_tests;
+mod txn_tests;
+mod versioned_tests;
 
 mod ssl {
     use skytable::aio::TlsConnection;
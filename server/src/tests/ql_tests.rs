@@ -0,0 +1,104 @@
+/*
+ * Created on Thu Sep 16 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use libstress::utils;
+    use skytable::{types::Array, Element, RespCode};
+    async fn test_ql_set_get() {
+        query.push("SET 'x' = '100'");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("GET 'x'");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("100".to_owned())
+        );
+    }
+    async fn test_ql_multi_set_get() {
+        query.push("SET 'x' = '100', 'y' = 200");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(2)
+        );
+        let query = skytable::query!("GET 'x', 'y', 'z'");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::Array(Array::Str(vec![
+                Some("100".to_owned()),
+                Some("200".to_owned()),
+                None
+            ]))
+        );
+    }
+    async fn test_ql_update_delete() {
+        query.push("SET 'x' = 'y'");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("UPDATE 'x' = 'z'");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("DELETE 'x'");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(1)
+        );
+        let query = skytable::query!("EXISTS 'x'");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(0)
+        );
+    }
+    async fn test_ql_create_table() {
+        let mykeyspace: &str = __MYENTITY__.split(':').collect::<Vec<&str>>()[0];
+        let mut rng = rand::thread_rng();
+        let tblname = utils::rand_alphastring(10, &mut rng);
+        query.push(format!(
+            "CREATE TABLE {}.{} (k str, v binstr) WITH volatile",
+            mykeyspace, tblname
+        ));
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+    }
+    async fn test_ql_syntax_error() {
+        query.push("GET 'x' 'y'");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString(
+                "syntax-error: expected the end of the statement, found a string at position 9"
+                    .to_owned()
+            ))
+        );
+    }
+}
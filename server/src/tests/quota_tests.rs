@@ -0,0 +1,467 @@
+/*
+ * Created on Wed Sep 29 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(skip = "create_keyspace create_keyspace_with alter_keyspace")]
+mod __private {
+    use libstress::utils;
+    use skytable::types::Array;
+    use skytable::{Element, RespCode};
+    /// Create a new keyspace with a `keymap(str,str)` table named `tbl` and switch to that
+    /// table, returning the name of the keyspace. Quotas apply to the whole keyspace, so
+    /// every test gets its own
+    async fn create_keyspace(con: &mut skytable::AsyncConnection) -> String {
+        create_keyspace_with(con, "keymap(str,str)").await
+    }
+    /// Like [`create_keyspace`], but with a table of the given model
+    async fn create_keyspace_with(con: &mut skytable::AsyncConnection, model: &str) -> String {
+        let mut rng = rand::thread_rng();
+        let ksname = utils::rand_alphastring(10, &mut rng);
+        let tblname = ksname.clone() + ":tbl";
+        for query in [
+            skytable::query!("CREATE", "KEYSPACE", &ksname),
+            skytable::query!("CREATE", "TABLE", &tblname, model),
+            skytable::query!("USE", &tblname),
+        ] {
+            assert_eq!(
+                con.run_simple_query(&query).await.unwrap(),
+                Element::RespCode(RespCode::Okay)
+            );
+        }
+        ksname
+    }
+    /// Set the limit of a quota of a keyspace
+    async fn alter_keyspace(
+        con: &mut skytable::AsyncConnection,
+        ksname: &str,
+        quota: &str,
+        limit: &str,
+    ) {
+        let query = skytable::query!("ALTER", "KEYSPACE", ksname, quota, limit);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+    }
+    async fn test_max_keys() {
+        let ksname = create_keyspace(&mut con).await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "2").await;
+        setkeys! {
+            con,
+            "x": "100",
+            "y": "200"
+        }
+        push!(query, "SET", "z", "300");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("MSET", "z", "300", "w", "400");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        // updates don't add keys
+        let query = skytable::query!("UPDATE", "x", "1000");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        // and removals are always fine
+        let query = skytable::query!("DEL", "x");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("SET", "z", "300");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        // a limit of zero removes the limit
+        alter_keyspace(&mut con, &ksname, "max_keys", "0").await;
+        let query = skytable::query!("SET", "w", "400");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+    }
+    async fn test_max_bytes_and_value_size() {
+        let ksname = create_keyspace(&mut con).await;
+        alter_keyspace(&mut con, &ksname, "max_value_size", "4").await;
+        alter_keyspace(&mut con, &ksname, "max_bytes", "10").await;
+        push!(query, "SET", "x", "toolong");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("USET", "x", "1234", "y", "5678");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("SET", "z", "123");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("APPEND", "x", "5");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        // replacing a value only counts what it adds
+        let query = skytable::query!("UPDATE", "x", "12");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("SET", "z", "12");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+    }
+    async fn test_hash_quotas() {
+        let ksname = create_keyspace_with(&mut con, "hashmap(str,str)").await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "1").await;
+        alter_keyspace(&mut con, &ksname, "max_bytes", "10").await;
+        // a field counts along with its value
+        push!(query, "HSET", "user", "name", "sayan");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("HSET", "other", "name", "x");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("HSET", "user", "lang", "c");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("HSET", "user", "name", "sn");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("HSET", "user", "id", "1");
+        runeq!(con, query, Element::UnsignedInt(1));
+        // the key goes away along with its last field, which makes room for another key
+        let query = skytable::query!("HDEL", "user", "name", "id");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("HSET", "other", "name", "x");
+        runeq!(con, query, Element::UnsignedInt(1));
+    }
+    async fn test_set_quotas() {
+        let ksname = create_keyspace_with(&mut con, "set(str,str)").await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "1").await;
+        alter_keyspace(&mut con, &ksname, "max_bytes", "6").await;
+        push!(query, "SADD", "tags", "rust", "db");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("SADD", "more", "c");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("SADD", "tags", "c");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        // existing members don't add anything
+        let query = skytable::query!("SADD", "tags", "rust");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("SUNIONSTORE", "tags", "tags");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("SUNIONSTORE", "copy", "tags");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("SREM", "tags", "db");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("SADD", "tags", "c");
+        runeq!(con, query, Element::UnsignedInt(1));
+    }
+    async fn test_sortedset_quotas() {
+        let ksname = create_keyspace_with(&mut con, "sortedset(str,str)").await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "1").await;
+        // every member counts along with its (8 byte) score
+        alter_keyspace(&mut con, &ksname, "max_bytes", "30").await;
+        push!(query, "ZADD", "board", "1", "alice");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("ZADD", "other", "1", "bob");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("ZADD", "board", "2", "carol");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("ZINCRBY", "board", "1", "dave");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        // changing a score doesn't add anything
+        let query = skytable::query!("ZINCRBY", "board", "1", "alice");
+        runeq!(con, query, Element::String("2".to_owned()));
+        let query = skytable::query!("ZREM", "board", "carol");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("ZINCRBY", "board", "1", "dave");
+        runeq!(con, query, Element::String("1".to_owned()));
+    }
+    async fn test_timeseries_quotas() {
+        let ksname = create_keyspace_with(&mut con, "timeseries(str,float)").await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "1").await;
+        // every sample counts as a timestamp and a value
+        alter_keyspace(&mut con, &ksname, "max_bytes", "32").await;
+        push!(query, "TSADD", "cpu", "1000", "0.5");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("TSCREATE", "mem", "0");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("TSADD", "mem", "1000", "0.5");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("TSADD", "cpu", "2000", "0.75");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("TSADD", "cpu", "3000", "0.25");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+    }
+    async fn test_max_tables() {
+        let ksname = create_keyspace(&mut con).await;
+        alter_keyspace(&mut con, &ksname, "max_tables", "2").await;
+        let query = skytable::query!(
+            "CREATE",
+            "TABLE",
+            ksname.clone() + ":two",
+            "keymap(str,str)"
+        );
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!(
+            "CREATE",
+            "TABLE",
+            ksname.clone() + ":three",
+            "keymap(str,str)"
+        );
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+    }
+    async fn test_quota_in_transaction() {
+        let ksname = create_keyspace(&mut con).await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "1").await;
+        setkeys! {
+            con,
+            "x": "100"
+        }
+        push!(query, "MULTI");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("SET", "y", "200");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned()))
+        );
+        let query = skytable::query!("EXEC");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("txn-aborted".to_owned()))
+        );
+    }
+    async fn test_quota_checked_on_exec() {
+        let ksname = create_keyspace(&mut con).await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "2").await;
+        push!(query, "MULTI");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        for query in [
+            skytable::query!("SET", "x", "100"),
+            skytable::query!("SET", "y", "200"),
+            skytable::query!("DEL", "x"),
+        ] {
+            runeq!(con, query, Element::String("QUEUED".to_owned()));
+        }
+        // another connection uses up a key after the writes were queued
+        let mut other = skytable::AsyncConnection::new("127.0.0.1", 2003)
+            .await
+            .unwrap();
+        let query = skytable::query!("USE", ksname.clone() + ":tbl");
+        assert_eq!(
+            other.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("SET", "z", "300");
+        assert_eq!(
+            other.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("EXEC");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Recursive(vec![
+                Element::RespCode(RespCode::Okay),
+                Element::RespCode(RespCode::ErrorString("quota-exceeded".to_owned())),
+                Element::UnsignedInt(1)
+            ]))
+        );
+        let query = skytable::query!("EXISTS", "y");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_quota_with_concurrent_writers() {
+        let ksname = create_keyspace(&mut con).await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "10").await;
+        // every writer adds its own keys at the same time as the others
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let tblname = ksname.clone() + ":tbl";
+                tokio::spawn(async move {
+                    let mut con = skytable::AsyncConnection::new("127.0.0.1", 2003)
+                        .await
+                        .unwrap();
+                    let query = skytable::query!("USE", tblname);
+                    runeq!(con, query, Element::RespCode(RespCode::Okay));
+                    let mut added = 0;
+                    for key in 0..10 {
+                        let query = skytable::query!("SET", format!("{}-{}", writer, key), "100");
+                        if con.run_simple_query(&query).await.unwrap()
+                            == Element::RespCode(RespCode::Okay)
+                        {
+                            added += 1;
+                        }
+                    }
+                    added
+                })
+            })
+            .collect();
+        let mut added = 0;
+        for writer in writers {
+            added += writer.await.unwrap();
+        }
+        assert_eq!(added, 10);
+        push!(query, "DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(10));
+    }
+    async fn test_quota_with_concurrent_copies() {
+        let ksname = create_keyspace(&mut con).await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "5").await;
+        // every writer copies two keys of its own table into the keyspace at the same time
+        let mut sources = Vec::new();
+        for writer in 0..8 {
+            let tblname = create_table!(con, __MYENTITY__, "keymap(str,str)");
+            let query = skytable::query!(
+                "ON",
+                &tblname,
+                "MSET",
+                format!("{}-x", writer),
+                "100",
+                format!("{}-y", writer),
+                "200"
+            );
+            runeq!(con, query, Element::UnsignedInt(2));
+            sources.push((tblname, writer));
+        }
+        let copiers: Vec<_> = sources
+            .into_iter()
+            .map(|(tblname, writer)| {
+                let target = ksname.clone() + ":tbl";
+                tokio::spawn(async move {
+                    let mut con = skytable::AsyncConnection::new("127.0.0.1", 2003)
+                        .await
+                        .unwrap();
+                    let query = skytable::query!("USE", tblname);
+                    runeq!(con, query, Element::RespCode(RespCode::Okay));
+                    let query = skytable::query!(
+                        "COPY",
+                        target,
+                        format!("{}-x", writer),
+                        format!("{}-y", writer)
+                    );
+                    match con.run_simple_query(&query).await.unwrap() {
+                        Element::UnsignedInt(copied) => copied,
+                        _ => 0,
+                    }
+                })
+            })
+            .collect();
+        let mut copied = 0;
+        for copier in copiers {
+            copied += copier.await.unwrap();
+        }
+        // only two of the copies fit
+        assert_eq!(copied, 4);
+        push!(query, "DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(4));
+    }
+    async fn test_inspect_quotas() {
+        let ksname = create_keyspace(&mut con).await;
+        alter_keyspace(&mut con, &ksname, "max_keys", "10").await;
+        setkeys! {
+            con,
+            "x": "100"
+        }
+        push!(query, "INSPECT", "QUOTAS", &ksname);
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Recursive(vec![
+                Element::String("max_tables".to_owned()),
+                Element::UnsignedInt(0),
+                Element::String("max_keys".to_owned()),
+                Element::UnsignedInt(10),
+                Element::String("max_bytes".to_owned()),
+                Element::UnsignedInt(0),
+                Element::String("max_value_size".to_owned()),
+                Element::UnsignedInt(0),
+                Element::String("tables".to_owned()),
+                Element::UnsignedInt(1),
+                Element::String("keys".to_owned()),
+                Element::UnsignedInt(1),
+                Element::String("bytes".to_owned()),
+                Element::UnsignedInt(3),
+            ]))
+        );
+    }
+    async fn test_alter_keyspace_errors() {
+        let ksname = create_keyspace(&mut con).await;
+        push!(query, "ALTER", "KEYSPACE", &ksname, "max_values", "1");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("unknown-property".to_owned()))
+        );
+        let query = skytable::query!("ALTER", "KEYSPACE", &ksname, "max_keys", "ten");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("ALTER", "KEYSPACE", "nosuchkeyspace", "max_keys", "1");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("container-not-found".to_owned()))
+        );
+        let query = skytable::query!("ALTER", "KEYSPACE", &ksname, "max_keys");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+    }
+}
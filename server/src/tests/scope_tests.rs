@@ -0,0 +1,116 @@
+/*
+ * Created on Fri Sep 17 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use skytable::{Element, Query, RespCode};
+    async fn test_on_set_get() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,str)");
+        query.push("ON");
+        query.push(&tblname);
+        query.push("SET");
+        query.push("x");
+        query.push("100");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        // the current table is untouched
+        let query = skytable::query!("GET", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::NotFound)
+        );
+        let query = skytable::query!("ON", &tblname, "GET", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("100".to_owned())
+        );
+    }
+    async fn test_on_statement() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,str)");
+        query.push(format!("ON {} SET 'x' = '100'", tblname));
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("ON", &tblname, "EXISTS", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(1)
+        );
+    }
+    async fn test_on_table_in_current_keyspace() {
+        let tblname = create_table!(con, __MYENTITY__, "keymap(str,str)");
+        let tbl = tblname.split(':').collect::<Vec<&str>>()[1];
+        query.push("ON");
+        query.push(tbl);
+        query.push("SET");
+        query.push("x");
+        query.push("100");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let mut query = Query::new();
+        query.push("ON");
+        query.push(&tblname);
+        query.push("GET");
+        query.push("x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("100".to_owned())
+        );
+    }
+    async fn test_on_nonexistent_table() {
+        query.push("ON");
+        query.push("thisdoesnotexist:atall");
+        query.push("GET");
+        query.push("x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("container-not-found".to_owned()))
+        );
+    }
+    async fn test_on_unsupported_action() {
+        query.push("ON");
+        query.push(&__MYENTITY__);
+        query.push("USE");
+        query.push("default:default");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("unsupported-in-scope".to_owned()))
+        );
+    }
+    async fn test_on_syntax_error() {
+        query.push("ON");
+        query.push(&__MYENTITY__);
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+    }
+}
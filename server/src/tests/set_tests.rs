@@ -0,0 +1,147 @@
+/*
+ * Created on Wed Sep 22 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(table = "set(str,str)", skip = "sorted")]
+mod __private {
+    use skytable::types::{Array, RawString};
+    use skytable::{Element, RespCode};
+    /// Returns the members of a typed array of strings in ascending order
+    fn sorted(element: Element) -> Vec<String> {
+        match element {
+            Element::Array(Array::Str(members)) => {
+                let mut members: Vec<String> = members.into_iter().map(Option::unwrap).collect();
+                members.sort();
+                members
+            }
+            x => panic!("expected a typed array, but got: {:?}", x),
+        }
+    }
+    async fn test_sadd_srem() {
+        push!(query, "SADD", "tags", "rust", "db");
+        runeq!(con, query, Element::UnsignedInt(2));
+        // only new members are counted
+        let query = skytable::query!("SADD", "tags", "rust", "c");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("SCARD", "tags");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("SREM", "tags", "c", "go");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("SISMEMBER", "tags", "c");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("SISMEMBER", "tags", "rust");
+        runeq!(con, query, Element::UnsignedInt(1));
+        // the key goes away along with its last member
+        let query = skytable::query!("SREM", "tags", "rust", "db");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_smembers() {
+        push!(query, "SADD", "tags", "rust", "db");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("SMEMBERS", "tags");
+        assert_eq!(
+            sorted(con.run_simple_query(&query).await.unwrap()),
+            vec!["db".to_owned(), "rust".to_owned()]
+        );
+        let query = skytable::query!("SMEMBERS", "notags");
+        runeq!(con, query, Element::Array(Array::Str(vec![])));
+    }
+    async fn test_set_algebra() {
+        push!(query, "SADD", "a", "1", "2", "3");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("SADD", "b", "2", "3", "4");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("SUNION", "a", "b");
+        assert_eq!(
+            sorted(con.run_simple_query(&query).await.unwrap()),
+            vec!["1", "2", "3", "4"]
+        );
+        let query = skytable::query!("SINTER", "a", "b");
+        assert_eq!(
+            sorted(con.run_simple_query(&query).await.unwrap()),
+            vec!["2", "3"]
+        );
+        let query = skytable::query!("SDIFF", "a", "b");
+        assert_eq!(
+            sorted(con.run_simple_query(&query).await.unwrap()),
+            vec!["1"]
+        );
+        // a missing key is an empty set
+        let query = skytable::query!("SINTER", "a", "c");
+        runeq!(con, query, Element::Array(Array::Str(vec![])));
+    }
+    async fn test_set_algebra_store() {
+        push!(query, "SADD", "a", "1", "2", "3");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("SADD", "b", "2", "3", "4");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("SINTERSTORE", "c", "a", "b");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("SMEMBERS", "c");
+        assert_eq!(
+            sorted(con.run_simple_query(&query).await.unwrap()),
+            vec!["2", "3"]
+        );
+        // the destination can be one of the keys
+        let query = skytable::query!("SUNIONSTORE", "a", "a", "b");
+        runeq!(con, query, Element::UnsignedInt(4));
+        // and an empty result removes the destination
+        let query = skytable::query!("SDIFFSTORE", "c", "c", "a");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(2));
+    }
+    async fn test_set_syntax_error() {
+        push!(query, "SADD", "tags");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+        let query = skytable::query!("SUNION");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+        let query = skytable::query!("SUNIONSTORE", "tags");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+    }
+    async fn test_set_bad_encoding() {
+        let mut query = skytable::Query::new();
+        query.push("SADD");
+        query.push("tags");
+        query.push(RawString::from(b"Hello \xF0\x90\x80World".to_vec()));
+        runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+    }
+    async fn test_set_wrong_model() {
+        push!(query, "HGET", "tags", "rust");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("wrong-model".to_owned()))
+        );
+    }
+    async fn test_inspect_set_table() {
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "model"),
+            Element::String("set".to_owned())
+        );
+    }
+}
@@ -0,0 +1,102 @@
+/*
+ * Created on Fri Sep 24 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(table = "keymap(str,binstr)")]
+mod __private {
+    use skytable::types::Array;
+    use skytable::{Element, RespCode};
+    async fn test_pfadd_pfcount() {
+        push!(query, "PFADD", "visitors", "sayan", "ferris", "sayan");
+        runeq!(con, query, Element::UnsignedInt(1));
+        // nothing new
+        let query = skytable::query!("PFADD", "visitors", "ferris");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("PFCOUNT", "visitors");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("PFCOUNT", "novisitors");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_pfmerge() {
+        push!(query, "PFADD", "monday", "sayan", "ferris");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("PFADD", "tuesday", "ferris", "crab");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("PFCOUNT", "monday", "tuesday");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("PFMERGE", "week", "monday", "tuesday");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("PFCOUNT", "week");
+        runeq!(con, query, Element::UnsignedInt(3));
+    }
+    async fn test_bloom_filter() {
+        push!(query, "BFRESERVE", "seen", "0.01", "1000");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("BFRESERVE", "seen", "0.01", "1000");
+        runeq!(con, query, Element::RespCode(RespCode::OverwriteError));
+        let query = skytable::query!("BFADD", "seen", "sayan");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("BFADD", "seen", "sayan");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("BFEXISTS", "seen", "sayan");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("BFMEXISTS", "seen", "sayan", "nosayan");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Recursive(vec![
+                Element::UnsignedInt(1),
+                Element::UnsignedInt(0)
+            ]))
+        );
+        // a missing key hasn't seen anything
+        let query = skytable::query!("BFEXISTS", "unseen", "sayan");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_bfadd_creates_filter() {
+        push!(query, "BFADD", "seen", "sayan");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("BFEXISTS", "seen", "sayan");
+        runeq!(con, query, Element::UnsignedInt(1));
+    }
+    async fn test_bfreserve_bad_params() {
+        push!(query, "BFRESERVE", "seen", "often", "1000");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("BFRESERVE", "seen", "1.5", "1000");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+        let query = skytable::query!("BFRESERVE", "seen", "0.01", "0");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+    }
+    async fn test_sketch_wrong_type() {
+        setkeys!(con, "visitors" : "many");
+        let query = skytable::query!("PFADD", "visitors", "sayan");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("PFADD", "seen", "sayan");
+        runeq!(con, query, Element::UnsignedInt(1));
+        // a counter isn't a filter
+        let query = skytable::query!("BFADD", "seen", "sayan");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+    }
+}
@@ -0,0 +1,87 @@
+/*
+ * Created on Wed Sep 08 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use skytable::{types::Array, Element, RespCode};
+    async fn test_slowlog_len() {
+        query.push("SLOWLOG");
+        query.push("LEN");
+        assert!(matches!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(_)
+        ));
+    }
+    async fn test_slowlog_get() {
+        query.push("SLOWLOG");
+        query.push("GET");
+        query.push("5");
+        match con.run_simple_query(&query).await.unwrap() {
+            Element::Array(Array::Str(entries)) => {
+                assert!(entries.len() <= 5);
+                assert!(entries
+                    .into_iter()
+                    .flatten()
+                    .all(|entry| entry.starts_with("id=")));
+            }
+            _ => panic!("Bad response for SLOWLOG GET"),
+        }
+    }
+    async fn test_slowlog_get_bad_count() {
+        query.push("SLOWLOG");
+        query.push("GET");
+        query.push("notanumber");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Wrongtype)
+        );
+    }
+    async fn test_slowlog_reset() {
+        query.push("SLOWLOG");
+        query.push("RESET");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+    }
+    async fn test_slowlog_unknown_query() {
+        query.push("SLOWLOG");
+        query.push("blahblah");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("unknown-slowlog-query".to_owned()))
+        );
+    }
+    async fn test_slowlog_syntax_error() {
+        query.push("SLOWLOG");
+        query.push("LEN");
+        query.push("extra");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+    }
+}
@@ -0,0 +1,182 @@
+/*
+ * Created on Thu Sep 23 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(table = "sortedset(str,str)")]
+mod __private {
+    use skytable::types::{Array, RawString};
+    use skytable::{Element, RespCode};
+    async fn test_zadd_zrem() {
+        push!(query, "ZADD", "board", "30", "alice", "10", "bob");
+        runeq!(con, query, Element::UnsignedInt(2));
+        // existing members only get their scores updated
+        let query = skytable::query!("ZADD", "board", "5", "alice", "20", "carol");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("ZCARD", "board");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("ZRANK", "board", "alice");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("ZREM", "board", "alice", "dave");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("ZRANK", "board", "alice");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+        // the key goes away along with its last member
+        let query = skytable::query!("ZREM", "board", "bob", "carol");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_zrank_zrevrank() {
+        push!(query, "ZADD", "board", "30", "alice", "10", "bob", "20", "carol");
+        runeq!(con, query, Element::UnsignedInt(3));
+        let query = skytable::query!("ZRANK", "board", "carol");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("ZREVRANK", "board", "alice");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("ZREVRANK", "board", "bob");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("ZRANK", "noboard", "bob");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+    }
+    async fn test_zadd_reranks() {
+        push!(query, "ZADD", "board", "30", "alice", "10", "bob");
+        runeq!(con, query, Element::UnsignedInt(2));
+        // bob overtakes alice
+        let query = skytable::query!("ZADD", "board", "35.5", "bob");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("ZREVRANK", "board", "bob");
+        runeq!(con, query, Element::UnsignedInt(0));
+        let query = skytable::query!("ZRANK", "board", "alice");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_zrange_zrevrange() {
+        push!(query, "ZADD", "board", "30", "alice", "10", "bob", "20", "carol", "40", "dave");
+        runeq!(con, query, Element::UnsignedInt(4));
+        let query = skytable::query!("ZRANGE", "board", "0", "1");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Str(vec![
+                Some("bob".to_owned()),
+                Some("carol".to_owned())
+            ]))
+        );
+        // the top three
+        let query = skytable::query!("ZREVRANGE", "board", "0", "2");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Str(vec![
+                Some("dave".to_owned()),
+                Some("alice".to_owned()),
+                Some("carol".to_owned())
+            ]))
+        );
+        // negative ranks are counted from the end
+        let query = skytable::query!("ZRANGE", "board", "-2", "-1");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Str(vec![
+                Some("alice".to_owned()),
+                Some("dave".to_owned())
+            ]))
+        );
+        let query = skytable::query!("ZRANGE", "board", "3", "1");
+        runeq!(con, query, Element::Array(Array::Str(vec![])));
+    }
+    async fn test_zrangebyscore() {
+        push!(query, "ZADD", "board", "30", "alice", "10", "bob", "20", "carol", "40", "dave");
+        runeq!(con, query, Element::UnsignedInt(4));
+        let query = skytable::query!("ZRANGEBYSCORE", "board", "15", "30");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Str(vec![
+                Some("carol".to_owned()),
+                Some("alice".to_owned())
+            ]))
+        );
+        let query = skytable::query!("ZRANGEBYSCORE", "board", "41", "50");
+        runeq!(con, query, Element::Array(Array::Str(vec![])));
+    }
+    async fn test_zscore_zincrby() {
+        push!(query, "ZADD", "board", "1.5", "alice");
+        runeq!(con, query, Element::UnsignedInt(1));
+        let query = skytable::query!("ZSCORE", "board", "alice");
+        runeq!(con, query, Element::String("1.5".to_owned()));
+        let query = skytable::query!("ZINCRBY", "board", "-3", "alice");
+        runeq!(con, query, Element::String("-1.5".to_owned()));
+        // a missing member starts with a zero score
+        let query = skytable::query!("ZINCRBY", "board", "2", "bob");
+        runeq!(con, query, Element::String("2".to_owned()));
+    }
+    async fn test_zscore_nil() {
+        push!(query, "ZSCORE", "board", "alice");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+    }
+    async fn test_sortedset_bad_score() {
+        push!(query, "ZADD", "board", "lots", "alice");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("ZADD", "board", "NaN", "alice");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("ZINCRBY", "board", "inf", "alice");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("ZRANGE", "board", "0", "last");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("DBSIZE");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_sortedset_syntax_error() {
+        push!(query, "ZADD", "board", "10");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+        let query = skytable::query!("ZADD", "board", "10", "alice", "20");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+        let query = skytable::query!("ZRANGE", "board", "0");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+    }
+    async fn test_sortedset_bad_encoding() {
+        let mut query = skytable::Query::new();
+        query.push("ZADD");
+        query.push("board");
+        query.push("10");
+        query.push(RawString::from(b"Hello \xF0\x90\x80World".to_vec()));
+        runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+    }
+    async fn test_sortedset_wrong_model() {
+        push!(query, "SISMEMBER", "board", "alice");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("wrong-model".to_owned()))
+        );
+    }
+    async fn test_inspect_sortedset_table() {
+        assert_eq!(
+            inspect_table_property!(con, &__MYENTITY__, "model"),
+            Element::String("sortedset".to_owned())
+        );
+    }
+}
@@ -0,0 +1,98 @@
+/*
+ * Created on Mon Sep 27 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(table = "keymap(str,str)")]
+mod __private {
+    use skytable::{Element, RespCode};
+    async fn test_append() {
+        push!(query, "APPEND", "log", "hello");
+        runeq!(con, query, Element::UnsignedInt(5));
+        let query = skytable::query!("APPEND", "log", " world");
+        runeq!(con, query, Element::UnsignedInt(11));
+        let query = skytable::query!("GET", "log");
+        runeq!(con, query, Element::String("hello world".to_owned()));
+    }
+    async fn test_strlen() {
+        setkeys! {
+            con,
+            "greeting": "hëllo"
+        }
+        push!(query, "STRLEN", "greeting");
+        runeq!(con, query, Element::UnsignedInt(6));
+        let query = skytable::query!("STRLEN", "nogreeting");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+        let query = skytable::query!("STRLEN", "greeting", "x");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+    }
+    async fn test_getrange() {
+        setkeys! {
+            con,
+            "greeting": "hello world"
+        }
+        push!(query, "GETRANGE", "greeting", "0", "4");
+        runeq!(con, query, Element::String("hello".to_owned()));
+        let query = skytable::query!("GETRANGE", "greeting", "-5", "-1");
+        runeq!(con, query, Element::String("world".to_owned()));
+        let query = skytable::query!("GETRANGE", "greeting", "6", "100");
+        runeq!(con, query, Element::String("world".to_owned()));
+        let query = skytable::query!("GETRANGE", "greeting", "5", "2");
+        runeq!(con, query, Element::String("".to_owned()));
+        let query = skytable::query!("GETRANGE", "nogreeting", "0", "1");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+        let query = skytable::query!("GETRANGE", "greeting", "zero", "1");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+    }
+    async fn test_setrange() {
+        setkeys! {
+            con,
+            "greeting": "hello world"
+        }
+        push!(query, "SETRANGE", "greeting", "6", "skies");
+        runeq!(con, query, Element::UnsignedInt(11));
+        let query = skytable::query!("SETRANGE", "greeting", "11", "!");
+        runeq!(con, query, Element::UnsignedInt(12));
+        let query = skytable::query!("GET", "greeting");
+        runeq!(con, query, Element::String("hello skies!".to_owned()));
+    }
+    async fn test_setrange_splits_char() {
+        setkeys! {
+            con,
+            "greeting": "hëllo"
+        }
+        push!(query, "SETRANGE", "greeting", "2", "e");
+        runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+        let query = skytable::query!("GETRANGE", "greeting", "0", "1");
+        runeq!(con, query, Element::RespCode(RespCode::EncodingError));
+    }
+    async fn test_getset() {
+        push!(query, "GETSET", "x", "100");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+        let query = skytable::query!("GETSET", "x", "200");
+        runeq!(con, query, Element::String("100".to_owned()));
+        let query = skytable::query!("GET", "x");
+        runeq!(con, query, Element::String("200".to_owned()));
+    }
+}
@@ -0,0 +1,87 @@
+/*
+ * Created on Mon Sep 06 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use skytable::{types::Array, Element, RespCode};
+    async fn test_sys_info_all_sections() {
+        query.push("SYS");
+        query.push("INFO");
+        match con.run_simple_query(&query).await.unwrap() {
+            Element::Array(Array::Str(lines)) => {
+                let lines: Vec<String> = lines.into_iter().flatten().collect();
+                for section in ["server", "clients", "persistence", "memory", "keyspaces"] {
+                    assert!(lines.contains(&format!("# {}", section)));
+                }
+                assert!(lines.iter().any(|line| line.starts_with("uptime:")));
+                assert!(lines
+                    .iter()
+                    .any(|line| line.starts_with("connected_clients:")));
+            }
+            _ => panic!("Bad response for SYS INFO"),
+        }
+    }
+    async fn test_sys_info_section() {
+        query.push("SYS");
+        query.push("INFO");
+        query.push("persistence");
+        match con.run_simple_query(&query).await.unwrap() {
+            Element::Array(Array::Str(lines)) => {
+                assert_eq!(lines[0], Some("# persistence".to_owned()));
+                assert!(lines.contains(&Some("poisoned:false".to_owned())));
+            }
+            _ => panic!("Bad response for SYS INFO"),
+        }
+    }
+    async fn test_sys_info_unknown_section() {
+        query.push("SYS");
+        query.push("INFO");
+        query.push("blahblah");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("unknown-sys-query".to_owned()))
+        );
+    }
+    async fn test_sys_info_syntax_error() {
+        query.push("SYS");
+        query.push("INFO");
+        query.push("server");
+        query.push("clients");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+    }
+    async fn test_sys_reload_syntax_error() {
+        query.push("SYS");
+        query.push("RELOAD");
+        query.push("now");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+    }
+}
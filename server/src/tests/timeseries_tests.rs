@@ -0,0 +1,148 @@
+/*
+ * Created on Sat Sep 25 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(table = "timeseries(str,float)")]
+mod __private {
+    use skytable::types::Array;
+    use skytable::{Element, RespCode};
+    async fn test_tsadd_tslen() {
+        push!(query, "TSADD", "cpu", "1000", "0.5");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("TSADD", "cpu", "2000", "0.75");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("TSLEN", "cpu");
+        runeq!(con, query, Element::UnsignedInt(2));
+        let query = skytable::query!("TSLEN", "mem");
+        runeq!(con, query, Element::UnsignedInt(0));
+    }
+    async fn test_tsadd_out_of_order() {
+        push!(query, "TSADD", "cpu", "2000", "0.5");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("TSADD", "cpu", "1000", "0.75");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("out-of-order-sample".to_owned()))
+        );
+        let query = skytable::query!("TSADD", "cpu", "2000", "0.75");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("out-of-order-sample".to_owned()))
+        );
+        let query = skytable::query!("TSLEN", "cpu");
+        runeq!(con, query, Element::UnsignedInt(1));
+    }
+    async fn test_tsadd_server_time() {
+        push!(query, "TSADD", "cpu", "*", "0.5");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        // the server time is long past this
+        let query = skytable::query!("TSADD", "cpu", "1000", "0.75");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("out-of-order-sample".to_owned()))
+        );
+    }
+    async fn test_tsadd_bad_sample() {
+        push!(query, "TSADD", "cpu", "-1000", "0.5");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("TSADD", "cpu", "1000", "NaN");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+    }
+    async fn test_tscreate_retention() {
+        push!(query, "TSCREATE", "cpu", "1000");
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("TSCREATE", "cpu", "0");
+        runeq!(con, query, Element::RespCode(RespCode::OverwriteError));
+        for timestamp in ["1000", "1500", "2000", "2500"] {
+            let query = skytable::query!("TSADD", "cpu", timestamp, "1");
+            runeq!(con, query, Element::RespCode(RespCode::Okay));
+        }
+        // only the samples of the last second are kept
+        let query = skytable::query!("TSLEN", "cpu");
+        runeq!(con, query, Element::UnsignedInt(3));
+    }
+    async fn test_tsget_tsrange() {
+        for (timestamp, value) in [("1000", "0.5"), ("2000", "-2"), ("3000", "1.25")] {
+            let query = skytable::query!("TSADD", "cpu", timestamp, value);
+            runeq!(con, query, Element::RespCode(RespCode::Okay));
+        }
+        push!(query, "TSGET", "cpu");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Recursive(vec![
+                Element::UnsignedInt(3000),
+                Element::String("1.25".to_owned())
+            ]))
+        );
+        let query = skytable::query!("TSRANGE", "cpu", "1000", "2000");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Recursive(vec![
+                Element::Array(Array::Recursive(vec![
+                    Element::UnsignedInt(1000),
+                    Element::UnsignedInt(2000)
+                ])),
+                Element::Array(Array::Recursive(vec![
+                    Element::String("0.5".to_owned()),
+                    Element::String("-2".to_owned())
+                ]))
+            ]))
+        );
+    }
+    async fn test_tsget_tsrange_nil() {
+        push!(query, "TSGET", "cpu");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+        let query = skytable::query!("TSRANGE", "cpu", "-", "+");
+        runeq!(
+            con,
+            query,
+            Element::Array(Array::Recursive(vec![
+                Element::Array(Array::Recursive(vec![])),
+                Element::Array(Array::Recursive(vec![]))
+            ]))
+        );
+    }
+    async fn test_tsagg_bad_args() {
+        push!(query, "TSAGG", "cpu", "-", "+", "median", "1000");
+        runeq!(con, query, Element::RespCode(RespCode::ActionError));
+        let query = skytable::query!("TSAGG", "cpu", "-", "+", "avg", "0");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("TSRANGE", "cpu", "+", "-");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+    }
+    async fn test_ts_wrong_model() {
+        push!(query, "SET", "cpu", "0.5");
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("wrong-model".to_owned()))
+        );
+    }
+}
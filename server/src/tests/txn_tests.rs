@@ -0,0 +1,258 @@
+/*
+ * Created on Mon Sep 13 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest]
+mod __private {
+    use skytable::{types::Array, Element, Query, RespCode};
+    async fn test_txn_exec() {
+        query.push("MULTI");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        for query in [
+            skytable::query!("SET", "x", "100"),
+            skytable::query!("UPDATE", "x", "200"),
+            skytable::query!("GET", "x"),
+            skytable::query!("DEL", "x", "y"),
+        ] {
+            assert_eq!(
+                con.run_simple_query(&query).await.unwrap(),
+                Element::String("QUEUED".to_owned())
+            );
+        }
+        let query = skytable::query!("EXEC");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::Array(Array::Recursive(vec![
+                Element::RespCode(RespCode::Okay),
+                Element::RespCode(RespCode::Okay),
+                Element::String("200".to_owned()),
+                Element::UnsignedInt(1)
+            ]))
+        );
+        // the transaction is closed now
+        let query = skytable::query!("EXEC");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("no-transaction".to_owned()))
+        );
+    }
+    async fn test_txn_discard() {
+        query.push("MULTI");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("SET", "x", "100");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("QUEUED".to_owned())
+        );
+        let query = skytable::query!("DISCARD");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("EXISTS", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::UnsignedInt(0)
+        );
+    }
+    async fn test_txn_nested_multi() {
+        query.push("MULTI");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("nested-transaction".to_owned()))
+        );
+        let query = skytable::query!("DISCARD");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+    }
+    async fn test_txn_unsupported_action_aborts() {
+        query.push("MULTI");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("MSET", "x", "100");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString(
+                "unsupported-in-transaction".to_owned()
+            ))
+        );
+        let query = skytable::query!("EXEC");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("txn-aborted".to_owned()))
+        );
+    }
+    async fn test_txn_syntax_error_aborts() {
+        query.push("MULTI");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("SET", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ActionError)
+        );
+        let query = skytable::query!("EXEC");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("txn-aborted".to_owned()))
+        );
+    }
+    async fn test_txn_watch_conflict() {
+        query.push("WATCH");
+        query.push("x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        // change the watched key from another connection
+        let mut other = skytable::AsyncConnection::new("127.0.0.1", 2003)
+            .await
+            .unwrap();
+        let mut query = Query::new();
+        query.push("USE");
+        query.push(&__MYENTITY__);
+        assert_eq!(
+            other.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("SET", "x", "100");
+        assert_eq!(
+            other.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("MULTI");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("SET", "x", "200");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("QUEUED".to_owned())
+        );
+        let query = skytable::query!("EXEC");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("txn-conflict".to_owned()))
+        );
+        let query = skytable::query!("GET", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("100".to_owned())
+        );
+    }
+    async fn test_txn_watch_same_value_written() {
+        setkeys! {
+            con,
+            "x": "1"
+        }
+        push!(query, "WATCH", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        // change the watched key and set it back from another connection
+        let mut other = skytable::AsyncConnection::new("127.0.0.1", 2003)
+            .await
+            .unwrap();
+        let query = skytable::query!("USE", &__MYENTITY__);
+        assert_eq!(
+            other.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        for value in ["2", "1"] {
+            let query = skytable::query!("UPDATE", "x", value);
+            assert_eq!(
+                other.run_simple_query(&query).await.unwrap(),
+                Element::RespCode(RespCode::Okay)
+            );
+        }
+        let query = skytable::query!("MULTI");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("UPDATE", "x", "3");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("QUEUED".to_owned())
+        );
+        // the value is the same but the key was written to
+        let query = skytable::query!("EXEC");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("txn-conflict".to_owned()))
+        );
+        let query = skytable::query!("GET", "x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("1".to_owned())
+        );
+    }
+    async fn test_txn_watch_unchanged() {
+        query.push("WATCH");
+        query.push("x");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("MULTI");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::Okay)
+        );
+        let query = skytable::query!("WATCH", "y");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::RespCode(RespCode::ErrorString("watch-in-transaction".to_owned()))
+        );
+        let query = skytable::query!("USET", "x", "100");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::String("QUEUED".to_owned())
+        );
+        let query = skytable::query!("EXEC");
+        assert_eq!(
+            con.run_simple_query(&query).await.unwrap(),
+            Element::Array(Array::Recursive(vec![Element::UnsignedInt(1)]))
+        );
+    }
+}
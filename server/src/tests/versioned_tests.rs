@@ -0,0 +1,101 @@
+/*
+ * Created on Tue Sep 28 2021
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2021, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[sky_macros::dbtest(table = "keymap(str,str)")]
+mod __private {
+    use skytable::types::Array;
+    use skytable::{Element, RespCode};
+    async fn test_getv_nil() {
+        push!(query, "GETV", "x");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+    }
+    async fn test_updatev() {
+        setkeys! {
+            con,
+            "x": "100"
+        }
+        push!(query, "GETV", "x");
+        let version = match con.run_simple_query(&query).await.unwrap() {
+            Element::Array(Array::Recursive(mut elements)) => {
+                assert_eq!(elements.remove(0), Element::String("100".to_owned()));
+                match elements.remove(0) {
+                    Element::UnsignedInt(version) => version,
+                    _ => panic!("Bad version for GETV"),
+                }
+            }
+            _ => panic!("Bad response for GETV"),
+        };
+        let query = skytable::query!("UPDATEV", "x", "200", (version + 1).to_string());
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("version-mismatch".to_owned()))
+        );
+        let query = skytable::query!("UPDATEV", "x", "200", version.to_string());
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        // the version moved on with the update
+        let query = skytable::query!("UPDATEV", "x", "300", version.to_string());
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("version-mismatch".to_owned()))
+        );
+        let query = skytable::query!("GET", "x");
+        runeq!(con, query, Element::String("200".to_owned()));
+        let query = skytable::query!("UPDATEV", "y", "200", "0");
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+    }
+    async fn test_delv() {
+        setkeys! {
+            con,
+            "x": "100"
+        }
+        push!(query, "GETV", "x");
+        let version = match con.run_simple_query(&query).await.unwrap() {
+            Element::Array(Array::Recursive(mut elements)) => match elements.remove(1) {
+                Element::UnsignedInt(version) => version,
+                _ => panic!("Bad version for GETV"),
+            },
+            _ => panic!("Bad response for GETV"),
+        };
+        let query = skytable::query!("DELV", "x", (version + 1).to_string());
+        runeq!(
+            con,
+            query,
+            Element::RespCode(RespCode::ErrorString("version-mismatch".to_owned()))
+        );
+        let query = skytable::query!("DELV", "x", version.to_string());
+        runeq!(con, query, Element::RespCode(RespCode::Okay));
+        let query = skytable::query!("DELV", "x", version.to_string());
+        runeq!(con, query, Element::RespCode(RespCode::NotFound));
+    }
+    async fn test_versioned_bad_version() {
+        push!(query, "UPDATEV", "x", "100", "one");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+        let query = skytable::query!("DELV", "x", "-1");
+        runeq!(con, query, Element::RespCode(RespCode::Wrongtype));
+    }
+}
@@ -29,6 +29,9 @@
 /// This trait provides a method `unsafe_unwrap` that is potentially unsafe and has
 /// the ability to **violate multiple safety gurantees** that rust provides. So,
 /// if you get `SIGILL`s or `SIGSEGV`s, by using this trait, blame yourself.
+///
+/// # Safety
+/// Implementors have to make sure that `unsafe_unwrap` returns the value without any checks
 pub unsafe trait Unwrappable<T> {
     /// Unwrap a _nullable_ (almost) type to get its value while asserting that the value
     /// cannot ever be null
@@ -154,9 +157,9 @@ macro_rules! action {
         $block:block
     ) => {
             $(#[$attr])*
-            pub async fn $fname<'a, T: 'a, Strm>($($argname: $argty,)*) -> std::io::Result<()>
+            pub async fn $fname<'a, T, Strm>($($argname: $argty,)*) -> std::io::Result<()>
             where
-                T: ProtocolConnectionExt<Strm>,
+                T: 'a + ProtocolConnectionExt<Strm>,
                 Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
                 $block
     };
@@ -166,9 +169,9 @@ macro_rules! action {
         $block:block
     ) => {
             $(#[$attr])*
-            pub async fn $fname<'a, T: 'a, Strm>($argone: $argonety, $argtwo: $argtwoty, mut $argthree: $argthreety) -> std::io::Result<()>
+            pub async fn $fname<'a, T, Strm>($argone: $argonety, $argtwo: $argtwoty, mut $argthree: $argthreety) -> std::io::Result<()>
             where
-                T: ProtocolConnectionExt<Strm>,
+                T: 'a + ProtocolConnectionExt<Strm>,
                 Strm: AsyncReadExt + AsyncWriteExt + Unpin + Send + Sync,
                 $block
     };
@@ -204,10 +207,10 @@ pub mod compiler {
         v
     }
 
-    pub unsafe fn extend_lifetime<'a, 'b, T>(inp: &'a T) -> &'b T {
+    pub unsafe fn extend_lifetime<'b, T>(inp: &T) -> &'b T {
         mem::transmute(inp)
     }
-    pub unsafe fn extend_lifetime_mut<'a, 'b, T>(inp: &'a mut T) -> &'b mut T {
+    pub unsafe fn extend_lifetime_mut<'b, T>(inp: &mut T) -> &'b mut T {
         mem::transmute(inp)
     }
 }
@@ -131,6 +131,7 @@ pub fn runner(
         println!("Data size (key+value): {} bytes", (per_kv_size * 2));
     }
     let host = hoststr!(host, port);
+    let zset_host = host.clone();
     let mut rand = thread_rng();
 
     let temp_table = libstress::utils::rand_alphastring(10, &mut rand);
@@ -143,6 +144,17 @@ pub fn runner(
     let switch_table = Query::from("use")
         .arg(format!("default:{}", &temp_table))
         .into_raw_query();
+    // sorted set operations are run against a single leaderboard in a table of its own
+    let temp_zset_table = libstress::utils::rand_alphastring(10, &mut rand);
+    let create_zset_table = Query::from("create")
+        .arg("table")
+        .arg(&temp_zset_table)
+        .arg("sortedset(binstr,binstr)")
+        .arg("volatile")
+        .into_raw_query();
+    let switch_zset_table = Query::from("use")
+        .arg(format!("default:{}", &temp_zset_table))
+        .into_raw_query();
     let mut create_table_connection = TcpStream::connect(&host).unwrap();
 
     // an okay response code size: `*1\n!1\n0\n`:
@@ -170,10 +182,35 @@ pub fn runner(
         Some(max_queries),
     );
 
-    // create table
-    create_table_connection.write_all(&create_table).unwrap();
-    let mut v = vec![0; response_okay_size];
-    let _ = create_table_connection.read_exact(&mut v).unwrap();
+    let zset_pool_config = PoolConfig::new(
+        max_connections,
+        move || {
+            let mut stream = TcpStream::connect(&zset_host).unwrap();
+            stream.write_all(&switch_zset_table.clone()).unwrap();
+            let mut v = vec![0; response_okay_size];
+            let _ = stream.read_exact(&mut v).unwrap();
+            stream
+        },
+        move |sock, packet: Vec<u8>| {
+            sock.write_all(&packet).unwrap();
+            // `ZADD`s and `ZREM`s of single members always return `1`, which has the same
+            // size as an `okay`
+            let mut v = vec![0; response_okay_size];
+            let _ = sock.read_exact(&mut v).unwrap();
+        },
+        |socket| {
+            socket.shutdown(std::net::Shutdown::Both).unwrap();
+        },
+        true,
+        Some(max_queries),
+    );
+
+    // create tables
+    for create_query in [&create_table, &create_zset_table] {
+        create_table_connection.write_all(create_query).unwrap();
+        let mut v = vec![0; response_okay_size];
+        let _ = create_table_connection.read_exact(&mut v).unwrap();
+    }
 
     // Create separate connection pools for get and set operations
 
@@ -211,19 +248,56 @@ pub fn runner(
             q.into_raw_query()
         })
         .collect();
+    /*
+    Every member is added to the leaderboard with a distinct score. We give all the scores
+    the same number of digits so that every `ZSCORE` response has the same size
+    */
+    let score_base = 10usize.pow(max_queries.to_string().len() as u32);
+    let score_len = (score_base + max_queries).to_string().len();
+    let zadd_packs: Vec<Vec<u8>> = (0..max_queries)
+        .map(|idx| {
+            let mut q = Query::from("ZADD");
+            q.push("leaderboard");
+            q.push((score_base + idx).to_string());
+            q.push(RawString::from(keys[idx].clone()));
+            q.into_raw_query()
+        })
+        .collect();
+    let zscore_packs: Vec<Vec<u8>> = (0..max_queries)
+        .map(|idx| {
+            let mut q = Query::from("ZSCORE");
+            q.push("leaderboard");
+            q.push(RawString::from(keys[idx].clone()));
+            q.into_raw_query()
+        })
+        .collect();
+    let zrem_packs: Vec<Vec<u8>> = (0..max_queries)
+        .map(|idx| {
+            let mut q = Query::from("ZREM");
+            q.push("leaderboard");
+            q.push(RawString::from(keys[idx].clone()));
+            q.into_raw_query()
+        })
+        .collect();
     if !json_out {
         println!("Per-packet size (GET): {} bytes", get_packs[0].len());
         println!("Per-packet size (SET): {} bytes", set_packs[0].len());
         println!("Per-packet size (UPDATE): {} bytes", update_packs[0].len());
+        println!("Per-packet size (ZADD): {} bytes", zadd_packs[0].len());
+        println!("Per-packet size (ZSCORE): {} bytes", zscore_packs[0].len());
+        println!("Per-packet size (ZREM): {} bytes", zrem_packs[0].len());
         println!("Initialization complete! Benchmark started");
     }
-    let mut report = report::AggregatedReport::new(3, runs, max_queries);
+    let mut report = report::AggregatedReport::new(6, runs, max_queries);
     for i in 1..runs + 1 {
         let mut dt = DevTime::new_complex();
         // clone in the keys
         let set_packs = set_packs.clone();
         let get_packs = get_packs.clone();
         let update_packs = update_packs.clone();
+        let zadd_packs = zadd_packs.clone();
+        let zscore_packs = zscore_packs.clone();
+        let zrem_packs = zrem_packs.clone();
 
         // bench SET
         let setpool = pool_config.get_pool();
@@ -255,6 +329,34 @@ pub fn runner(
         update_pool.execute_and_finish_iter(update_packs);
         dt.stop_timer("UPDATE").unwrap();
 
+        // bench ZADD
+        let zadd_pool = zset_pool_config.get_pool();
+        dt.create_timer("ZADD").unwrap();
+        dt.start_timer("ZADD").unwrap();
+        zadd_pool.execute_and_finish_iter(zadd_packs);
+        dt.stop_timer("ZADD").unwrap();
+
+        // bench ZSCORE
+        let zscore_response_packet_size =
+            calculate_monoelement_dataframe_size(score_len) + SIMPLE_QUERY_SIZE;
+        let zscore_pool =
+            zset_pool_config.with_loop_closure(move |sock: &mut TcpStream, packet: Vec<u8>| {
+                sock.write_all(&packet).unwrap();
+                let mut v = vec![0; zscore_response_packet_size];
+                let _ = sock.read_exact(&mut v).unwrap();
+            });
+        dt.create_timer("ZSCORE").unwrap();
+        dt.start_timer("ZSCORE").unwrap();
+        zscore_pool.execute_and_finish_iter(zscore_packs);
+        dt.stop_timer("ZSCORE").unwrap();
+
+        // bench ZREM (this also empties the leaderboard for the next run)
+        let zrem_pool = zset_pool_config.get_pool();
+        dt.create_timer("ZREM").unwrap();
+        dt.start_timer("ZREM").unwrap();
+        zrem_pool.execute_and_finish_iter(zrem_packs);
+        dt.stop_timer("ZREM").unwrap();
+
         if !json_out {
             println!("Finished run: {}", i);
         }
@@ -142,9 +142,7 @@ fn parse_test_sig(
     for attr in &input.attrs {
         if attr.path.is_ident("test") {
             let msg = "second test attribute is supplied";
-            return syn::Error::new_spanned(&attr, msg)
-                .to_compile_error()
-                .into();
+            return syn::Error::new_spanned(attr, msg).to_compile_error().into();
         }
     }
 
@@ -295,9 +293,9 @@ fn parse_string(int: syn::Lit, span: Span, field: &str) -> Result<String, syn::E
 ///
 /// The `#[dbtest]` macro expects several things. The calling crate:
 /// - should have the `tokio` crate as a dependency and should have the
-/// `features` set to full
+///   `features` set to full
 /// - should have the `skytable` crate as a dependency and should have the `features` set to `async` and version
-/// upstreamed to `next` on skytable/client-rust
+///   upstreamed to `next` on skytable/client-rust
 ///
 /// ## Conventions
 /// Since `proc_macro` cannot accept _file-linked_ modules and only accepts inline modules, we have made a workaround, which
@@ -351,7 +349,7 @@ pub fn array(args: TokenStream, item: TokenStream) -> TokenStream {
             } else {
                 // so we have the form we expect
                 let (declaration, expression) = (ret[0], ret[1]);
-                let expression = expression.trim().replace(" ;", "");
+                let expression = expression.trim().trim_end_matches(';').trim().to_string();
                 if !(expression.starts_with('[') && expression.ends_with(']')) {
                     syn::Error::new_spanned(declaration, "Expected an array")
                         .to_compile_error()